@@ -1,5 +1,8 @@
 use crate::transaction::Transaction;
 
+// Previous-block hash recorded in every genesis block
+pub const GENESIS_PRE_BLOCK_HASH: &str = "None";
+
 
 #[derive(Clone, bincode::Encode, bincode::Decode)]
 pub struct Block {
@@ -0,0 +1,12 @@
+/// A block's fields without its transactions, exchanged during headers-first sync. Carries
+/// enough to check proof-of-work without the transaction list.
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct BlockHeader {
+    pub(in crate::block) timestamp: i64,
+    pub(in crate::block) pre_block_hash: String,
+    // Hash over the block's transaction ids, committing the header to them
+    pub(in crate::block) merkle_root: Vec<u8>,
+    pub(in crate::block) hash: String,
+    pub(in crate::block) nonce: i64,
+    pub(in crate::block) height: usize,
+}
@@ -0,0 +1,39 @@
+use bincode::config::standard;
+
+use crate::block::BlockHeader;
+
+impl BlockHeader {
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::encode_to_vec(self, standard()).unwrap()
+    }
+
+    pub fn try_deserialize(bytes: &[u8]) -> Result<BlockHeader, bincode::error::DecodeError> {
+        let (header, _) = bincode::decode_from_slice(bytes, standard())?;
+
+        Ok(header)
+    }
+
+    pub fn get_timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub fn get_pre_block_hash(&self) -> &str {
+        self.pre_block_hash.as_str()
+    }
+
+    pub fn get_merkle_root(&self) -> &[u8] {
+        self.merkle_root.as_slice()
+    }
+
+    pub fn get_hash(&self) -> &str {
+        self.hash.as_str()
+    }
+
+    pub fn get_nonce(&self) -> i64 {
+        self.nonce
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+}
@@ -1,10 +1,34 @@
+use std::collections::HashSet;
+
 use bincode::config::standard;
+use data_encoding::HEXLOWER;
 use sled::IVec;
 
-use crate::{block::Block, proof_of_work::ProofOfWork, transaction::Transaction, util};
+use crate::{
+    block::{Block, BlockHeader, GENESIS_PRE_BLOCK_HASH},
+    config::GLOBAL_CONFIG,
+    proof_of_work::ProofOfWork,
+    transaction::Transaction,
+    util,
+};
 
 impl Block {
     pub fn new_block(pre_block_hash: String, transactions: &[Transaction], height: usize) -> Block {
+        Self::new_block_with_target_bits(
+            pre_block_hash,
+            transactions,
+            height,
+            GLOBAL_CONFIG.get_target_bits(),
+        )
+    }
+
+    /// Like `new_block`, mining at `target_bits` instead of the configured difficulty
+    pub fn new_block_with_target_bits(
+        pre_block_hash: String,
+        transactions: &[Transaction],
+        height: usize,
+        target_bits: usize,
+    ) -> Block {
         let mut block = Block {
             timestamp: util::current_timestamp(),
             pre_block_hash,
@@ -13,7 +37,7 @@ impl Block {
             nonce: 0,
             height,
         };
-        let pow = ProofOfWork::new_proof_of_work(block.clone());
+        let pow = ProofOfWork::new_proof_of_work(block.clone(), target_bits);
         let (nonce, hash) = pow.run();
         block.nonce = nonce;
         block.hash = hash;
@@ -60,6 +84,20 @@ impl Block {
         self.transactions = transactions.to_vec();
     }
 
+    /// Replace each transaction matching `should_prune` with its id-only placeholder, which
+    /// leaves `hash_transactions` and so the block hash unchanged. Returns how many were
+    /// replaced.
+    pub fn prune_transactions(&mut self, should_prune: impl Fn(&Transaction) -> bool) -> usize {
+        let mut pruned = 0;
+        for tx in self.transactions.iter_mut() {
+            if !tx.is_pruned() && should_prune(tx) {
+                *tx = tx.pruned();
+                pruned += 1;
+            }
+        }
+        pruned
+    }
+
     pub fn get_pre_block_hash(&self) -> &str {
         self.pre_block_hash.as_str()
     }
@@ -72,6 +110,10 @@ impl Block {
         self.hash = hash.to_string();
     }
 
+    pub fn set_nonce_for_test(&mut self, nonce: i64) {
+        self.nonce = nonce;
+    }
+
     pub fn get_hash_bytes(&self) -> Vec<u8> {
         self.hash.as_bytes().to_vec()
     }
@@ -88,6 +130,22 @@ impl Block {
         self.nonce
     }
 
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            timestamp: self.timestamp,
+            pre_block_hash: self.pre_block_hash.clone(),
+            merkle_root: self.hash_transactions(),
+            hash: self.hash.clone(),
+            nonce: self.nonce,
+            height: self.height,
+        }
+    }
+
+    /// The serialized block without its transactions
+    pub fn header_bytes(&self) -> Vec<u8> {
+        self.header().serialize()
+    }
+
     pub fn hash_transactions(&self) -> Vec<u8> {
         let mut txhashs = vec![];
         for transaction in &self.transactions {
@@ -96,10 +154,54 @@ impl Block {
         util::sha256_digest(txhashs.as_slice())
     }
 
+    /// Check that the first transaction is a coinbase and no other transaction is one
+    pub fn validate_coinbase(&self) -> Result<(), String> {
+        if !self
+            .transactions
+            .first()
+            .is_some_and(Transaction::is_coinbase)
+        {
+            return Err(format!(
+                "block {} does not start with a coinbase",
+                self.hash
+            ));
+        }
+        if let Some(idx) = self.transactions[1..]
+            .iter()
+            .position(Transaction::is_coinbase)
+        {
+            return Err(format!(
+                "block {} has a second coinbase at index {}",
+                self.hash,
+                idx + 1
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that no two transactions in the block share an id
+    pub fn validate_unique_txids(&self) -> Result<(), String> {
+        let mut seen = HashSet::new();
+        for tx in &self.transactions {
+            if !seen.insert(tx.get_id()) {
+                return Err(format!(
+                    "block {} contains transaction {} more than once",
+                    self.hash,
+                    HEXLOWER.encode(tx.get_id())
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn generate_genesis_block(transaction: &Transaction) -> Block {
         let transactions = vec![transaction.clone()];
 
-        Block::new_block_without_proof_of_work(String::from("None"), &transactions, 0)
+        Block::new_block_without_proof_of_work(
+            String::from(GENESIS_PRE_BLOCK_HASH),
+            &transactions,
+            0,
+        )
     }
 }
 
@@ -1 +1,2 @@
+pub mod block_header_impl;
 pub mod block_impl;
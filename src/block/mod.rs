@@ -1,5 +1,6 @@
 mod data;
 mod implementation;
 
-// Re-export the main struct
-pub use data::block::Block;
+// Re-export the main struct and constants
+pub use data::block::{Block, GENESIS_PRE_BLOCK_HASH};
+pub use data::block_header::BlockHeader;
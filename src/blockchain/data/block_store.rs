@@ -0,0 +1,22 @@
+use crate::blockchain::{BlockchainError, StoreBatch};
+
+/// Storage backend holding serialized blocks, the main-chain height index and the tip hash
+pub trait BlockStore: Send + Sync {
+    /// Serialized block stored under `block_hash`
+    fn get_block(&self, block_hash: &[u8]) -> Result<Option<Vec<u8>>, BlockchainError>;
+
+    /// Every stored serialized block, in no particular order
+    fn get_all_blocks(&self) -> Result<Vec<Vec<u8>>, BlockchainError>;
+
+    /// Hash of the tip block, if one has been recorded
+    fn get_tip(&self) -> Result<Option<String>, BlockchainError>;
+
+    /// Hash of the main-chain block at `height`
+    fn get_hash_at_height(&self, height: usize) -> Result<Option<String>, BlockchainError>;
+
+    /// Apply every write in `batch` atomically
+    fn write(&self, batch: &StoreBatch) -> Result<(), BlockchainError>;
+
+    /// Remove every entry of the height index
+    fn clear_heights(&self) -> Result<(), BlockchainError>;
+}
@@ -1,13 +1,36 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock, mpsc::Sender};
 
 use sled::Db;
 
+use crate::blockchain::{BlockStore, TipUpdate};
+
 pub const TIP_BLOCK_HASH_KEY: &str = "tip_block_hash";
 pub const BLOCKS_TREE: &str = "blocks";
+// Key in the blocks tree holding the big-endian u32 storage format version
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+// Storage format version this build reads and writes. Bump it, with a migration step,
+// whenever the encoding of stored data changes. Version 1 added transaction lock times,
+// multisig outputs and signatures and data outputs, which stores from before versioning
+// cannot be upgraded to. Version 2 keeps spent outputs as empty slots in UTXO entries and
+// records undo data as the diff each block applied.
+pub const SCHEMA_VERSION: u32 = 2;
+// Maps big-endian block height to the hash of the main-chain block at that height
+pub const HEIGHT_INDEX_TREE: &str = "heights";
+// Number of hash characters shown in export_dot node labels
+pub const DOT_SHORT_HASH_LEN: usize = 8;
+// Bytes a mined block can outgrow its unmined encoding by: the 64-character hash plus a
+// nonce and transaction count at full varint width
+pub const MINED_BLOCK_SIZE_SLACK: usize = 64 + 8 + 8;
+// Hashes a block locator lists one height apart below the tip before the gaps start doubling
+pub const LOCATOR_DENSE_LEN: usize = 10;
 
 
 #[derive(Clone)]
 pub struct Blockchain {
     pub(in crate::blockchain) tip_hash: Arc<RwLock<String>>, // Optimized: Arc<str> instead of String
+    // Trees other modules keep next to the chain, such as the UTXO set
     pub(in crate::blockchain) db: Db,
+    pub(in crate::blockchain) store: Arc<dyn BlockStore>,
+    // Receivers handed out by `subscribe`, shared by every clone of the chain
+    pub(in crate::blockchain) tip_subscribers: Arc<Mutex<Vec<Sender<TipUpdate>>>>,
 }
@@ -0,0 +1,16 @@
+/// Failures of the non-panicking `Blockchain::try_*` methods
+#[derive(Debug)]
+pub enum BlockchainError {
+    // The chain has no tip, or the tip hash names no stored block
+    TipNotFound,
+    // No block is stored under this hash
+    BlockNotFound(String),
+    // The underlying sled database failed
+    DbError(String),
+    // A stored block could not be decoded
+    Deserialize(String),
+    // The store was written with this storage format version, newer than this build reads
+    UnsupportedSchema(u32),
+    // The store was written with this storage format version, too old to upgrade in place
+    ResyncRequired(u32),
+}
@@ -1,6 +1,8 @@
-use sled::Db;
+use std::sync::Arc;
+
+use crate::blockchain::BlockStore;
 
 pub struct BlockchainIterator {
-    pub(in crate::blockchain) db: Db,
+    pub(in crate::blockchain) store: Arc<dyn BlockStore>,
     pub(in crate::blockchain) current_hash: String,
 }
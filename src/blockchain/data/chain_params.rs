@@ -0,0 +1,14 @@
+// Tree holding the parameters a chain was created with
+pub const CHAIN_PARAMS_TREE: &str = "chain_params";
+pub const CHAIN_PARAMS_KEY: &str = "params";
+
+/// The parameters that tell one network apart from another, fixed when its genesis block
+/// is created by `Blockchain::create_blockchain_with_params`
+#[derive(Clone, Debug, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct ChainParams {
+    // Value of the genesis coinbase output
+    pub(in crate::blockchain) genesis_reward: i32,
+    pub(in crate::blockchain) genesis_address: String,
+    // Proof-of-work difficulty the network mines and checks blocks at
+    pub(in crate::blockchain) target_bits: usize,
+}
@@ -0,0 +1,11 @@
+/// Aggregate figures over the main chain, from `Blockchain::stats`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainStats {
+    pub(in crate::blockchain) block_count: usize,
+    // Every transaction in every block, coinbases included
+    pub(in crate::blockchain) transaction_count: usize,
+    // Total value of all coinbase outputs ever mined
+    pub(in crate::blockchain) coinbase_issuance: i64,
+    // Total value of the outputs no main-chain transaction spends
+    pub(in crate::blockchain) circulating_supply: i64,
+}
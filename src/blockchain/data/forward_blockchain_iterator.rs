@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use crate::blockchain::BlockStore;
+
+pub struct ForwardBlockchainIterator {
+    pub(in crate::blockchain) store: Arc<dyn BlockStore>,
+    // Hashes still to yield, tip first, so the next block is popped off the end
+    pub(in crate::blockchain) pending_hashes: Vec<String>,
+}
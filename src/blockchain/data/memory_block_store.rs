@@ -0,0 +1,17 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::RwLock,
+};
+
+/// Block storage kept in memory, for tests and benchmarks that should not touch the disk
+#[derive(Default)]
+pub struct MemoryBlockStore {
+    pub(in crate::blockchain) inner: RwLock<MemoryBlockStoreInner>,
+}
+
+#[derive(Default)]
+pub(in crate::blockchain) struct MemoryBlockStoreInner {
+    pub(in crate::blockchain) blocks: HashMap<Vec<u8>, Vec<u8>>,
+    pub(in crate::blockchain) heights: BTreeMap<usize, String>,
+    pub(in crate::blockchain) tip: Option<String>,
+}
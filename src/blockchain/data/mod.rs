@@ -1,2 +1,12 @@
 pub mod blockchain;
+pub mod blockchain_error;
 pub mod blockchain_iterator;
+pub mod reorg;
+pub mod block_store;
+pub mod memory_block_store;
+pub mod sled_block_store;
+pub mod store_batch;
+pub mod forward_blockchain_iterator;
+pub mod chain_stats;
+pub mod chain_params;
+pub mod tip_update;
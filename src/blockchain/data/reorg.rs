@@ -0,0 +1,9 @@
+use crate::Block;
+
+/// Blocks leaving and joining the main chain when `add_block` switches to a longer branch
+pub struct Reorg {
+    // Old main-chain blocks, old tip first
+    pub(in crate::blockchain) disconnected: Vec<Block>,
+    // New branch blocks, oldest first, ending with the new tip
+    pub(in crate::blockchain) connected: Vec<Block>,
+}
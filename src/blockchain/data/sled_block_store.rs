@@ -0,0 +1,7 @@
+use sled::Db;
+
+/// Block storage in the `BLOCKS_TREE` and `HEIGHT_INDEX_TREE` trees of a sled database
+#[derive(Clone)]
+pub struct SledBlockStore {
+    pub(in crate::blockchain) db: Db,
+}
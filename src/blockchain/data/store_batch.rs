@@ -0,0 +1,10 @@
+/// Writes applied together by `BlockStore::write`
+#[derive(Default)]
+pub struct StoreBatch {
+    pub(in crate::blockchain) blocks: Vec<(String, Vec<u8>)>,
+    // Hashes of blocks deleted after the inserts are applied
+    pub(in crate::blockchain) removed_blocks: Vec<String>,
+    // Height, hash and whether an existing entry at that height is overwritten
+    pub(in crate::blockchain) heights: Vec<(usize, String, bool)>,
+    pub(in crate::blockchain) tip: Option<String>,
+}
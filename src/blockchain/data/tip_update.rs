@@ -0,0 +1,6 @@
+/// The new tip a chain moved to, sent to every `Blockchain::subscribe` receiver
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TipUpdate {
+    pub(in crate::blockchain) hash: String,
+    pub(in crate::blockchain) height: usize,
+}
@@ -0,0 +1,30 @@
+use std::fmt;
+
+use crate::blockchain::{BlockchainError, SCHEMA_VERSION};
+
+impl fmt::Display for BlockchainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockchainError::TipNotFound => write!(f, "tip block not found"),
+            BlockchainError::BlockNotFound(hash) => write!(f, "block {hash} not found"),
+            BlockchainError::DbError(e) => write!(f, "database error: {e}"),
+            BlockchainError::Deserialize(e) => write!(f, "unable to deserialize block: {e}"),
+            BlockchainError::UnsupportedSchema(version) => write!(
+                f,
+                "storage schema version {version} is newer than the supported version {SCHEMA_VERSION}"
+            ),
+            BlockchainError::ResyncRequired(version) => write!(
+                f,
+                "storage schema version {version} cannot be upgraded to version {SCHEMA_VERSION}; remove the data directory and sync the chain again"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BlockchainError {}
+
+impl From<sled::Error> for BlockchainError {
+    fn from(e: sled::Error) -> Self {
+        BlockchainError::DbError(e.to_string())
+    }
+}
@@ -1,25 +1,46 @@
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc, RwLock,
+        mpsc::{self, Receiver},
+    },
+    thread,
 };
 
+use bincode::config::standard;
 use data_encoding::HEXLOWER;
-use sled::{Db, Tree, transaction::TransactionResult};
+use log::{error, info};
+use sled::Db;
 
 use crate::{
-    blockchain::{Blockchain, BLOCKS_TREE, TIP_BLOCK_HASH_KEY}, util::{self, current_dir}, Block, BlockchainIterator, TXOutput, Transaction
+    blockchain::{Blockchain, BlockchainError, ChainParams, ChainStats, TipUpdate, BlockStore, MemoryBlockStore, Reorg, SledBlockStore, StoreBatch, BLOCKS_TREE, DOT_SHORT_HASH_LEN, SCHEMA_VERSION, SCHEMA_VERSION_KEY, CHAIN_PARAMS_KEY, CHAIN_PARAMS_TREE, LOCATOR_DENSE_LEN, MINED_BLOCK_SIZE_SLACK, TIP_BLOCK_HASH_KEY}, config::GLOBAL_CONFIG, util::{self, current_dir}, utxo_set::UTXO_TREE, Block, GENESIS_PRE_BLOCK_HASH, BlockchainIterator, ForwardBlockchainIterator, ProofOfWork, SUBSIDY, TXOutput, Transaction, UTXOSet
 };
 
 impl Blockchain {
     pub fn create_blockchain(genesis_address: &str) -> Blockchain {
+        Self::create_blockchain_with_params(&ChainParams::new(genesis_address))
+    }
+
+    /// Open the local blockchain, creating it with a genesis block built from `params` if
+    /// none exists yet
+    pub fn create_blockchain_with_params(params: &ChainParams) -> Blockchain {
         let db = sled::open(current_dir().join("data")).unwrap();
-        let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
+        Self::create_blockchain_in(db, params)
+    }
+
+    /// Like `create_blockchain_with_params`, keeping the chain in `db`. The parameters are
+    /// recorded next to the chain only when its genesis block is created.
+    pub fn create_blockchain_in(db: Db, params: &ChainParams) -> Blockchain {
+        Self::open_schema(&db);
+        let store = SledBlockStore::new(db.clone());
 
-        let data = blocks_tree.get(TIP_BLOCK_HASH_KEY).unwrap();
-        let tip_hash = if let Some(data) = data {
-            String::from_utf8(data.to_vec()).unwrap()
+        let tip_hash = if let Some(tip_hash) = store.get_tip().unwrap() {
+            tip_hash
         } else {
-            let coinbase_tx = Transaction::new_coinbase_tx(genesis_address);
+            let coinbase_tx = Transaction::new_coinbase_tx_with_reward(
+                params.get_genesis_address(),
+                params.get_genesis_reward(),
+            );
             let mut block = Block::generate_genesis_block(&coinbase_tx);
             
             // Genesis blocks from generate_genesis_block have empty hashes
@@ -29,47 +50,166 @@ impl Blockchain {
                 block.set_hash_for_test(&genesis_hash);
             }
             
-            Self::update_blocks_tree(&blocks_tree, &block);
+            Self::store_tip_block(&store, &block);
+            let params_bytes = bincode::encode_to_vec(params, standard()).unwrap();
+            let _ = db
+                .open_tree(CHAIN_PARAMS_TREE)
+                .and_then(|tree| tree.insert(CHAIN_PARAMS_KEY, params_bytes));
             String::from(block.get_hash())
         };
         Blockchain {
             tip_hash: Arc::new(RwLock::new(tip_hash)),
             db,
+            store: Arc::new(store),
+            tip_subscribers: Arc::default(),
         }
     }
 
+    /// The parameters the chain was created with, if it was created by
+    /// `create_blockchain_with_params`
+    pub fn get_chain_params(&self) -> Option<ChainParams> {
+        let bytes = self
+            .db
+            .open_tree(CHAIN_PARAMS_TREE)
+            .ok()?
+            .get(CHAIN_PARAMS_KEY)
+            .ok()??;
+        bincode::decode_from_slice(&bytes, standard())
+            .ok()
+            .map(|(params, _)| params)
+    }
+
+    /// The difficulty blocks are mined and checked at: the `target_bits` of the chain's
+    /// `ChainParams`, or the configured one for a chain created without them
+    pub fn get_target_bits(&self) -> usize {
+        self.get_chain_params()
+            .map_or_else(|| GLOBAL_CONFIG.get_target_bits(), |params| params.get_target_bits())
+    }
+
     pub fn new_blockchain() -> Blockchain {
         let db = sled::open(util::current_dir().join("data")).unwrap();
-        let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
-        let tip_bytes = blocks_tree
-            .get(TIP_BLOCK_HASH_KEY)
+        Self::open_schema(&db);
+        let store = SledBlockStore::new(db.clone());
+        let tip_hash = store
+            .get_tip()
             .unwrap()
             .expect("No existing blockchain found. Create one first.");
-        let tip_hash = String::from_utf8(tip_bytes.to_vec()).unwrap();
         Blockchain {
             tip_hash: Arc::new(RwLock::new(tip_hash)),
             db,
+            store: Arc::new(store),
+            tip_subscribers: Arc::default(),
+        }
+    }
+
+    /// Open the local blockchain, or an empty one (no genesis) if none exists yet.
+    /// An empty chain is filled by syncing every block, genesis included, from a peer.
+    pub fn open_blockchain() -> Blockchain {
+        let db = sled::open(util::current_dir().join("data")).unwrap();
+        Self::open_schema(&db);
+        let store = Arc::new(SledBlockStore::new(db.clone()));
+        Self::new_with_store(db, store)
+    }
+
+    /// Check the storage format version recorded in `db` before its chain is used,
+    /// upgrading older stores in place. A store from before versioning counts as version 0
+    /// and an empty one is stamped with `SCHEMA_VERSION`. Fails, leaving the store
+    /// untouched, if it was written by a newer build or holds version 0 blocks, which have
+    /// to be synced again. Returns the version found.
+    pub fn check_schema_version(db: &Db) -> Result<u32, BlockchainError> {
+        let blocks_tree = db.open_tree(BLOCKS_TREE)?;
+        let found = match blocks_tree.get(SCHEMA_VERSION_KEY)? {
+            Some(bytes) => u32::from_be_bytes(bytes.as_ref().try_into().map_err(|_| {
+                BlockchainError::DbError(String::from("malformed schema version"))
+            })?),
+            None if blocks_tree.get(TIP_BLOCK_HASH_KEY)?.is_some() => 0,
+            None => SCHEMA_VERSION,
+        };
+        if found > SCHEMA_VERSION {
+            return Err(BlockchainError::UnsupportedSchema(found));
+        }
+        // Version 0 transactions lack lock times, multisig and data outputs. Re-encoding
+        // them would change the bytes their signatures cover, so they cannot be upgraded.
+        if found == 0 {
+            return Err(BlockchainError::ResyncRequired(found));
+        }
+        for version in found..SCHEMA_VERSION {
+            // Each step upgrades stored data from `version` to `version + 1`
+            info!(
+                "Migrating blockchain storage from schema version {version} to {}",
+                version + 1
+            );
+            if version == 1 {
+                // Version 2 keeps spent outputs as empty slots in UTXO entries and records
+                // undo data as the diff each block applied, so rebuild both from the blocks
+                if let Some(tip_hash) = SledBlockStore::new(db.clone()).get_tip()? {
+                    UTXOSet::new(Self::new_with_tip(db.clone(), tip_hash)).reindex();
+                }
+            }
+        }
+        blocks_tree.insert(SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_be_bytes())?;
+        Ok(found)
+    }
+
+    // Refuse to open a store this build cannot read
+    fn open_schema(db: &Db) {
+        if let Err(e) = Self::check_schema_version(db) {
+            panic!("Unable to open blockchain: {e}");
         }
     }
 
     pub fn new_with_tip(db: Db, tip_hash: String) -> Self {
         Blockchain {
             tip_hash: Arc::new(RwLock::new(tip_hash)),
+            store: Arc::new(SledBlockStore::new(db.clone())),
             db,
+            tip_subscribers: Arc::default(),
         }
     }
 
     pub fn new_with_empty_tip(db: Db) -> Self {
+        Self::new_with_tip(db, String::new())
+    }
+
+    /// A chain kept in `store`, starting from the tip it has recorded (empty if none).
+    /// `db` holds the trees other modules keep next to the chain, such as the UTXO set.
+    pub fn new_with_store(db: Db, store: Arc<dyn BlockStore>) -> Self {
+        let tip_hash = store.get_tip().unwrap().unwrap_or_default();
         Blockchain {
-            tip_hash: Arc::new(RwLock::new(String::new())),
+            tip_hash: Arc::new(RwLock::new(tip_hash)),
             db,
+            store,
+            tip_subscribers: Arc::default(),
         }
     }
 
+    /// An empty chain whose blocks live in memory, with a temporary sled database for the
+    /// UTXO set and other side trees. Nothing outlives the returned chain.
+    pub fn new_in_memory() -> Self {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        Self::new_with_store(db, Arc::new(MemoryBlockStore::new()))
+    }
+
+    pub fn get_store(&self) -> &Arc<dyn BlockStore> {
+        &self.store
+    }
+
     pub fn get_db(&self) -> &Db {
         &self.db
     }
 
+    /// Write every buffered change to the chain and its side trees to disk
+    pub fn flush(&self) -> Result<(), BlockchainError> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Flush the chain and drop this handle to its database. The directory lock is released
+    /// once every other clone of the chain has been dropped as well.
+    pub fn close(self) -> Result<(), BlockchainError> {
+        self.flush()
+    }
+
     pub fn get_tip_hash(&self) -> String {
         self.tip_hash.read().unwrap().clone()
     }
@@ -79,70 +219,367 @@ impl Blockchain {
         *tip_hash = new_tip_hash.to_string();
     }
 
-    pub fn get_best_height(&self) -> usize {
-        let block_tree = self.db.open_tree(BLOCKS_TREE).unwrap();
-        let tip_block_bytes = block_tree
-            .get(self.get_tip_hash())
+    /// A receiver getting the new tip hash and height every time `add_block`,
+    /// `add_blocks` or `mine_block` moves the tip
+    pub fn subscribe(&self) -> Receiver<TipUpdate> {
+        let (sender, receiver) = mpsc::channel();
+        self.tip_subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    // Tell every live subscriber about the new tip, forgetting those whose receiver is gone
+    fn notify_tip(&self, hash: &str, height: usize) {
+        let update = TipUpdate {
+            hash: hash.to_string(),
+            height,
+        };
+        self.tip_subscribers
+            .lock()
             .unwrap()
-            .expect("The tip hash is valid");
-        let tip_block = Block::deserialize(tip_block_bytes.as_ref());
-        tip_block.get_height()
+            .retain(|sender| sender.send(update.clone()).is_ok());
     }
 
-    fn update_blocks_tree(blocks_tree: &Tree, block: &Block) {
-        let block_hash = block.get_hash();
-        let _: TransactionResult<(), ()> = blocks_tree.transaction(|tx_db| {
-            let _ = tx_db.insert(block_hash, block.clone());
-            let _ = tx_db.insert(TIP_BLOCK_HASH_KEY, block_hash);
-            Ok(())
-        });
+    /// True while the chain has no blocks at all, not even a genesis block
+    pub fn is_empty(&self) -> bool {
+        self.get_tip_hash().is_empty()
+    }
+
+    pub fn get_best_height(&self) -> usize {
+        self.try_get_best_height().expect("The tip hash is valid")
+    }
+
+    /// Height of the tip block, or an error instead of a panic when it cannot be read
+    pub fn try_get_best_height(&self) -> Result<usize, BlockchainError> {
+        Ok(self.try_get_tip_block()?.get_height())
     }
 
+    fn try_get_tip_block(&self) -> Result<Block, BlockchainError> {
+        let tip_hash = self.get_tip_hash();
+        match self.try_get_block(tip_hash.as_bytes()) {
+            Err(BlockchainError::BlockNotFound(_)) => Err(BlockchainError::TipNotFound),
+            result => result,
+        }
+    }
+
+    fn try_get_block(&self, block_hash: &[u8]) -> Result<Block, BlockchainError> {
+        let block_bytes = self.store.get_block(block_hash)?.ok_or_else(|| {
+            BlockchainError::BlockNotFound(String::from_utf8_lossy(block_hash).to_string())
+        })?;
+        Block::try_deserialize(block_bytes.as_ref())
+            .map_err(|e| BlockchainError::Deserialize(e.to_string()))
+    }
+
+    fn store_tip_block(store: &dyn BlockStore, block: &Block) {
+        let mut batch = StoreBatch::new();
+        batch.insert_block(block.get_hash(), block.serialize());
+        batch.set_height(block.get_height(), block.get_hash());
+        batch.set_tip(block.get_hash());
+        let _ = store.write(&batch);
+    }
+
+    /// Mine a block on the tip from `transactions`, trimmed to the configured block size
+    /// and transaction count limits
     pub fn mine_block(&self, transactions: &[Transaction]) -> Block {
-        for transaction in transactions {
+        self.mine_block_with_limits(
+            transactions,
+            GLOBAL_CONFIG.get_max_block_size(),
+            GLOBAL_CONFIG.get_max_block_txs(),
+        )
+    }
+
+    /// Same as `mine_block`, keeping the block within `max_block_size` serialized bytes and
+    /// `max_block_txs` transactions. Transactions that do not fit are left out.
+    pub fn mine_block_with_limits(
+        &self,
+        transactions: &[Transaction],
+        max_block_size: usize,
+        max_block_txs: usize,
+    ) -> Block {
+        let transactions =
+            self.select_block_transactions(transactions, max_block_size, max_block_txs);
+        for transaction in &transactions {
             if !transaction.verify(self) {
                 panic!("ERROR: Invalid transaction")
             }
         }
         let best_height = self.get_best_height();
 
-        let block = Block::new_block(self.get_tip_hash(), transactions, best_height + 1);
+        let block = Block::new_block_with_target_bits(
+            self.get_tip_hash(),
+            &transactions,
+            best_height + 1,
+            self.get_target_bits(),
+        );
         let block_hash = block.get_hash();
 
-        let blocks_tree = self.db.open_tree(BLOCKS_TREE).unwrap();
-        Self::update_blocks_tree(&blocks_tree, &block);
+        Self::store_tip_block(self.store.as_ref(), &block);
         self.set_tip_hash(block_hash);
+        self.notify_tip(block_hash, block.get_height());
 
         block
     }
 
-    pub fn add_block(&self, block: &Block) {
-        let block_tree = self.db.open_tree(BLOCKS_TREE).unwrap();
-        if block_tree.get(block.get_hash()).unwrap().is_some() {
-            return;
+    /// The transactions of `transactions`, in their original order, that fit in a block on
+    /// the tip of at most `max_block_size` serialized bytes and `max_block_txs` transactions.
+    /// Coinbase transactions are always kept; the rest are taken first come, first served.
+    pub fn select_block_transactions(
+        &self,
+        transactions: &[Transaction],
+        max_block_size: usize,
+        max_block_txs: usize,
+    ) -> Vec<Transaction> {
+        let height = self.try_get_best_height().map_or(0, |height| height + 1);
+        let empty = Block::new_block_without_proof_of_work(self.get_tip_hash(), &[], height);
+        let mut size = empty.serialize().len() + MINED_BLOCK_SIZE_SLACK;
+        let mut count = 0;
+        for tx in transactions.iter().filter(|tx| tx.is_coinbase()) {
+            size += tx.serialize().len();
+            count += 1;
         }
-        let _: TransactionResult<(), ()> = block_tree.transaction(|tx_db| {
-            let _ = tx_db.insert(block.get_hash(), block.serialize()).unwrap();
 
-            let tip_block_bytes = tx_db
-                .get(self.get_tip_hash())
-                .unwrap()
+        let mut selected = vec![false; transactions.len()];
+        for (idx, tx) in transactions.iter().enumerate() {
+            if tx.is_coinbase() {
+                selected[idx] = true;
+                continue;
+            }
+            let tx_size = tx.serialize().len();
+            if count < max_block_txs && size + tx_size <= max_block_size {
+                size += tx_size;
+                count += 1;
+                selected[idx] = true;
+            }
+        }
+        transactions
+            .iter()
+            .zip(selected)
+            .filter(|(_, keep)| *keep)
+            .map(|(tx, _)| tx.clone())
+            .collect()
+    }
+
+    /// Store `block`, moving the tip to it when it makes the chain longer. If it extends a
+    /// branch other than the current tip, returns the blocks leaving and joining the main chain
+    /// so the UTXO set can follow the reorganization. A block whose branch contradicts a
+    /// configured checkpoint, or that the store fails to write, is not stored.
+    pub fn add_block(&self, block: &Block) -> Option<Reorg> {
+        if self.store.get_block(block.get_hash().as_bytes()).unwrap().is_some() {
+            return None;
+        }
+        if !self.is_checkpoint_valid(block.get_height(), block.get_hash()) {
+            error!(
+                "Rejected block {}: contradicts the checkpoint at height {}",
+                block.get_hash(),
+                block.get_height()
+            );
+            return None;
+        }
+        let (becomes_tip, reorg) = if self.is_empty() {
+            (true, None)
+        } else {
+            let tip_block = self
+                .get_block(self.get_tip_hash().as_bytes())
                 .expect("The tip hash is not valid");
-            let tip_block = Block::deserialize(tip_block_bytes.as_ref());
-            if block.get_height() > tip_block.get_height() {
-                let _ = tx_db.insert(TIP_BLOCK_HASH_KEY, block.get_hash()).unwrap();
-                self.set_tip_hash(block.get_hash());
+            let becomes_tip = block.get_height() > tip_block.get_height();
+            let reorg = if becomes_tip && block.get_pre_block_hash() != tip_block.get_hash() {
+                self.find_reorg(block, tip_block)
+            } else {
+                None
+            };
+            // Side-branch blocks stored before a checkpoint was configured cannot join
+            if let Some(reorg) = &reorg
+                && let Some(conflict) = reorg
+                    .get_connected()
+                    .iter()
+                    .find(|b| !self.is_checkpoint_valid(b.get_height(), b.get_hash()))
+            {
+                error!(
+                    "Rejected block {}: its branch contradicts the checkpoint at height {}",
+                    block.get_hash(),
+                    conflict.get_height()
+                );
+                return None;
             }
-            Ok(())
-        });
+            (becomes_tip, reorg)
+        };
+
+        let mut batch = StoreBatch::new();
+        batch.insert_block(block.get_hash(), block.serialize());
+        if becomes_tip {
+            batch.set_height(block.get_height(), block.get_hash());
+        } else {
+            // Blocks synced tip first fill in the heights below the tip
+            batch.fill_height(block.get_height(), block.get_hash());
+        }
+        if let Some(reorg) = &reorg {
+            for connected in reorg.get_connected() {
+                batch.set_height(connected.get_height(), connected.get_hash());
+            }
+        }
+        if becomes_tip {
+            batch.set_tip(block.get_hash());
+        }
+        // The block, its height entries and the tip commit together or not at all, so a
+        // failed write leaves both the store and the in-memory tip where they were
+        if let Err(e) = self.store.write(&batch) {
+            error!("Unable to store block {}: {e}", block.get_hash());
+            return None;
+        }
+        if becomes_tip {
+            self.set_tip_hash(block.get_hash());
+            self.notify_tip(block.get_hash(), block.get_height());
+        }
+        reorg
+    }
+
+    /// Store a contiguous run of blocks extending the current tip (or starting at genesis on
+    /// an empty chain) in a single database transaction, moving the tip once to the last one.
+    /// Nothing is stored if the batch does not link up or contradicts a checkpoint.
+    pub fn add_blocks(&self, blocks: &[Block]) -> Result<(), String> {
+        let Some(last) = blocks.last() else {
+            return Ok(());
+        };
+        let (mut expected_pre_hash, mut expected_height) = if self.is_empty() {
+            (String::from(GENESIS_PRE_BLOCK_HASH), 0)
+        } else {
+            let tip_hash = self.get_tip_hash();
+            let tip_block = self
+                .get_block(tip_hash.as_bytes())
+                .ok_or_else(|| format!("tip block {tip_hash} not found"))?;
+            (tip_hash, tip_block.get_height() + 1)
+        };
+        for block in blocks {
+            if block.get_pre_block_hash() != expected_pre_hash {
+                return Err(format!(
+                    "block {} does not follow {expected_pre_hash}",
+                    block.get_hash()
+                ));
+            }
+            if block.get_height() != expected_height {
+                return Err(format!(
+                    "block {} has height {} instead of {expected_height}",
+                    block.get_hash(),
+                    block.get_height()
+                ));
+            }
+            if !self.is_checkpoint_valid(block.get_height(), block.get_hash()) {
+                return Err(format!(
+                    "block {} contradicts the checkpoint at height {}",
+                    block.get_hash(),
+                    block.get_height()
+                ));
+            }
+            expected_pre_hash = block.get_hash().to_string();
+            expected_height += 1;
+        }
+
+        let mut batch = StoreBatch::new();
+        for block in blocks {
+            batch.insert_block(block.get_hash(), block.serialize());
+            batch.set_height(block.get_height(), block.get_hash());
+        }
+        batch.set_tip(last.get_hash());
+        self.store
+            .write(&batch)
+            .map_err(|e| format!("unable to store blocks: {e}"))?;
+        self.set_tip_hash(last.get_hash());
+        self.notify_tip(last.get_hash(), last.get_height());
+        Ok(())
+    }
+
+    /// Walk `block`'s branch back to where it joins the main chain. Returns None if the
+    /// branch has a missing ancestor, in which case no reorganization can be computed.
+    fn find_reorg(&self, block: &Block, tip_block: Block) -> Option<Reorg> {
+        let mut connected = vec![block.clone()];
+        let mut parent = self.get_block(block.get_pre_block_hash().as_bytes())?;
+        while !self.is_on_main_chain(&parent) {
+            let grandparent = self.get_block(parent.get_pre_block_hash().as_bytes())?;
+            connected.push(parent);
+            parent = grandparent;
+        }
+        connected.reverse();
+
+        let mut disconnected = vec![];
+        let mut current = tip_block;
+        while current.get_hash() != parent.get_hash() {
+            let previous = self.get_block(current.get_pre_block_hash().as_bytes())?;
+            disconnected.push(current);
+            current = previous;
+        }
+        Some(Reorg {
+            disconnected,
+            connected,
+        })
+    }
+
+    /// False only if a checkpoint is configured at `height` and names a hash other than `hash`
+    pub fn is_checkpoint_valid(&self, height: usize, hash: &str) -> bool {
+        GLOBAL_CONFIG
+            .get_checkpoints()
+            .get(&height)
+            .is_none_or(|expected| expected == hash)
+    }
+
+    fn is_on_main_chain(&self, block: &Block) -> bool {
+        self.get_block_by_height(block.get_height())
+            .is_some_and(|main| main.get_hash() == block.get_hash())
     }
 
     pub fn iterator(&self) -> BlockchainIterator {
-        BlockchainIterator::new(self.db.clone(), self.get_tip_hash())
+        BlockchainIterator::new_with_store(self.store.clone(), self.get_tip_hash())
+    }
+
+    /// Iterate the main chain from genesis up to the tip, in increasing height order
+    pub fn forward_iterator(&self) -> ForwardBlockchainIterator {
+        ForwardBlockchainIterator::new_with_store(self.store.clone(), self.get_tip_hash())
+    }
+
+    /// Like `iterator`, but fails up front if the tip block cannot be read instead of
+    /// silently yielding nothing
+    pub fn try_iterator(&self) -> Result<BlockchainIterator, BlockchainError> {
+        self.try_get_tip_block()?;
+        Ok(self.iterator())
     }
 
-    pub fn find_utxo(&self) -> HashMap<String, Vec<TXOutput>> {
-        let mut utxo: HashMap<String, Vec<TXOutput>> = HashMap::new();
+    /// Block and transaction counts, coinbase issuance and circulating supply of the main
+    /// chain, gathered in a single pass from the tip back to genesis
+    pub fn stats(&self) -> ChainStats {
+        let mut stats = ChainStats {
+            block_count: 0,
+            transaction_count: 0,
+            coinbase_issuance: 0,
+            circulating_supply: 0,
+        };
+        // Outputs are only spent by later transactions, so walking back from the tip sees
+        // every spend of an output before the output itself
+        let mut spent: HashSet<(Vec<u8>, usize)> = HashSet::new();
+        for block in self.iterator() {
+            stats.block_count += 1;
+            stats.transaction_count += block.get_transactions().len();
+            for tx in block.get_transactions().iter().filter(|tx| !tx.is_coinbase()) {
+                for vin in tx.get_vin() {
+                    spent.insert((vin.get_txid().to_vec(), vin.get_vout()));
+                }
+            }
+            for tx in block.get_transactions() {
+                for (idx, out) in tx.get_vout().iter().enumerate() {
+                    let value = out.get_value() as i64;
+                    if tx.is_coinbase() {
+                        stats.coinbase_issuance += value;
+                    }
+                    if !out.is_burn() && !spent.contains(&(tx.get_id().to_vec(), idx)) {
+                        stats.circulating_supply += value;
+                    }
+                }
+            }
+        }
+        stats
+    }
+
+    /// Unspent outputs of every main-chain transaction with any left, keyed by txid hex. Each
+    /// output sits at its vout position, with None for the spent ones.
+    pub fn find_utxo(&self) -> HashMap<String, Vec<Option<TXOutput>>> {
+        let mut utxo: HashMap<String, Vec<Option<TXOutput>>> = HashMap::new();
         let mut spent_txos: HashMap<String, Vec<usize>> = HashMap::new();
 
         let mut iterator = self.iterator();
@@ -152,21 +589,22 @@ impl Blockchain {
                 break;
             }
             let block = option.unwrap();
-            'outer: for tx in block.get_transactions() {
+            for tx in block.get_transactions() {
                 let txid_hex = HEXLOWER.encode(tx.get_id());
-                for (idx, out) in tx.get_vout().iter().enumerate() {
-                    if let Some(outs) = spent_txos.get(txid_hex.as_str()) {
-                        for spend_out_idx in outs {
-                            if idx.eq(spend_out_idx) {
-                                continue 'outer;
-                            }
-                        }
-                    }
-                    if utxo.contains_key(txid_hex.as_str()) {
-                        utxo.get_mut(txid_hex.as_str()).unwrap().push(out.clone());
-                    } else {
-                        utxo.insert(txid_hex.clone(), vec![out.clone()]);
-                    }
+                // Blank only the spent outputs; the rest stay unspent at their positions and
+                // the transaction's own inputs must still be recorded as spends
+                let spent = spent_txos.get(txid_hex.as_str());
+                let outs: Vec<Option<TXOutput>> = tx
+                    .get_vout()
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, out)| {
+                        let is_spent = spent.is_some_and(|outs| outs.contains(&idx));
+                        (!is_spent).then(|| out.clone())
+                    })
+                    .collect();
+                if outs.iter().any(Option::is_some) {
+                    utxo.insert(txid_hex.clone(), outs);
                 }
                 if tx.is_coinbase() {
                     continue;
@@ -188,6 +626,7 @@ impl Blockchain {
         utxo
     }
 
+    /// Find the transaction with id `txid` in any block of the current chain
     pub fn find_transaction(&self, txid: &[u8]) -> Option<Transaction> {
         let mut iterator = self.iterator();
         loop {
@@ -205,13 +644,385 @@ impl Blockchain {
         None
     }
 
+    /// Height of the main-chain block containing the transaction with id `txid`
+    pub fn find_transaction_height(&self, txid: &[u8]) -> Option<usize> {
+        self.iterator()
+            .find(|block| block.get_transactions().iter().any(|tx| txid.eq(tx.get_id())))
+            .map(|block| block.get_height())
+    }
+
+    /// How many blocks, its own included, confirm the transaction with id `txid`: one in
+    /// the tip block, more the deeper it is. None if it is not on the main chain.
+    pub fn get_confirmations(&self, txid: &[u8]) -> Option<usize> {
+        let height = self.find_transaction_height(txid)?;
+        let best_height = self.try_get_best_height().ok()?;
+        Some(best_height - height + 1)
+    }
+
+    /// Check that `block` re-includes no transaction already confirmed by one of its
+    /// ancestors, walking back from its parent rather than the tip so blocks on a side
+    /// branch are checked against their own history
+    pub fn validate_not_confirmed(&self, block: &Block) -> Result<(), String> {
+        let txids: HashSet<&[u8]> = block
+            .get_transactions()
+            .iter()
+            .map(|tx| tx.get_id())
+            .collect();
+        let ancestors = BlockchainIterator::new_with_store(
+            self.store.clone(),
+            block.get_pre_block_hash().to_string(),
+        );
+        for ancestor in ancestors {
+            if let Some(tx) = ancestor
+                .get_transactions()
+                .iter()
+                .find(|tx| txids.contains(tx.get_id()))
+            {
+                return Err(format!(
+                    "block {} re-includes transaction {} confirmed in block {}",
+                    block.get_hash(),
+                    HEXLOWER.encode(tx.get_id()),
+                    ancestor.get_hash()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up a stored block by hash. Entries that do not decode as a block, such as the tip
+    /// pointer stored alongside the blocks, yield None instead of panicking.
     pub fn get_block(&self, block_hash: &[u8]) -> Option<Block> {
-        let block_tree = self.db.open_tree(BLOCKS_TREE).unwrap();
-        if let Some(block_bytes) = block_tree.get(block_hash).unwrap() {
-            let block = Block::deserialize(block_bytes.as_ref());
+        if block_hash.eq(TIP_BLOCK_HASH_KEY.as_bytes()) {
+            return None;
+        }
+        let block_bytes = self.store.get_block(block_hash).ok()??;
+        match Block::try_deserialize(block_bytes.as_ref()) {
+            Ok(block) => Some(block),
+            Err(e) => {
+                error!(
+                    "Stored block {} is corrupt: {e}",
+                    String::from_utf8_lossy(block_hash)
+                );
+                None
+            }
+        }
+    }
+
+    /// Find the block at `height` on the current chain. Uses the height index, falling back
+    /// to walking back from the tip for chains stored before the index existed.
+    pub fn get_block_by_height(&self, height: usize) -> Option<Block> {
+        let tip = self.get_block(self.get_tip_hash().as_bytes())?;
+        if height > tip.get_height() {
+            return None;
+        }
+        if let Some(hash) = self.get_hash_at_height(height)
+            && let Some(block) = self.get_block(hash.as_bytes())
+        {
             return Some(block);
         }
-        None
+        self.iterator().find(|block| block.get_height() == height)
+    }
+
+    /// Hash of the main-chain block at `height`, from the height index
+    pub fn get_hash_at_height(&self, height: usize) -> Option<String> {
+        self.store.get_hash_at_height(height).unwrap()
+    }
+
+    /// Rebuild the height index from the blocks reachable from the current tip
+    pub fn reindex_heights(&self) {
+        self.store.clear_heights().unwrap();
+        let mut batch = StoreBatch::new();
+        for block in self.iterator() {
+            batch.set_height(block.get_height(), block.get_hash());
+        }
+        self.store.write(&batch).unwrap();
+    }
+
+    /// Check the chain at its difficulty
+    pub fn verify_chain(&self) -> bool {
+        self.verify_chain_with_target_bits(self.get_target_bits())
+    }
+
+    /// Check that the chain links from the tip back to a genesis block: every block
+    /// points at its stored parent, heights drop by one per step and end at 0, and every
+    /// block above genesis satisfies `target_bits` of proof-of-work.
+    pub fn verify_chain_with_target_bits(&self, target_bits: usize) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let mut current = match self.get_block(self.get_tip_hash().as_bytes()) {
+            Some(block) => block,
+            None => return false,
+        };
+        while current.get_height() > 0 {
+            if !ProofOfWork::new_proof_of_work(current.clone(), target_bits).validate() {
+                return false;
+            }
+            let parent = match self.get_block(current.get_pre_block_hash().as_bytes()) {
+                Some(parent) => parent,
+                None => return false,
+            };
+            if parent.get_height() + 1 != current.get_height() {
+                return false;
+            }
+            current = parent;
+        }
+        current.get_pre_block_hash() == GENESIS_PRE_BLOCK_HASH
+    }
+
+    /// Verify every transaction in `block`, spreading them over one thread per available core.
+    /// Returns false if any transaction is malformed, spends an unknown transaction or carries
+    /// a bad signature.
+    pub fn verify_block_transactions(&self, block: &Block) -> bool {
+        let transactions = block.get_transactions();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = transactions.len().div_ceil(threads).max(1);
+        thread::scope(|scope| {
+            let handles: Vec<_> = transactions
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || chunk.iter().all(|tx| self.verify_block_transaction(tx)))
+                })
+                .collect();
+            // Chunks not joined after a failure are joined when the scope ends
+            handles
+                .into_iter()
+                .all(|handle| handle.join().unwrap_or(false))
+        })
+    }
+
+    /// Verify the transactions in `block` one after another on the calling thread
+    pub fn verify_block_transactions_sequential(&self, block: &Block) -> bool {
+        block
+            .get_transactions()
+            .iter()
+            .all(|tx| self.verify_block_transaction(tx))
+    }
+
+    fn verify_block_transaction(&self, tx: &Transaction) -> bool {
+        if tx.validate_structure().is_err() {
+            return false;
+        }
+        if tx.is_coinbase() {
+            return true;
+        }
+        // Transaction::verify panics on an unknown previous transaction
+        tx.get_vin()
+            .iter()
+            .all(|vin| self.find_transaction(vin.get_txid()).is_some())
+            && tx.verify(self)
+    }
+
+    /// Inputs minus outputs of `tx`, looking the transactions it spends up among
+    /// `block_txs` first and then on the chain. A coinbase pays no fee.
+    pub fn transaction_fee(
+        &self,
+        tx: &Transaction,
+        block_txs: &[Transaction],
+    ) -> Result<i64, String> {
+        if tx.is_coinbase() {
+            return Ok(0);
+        }
+        let txid_hex = HEXLOWER.encode(tx.get_id());
+        let mut total_input: i64 = 0;
+        for vin in tx.get_vin() {
+            let prev_tx = block_txs
+                .iter()
+                .find(|candidate| candidate.get_id() == vin.get_txid())
+                .cloned()
+                .or_else(|| self.find_transaction(vin.get_txid()));
+            let out = prev_tx
+                .as_ref()
+                .and_then(|prev_tx| prev_tx.get_vout().get(vin.get_vout()))
+                .ok_or_else(|| {
+                    format!(
+                        "transaction {txid_hex} spends unknown output {}:{}",
+                        HEXLOWER.encode(vin.get_txid()),
+                        vin.get_vout()
+                    )
+                })?;
+            total_input += out.get_value() as i64;
+        }
+        let total_output: i64 = tx.get_vout().iter().map(|out| out.get_value() as i64).sum();
+        if total_output > total_input {
+            return Err(format!(
+                "transaction {txid_hex} outputs {total_output} exceed inputs {total_input}"
+            ));
+        }
+        Ok(total_input - total_output)
+    }
+
+    /// Sum of the fees paid by the transactions in `block`
+    pub fn block_fees(&self, block: &Block) -> Result<i64, String> {
+        block
+            .get_transactions()
+            .iter()
+            .map(|tx| self.transaction_fee(tx, block.get_transactions()))
+            .sum()
+    }
+
+    /// Check that the coinbase of `block` claims no more than the subsidy plus the fees
+    /// of its other transactions. The genesis reward is set by the chain's parameters
+    /// instead, so a genesis block always passes.
+    pub fn validate_coinbase_value(&self, block: &Block) -> Result<(), String> {
+        if block.get_height() == 0 {
+            return Ok(());
+        }
+        let claimed: i64 = block
+            .get_transactions()
+            .iter()
+            .filter(|tx| tx.is_coinbase())
+            .flat_map(|tx| tx.get_vout())
+            .map(|out| out.get_value() as i64)
+            .sum();
+        let fees = self.block_fees(block)?;
+        if claimed > SUBSIDY as i64 + fees {
+            return Err(format!(
+                "block {} coinbase claims {claimed}, more than subsidy {SUBSIDY} plus fees {fees}",
+                block.get_hash()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fully validate the chain from the tip back to genesis at its difficulty
+    pub fn validate_chain(&self) -> Result<(), String> {
+        self.validate_chain_with_target_bits(self.get_target_bits())
+    }
+
+    /// Walk every block from the tip back to genesis, checking that each mined block's hash
+    /// matches its contents and satisfies `target_bits` of proof-of-work, that each block has
+    /// exactly one coinbase, first, claiming no more than the subsidy plus fees, and no
+    /// transaction twice, that blocks link to their parents at contiguous heights and that
+    /// every non-coinbase transaction verifies. Returns the first problem found.
+    pub fn validate_chain_with_target_bits(&self, target_bits: usize) -> Result<(), String> {
+        self.walk_chain(target_bits, true)
+    }
+
+    /// Validate the chain at its difficulty without looking inside transactions
+    pub fn validate_chain_structure(&self) -> Result<(), String> {
+        self.validate_chain_structure_with_target_bits(self.get_target_bits())
+    }
+
+    /// Like `validate_chain_with_target_bits`, checking only hashes, proof-of-work and
+    /// linkage, so it still passes once `prune_below` has discarded spent transactions
+    pub fn validate_chain_structure_with_target_bits(
+        &self,
+        target_bits: usize,
+    ) -> Result<(), String> {
+        self.walk_chain(target_bits, false)
+    }
+
+    // Shared walk of the validate_chain variants; `check_transactions` adds the coinbase
+    // rules and transaction verification
+    fn walk_chain(&self, target_bits: usize, check_transactions: bool) -> Result<(), String> {
+        if self.is_empty() {
+            return Err(String::from("the chain is empty"));
+        }
+        let tip_hash = self.get_tip_hash();
+        let mut current = self
+            .get_block(tip_hash.as_bytes())
+            .ok_or_else(|| format!("tip block {tip_hash} not found"))?;
+        loop {
+            let hash = current.get_hash();
+            // The genesis block is created without proof-of-work
+            if current.get_height() > 0 {
+                let pow = ProofOfWork::new_proof_of_work(current.clone(), target_bits);
+                let digest = util::sha256_digest(&pow.prepare_data(current.get_nonce()));
+                if HEXLOWER.encode(&digest) != hash {
+                    return Err(format!("block {hash} does not match its contents"));
+                }
+                if !pow.validate() {
+                    return Err(format!("block {hash} fails proof-of-work"));
+                }
+            }
+            if check_transactions {
+                self.validate_block_transactions(&current)?;
+            }
+
+            if current.get_height() == 0 {
+                if current.get_pre_block_hash() != GENESIS_PRE_BLOCK_HASH {
+                    return Err(format!("genesis block {hash} has a parent"));
+                }
+                return Ok(());
+            }
+            let pre_hash = current.get_pre_block_hash();
+            let parent = self
+                .get_block(pre_hash.as_bytes())
+                .ok_or_else(|| format!("parent {pre_hash} of block {hash} not found"))?;
+            if parent.get_hash() != pre_hash {
+                return Err(format!("block {hash} does not link to parent {pre_hash}"));
+            }
+            if parent.get_height() + 1 != current.get_height() {
+                return Err(format!(
+                    "block {hash} at height {} follows a block at height {}",
+                    current.get_height(),
+                    parent.get_height()
+                ));
+            }
+            current = parent;
+        }
+    }
+
+    // The coinbase rules of `block` and the verification of each of its other transactions
+    fn validate_block_transactions(&self, block: &Block) -> Result<(), String> {
+        let hash = block.get_hash();
+        block.validate_coinbase()?;
+        block.validate_unique_txids()?;
+        for tx in block.get_transactions() {
+            if tx.is_coinbase() {
+                continue;
+            }
+            let txid_hex = HEXLOWER.encode(tx.get_id());
+            for vin in tx.get_vin() {
+                if self.find_transaction(vin.get_txid()).is_none() {
+                    return Err(format!(
+                        "transaction {txid_hex} in block {hash} spends unknown transaction {}",
+                        HEXLOWER.encode(vin.get_txid())
+                    ));
+                }
+            }
+            if !tx.verify(self) {
+                return Err(format!(
+                    "transaction {txid_hex} in block {hash} fails to verify"
+                ));
+            }
+        }
+        self.validate_coinbase_value(block)
+    }
+
+    /// Replace every transaction of a main-chain block below `height` whose outputs are all
+    /// spent with its `Transaction::pruned` placeholder. Block hashes and merkle roots are
+    /// unchanged; a transaction with an output still in the UTXO set is always kept.
+    /// Returns how many transactions were pruned.
+    pub fn prune_below(&self, height: usize) -> Result<usize, BlockchainError> {
+        let mut spent: HashSet<(Vec<u8>, usize)> = HashSet::new();
+        for block in self.iterator() {
+            for tx in block.get_transactions().iter().filter(|tx| !tx.is_coinbase()) {
+                for vin in tx.get_vin() {
+                    spent.insert((vin.get_txid().to_vec(), vin.get_vout()));
+                }
+            }
+        }
+        let utxo_tree = self
+            .db
+            .open_tree(UTXO_TREE)
+            .map_err(|e| BlockchainError::DbError(e.to_string()))?;
+
+        let mut pruned = 0;
+        let mut batch = StoreBatch::new();
+        for mut block in self.iterator().filter(|block| block.get_height() < height) {
+            let block_pruned = block.prune_transactions(|tx| {
+                (0..tx.get_vout().len()).all(|idx| spent.contains(&(tx.get_id().to_vec(), idx)))
+                    && !utxo_tree.contains_key(tx.get_id()).unwrap_or(true)
+            });
+            if block_pruned > 0 {
+                pruned += block_pruned;
+                batch.insert_block(block.get_hash(), block.serialize());
+            }
+        }
+        self.store.write(&batch)?;
+        Ok(pruned)
     }
 
     pub fn get_block_hashes(&self) -> Vec<Vec<u8>> {
@@ -227,4 +1038,154 @@ impl Blockchain {
         }
         blocks
     }
+
+    /// Hashes of up to `limit` main-chain blocks after `start_hash`, oldest first. Empty if
+    /// `start_hash` is unknown or not on the main chain.
+    pub fn get_block_hashes_from(&self, start_hash: &str, limit: usize) -> Vec<String> {
+        let Some(start) = self.get_block(start_hash.as_bytes()) else {
+            return vec![];
+        };
+        if !self.is_on_main_chain(&start) {
+            return vec![];
+        }
+        let mut hashes = vec![];
+        let mut height = start.get_height() + 1;
+        while hashes.len() < limit {
+            match self.get_block_by_height(height) {
+                Some(block) => hashes.push(block.get_hash().to_string()),
+                None => break,
+            }
+            height += 1;
+        }
+        hashes
+    }
+
+    /// Main-chain hashes from the tip back to genesis, one height apart for the first
+    /// `LOCATOR_DENSE_LEN` and doubling the gap after that. A peer finds the newest of them
+    /// it also has to work out where the two chains fork. Empty for an empty chain.
+    pub fn block_locator(&self) -> Vec<String> {
+        let Some(tip) = self.get_block(self.get_tip_hash().as_bytes()) else {
+            return vec![];
+        };
+        let mut locator = vec![];
+        let mut height = tip.get_height();
+        let mut step = 1;
+        loop {
+            if let Some(block) = self.get_block_by_height(height) {
+                locator.push(block.get_hash().to_string());
+            }
+            if height == 0 {
+                break;
+            }
+            if locator.len() >= LOCATOR_DENSE_LEN {
+                step *= 2;
+            }
+            height = height.saturating_sub(step);
+        }
+        locator
+    }
+
+    /// Hashes of the main-chain blocks above the newest `locator` entry on the main chain,
+    /// newest first like `get_block_hashes`. Every block hash if none of them is.
+    pub fn get_block_hashes_after_locator(&self, locator: &[String]) -> Vec<Vec<u8>> {
+        let fork_point = locator.iter().find_map(|hash| {
+            self.get_block(hash.as_bytes())
+                .filter(|block| self.is_on_main_chain(block))
+        });
+        let Some(fork_point) = fork_point else {
+            return self.get_block_hashes();
+        };
+        self.iterator()
+            .take_while(|block| block.get_height() > fork_point.get_height())
+            .map(|block| block.get_hash_bytes())
+            .collect()
+    }
+
+    /// Up to `n` main-chain blocks ending at the tip, newest first, stopping at genesis
+    pub fn get_last_n_blocks(&self, n: usize) -> Vec<Block> {
+        self.iterator().take(n).collect()
+    }
+
+    /// Hashes of the stored blocks not reachable from the tip, such as side branches left
+    /// behind by a reorg, ordered by height then hash
+    pub fn find_orphan_blocks(&self) -> Vec<String> {
+        let main_hashes: HashSet<String> = self
+            .iterator()
+            .map(|block| block.get_hash().to_string())
+            .collect();
+        let mut orphans: Vec<Block> = self
+            .store
+            .get_all_blocks()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|bytes| Block::try_deserialize(bytes).ok())
+            .filter(|block| !main_hashes.contains(block.get_hash()))
+            .collect();
+        orphans.sort_by_key(|block| (block.get_height(), block.get_hash().to_string()));
+        orphans
+            .iter()
+            .map(|block| block.get_hash().to_string())
+            .collect()
+    }
+
+    /// Delete every block `find_orphan_blocks` reports. Returns how many were deleted.
+    pub fn purge_orphans(&self) -> Result<usize, BlockchainError> {
+        let orphans = self.find_orphan_blocks();
+        let mut batch = StoreBatch::new();
+        for hash in &orphans {
+            batch.remove_block(hash);
+        }
+        self.store.write(&batch)?;
+        Ok(orphans.len())
+    }
+
+    /// Render the stored blocks as a Graphviz DOT digraph, one node per block with an edge to
+    /// its parent. Main-chain blocks are drawn bold; blocks on side branches are dashed.
+    pub fn export_dot(&self) -> String {
+        let main_chain: Vec<Block> = self.iterator().collect();
+        let main_hashes: HashSet<&str> = main_chain.iter().map(|block| block.get_hash()).collect();
+
+        let mut side_blocks: Vec<Block> = self
+            .store
+            .get_all_blocks()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|bytes| Block::try_deserialize(bytes).ok())
+            .filter(|block| !main_hashes.contains(block.get_hash()))
+            .collect();
+        side_blocks.sort_by_key(|block| (block.get_height(), block.get_hash().to_string()));
+
+        let mut dot =
+            String::from("digraph blockchain {\n    rankdir=RL;\n    node [shape=box];\n");
+        for (blocks, style) in [(&main_chain, "bold"), (&side_blocks, "dashed")] {
+            for block in blocks {
+                let short_hash: String =
+                    block.get_hash().chars().take(DOT_SHORT_HASH_LEN).collect();
+                dot.push_str(
+                    format!(
+                        "    \"{}\" [label=\"{short_hash}\\nheight {}\\n{} txs\", style={style}];\n",
+                        block.get_hash(),
+                        block.get_height(),
+                        block.get_transactions().len()
+                    )
+                    .as_str(),
+                );
+            }
+        }
+        for block in main_chain.iter().chain(side_blocks.iter()) {
+            if block.get_pre_block_hash() == GENESIS_PRE_BLOCK_HASH {
+                continue;
+            }
+            dot.push_str(
+                format!(
+                    "    \"{}\" -> \"{}\";\n",
+                    block.get_hash(),
+                    block.get_pre_block_hash()
+                )
+                .as_str(),
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
 }
@@ -1,10 +1,23 @@
+use std::sync::Arc;
+
 use sled::Db;
 
-use crate::{BLOCKS_TREE, Block, blockchain::BlockchainIterator};
+use crate::{
+    Block,
+    blockchain::{BlockStore, BlockchainIterator, SledBlockStore},
+};
 
 impl BlockchainIterator {
     pub fn new(db: Db, current_hash: String) -> Self {
-        Self { db, current_hash }
+        Self::new_with_store(Arc::new(SledBlockStore::new(db)), current_hash)
+    }
+
+    /// Iterate the blocks in `store` from `current_hash` back to genesis
+    pub fn new_with_store(store: Arc<dyn BlockStore>, current_hash: String) -> Self {
+        Self {
+            store,
+            current_hash,
+        }
     }
 }
 
@@ -17,8 +30,7 @@ impl Iterator for BlockchainIterator {
             return None;
         }
         
-        let block_tree = self.db.open_tree(BLOCKS_TREE).ok()?;
-        let data = block_tree.get(&self.current_hash).ok()??;
+        let data = self.store.get_block(self.current_hash.as_bytes()).ok()??;
         let block = Block::try_deserialize(&data).ok()?;
         
         // Update current_hash to the previous block's hash for next iteration
@@ -0,0 +1,36 @@
+use crate::{SUBSIDY, blockchain::ChainParams, config::GLOBAL_CONFIG};
+
+impl ChainParams {
+    /// Parameters paying the genesis reward to `genesis_address`, otherwise matching the
+    /// chain `Blockchain::create_blockchain` creates: a `SUBSIDY` genesis reward and the
+    /// configured difficulty
+    pub fn new(genesis_address: &str) -> ChainParams {
+        ChainParams {
+            genesis_reward: SUBSIDY,
+            genesis_address: String::from(genesis_address),
+            target_bits: GLOBAL_CONFIG.get_target_bits(),
+        }
+    }
+
+    pub fn with_genesis_reward(mut self, genesis_reward: i32) -> ChainParams {
+        self.genesis_reward = genesis_reward;
+        self
+    }
+
+    pub fn with_target_bits(mut self, target_bits: usize) -> ChainParams {
+        self.target_bits = target_bits;
+        self
+    }
+
+    pub fn get_genesis_reward(&self) -> i32 {
+        self.genesis_reward
+    }
+
+    pub fn get_genesis_address(&self) -> &str {
+        self.genesis_address.as_str()
+    }
+
+    pub fn get_target_bits(&self) -> usize {
+        self.target_bits
+    }
+}
@@ -0,0 +1,19 @@
+use crate::blockchain::ChainStats;
+
+impl ChainStats {
+    pub fn get_block_count(&self) -> usize {
+        self.block_count
+    }
+
+    pub fn get_transaction_count(&self) -> usize {
+        self.transaction_count
+    }
+
+    pub fn get_coinbase_issuance(&self) -> i64 {
+        self.coinbase_issuance
+    }
+
+    pub fn get_circulating_supply(&self) -> i64 {
+        self.circulating_supply
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use crate::{
+    Block,
+    blockchain::{BlockStore, BlockchainIterator, ForwardBlockchainIterator},
+};
+
+impl ForwardBlockchainIterator {
+    /// Iterate the blocks in `store` from genesis up to `tip_hash`. The hashes along the
+    /// way are collected up front; the blocks themselves are read one at a time.
+    pub fn new_with_store(store: Arc<dyn BlockStore>, tip_hash: String) -> Self {
+        let pending_hashes = BlockchainIterator::new_with_store(store.clone(), tip_hash)
+            .map(|block| block.get_hash().to_string())
+            .collect();
+        Self {
+            store,
+            pending_hashes,
+        }
+    }
+}
+
+impl Iterator for ForwardBlockchainIterator {
+    type Item = Block;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hash = self.pending_hashes.pop()?;
+        let data = self.store.get_block(hash.as_bytes()).ok()??;
+        Block::try_deserialize(&data).ok()
+    }
+}
@@ -0,0 +1,57 @@
+use crate::blockchain::{BlockStore, BlockchainError, MemoryBlockStore, StoreBatch};
+
+impl MemoryBlockStore {
+    pub fn new() -> MemoryBlockStore {
+        MemoryBlockStore::default()
+    }
+}
+
+impl BlockStore for MemoryBlockStore {
+    fn get_block(&self, block_hash: &[u8]) -> Result<Option<Vec<u8>>, BlockchainError> {
+        Ok(self.inner.read().unwrap().blocks.get(block_hash).cloned())
+    }
+
+    fn get_all_blocks(&self) -> Result<Vec<Vec<u8>>, BlockchainError> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .blocks
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    fn get_tip(&self) -> Result<Option<String>, BlockchainError> {
+        Ok(self.inner.read().unwrap().tip.clone())
+    }
+
+    fn get_hash_at_height(&self, height: usize) -> Result<Option<String>, BlockchainError> {
+        Ok(self.inner.read().unwrap().heights.get(&height).cloned())
+    }
+
+    fn write(&self, batch: &StoreBatch) -> Result<(), BlockchainError> {
+        // Holding the write lock for the whole batch keeps it atomic to readers
+        let mut inner = self.inner.write().unwrap();
+        for (hash, bytes) in batch.get_blocks() {
+            inner.blocks.insert(hash.as_bytes().to_vec(), bytes.clone());
+        }
+        for hash in batch.get_removed_blocks() {
+            inner.blocks.remove(hash.as_bytes());
+        }
+        for (height, hash, overwrite) in batch.get_heights() {
+            if *overwrite || !inner.heights.contains_key(height) {
+                inner.heights.insert(*height, hash.clone());
+            }
+        }
+        if let Some(tip) = batch.get_tip() {
+            inner.tip = Some(tip.to_string());
+        }
+        Ok(())
+    }
+
+    fn clear_heights(&self) -> Result<(), BlockchainError> {
+        self.inner.write().unwrap().heights.clear();
+        Ok(())
+    }
+}
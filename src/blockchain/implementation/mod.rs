@@ -1,2 +1,11 @@
+pub mod blockchain_error_impl;
 pub mod blockchain_impl;
 pub mod blockchain_iterator_impl;
+pub mod reorg_impl;
+pub mod memory_block_store_impl;
+pub mod sled_block_store_impl;
+pub mod store_batch_impl;
+pub mod forward_blockchain_iterator_impl;
+pub mod chain_stats_impl;
+pub mod chain_params_impl;
+pub mod tip_update_impl;
@@ -0,0 +1,11 @@
+use crate::{Block, blockchain::Reorg};
+
+impl Reorg {
+    pub fn get_disconnected(&self) -> &[Block] {
+        self.disconnected.as_slice()
+    }
+
+    pub fn get_connected(&self) -> &[Block] {
+        self.connected.as_slice()
+    }
+}
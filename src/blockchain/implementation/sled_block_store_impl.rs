@@ -0,0 +1,85 @@
+use sled::{
+    Db, Transactional,
+    transaction::{ConflictableTransactionError, TransactionError},
+};
+
+use crate::blockchain::{
+    BLOCKS_TREE, BlockStore, BlockchainError, HEIGHT_INDEX_TREE, SCHEMA_VERSION_KEY,
+    SledBlockStore, StoreBatch, TIP_BLOCK_HASH_KEY,
+};
+
+impl SledBlockStore {
+    pub fn new(db: Db) -> SledBlockStore {
+        SledBlockStore { db }
+    }
+
+    fn height_key(height: usize) -> [u8; 8] {
+        (height as u64).to_be_bytes()
+    }
+}
+
+impl BlockStore for SledBlockStore {
+    fn get_block(&self, block_hash: &[u8]) -> Result<Option<Vec<u8>>, BlockchainError> {
+        let blocks_tree = self.db.open_tree(BLOCKS_TREE)?;
+        Ok(blocks_tree.get(block_hash)?.map(|bytes| bytes.to_vec()))
+    }
+
+    fn get_all_blocks(&self) -> Result<Vec<Vec<u8>>, BlockchainError> {
+        let blocks_tree = self.db.open_tree(BLOCKS_TREE)?;
+        let mut blocks = vec![];
+        for item in blocks_tree.iter() {
+            let (key, value) = item?;
+            // The tip pointer and schema version are stored alongside the blocks
+            if key.as_ref() != TIP_BLOCK_HASH_KEY.as_bytes()
+                && key.as_ref() != SCHEMA_VERSION_KEY.as_bytes()
+            {
+                blocks.push(value.to_vec());
+            }
+        }
+        Ok(blocks)
+    }
+
+    fn get_tip(&self) -> Result<Option<String>, BlockchainError> {
+        let blocks_tree = self.db.open_tree(BLOCKS_TREE)?;
+        Ok(blocks_tree
+            .get(TIP_BLOCK_HASH_KEY)?
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string()))
+    }
+
+    fn get_hash_at_height(&self, height: usize) -> Result<Option<String>, BlockchainError> {
+        let height_tree = self.db.open_tree(HEIGHT_INDEX_TREE)?;
+        Ok(height_tree
+            .get(Self::height_key(height))?
+            .map(|hash| String::from_utf8_lossy(&hash).to_string()))
+    }
+
+    fn write(&self, batch: &StoreBatch) -> Result<(), BlockchainError> {
+        let blocks_tree = self.db.open_tree(BLOCKS_TREE)?;
+        let height_tree = self.db.open_tree(HEIGHT_INDEX_TREE)?;
+        (&blocks_tree, &height_tree)
+            .transaction(|(tx_blocks, tx_heights)| {
+                for (hash, bytes) in batch.get_blocks() {
+                    tx_blocks.insert(hash.as_bytes(), bytes.as_slice())?;
+                }
+                for hash in batch.get_removed_blocks() {
+                    tx_blocks.remove(hash.as_bytes())?;
+                }
+                for (height, hash, overwrite) in batch.get_heights() {
+                    let key = Self::height_key(*height);
+                    if *overwrite || tx_heights.get(key)?.is_none() {
+                        tx_heights.insert(&key, hash.as_bytes())?;
+                    }
+                }
+                if let Some(tip) = batch.get_tip() {
+                    tx_blocks.insert(TIP_BLOCK_HASH_KEY, tip.as_bytes())?;
+                }
+                Ok::<(), ConflictableTransactionError<()>>(())
+            })
+            .map_err(|e: TransactionError<()>| BlockchainError::DbError(format!("{e:?}")))
+    }
+
+    fn clear_heights(&self) -> Result<(), BlockchainError> {
+        self.db.open_tree(HEIGHT_INDEX_TREE)?.clear()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::blockchain::StoreBatch;
+
+impl StoreBatch {
+    pub fn new() -> StoreBatch {
+        StoreBatch::default()
+    }
+
+    /// Store `bytes` under `hash`
+    pub fn insert_block(&mut self, hash: &str, bytes: Vec<u8>) {
+        self.blocks.push((hash.to_string(), bytes));
+    }
+
+    /// Delete the block stored under `hash`
+    pub fn remove_block(&mut self, hash: &str) {
+        self.removed_blocks.push(hash.to_string());
+    }
+
+    /// Index `hash` as the main-chain block at `height`
+    pub fn set_height(&mut self, height: usize, hash: &str) {
+        self.heights.push((height, hash.to_string(), true));
+    }
+
+    /// Index `hash` at `height` only if no block is indexed there yet
+    pub fn fill_height(&mut self, height: usize, hash: &str) {
+        self.heights.push((height, hash.to_string(), false));
+    }
+
+    pub fn set_tip(&mut self, hash: &str) {
+        self.tip = Some(hash.to_string());
+    }
+
+    pub fn get_blocks(&self) -> &[(String, Vec<u8>)] {
+        self.blocks.as_slice()
+    }
+
+    pub fn get_removed_blocks(&self) -> &[String] {
+        self.removed_blocks.as_slice()
+    }
+
+    pub fn get_heights(&self) -> &[(usize, String, bool)] {
+        self.heights.as_slice()
+    }
+
+    pub fn get_tip(&self) -> Option<&str> {
+        self.tip.as_deref()
+    }
+}
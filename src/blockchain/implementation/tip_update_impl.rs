@@ -0,0 +1,11 @@
+use crate::blockchain::TipUpdate;
+
+impl TipUpdate {
+    pub fn get_hash(&self) -> &str {
+        self.hash.as_str()
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+}
@@ -2,5 +2,15 @@ mod data;
 mod implementation;
 
 // Re-export the main struct and constants
-pub use data::blockchain::{Blockchain, TIP_BLOCK_HASH_KEY, BLOCKS_TREE};
+pub use data::blockchain::{Blockchain, TIP_BLOCK_HASH_KEY, BLOCKS_TREE, SCHEMA_VERSION, SCHEMA_VERSION_KEY, DOT_SHORT_HASH_LEN, HEIGHT_INDEX_TREE, LOCATOR_DENSE_LEN, MINED_BLOCK_SIZE_SLACK};
 pub use data::blockchain_iterator::BlockchainIterator;
+pub use data::forward_blockchain_iterator::ForwardBlockchainIterator;
+pub use data::reorg::Reorg;
+pub use data::blockchain_error::BlockchainError;
+pub use data::block_store::BlockStore;
+pub use data::memory_block_store::MemoryBlockStore;
+pub use data::sled_block_store::SledBlockStore;
+pub use data::store_batch::StoreBatch;
+pub use data::chain_stats::ChainStats;
+pub use data::chain_params::{ChainParams, CHAIN_PARAMS_KEY, CHAIN_PARAMS_TREE};
+pub use data::tip_update::TipUpdate;
@@ -0,0 +1,9 @@
+/// A block named on the command line, by main-chain height or by hash
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockId {
+    Height(usize),
+    Hash(String),
+}
+
+// Block hashes are hex-encoded SHA-256 digests
+pub const BLOCK_HASH_HEX_LEN: usize = 64;
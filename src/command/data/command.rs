@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use structopt::StructOpt;
 
+use crate::command::BlockId;
+
 #[derive(Debug, StructOpt)]
 pub enum Command {
     #[structopt(name = "createblockchain", about = "Create a new blockchain")]
@@ -9,6 +13,24 @@ pub enum Command {
     },
     #[structopt(name = "createwallet", about = "Create a new wallet")]
     Createwallet,
+    #[structopt(name = "createwallets", about = "Create several new wallets at once")]
+    CreateWallets {
+        #[structopt(name = "count", help = "Number of wallets to create")]
+        count: usize,
+    },
+    #[structopt(name = "exportkey", about = "Print the private key of a local wallet")]
+    ExportKey {
+        #[structopt(name = "address", help = "The wallet address")]
+        address: String,
+    },
+    #[structopt(
+        name = "importkey",
+        about = "Add a wallet from an exported private key"
+    )]
+    ImportKey {
+        #[structopt(name = "key", help = "The private key printed by exportkey")]
+        key: String,
+    },
     #[structopt(
         name = "getbalance",
         about = "Get the wallet balance of the target address"
@@ -19,6 +41,11 @@ pub enum Command {
     },
     #[structopt(name = "listaddresses", about = "Print local wallet addresses")]
     ListAddresses,
+    #[structopt(
+        name = "listbalances",
+        about = "Print the balance of every local wallet and their total"
+    )]
+    ListBalances,
     #[structopt(name = "send", about = "Add new block to chain")]
     Send {
         #[structopt(name = "from", help = "Source wallet address")]
@@ -27,16 +54,125 @@ pub enum Command {
         to: String,
         #[structopt(name = "amount", help = "Amount to send")]
         amount: i32,
+        #[structopt(
+            long = "to",
+            name = "recipient",
+            number_of_values = 1,
+            parse(try_from_str = parse_recipient),
+            help = "Additional ADDRESS:AMOUNT recipient, may be repeated"
+        )]
+        recipients: Vec<(String, i32)>,
         #[structopt(name = "mine", help = "Mine immediately on the same node")]
         mine: usize,
     },
+    #[structopt(
+        name = "buildunsigned",
+        about = "Write an unsigned transaction and the outputs it spends to a file for offline signing"
+    )]
+    BuildUnsigned {
+        #[structopt(name = "from", help = "Source wallet address")]
+        from: String,
+        #[structopt(name = "to", help = "Destination wallet address")]
+        to: String,
+        #[structopt(name = "amount", help = "Amount to send")]
+        amount: i32,
+        #[structopt(name = "file", parse(from_os_str), help = "Where to write the bundle")]
+        file: PathBuf,
+    },
+    #[structopt(
+        name = "signbundle",
+        about = "Sign a buildunsigned bundle in place with a local wallet, without the chain"
+    )]
+    SignBundle {
+        #[structopt(name = "file", parse(from_os_str), help = "The bundle written by buildunsigned")]
+        file: PathBuf,
+        #[structopt(name = "address", help = "The local wallet address the bundle spends from")]
+        address: String,
+    },
+    #[structopt(
+        name = "sendbundle",
+        about = "Send the transaction of a signed bundle to the central node"
+    )]
+    SendBundle {
+        #[structopt(name = "file", parse(from_os_str), help = "The bundle signed by signbundle")]
+        file: PathBuf,
+    },
+    #[structopt(
+        name = "getrawtx",
+        about = "Print the hex of a serialized transaction on the chain"
+    )]
+    GetRawTx {
+        #[structopt(name = "txid", help = "The transaction id in hex")]
+        txid: String,
+    },
+    #[structopt(
+        name = "decoderawtx",
+        about = "Print the fields of a transaction from its getrawtx hex"
+    )]
+    DecodeRawTx {
+        #[structopt(name = "hex", help = "The serialized transaction in hex")]
+        hex: String,
+    },
+    #[structopt(
+        name = "listmempool",
+        about = "Print the pending transactions and fees the node last saved on shutdown, not a running node's pool"
+    )]
+    ListMempool,
     #[structopt(name = "printchain", about = "Print blockchain all block")]
     Printchain,
+    #[structopt(name = "getblock", about = "Print one block by hash or main-chain height")]
+    GetBlock {
+        #[structopt(name = "id", help = "The block hash, or its height on the main chain")]
+        id: BlockId,
+    },
+    #[structopt(name = "exportdot", about = "Print the chain as a Graphviz DOT graph")]
+    ExportDot,
+    #[structopt(name = "validate", about = "Fully validate every block of the chain")]
+    Validate,
     #[structopt(name = "reindexutxo", about = "rebuild UTXO index set")]
     Reindexutxo,
+    #[structopt(
+        name = "rescan",
+        about = "Rebuild the UTXO set from the chain and print every local wallet balance"
+    )]
+    Rescan,
+    #[structopt(
+        name = "prune",
+        about = "Discard fully spent transactions from blocks below a height"
+    )]
+    Prune {
+        #[structopt(name = "height", help = "Only blocks below this height are pruned")]
+        height: usize,
+    },
     #[structopt(name = "startnode", about = "Start a node")]
     StartNode {
         #[structopt(name = "miner", help = "Enable mining mode and send reward to ADDRESS")]
         miner: Option<String>,
+        #[structopt(
+            long = "sync-from",
+            help = "Start with no local chain and sync it entirely from this peer"
+        )]
+        sync_from: Option<String>,
+        #[structopt(
+            long = "http",
+            help = "Also serve read-only JSON queries on this address (needs the http feature)"
+        )]
+        http: Option<String>,
+        #[structopt(
+            long = "listen",
+            help = "Listen on this address instead of the NODE_ADDRESS one"
+        )]
+        listen: Option<String>,
     },
 }
+
+// Parses an `ADDRESS:AMOUNT` recipient for `send --to`
+fn parse_recipient(recipient: &str) -> Result<(String, i32), String> {
+    let (address, amount) = recipient
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected ADDRESS:AMOUNT, got {recipient}"))?;
+    let amount = amount
+        .parse::<i32>()
+        .map_err(|e| format!("invalid amount in {recipient}: {e}"))?;
+    Ok((address.to_string(), amount))
+}
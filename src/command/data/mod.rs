@@ -1,2 +1,3 @@
 pub mod opt;
 pub mod command;
+pub mod block_id;
@@ -0,0 +1,35 @@
+use std::{fmt, str::FromStr};
+
+use crate::command::{BLOCK_HASH_HEX_LEN, BlockId};
+
+impl FromStr for BlockId {
+    type Err = String;
+
+    /// Decimal digits shorter than a full hash are a height; anything else must be a hex
+    /// hash
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let id = id.trim();
+        if id.is_empty() {
+            return Err(String::from("expected a block hash or height"));
+        }
+        if id.len() < BLOCK_HASH_HEX_LEN && id.chars().all(|c| c.is_ascii_digit()) {
+            return id
+                .parse::<usize>()
+                .map(BlockId::Height)
+                .map_err(|e| format!("invalid block height {id}: {e}"));
+        }
+        if !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("{id} is neither a block height nor a hex hash"));
+        }
+        Ok(BlockId::Hash(id.to_ascii_lowercase()))
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockId::Height(height) => write!(f, "at height {height}"),
+            BlockId::Hash(hash) => write!(f, "{hash}"),
+        }
+    }
+}
@@ -1,4 +1,7 @@
 mod data;
+mod implementation;
 
 pub use data::opt::Opt;
 pub use data::command::Command;
+pub use data::block_id::{BlockId, BLOCK_HASH_HEX_LEN};
+pub use implementation::block_id_impl;
@@ -2,12 +2,63 @@ use std::{collections::HashMap, sync::RwLock};
 
 use once_cell::sync::Lazy;
 
+use crate::{proof_of_work::TARGET_BITS, wallet::VERSION};
+
 pub static GLOBAL_CONFIG: Lazy<Config> = Lazy::new(Config::new);
 
 pub static DEFAULT_NODE_ADDR: &str = "127.0.0.1:2001";
 
 pub const NODE_ADDRESS_KEY: &str = "NODE_ADDRESS";
 pub const MINING_ADDRESS_KEY: &str = "MINING_ADDRESS";
+pub const SUPPLY_CHECK_KEY: &str = "SUPPLY_CHECK";
+pub const HEARTBEAT_INTERVAL_KEY: &str = "HEARTBEAT_INTERVAL";
+pub const MAX_CONNECTIONS_KEY: &str = "MAX_CONNECTIONS";
+pub const MEMPOOL_EXPIRY_KEY: &str = "MEMPOOL_EXPIRY";
+pub const COINBASE_MATURITY_KEY: &str = "COINBASE_MATURITY";
+pub const MAX_BLOCK_SIZE_KEY: &str = "MAX_BLOCK_SIZE";
+pub const MAX_BLOCK_TXS_KEY: &str = "MAX_BLOCK_TXS";
+pub const TARGET_BITS_KEY: &str = "BLOCKCHAIN_TARGET_BITS";
+// Version byte prefixed to addresses, so testnet and mainnet addresses cannot be mixed up
+pub const ADDRESS_VERSION_KEY: &str = "ADDRESS_VERSION";
+// Comma-separated bootstrap peers contacted on startup
+pub const SEED_NODES_KEY: &str = "SEED_NODES";
+// Comma-separated height:hash pairs every accepted chain must pass through
+pub const CHECKPOINTS_KEY: &str = "CHECKPOINTS";
+// Network identifier exchanged in the version handshake, so testnet and mainnet peers never
+// talk to each other
+pub const NETWORK_MAGIC_KEY: &str = "NETWORK_MAGIC";
+// Largest encoded package, in bytes, a peer may send before its connection is dropped
+pub const MAX_PACKAGE_SIZE_KEY: &str = "MAX_PACKAGE_SIZE";
+// Seconds a connection may stay silent before its handler hangs up
+pub const READ_TIMEOUT_KEY: &str = "READ_TIMEOUT";
+// Smallest value a new spendable output may carry
+pub const DUST_THRESHOLD_KEY: &str = "DUST_THRESHOLD";
+// Derive wallet addresses from, and spend with, compressed public keys
+pub const COMPRESSED_PUBKEYS_KEY: &str = "COMPRESSED_PUBKEYS";
+// Times an outbound connection is tried before the peer is given up on
+pub const CONNECT_ATTEMPTS_KEY: &str = "CONNECT_ATTEMPTS";
+
+pub const DEFAULT_HEARTBEAT_INTERVAL_SECS: u64 = 60;
+pub const DEFAULT_MAX_CONNECTIONS: usize = 128;
+pub const DEFAULT_MEMPOOL_EXPIRY_SECS: i64 = 24 * 60 * 60;
+pub const DEFAULT_READ_TIMEOUT_SECS: u64 = 60;
+pub const DEFAULT_CONNECT_ATTEMPTS: u32 = 3;
+// No maturity wait, so a fresh chain can spend its genesis reward right away
+pub const DEFAULT_COINBASE_MATURITY: usize = 0;
+// Serialized bytes and transactions, coinbase included, the miner puts in one block
+pub const DEFAULT_MAX_BLOCK_SIZE: usize = 1_000_000;
+pub const DEFAULT_MAX_BLOCK_TXS: usize = 2_000;
+// Proof-of-work difficulty used to mine and validate blocks; tests lower it to mine quickly
+pub const DEFAULT_TARGET_BITS: usize = TARGET_BITS;
+pub const DEFAULT_ADDRESS_VERSION: u8 = VERSION;
+pub const DEFAULT_NETWORK_MAGIC: u32 = 0xf9be_b4d9;
+// Only zero-value spendable outputs count as dust unless configured otherwise
+pub const DEFAULT_DUST_THRESHOLD: i32 = 1;
+// Room for a full default-sized block plus the package around it
+pub const DEFAULT_MAX_PACKAGE_SIZE: usize = 4 * 1024 * 1024;
+// Upper bound on the configured package size. Decoding refuses any declared length past it
+// before allocating, so it bounds the memory one package can claim.
+pub const MAX_PACKAGE_SIZE_CEILING: usize = 64 * 1024 * 1024;
 
 pub struct Config {
     pub inner: RwLock<HashMap<String, String>>,
@@ -1,9 +1,27 @@
-use std::{collections::HashMap, env, sync::RwLock};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    net::SocketAddr,
+    sync::RwLock,
+};
 
 use crate::config::{
     Config,
-    data::config::{DEFAULT_NODE_ADDR, MINING_ADDRESS_KEY, NODE_ADDRESS_KEY},
+    data::config::{
+        ADDRESS_VERSION_KEY, CHECKPOINTS_KEY, COINBASE_MATURITY_KEY, COMPRESSED_PUBKEYS_KEY,
+        CONNECT_ATTEMPTS_KEY, DEFAULT_ADDRESS_VERSION, DEFAULT_COINBASE_MATURITY,
+        DEFAULT_CONNECT_ATTEMPTS, DEFAULT_DUST_THRESHOLD, DEFAULT_HEARTBEAT_INTERVAL_SECS,
+        DEFAULT_MAX_BLOCK_SIZE, DEFAULT_MAX_BLOCK_TXS, DEFAULT_MAX_CONNECTIONS,
+        DEFAULT_MAX_PACKAGE_SIZE, DEFAULT_MEMPOOL_EXPIRY_SECS, DEFAULT_NETWORK_MAGIC,
+        DEFAULT_NODE_ADDR, DEFAULT_READ_TIMEOUT_SECS, DEFAULT_TARGET_BITS, DUST_THRESHOLD_KEY,
+        HEARTBEAT_INTERVAL_KEY, MAX_BLOCK_SIZE_KEY, MAX_BLOCK_TXS_KEY, MAX_CONNECTIONS_KEY,
+        MAX_PACKAGE_SIZE_CEILING, MAX_PACKAGE_SIZE_KEY, MEMPOOL_EXPIRY_KEY, MINING_ADDRESS_KEY,
+        NETWORK_MAGIC_KEY, NODE_ADDRESS_KEY, READ_TIMEOUT_KEY, SEED_NODES_KEY, SUPPLY_CHECK_KEY,
+        TARGET_BITS_KEY,
+    },
 };
+use crate::proof_of_work::{MAX_TARGET_BITS, MIN_TARGET_BITS};
+use crate::server::CENTRAL_NODE;
 
 impl Config {
     pub fn new() -> Config {
@@ -13,6 +31,29 @@ impl Config {
         }
         let mut map = HashMap::new();
         map.insert(String::from(NODE_ADDRESS_KEY), node_addr);
+        for key in [
+            SUPPLY_CHECK_KEY,
+            HEARTBEAT_INTERVAL_KEY,
+            MAX_CONNECTIONS_KEY,
+            MEMPOOL_EXPIRY_KEY,
+            COINBASE_MATURITY_KEY,
+            MAX_BLOCK_SIZE_KEY,
+            MAX_BLOCK_TXS_KEY,
+            TARGET_BITS_KEY,
+            ADDRESS_VERSION_KEY,
+            SEED_NODES_KEY,
+            CHECKPOINTS_KEY,
+            NETWORK_MAGIC_KEY,
+            MAX_PACKAGE_SIZE_KEY,
+            READ_TIMEOUT_KEY,
+            DUST_THRESHOLD_KEY,
+            COMPRESSED_PUBKEYS_KEY,
+            CONNECT_ATTEMPTS_KEY,
+        ] {
+            if let Ok(value) = env::var(key) {
+                map.insert(String::from(key), value);
+            }
+        }
 
         Config {
             inner: RwLock::new(map),
@@ -24,6 +65,16 @@ impl Config {
         inner.get(NODE_ADDRESS_KEY).unwrap().clone()
     }
 
+    /// Override the listen address. Rejects addresses that do not parse as a `SocketAddr`.
+    pub fn set_node_addr(&self, addr: String) -> Result<(), String> {
+        if let Err(e) = addr.parse::<SocketAddr>() {
+            return Err(format!("invalid node address {addr}: {e}"));
+        }
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(NODE_ADDRESS_KEY), addr);
+        Ok(())
+    }
+
     pub fn set_mining_addr(&self, addr: String) {
         let mut inner = self.inner.write().unwrap();
         let _ = inner.insert(String::from(MINING_ADDRESS_KEY), addr);
@@ -41,6 +92,278 @@ impl Config {
         let inner = self.inner.read().unwrap();
         inner.contains_key(MINING_ADDRESS_KEY)
     }
+
+    pub fn set_supply_check(&self, enabled: bool) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(SUPPLY_CHECK_KEY), enabled.to_string());
+    }
+
+    /// Whether `UTXOSet::update` should assert the supply invariant after every block
+    pub fn is_supply_check_enabled(&self) -> bool {
+        let inner = self.inner.read().unwrap();
+        matches!(
+            inner.get(SUPPLY_CHECK_KEY).map(String::as_str),
+            Some("1") | Some("true")
+        )
+    }
+
+    pub fn set_heartbeat_interval_secs(&self, secs: u64) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(HEARTBEAT_INTERVAL_KEY), secs.to_string());
+    }
+
+    /// Seconds between server status log lines.
+    /// `DEFAULT_HEARTBEAT_INTERVAL_SECS` when unset or zero, so the heartbeat never spins.
+    pub fn get_heartbeat_interval_secs(&self) -> u64 {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(HEARTBEAT_INTERVAL_KEY)
+            .and_then(|secs| secs.parse().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_HEARTBEAT_INTERVAL_SECS)
+    }
+
+    pub fn set_read_timeout_secs(&self, secs: u64) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(READ_TIMEOUT_KEY), secs.to_string());
+    }
+
+    /// Seconds `serve` waits for the next package before dropping a silent connection.
+    /// `DEFAULT_READ_TIMEOUT_SECS` when unset or zero.
+    pub fn get_read_timeout_secs(&self) -> u64 {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(READ_TIMEOUT_KEY)
+            .and_then(|secs| secs.parse().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_READ_TIMEOUT_SECS)
+    }
+
+    pub fn set_connect_attempts(&self, attempts: u32) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(CONNECT_ATTEMPTS_KEY), attempts.to_string());
+    }
+
+    /// Times `connect_with_retry` tries to reach a peer before failing.
+    /// `DEFAULT_CONNECT_ATTEMPTS` when unset or zero.
+    pub fn get_connect_attempts(&self) -> u32 {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(CONNECT_ATTEMPTS_KEY)
+            .and_then(|attempts| attempts.parse().ok())
+            .filter(|attempts| *attempts > 0)
+            .unwrap_or(DEFAULT_CONNECT_ATTEMPTS)
+    }
+
+    pub fn set_max_connections(&self, max: usize) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(MAX_CONNECTIONS_KEY), max.to_string());
+    }
+
+    /// Most connection handler threads the server runs at once
+    pub fn get_max_connections(&self) -> usize {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(MAX_CONNECTIONS_KEY)
+            .and_then(|max| max.parse().ok())
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS)
+    }
+
+    pub fn set_mempool_expiry_secs(&self, secs: i64) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(MEMPOOL_EXPIRY_KEY), secs.to_string());
+    }
+
+    /// Seconds a transaction may wait in the memory pool before it is evicted
+    pub fn get_mempool_expiry_secs(&self) -> i64 {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(MEMPOOL_EXPIRY_KEY)
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(DEFAULT_MEMPOOL_EXPIRY_SECS)
+    }
+
+    pub fn set_coinbase_maturity(&self, depth: usize) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(COINBASE_MATURITY_KEY), depth.to_string());
+    }
+
+    /// Blocks a coinbase output must wait before a new `UTXOSet` lets it be spent
+    pub fn get_coinbase_maturity(&self) -> usize {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(COINBASE_MATURITY_KEY)
+            .and_then(|depth| depth.parse().ok())
+            .unwrap_or(DEFAULT_COINBASE_MATURITY)
+    }
+
+    pub fn set_max_block_size(&self, bytes: usize) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(MAX_BLOCK_SIZE_KEY), bytes.to_string());
+    }
+
+    /// Largest serialized block, in bytes, that `Blockchain::mine_block` produces
+    pub fn get_max_block_size(&self) -> usize {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(MAX_BLOCK_SIZE_KEY)
+            .and_then(|bytes| bytes.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BLOCK_SIZE)
+    }
+
+    pub fn set_max_block_txs(&self, max: usize) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(MAX_BLOCK_TXS_KEY), max.to_string());
+    }
+
+    /// Most transactions, coinbase included, that `Blockchain::mine_block` puts in a block
+    pub fn get_max_block_txs(&self) -> usize {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(MAX_BLOCK_TXS_KEY)
+            .and_then(|max| max.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BLOCK_TXS)
+    }
+
+    pub fn set_target_bits(&self, target_bits: usize) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(TARGET_BITS_KEY), target_bits.to_string());
+    }
+
+    /// Proof-of-work difficulty blocks are mined and validated at, falling back to
+    /// `DEFAULT_TARGET_BITS` when unset or outside `MIN_TARGET_BITS..=MAX_TARGET_BITS`
+    pub fn get_target_bits(&self) -> usize {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(TARGET_BITS_KEY)
+            .and_then(|bits| bits.parse().ok())
+            .filter(|bits| (MIN_TARGET_BITS..=MAX_TARGET_BITS).contains(bits))
+            .unwrap_or(DEFAULT_TARGET_BITS)
+    }
+
+    pub fn set_address_version(&self, version: u8) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(ADDRESS_VERSION_KEY), version.to_string());
+    }
+
+    /// Version byte new addresses are encoded with and `validate_address` accepts
+    pub fn get_address_version(&self) -> u8 {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(ADDRESS_VERSION_KEY)
+            .and_then(|version| version.parse().ok())
+            .unwrap_or(DEFAULT_ADDRESS_VERSION)
+    }
+
+    pub fn set_network_magic(&self, magic: u32) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(NETWORK_MAGIC_KEY), magic.to_string());
+    }
+
+    /// Network identifier sent in, and required of, every version handshake
+    pub fn get_network_magic(&self) -> u32 {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(NETWORK_MAGIC_KEY)
+            .and_then(|magic| magic.parse().ok())
+            .unwrap_or(DEFAULT_NETWORK_MAGIC)
+    }
+
+    pub fn set_max_package_size(&self, bytes: usize) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(MAX_PACKAGE_SIZE_KEY), bytes.to_string());
+    }
+
+    /// Largest encoded package, in bytes, `serve` reads from a peer. `DEFAULT_MAX_PACKAGE_SIZE`
+    /// when unset or zero, and never more than `MAX_PACKAGE_SIZE_CEILING`.
+    pub fn get_max_package_size(&self) -> usize {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(MAX_PACKAGE_SIZE_KEY)
+            .and_then(|bytes| bytes.parse().ok())
+            .filter(|bytes| *bytes > 0)
+            .unwrap_or(DEFAULT_MAX_PACKAGE_SIZE)
+            .min(MAX_PACKAGE_SIZE_CEILING)
+    }
+
+    pub fn set_dust_threshold(&self, value: i32) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(DUST_THRESHOLD_KEY), value.to_string());
+    }
+
+    /// Spendable outputs worth less than this are dust, which new transactions may not create
+    pub fn get_dust_threshold(&self) -> i32 {
+        let inner = self.inner.read().unwrap();
+        inner
+            .get(DUST_THRESHOLD_KEY)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_DUST_THRESHOLD)
+    }
+
+    pub fn set_compressed_pubkeys(&self, enabled: bool) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(COMPRESSED_PUBKEYS_KEY), enabled.to_string());
+    }
+
+    /// Whether wallets derive their addresses from, and spend with, the compressed form of
+    /// their public key. Switching it changes every wallet's address.
+    pub fn is_compressed_pubkeys_enabled(&self) -> bool {
+        let inner = self.inner.read().unwrap();
+        matches!(
+            inner.get(COMPRESSED_PUBKEYS_KEY).map(String::as_str),
+            Some("1") | Some("true")
+        )
+    }
+
+    /// Set the bootstrap peers from a comma-separated list
+    pub fn set_seed_nodes(&self, seeds: &str) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(SEED_NODES_KEY), String::from(seeds));
+    }
+
+    /// Bootstrap peers in the order given, without blanks or duplicates. Defaults to the
+    /// central node when none are configured.
+    pub fn get_seed_nodes(&self) -> Vec<String> {
+        let inner = self.inner.read().unwrap();
+        let mut seeds: Vec<String> = vec![];
+        if let Some(list) = inner.get(SEED_NODES_KEY) {
+            for seed in list.split(',').map(str::trim) {
+                if !seed.is_empty() && !seeds.iter().any(|known| known == seed) {
+                    seeds.push(String::from(seed));
+                }
+            }
+        }
+        if seeds.is_empty() {
+            seeds.push(String::from(CENTRAL_NODE));
+        }
+        seeds
+    }
+
+    /// Set the checkpoints from a comma-separated list of `height:hash` pairs
+    pub fn set_checkpoints(&self, checkpoints: &str) {
+        let mut inner = self.inner.write().unwrap();
+        let _ = inner.insert(String::from(CHECKPOINTS_KEY), String::from(checkpoints));
+    }
+
+    /// Block hash expected at each checkpointed height, skipping malformed pairs. A height
+    /// listed twice keeps its last hash.
+    pub fn get_checkpoints(&self) -> BTreeMap<usize, String> {
+        let inner = self.inner.read().unwrap();
+        let mut checkpoints = BTreeMap::new();
+        if let Some(list) = inner.get(CHECKPOINTS_KEY) {
+            for pair in list.split(',') {
+                let Some((height, hash)) = pair.split_once(':') else {
+                    continue;
+                };
+                if let Ok(height) = height.trim().parse()
+                    && !hash.trim().is_empty()
+                {
+                    checkpoints.insert(height, String::from(hash.trim()));
+                }
+            }
+        }
+        checkpoints
+    }
 }
 
 impl Default for Config {
@@ -1,5 +1,16 @@
 mod data;
 mod implementation;
 
-pub use data::config::{Config, DEFAULT_NODE_ADDR, NODE_ADDRESS_KEY, MINING_ADDRESS_KEY, GLOBAL_CONFIG};
+pub use data::config::{
+    ADDRESS_VERSION_KEY, CHECKPOINTS_KEY, COINBASE_MATURITY_KEY, COMPRESSED_PUBKEYS_KEY,
+    CONNECT_ATTEMPTS_KEY, Config, DEFAULT_ADDRESS_VERSION, DEFAULT_COINBASE_MATURITY,
+    DEFAULT_CONNECT_ATTEMPTS, DEFAULT_DUST_THRESHOLD, DEFAULT_HEARTBEAT_INTERVAL_SECS,
+    DEFAULT_MAX_BLOCK_SIZE, DEFAULT_MAX_BLOCK_TXS, DEFAULT_MAX_CONNECTIONS,
+    DEFAULT_MAX_PACKAGE_SIZE, DEFAULT_MEMPOOL_EXPIRY_SECS, DEFAULT_NETWORK_MAGIC,
+    DEFAULT_NODE_ADDR, DEFAULT_READ_TIMEOUT_SECS, DEFAULT_TARGET_BITS, DUST_THRESHOLD_KEY,
+    GLOBAL_CONFIG, HEARTBEAT_INTERVAL_KEY, MAX_BLOCK_SIZE_KEY, MAX_BLOCK_TXS_KEY,
+    MAX_CONNECTIONS_KEY, MAX_PACKAGE_SIZE_CEILING, MAX_PACKAGE_SIZE_KEY, MEMPOOL_EXPIRY_KEY,
+    MINING_ADDRESS_KEY, NETWORK_MAGIC_KEY, NODE_ADDRESS_KEY, READ_TIMEOUT_KEY, SEED_NODES_KEY,
+    SUPPLY_CHECK_KEY, TARGET_BITS_KEY,
+};
 pub use implementation::config_impl;
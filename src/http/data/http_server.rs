@@ -0,0 +1,12 @@
+use crate::Blockchain;
+
+// Largest request head (request line plus headers) read from a client
+pub const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+// Read timeout for a client that connects but never finishes its request
+pub const HTTP_READ_TIMEOUT: u64 = 5000;
+
+/// Read-only JSON view of the chain served over HTTP, separate from the P2P `Server`
+pub struct HttpServer {
+    pub(in crate::http) blockchain: Blockchain,
+}
@@ -0,0 +1,86 @@
+use data_encoding::HEXLOWER;
+
+use crate::{Block, Transaction, wallet::wallet_util::convert_address};
+
+/// Quote `value` as a JSON string
+pub fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(format!("\\u{:04x}", c as u32).as_str()),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub fn error_json(message: &str) -> String {
+    format!("{{\"error\":{}}}", json_string(message))
+}
+
+pub fn block_json(block: &Block) -> String {
+    let txids: Vec<String> = block
+        .get_transactions()
+        .iter()
+        .map(|tx| json_string(HEXLOWER.encode(tx.get_id()).as_str()))
+        .collect();
+    format!(
+        "{{\"hash\":{},\"pre_block_hash\":{},\"height\":{},\"timestamp\":{},\"nonce\":{},\"transactions\":[{}]}}",
+        json_string(block.get_hash()),
+        json_string(block.get_pre_block_hash()),
+        block.get_height(),
+        block.get_timestamp(),
+        block.get_nonce(),
+        txids.join(","),
+    )
+}
+
+pub fn transaction_json(tx: &Transaction) -> String {
+    let vin: Vec<String> = tx
+        .get_vin()
+        .iter()
+        .map(|input| {
+            format!(
+                "{{\"txid\":{},\"vout\":{}}}",
+                json_string(HEXLOWER.encode(input.get_txid()).as_str()),
+                input.get_vout()
+            )
+        })
+        .collect();
+    let vout: Vec<String> = tx
+        .get_vout()
+        .iter()
+        .map(|output| {
+            format!(
+                "{{\"value\":{},\"address\":{}}}",
+                output.get_value(),
+                json_string(convert_address(output.get_pub_key_hash()).as_str())
+            )
+        })
+        .collect();
+    format!(
+        "{{\"txid\":{},\"coinbase\":{},\"vin\":[{}],\"vout\":[{}]}}",
+        json_string(HEXLOWER.encode(tx.get_id()).as_str()),
+        tx.is_coinbase(),
+        vin.join(","),
+        vout.join(","),
+    )
+}
+
+/// Reason phrase for the status codes the HTTP interface returns
+pub fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
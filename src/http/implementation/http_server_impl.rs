@@ -0,0 +1,117 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use data_encoding::HEXLOWER;
+use log::{error, info};
+
+use crate::{
+    Blockchain, UTXOSet,
+    http::{
+        HttpServer,
+        data::http_server::{HTTP_READ_TIMEOUT, MAX_REQUEST_SIZE},
+        http_utils::{block_json, error_json, json_string, status_text, transaction_json},
+    },
+    wallet::wallet_util::validate_address,
+};
+
+impl HttpServer {
+    pub fn new(blockchain: Blockchain) -> HttpServer {
+        HttpServer { blockchain }
+    }
+
+    /// Serve requests on `listener` from a background thread so the caller, typically the
+    /// P2P `Server`, keeps running
+    pub fn spawn(self, listener: TcpListener) -> JoinHandle<()> {
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => self.handle_connection(stream),
+                    Err(e) => error!("Error accepting HTTP connection: {e}"),
+                }
+            }
+        })
+    }
+
+    fn handle_connection(&self, stream: TcpStream) {
+        let _ = stream.set_read_timeout(Some(Duration::from_millis(HTTP_READ_TIMEOUT)));
+        let mut reader = BufReader::new((&stream).take(MAX_REQUEST_SIZE as u64));
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+        // Drain the headers; no endpoint needs them
+        let mut header = String::new();
+        while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+            header.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (status, body) = match (parts.next(), parts.next()) {
+            (Some("GET"), Some(path)) => self.handle_get(path),
+            (Some(_), Some(_)) => (405, error_json("Only GET is supported")),
+            _ => (400, error_json("Malformed request line")),
+        };
+        info!("HTTP {} -> {status}", request_line.trim_end());
+
+        let response = format!(
+            "HTTP/1.1 {status} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            status_text(status),
+            body.len(),
+        );
+        let _ = (&stream).write_all(response.as_bytes());
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+
+    /// Route a GET request for `path`, returning the status code and JSON body
+    pub fn handle_get(&self, path: &str) -> (u16, String) {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["height"] => (200, self.height_json()),
+            ["block", hash] => match self.blockchain.get_block(hash.as_bytes()) {
+                Some(block) => (200, block_json(&block)),
+                None => (404, error_json("Block not found")),
+            },
+            ["tx", txid_hex] => {
+                let Ok(txid) = HEXLOWER.decode(txid_hex.as_bytes()) else {
+                    return (400, error_json("Transaction id must be lowercase hex"));
+                };
+                match self.blockchain.find_transaction(txid.as_slice()) {
+                    Some(tx) => (200, transaction_json(&tx)),
+                    None => (404, error_json("Transaction not found")),
+                }
+            }
+            ["balance", address] => {
+                if !validate_address(address) {
+                    return (400, error_json("Address is not valid"));
+                }
+                let utxo_set = UTXOSet::new(self.blockchain.clone());
+                let balances = utxo_set.get_balances(&[address.to_string()]);
+                let balance = balances.get(*address).copied().unwrap_or(0);
+                (
+                    200,
+                    format!(
+                        "{{\"address\":{},\"balance\":{balance}}}",
+                        json_string(address)
+                    ),
+                )
+            }
+            _ => (404, error_json("Unknown endpoint")),
+        }
+    }
+
+    fn height_json(&self) -> String {
+        let tip_hash = self.blockchain.get_tip_hash();
+        match self.blockchain.get_block(tip_hash.as_bytes()) {
+            Some(tip) => format!(
+                "{{\"height\":{},\"tip\":{}}}",
+                tip.get_height(),
+                json_string(tip.get_hash())
+            ),
+            None => String::from("{\"height\":null,\"tip\":null}"),
+        }
+    }
+}
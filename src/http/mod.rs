@@ -0,0 +1,6 @@
+mod data;
+pub mod http_utils;
+mod implementation;
+
+pub use data::http_server::*;
+pub use implementation::http_server_impl;
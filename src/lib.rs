@@ -11,14 +11,16 @@ pub mod utxo_set;
 pub mod server;
 pub mod wallet;
 pub mod command;
+#[cfg(feature = "http")]
+pub mod http;
 
 // Convenience re-exports for commonly used types
-pub use block::Block;
-pub use transaction::{Transaction, TXInput, TXOutput};
-pub use proof_of_work::{ProofOfWork, MAX_NONCE, TARGET_BITS};
-pub use blockchain::{Blockchain, TIP_BLOCK_HASH_KEY, BLOCKS_TREE, BlockchainIterator};
+pub use block::{Block, BlockHeader, GENESIS_PRE_BLOCK_HASH};
+pub use transaction::{Transaction, LOCK_TIME_THRESHOLD, SUBSIDY, TXInput, TXOutput, BURN_PUB_KEY_HASH_LEN, MAX_DATA_OUTPUT_LEN, TxEstimate, COMPRESSED_PUBLIC_KEY_LEN, PUBLIC_KEY_LEN, SIGNATURE_LEN, MultiSigLock, SigCache, SigCacheKey, GLOBAL_SIG_CACHE, SIG_CACHE_CAPACITY};
+pub use proof_of_work::{ProofOfWork, PowAlgorithm, MAX_NONCE, MAX_RETARGET_STEP, MAX_TARGET_BITS, MIN_TARGET_BITS, PROGRESS_INTERVAL, TARGET_BITS};
+pub use blockchain::{Blockchain, TIP_BLOCK_HASH_KEY, BLOCKS_TREE, SCHEMA_VERSION, SCHEMA_VERSION_KEY, HEIGHT_INDEX_TREE, LOCATOR_DENSE_LEN, BlockchainIterator, ForwardBlockchainIterator, BlockchainError, Reorg, BlockStore, MemoryBlockStore, SledBlockStore, StoreBatch, MINED_BLOCK_SIZE_SLACK, ChainStats, ChainParams, CHAIN_PARAMS_TREE, TipUpdate};
 pub use common::BincodeBigInt;
 pub use nodes::{Node, Nodes};
-pub use memory_pool::{MemoryPool, BlockInTransit};
-pub use utxo_set::{UTXOSet, UTXO_TREE};
+pub use memory_pool::{MemoryPool, BlockInTransit, ReplaceResult};
+pub use utxo_set::{UTXOSet, UtxoDiff, COINBASE_HEIGHT_TREE, MAX_UNDO_DEPTH, UTXO_TREE, UTXO_UNDO_TREE};
 pub use util::new_key_pair;
@@ -1,13 +1,14 @@
 use data_encoding::HEXLOWER;
 use log::LevelFilter;
 use rust_blockchain::{
-    Blockchain, Transaction, UTXOSet,
-    command::{Command, Opt},
+    Blockchain, MemoryPool, TXOutput, Transaction, UTXOSet,
+    command::{BlockId, Command, Opt},
     config::GLOBAL_CONFIG,
+    memory_pool::MEMPOOL_FILE,
     server::{CENTRAL_NODE, Server, send_tx},
     util,
     wallet::{
-        ADDRESS_CHECK_SUM_LEN, Wallets,
+        ADDRESS_CHECK_SUM_LEN, Wallet, Wallets,
         wallet_util::{convert_address, hash_pub_key, validate_address},
     },
 };
@@ -30,6 +31,28 @@ fn main() {
             let address = wallet.create_wallet();
             println!("Your new address: {address}")
         }
+        Command::CreateWallets { count } => {
+            let mut wallets = Wallets::new();
+            for address in wallets.create_wallets(count) {
+                println!("{address}")
+            }
+        }
+        Command::ExportKey { address } => {
+            let wallets = Wallets::new();
+            match wallets.get_wallet(address.as_str()) {
+                Some(wallet) => println!("{}", wallet.export_private_key()),
+                None => panic!("ERROR: No local wallet for address {address}"),
+            }
+        }
+        Command::ImportKey { key } => {
+            let wallet = match Wallet::from_private_key(key.as_str()) {
+                Ok(wallet) => wallet,
+                Err(e) => panic!("ERROR: {e}"),
+            };
+            let mut wallets = Wallets::new();
+            let address = wallets.add_wallet(wallet);
+            println!("Imported address: {address}")
+        }
         Command::GetBalance { address } => {
             let address_valid = validate_address(address.as_str());
             if !address_valid {
@@ -53,32 +76,48 @@ fn main() {
                 println!("{address}")
             }
         }
+        Command::ListBalances => {
+            let wallets = Wallets::new();
+            let addresses = wallets.get_addresses();
+            if addresses.is_empty() {
+                println!("No local wallets. Create one with createwallet.");
+                return;
+            }
+            let blockchain = Blockchain::new_blockchain();
+            print_balances(&UTXOSet::new(blockchain), &addresses);
+        }
         Command::Send {
             from,
             to,
             amount,
             mine,
+            recipients,
         } => {
             if !validate_address(from.as_str()) {
                 panic!("ERROR: Sender address is not valid")
             }
-            if !validate_address(to.as_str()) {
-                panic!("ERROR: Recipient address is not valid")
+            let mut outputs = vec![(to, amount)];
+            outputs.extend(recipients);
+            for (to, _) in &outputs {
+                if !validate_address(to.as_str()) {
+                    panic!("ERROR: Recipient address {to} is not valid")
+                }
             }
             let blockchain = Blockchain::new_blockchain();
             let utxo_set = UTXOSet::new(blockchain.clone());
 
             let transaction =
-                Transaction::new_utxo_transaction(from.as_str(), to.as_str(), amount, &utxo_set);
+                Transaction::new_utxo_transaction_multi(from.as_str(), &outputs, &utxo_set)
+                    .unwrap_or_else(|e| panic!("ERROR: {e}"));
 
             if mine == MINE_TRUE {
                 let coinbase_tx = Transaction::new_coinbase_tx(from.as_str());
 
-                let block = blockchain.mine_block(&[transaction, coinbase_tx]);
+                let block = blockchain.mine_block(&[coinbase_tx, transaction]);
 
                 utxo_set.update(&block);
-            } else {
-                send_tx(CENTRAL_NODE, &transaction);
+            } else if let Err(e) = send_tx(CENTRAL_NODE, &transaction) {
+                panic!("ERROR: Unable to send transaction to {CENTRAL_NODE}: {e}")
             }
             println!("Success!")
         }
@@ -96,37 +135,154 @@ fn main() {
                 for tx in block.get_transactions() {
                     let cur_txid_hex = HEXLOWER.encode(tx.get_id());
                     println!("- Transaction txid_hex: {cur_txid_hex}");
-
-                    if !tx.is_coinbase() {
-                        for input in tx.get_vin() {
-                            let txid_hex = HEXLOWER.encode(input.get_txid());
-                            let pub_key_hash = hash_pub_key(input.get_pub_key());
-                            let address = convert_address(pub_key_hash.as_slice());
-                            println!(
-                                "-- Input txid = {}, vout = {}, from = {}",
-                                txid_hex,
-                                input.get_vout(),
-                                address,
-                            )
-                        }
-                    }
-                    for output in tx.get_vout() {
-                        let pub_key_hash = output.get_pub_key_hash();
-                        let address = convert_address(pub_key_hash);
-                        println!("-- Output value = {}, to = {}", output.get_value(), address,)
-                    }
+                    print_inputs_and_outputs(tx);
                 }
                 println!()
             }
         }
+        Command::GetBlock { id } => {
+            let blockchain = Blockchain::new_blockchain();
+            let block = match &id {
+                BlockId::Height(height) => blockchain.get_block_by_height(*height),
+                BlockId::Hash(hash) => blockchain.get_block(hash.as_bytes()),
+            };
+            match block {
+                Some(block) => {
+                    println!("Block hash: {}", block.get_hash());
+                    println!("Pre block hash: {}", block.get_pre_block_hash());
+                    println!("Height: {}", block.get_height());
+                    println!("Timestamp: {}", block.get_timestamp());
+                    println!("Nonce: {}", block.get_nonce());
+                    for tx in block.get_transactions() {
+                        println!("- Transaction txid_hex: {}", HEXLOWER.encode(tx.get_id()));
+                        print_inputs_and_outputs(tx);
+                    }
+                }
+                None => println!("Error: No block {id} on the chain"),
+            }
+        }
+        Command::BuildUnsigned {
+            from,
+            to,
+            amount,
+            file,
+        } => {
+            if !validate_address(from.as_str()) {
+                panic!("ERROR: Sender address is not valid")
+            }
+            if !validate_address(to.as_str()) {
+                panic!("ERROR: Recipient address is not valid")
+            }
+            let payload = util::base58_decode(from.as_str());
+            let pub_key_hash = &payload[1..payload.len() - ADDRESS_CHECK_SUM_LEN];
+
+            let utxo_set = UTXOSet::new(Blockchain::new_blockchain());
+            let (tx, prev_outputs) = utxo_set
+                .build_unsigned(pub_key_hash, &[TXOutput::new(amount, to.as_str())])
+                .unwrap_or_else(|e| panic!("ERROR: {e}"));
+            if let Err(e) = Transaction::save_bundle(&file, &tx, &prev_outputs) {
+                panic!("ERROR: Unable to write {}: {e}", file.display())
+            }
+            println!("Wrote an unsigned transaction to {}", file.display())
+        }
+        Command::SignBundle { file, address } => {
+            let (mut tx, prev_outputs) = Transaction::load_bundle(&file)
+                .unwrap_or_else(|e| panic!("ERROR: Unable to read {}: {e}", file.display()));
+            let wallets = Wallets::new();
+            let wallet = match wallets.get_wallet(address.as_str()) {
+                Some(wallet) => wallet,
+                None => panic!("ERROR: No local wallet for address {address}"),
+            };
+            if let Err(e) = tx.sign_offline(wallet.get_pkcs8(), &prev_outputs) {
+                panic!("ERROR: {e}")
+            }
+            if let Err(e) = Transaction::save_bundle(&file, &tx, &prev_outputs) {
+                panic!("ERROR: Unable to write {}: {e}", file.display())
+            }
+            println!("Signed transaction {}", HEXLOWER.encode(tx.get_id()))
+        }
+        Command::SendBundle { file } => {
+            let (tx, _) = Transaction::load_bundle(&file)
+                .unwrap_or_else(|e| panic!("ERROR: Unable to read {}: {e}", file.display()));
+            if tx.get_id().is_empty() {
+                panic!("ERROR: The bundle is not signed yet, run signbundle first")
+            }
+            if let Err(e) = send_tx(CENTRAL_NODE, &tx) {
+                panic!("ERROR: Unable to send transaction to {CENTRAL_NODE}: {e}")
+            }
+            println!("Success!")
+        }
+        Command::GetRawTx { txid } => {
+            let txid_bytes = HEXLOWER
+                .decode(txid.to_ascii_lowercase().as_bytes())
+                .unwrap_or_else(|e| panic!("ERROR: Invalid txid: {e}"));
+            match Blockchain::new_blockchain().find_transaction(txid_bytes.as_slice()) {
+                Some(tx) => println!("{}", tx.to_raw_hex()),
+                None => println!("Error: No transaction {txid} on the chain"),
+            }
+        }
+        Command::DecodeRawTx { hex } => match Transaction::from_raw_hex(hex.as_str()) {
+            Ok(tx) => {
+                println!("Txid: {}", HEXLOWER.encode(tx.get_id()));
+                println!("Coinbase: {}", tx.is_coinbase());
+                print_inputs_and_outputs(&tx);
+            }
+            Err(e) => println!("Error: {e}"),
+        },
+        Command::ExportDot => {
+            print!("{}", Blockchain::new_blockchain().export_dot());
+        }
+        Command::Validate => match Blockchain::new_blockchain().validate_chain() {
+            Ok(()) => println!("OK"),
+            Err(e) => println!("Error: {e}"),
+        },
+        Command::Prune { height } => match Blockchain::new_blockchain().prune_below(height) {
+            Ok(pruned) => println!("Pruned {pruned} transactions"),
+            Err(e) => println!("Error: {e}"),
+        },
+        Command::ListMempool => {
+            // The pool is only written when the node shuts down, so a running node may hold
+            // transactions this does not list
+            let utxo_set = UTXOSet::new(Blockchain::new_blockchain());
+            let memory_pool = MemoryPool::new();
+            let path = util::current_dir().join(MEMPOOL_FILE);
+            if let Err(e) = memory_pool.load_from_disk(&path, &utxo_set) {
+                println!("Error: Unable to read {}: {e}", path.display());
+                return;
+            }
+            for (txid, fee) in memory_pool.get_txids_with_fees() {
+                println!("{txid} fee = {fee}");
+            }
+            println!(
+                "{} pending transactions saved in {}",
+                memory_pool.len(),
+                path.display()
+            );
+        }
         Command::Reindexutxo => {
             let blockchain = Blockchain::new_blockchain();
             let utxo_set = UTXOSet::new(blockchain);
-            utxo_set.reindex();
-            let count = utxo_set.count_transactions();
+            let count = utxo_set.reindex();
             println!("Done! There are {count} transactions in the UTXO set.");
         }
-        Command::StartNode { miner } => {
+        Command::Rescan => {
+            let blockchain = Blockchain::new_blockchain();
+            let utxo_set = UTXOSet::new(blockchain);
+            let count = utxo_set.reindex();
+            println!("Rescanned the chain: {count} transactions in the UTXO set.");
+            print_balances(&utxo_set, &Wallets::new().get_addresses());
+        }
+        Command::StartNode {
+            miner,
+            sync_from,
+            http,
+            listen,
+        } => {
+            if let Some(addr) = listen
+                && let Err(e) = GLOBAL_CONFIG.set_node_addr(addr)
+            {
+                panic!("ERROR: {e}")
+            }
             if let Some(addr) = miner {
                 if !validate_address(addr.as_str()) {
                     panic!("Wrong miner address!")
@@ -134,9 +290,66 @@ fn main() {
                 println!("Mining is on. Address to receive rewards: {addr}");
                 GLOBAL_CONFIG.set_mining_addr(addr);
             }
-            let blockchain = Blockchain::new_blockchain();
             let sockert_addr = GLOBAL_CONFIG.get_node_addr();
-            Server::new(blockchain).run(sockert_addr.as_str());
+            let blockchain = match sync_from {
+                Some(_) => Blockchain::open_blockchain(),
+                None => Blockchain::new_blockchain(),
+            };
+            if let Some(http_addr) = http {
+                start_http(http_addr.as_str(), blockchain.clone());
+            }
+            let server = match sync_from {
+                Some(peer) => Server::new_with_sync_from(blockchain, peer),
+                None => Server::new(blockchain),
+            }
+            .with_mempool_file(util::current_dir().join(MEMPOOL_FILE));
+            server.run(sockert_addr.as_str());
+        }
+    }
+}
+
+fn print_balances(utxo_set: &UTXOSet, addresses: &[String]) {
+    let mut total: i64 = 0;
+    for (address, balance) in &utxo_set.get_balances(addresses) {
+        println!("{address}: {balance}");
+        total += *balance as i64;
+    }
+    println!("Total: {total}");
+}
+
+fn print_inputs_and_outputs(tx: &Transaction) {
+    if !tx.is_coinbase() {
+        for input in tx.get_vin() {
+            let txid_hex = HEXLOWER.encode(input.get_txid());
+            let pub_key_hash = hash_pub_key(input.get_pub_key());
+            let address = convert_address(pub_key_hash.as_slice());
+            println!(
+                "-- Input txid = {}, vout = {}, from = {}",
+                txid_hex,
+                input.get_vout(),
+                address,
+            )
+        }
+    }
+    for output in tx.get_vout() {
+        if let Some(data) = output.get_data() {
+            println!("-- Data output = {}", HEXLOWER.encode(data));
+            continue;
         }
+        let pub_key_hash = output.get_pub_key_hash();
+        let address = convert_address(pub_key_hash);
+        println!("-- Output value = {}, to = {}", output.get_value(), address,)
     }
 }
+
+#[cfg(feature = "http")]
+fn start_http(addr: &str, blockchain: Blockchain) {
+    let listener = std::net::TcpListener::bind(addr).unwrap();
+    println!("Serving HTTP queries on {addr}");
+    rust_blockchain::http::HttpServer::new(blockchain).spawn(listener);
+}
+
+#[cfg(not(feature = "http"))]
+fn start_http(_addr: &str, _blockchain: Blockchain) {
+    panic!("ERROR: --http needs a build with the http feature")
+}
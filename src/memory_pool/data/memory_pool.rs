@@ -1,7 +1,10 @@
 use std::{collections::HashMap, sync::RwLock};
 
-use crate::Transaction;
+use crate::memory_pool::data::pool_entry::PoolEntry;
+
+// File the node keeps pending transactions in across restarts
+pub const MEMPOOL_FILE: &str = "mempool.dat";
 
 pub struct MemoryPool {
-    pub(in crate::memory_pool) inner: RwLock<HashMap<String, Transaction>>,
+    pub(in crate::memory_pool) inner: RwLock<HashMap<String, PoolEntry>>,
 }
@@ -1,2 +1,4 @@
 pub mod memory_pool;
 pub mod block_in_transit;
+pub mod pool_entry;
+pub mod replace_result;
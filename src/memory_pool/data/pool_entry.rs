@@ -0,0 +1,9 @@
+use crate::Transaction;
+
+#[derive(Clone)]
+pub struct PoolEntry {
+    pub(in crate::memory_pool) tx: Transaction,
+    pub(in crate::memory_pool) fee: i32,
+    // Unix timestamp the transaction entered the pool
+    pub(in crate::memory_pool) added_at: i64,
+}
@@ -0,0 +1,12 @@
+/// Outcome of offering a transaction to the pool with `MemoryPool::try_replace`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplaceResult {
+    // No pooled transaction spends the same inputs
+    Added,
+    // Pays a strictly higher fee than the listed pooled transactions, which were evicted
+    Replaced(Vec<String>),
+    // A conflicting pooled transaction pays at least as much
+    Rejected,
+    // Spends an output missing from the UTXO set or pays out more than it spends
+    Invalid(String),
+}
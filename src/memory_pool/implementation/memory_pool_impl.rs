@@ -1,8 +1,15 @@
-use std::{collections::HashMap, sync::RwLock};
+use std::{collections::HashMap, fs, io, path::Path, sync::RwLock};
 
+use bincode::config::standard;
 use data_encoding::HEXLOWER;
 
-use crate::{Transaction, memory_pool::MemoryPool};
+use log::info;
+
+use crate::{
+    Transaction, UTXOSet,
+    memory_pool::{MemoryPool, ReplaceResult, data::pool_entry::PoolEntry},
+    util,
+};
 
 impl MemoryPool {
     pub fn new() -> MemoryPool {
@@ -15,18 +22,150 @@ impl MemoryPool {
         self.inner.read().unwrap().contains_key(txid_hex)
     }
 
-    pub fn add(&self, tx: Transaction) {
+    /// Add a transaction with no known fee. Returns false if it conflicts with a pooled one.
+    pub fn add(&self, tx: Transaction) -> bool {
+        self.add_with_fee(tx, 0)
+    }
+
+    /// Add a transaction paying `fee`. A transaction spending an input already spent by a
+    /// pooled transaction only replaces it when it pays a strictly higher fee.
+    pub fn add_with_fee(&self, tx: Transaction, fee: i32) -> bool {
+        self.add_entry(tx, fee, util::current_timestamp()).is_some()
+    }
+
+    /// Add `tx` with its fee worked out from the outputs it spends in `utxo_set`, replacing
+    /// any conflicting pooled transactions that pay a strictly lower fee
+    pub fn try_replace(&self, tx: Transaction, utxo_set: &UTXOSet) -> ReplaceResult {
+        let fee = match Self::compute_fee(&tx, utxo_set) {
+            Ok(fee) => fee,
+            Err(reason) => return ReplaceResult::Invalid(reason),
+        };
+        match self.add_entry(tx, fee, util::current_timestamp()) {
+            None => ReplaceResult::Rejected,
+            Some(replaced) if replaced.is_empty() => ReplaceResult::Added,
+            Some(replaced) => ReplaceResult::Replaced(replaced),
+        }
+    }
+
+    fn compute_fee(tx: &Transaction, utxo_set: &UTXOSet) -> Result<i32, String> {
+        if tx.is_coinbase() {
+            return Ok(0);
+        }
+        let mut total_input: i64 = 0;
+        for vin in tx.get_vin() {
+            let Some(out) = utxo_set.get_output(vin.get_txid(), vin.get_vout()) else {
+                return Err(format!(
+                    "input {}:{} is not in the UTXO set",
+                    HEXLOWER.encode(vin.get_txid()),
+                    vin.get_vout()
+                ));
+            };
+            total_input += out.get_value() as i64;
+        }
+        let total_output: i64 = tx.get_vout().iter().map(|out| out.get_value() as i64).sum();
+        let fee = total_input - total_output;
+        if fee < 0 {
+            return Err(format!(
+                "outputs {total_output} exceed inputs {total_input}"
+            ));
+        }
+        i32::try_from(fee).map_err(|_| format!("fee {fee} is out of range"))
+    }
+
+    /// Insert `tx` unless a conflicting pooled transaction pays at least `fee`. Returns the
+    /// txid hexes of the conflicts it replaced, or None if it was rejected.
+    fn add_entry(&self, tx: Transaction, fee: i32, added_at: i64) -> Option<Vec<String>> {
+        let txid_hex = HEXLOWER.encode(tx.get_id());
+        let mut inner = self.inner.write().unwrap();
+
+        let mut conflicts = Self::find_conflicts(&inner, &tx);
+        if conflicts
+            .iter()
+            .any(|conflict_hex| inner.get(conflict_hex).unwrap().fee >= fee)
+        {
+            return None;
+        }
+        for conflict_hex in &conflicts {
+            inner.remove(conflict_hex);
+        }
+        inner.insert(txid_hex, PoolEntry { tx, fee, added_at });
+        conflicts.sort();
+        Some(conflicts)
+    }
+
+    /// Returns the txid hex of a pooled transaction spending any input also spent by `tx`
+    pub fn conflicts_with(&self, tx: &Transaction) -> Option<String> {
+        let inner = self.inner.read().unwrap();
+        Self::find_conflicts(&inner, tx).into_iter().next()
+    }
+
+    fn find_conflicts(inner: &HashMap<String, PoolEntry>, tx: &Transaction) -> Vec<String> {
+        if tx.is_coinbase() {
+            return vec![];
+        }
         let txid_hex = HEXLOWER.encode(tx.get_id());
-        self.inner.write().unwrap().insert(txid_hex, tx);
+        let mut conflicts = vec![];
+        for (pooled_hex, entry) in inner.iter() {
+            if pooled_hex.eq(&txid_hex) || entry.tx.is_coinbase() {
+                continue;
+            }
+            let double_spend = entry.tx.get_vin().iter().any(|pooled_in| {
+                tx.get_vin().iter().any(|vin| {
+                    pooled_in.get_txid().eq(vin.get_txid())
+                        && pooled_in.get_vout() == vin.get_vout()
+                })
+            });
+            if double_spend {
+                conflicts.push(pooled_hex.clone());
+            }
+        }
+        conflicts
     }
 
     pub fn get(&self, txid_hex: &str) -> Option<Transaction> {
-        if let Some(tx) = self.inner.read().unwrap().get(txid_hex) {
-            return Some(tx.clone());
+        if let Some(entry) = self.inner.read().unwrap().get(txid_hex) {
+            return Some(entry.tx.clone());
         }
         None
     }
 
+    pub fn get_fee(&self, txid_hex: &str) -> Option<i32> {
+        self.inner
+            .read()
+            .unwrap()
+            .get(txid_hex)
+            .map(|entry| entry.fee)
+    }
+
+    /// Unix timestamp the pooled transaction was added at
+    pub fn get_added_at(&self, txid_hex: &str) -> Option<i64> {
+        self.inner
+            .read()
+            .unwrap()
+            .get(txid_hex)
+            .map(|entry| entry.added_at)
+    }
+
+    pub fn set_added_at_for_test(&self, txid_hex: &str, timestamp: i64) {
+        if let Some(entry) = self.inner.write().unwrap().get_mut(txid_hex) {
+            entry.added_at = timestamp;
+        }
+    }
+
+    /// Remove transactions that have waited in the pool longer than `max_age_secs`.
+    /// Returns how many were removed.
+    pub fn evict_expired(&self, max_age_secs: i64) -> usize {
+        let now = util::current_timestamp();
+        let mut inner = self.inner.write().unwrap();
+        let before = inner.len();
+        inner.retain(|_, entry| now - entry.added_at <= max_age_secs);
+        let evicted = before - inner.len();
+        if evicted > 0 {
+            info!("Evicted {evicted} expired transactions from the memory pool");
+        }
+        evicted
+    }
+
     pub fn remove(&self, txid_hex: &str) {
         let mut inner = self.inner.write().unwrap();
         inner.remove(txid_hex);
@@ -35,12 +174,34 @@ impl MemoryPool {
     pub fn get_all(&self) -> Vec<Transaction> {
         let inner = self.inner.read().unwrap();
         let mut txs = vec![];
-        for (_, v) in inner.iter() {
-            txs.push(v.clone());
+        for (_, entry) in inner.iter() {
+            txs.push(entry.tx.clone());
         }
         txs
     }
 
+    /// Txid hex and fee of every pooled transaction, highest fee first
+    pub fn get_txids_with_fees(&self) -> Vec<(String, i32)> {
+        let inner = self.inner.read().unwrap();
+        let mut entries: Vec<(String, i32)> = inner
+            .iter()
+            .map(|(txid_hex, entry)| (txid_hex.clone(), entry.fee))
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        entries
+    }
+
+    /// Pooled transactions whose lock time allows them into a block at `block_height`
+    /// mined at `block_time`
+    pub fn get_final(&self, block_height: usize, block_time: i64) -> Vec<Transaction> {
+        let inner = self.inner.read().unwrap();
+        inner
+            .values()
+            .filter(|entry| entry.tx.is_final(block_height, block_time))
+            .map(|entry| entry.tx.clone())
+            .collect()
+    }
+
     pub fn len(&self) -> usize {
         self.inner.read().unwrap().len()
     }
@@ -48,6 +209,44 @@ impl MemoryPool {
     pub fn is_empty(&self) -> bool {
         self.inner.read().unwrap().is_empty()
     }
+
+    /// Write the pooled transactions, their fees and insertion times to `path`
+    pub fn save_to_disk(&self, path: &Path) -> io::Result<()> {
+        let entries: Vec<(Transaction, i32, i64)> = self
+            .inner
+            .read()
+            .unwrap()
+            .values()
+            .map(|entry| (entry.tx.clone(), entry.fee, entry.added_at))
+            .collect();
+        let bytes = bincode::encode_to_vec(&entries, standard())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, bytes)
+    }
+
+    /// Add the transactions saved at `path`, dropping any that spend an output no longer in
+    /// `utxo_set`. Returns how many were added; a missing file adds none.
+    pub fn load_from_disk(&self, path: &Path, utxo_set: &UTXOSet) -> io::Result<usize> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let bytes = fs::read(path)?;
+        let (entries, _): (Vec<(Transaction, i32, i64)>, _) =
+            bincode::decode_from_slice(&bytes, standard())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut loaded = 0;
+        for (tx, fee, added_at) in entries {
+            let unspent = tx.is_coinbase()
+                || tx
+                    .get_vin()
+                    .iter()
+                    .all(|vin| utxo_set.is_spendable(vin.get_txid(), vin.get_vout()));
+            if unspent && self.add_entry(tx, fee, added_at).is_some() {
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
 }
 
 impl Default for MemoryPool {
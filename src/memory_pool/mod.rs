@@ -1,7 +1,8 @@
 mod data;
 mod implementation;
 
-pub use data::memory_pool::MemoryPool;
+pub use data::memory_pool::{MEMPOOL_FILE, MemoryPool};
+pub use data::replace_result::ReplaceResult;
 pub use implementation::memory_pool_impl;
 pub use data::block_in_transit::BlockInTransit;
 pub use implementation::block_in_transit_impl;
@@ -1,4 +1,6 @@
 #[derive(Clone)]
 pub struct Node {
     pub(in crate::nodes) addr: String,
+    // Unix timestamp (seconds) of the last package received from this peer
+    pub(in crate::nodes) last_seen: i64,
 }
@@ -1,7 +1,21 @@
-use std::sync::RwLock;
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
 
 use crate::Node;
 
+// Misbehavior score at which a peer is banned automatically
+pub const BAN_SCORE_THRESHOLD: u32 = 100;
+
+// How long an automatic ban lasts
+pub const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
 pub struct Nodes {
     pub(in crate::nodes) inner: RwLock<Vec<Node>>,
+    // Banned address -> when the ban expires
+    pub(in crate::nodes) bans: RwLock<HashMap<String, Instant>>,
+    // Address -> accumulated misbehavior score
+    pub(in crate::nodes) scores: RwLock<HashMap<String, u32>>,
 }
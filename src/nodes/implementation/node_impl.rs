@@ -1,16 +1,28 @@
 use std::net::SocketAddr;
 
-use crate::nodes::Node;
+use crate::{nodes::Node, util};
 
 impl Node {
     pub fn new(addr: String) -> Node {
-        Node { addr }
+        Node {
+            addr,
+            last_seen: util::current_timestamp(),
+        }
     }
 
     pub fn get_addr(&self) -> String {
         self.addr.clone()
     }
 
+    pub fn get_last_seen(&self) -> i64 {
+        self.last_seen
+    }
+
+    /// Whether this peer was heard from within the last `max_age_secs` seconds
+    pub fn is_active(&self, max_age_secs: i64) -> bool {
+        util::current_timestamp() - self.last_seen <= max_age_secs
+    }
+
     pub fn parse_socket_addr(&self) -> SocketAddr {
         self.addr.parse().unwrap()
     }
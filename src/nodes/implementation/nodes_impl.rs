@@ -1,15 +1,31 @@
-use std::sync::RwLock;
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
 
-use crate::{Node, nodes::data::nodes::Nodes};
+use log::info;
+
+use crate::{
+    Node,
+    nodes::data::nodes::{BAN_SCORE_THRESHOLD, DEFAULT_BAN_DURATION, Nodes},
+    util,
+};
 
 impl Nodes {
     pub fn new() -> Nodes {
         Nodes {
             inner: RwLock::new(vec![]),
+            bans: RwLock::new(HashMap::new()),
+            scores: RwLock::new(HashMap::new()),
         }
     }
 
+    /// Add a peer unless it is already known or currently banned
     pub fn add_node(&self, addr: String) {
+        if self.is_banned(addr.as_str()) {
+            return;
+        }
         let mut inner = self.inner.write().unwrap();
         if !inner.iter().any(|x| x.get_addr().eq(addr.as_str())) {
             inner.push(Node::new(addr));
@@ -43,13 +59,106 @@ impl Nodes {
         self.inner.read().unwrap().is_empty()
     }
 
+    /// Record that a package was just received from `addr`. Unknown peers are ignored.
+    pub fn mark_seen(&self, addr: &str) {
+        self.set_last_seen(addr, util::current_timestamp());
+    }
+
+    pub fn set_last_seen_for_test(&self, addr: &str, timestamp: i64) {
+        self.set_last_seen(addr, timestamp);
+    }
+
+    fn set_last_seen(&self, addr: &str, timestamp: i64) {
+        let mut inner = self.inner.write().unwrap();
+        if let Some(node) = inner.iter_mut().find(|x| x.get_addr().eq(addr)) {
+            node.last_seen = timestamp;
+        }
+    }
+
+    pub fn get_last_seen(&self, addr: &str) -> Option<i64> {
+        let inner = self.inner.read().unwrap();
+        inner
+            .iter()
+            .find(|x| x.get_addr().eq(addr))
+            .map(|node| node.get_last_seen())
+    }
+
+    /// Peers heard from within the last `max_age_secs` seconds
+    pub fn get_active_nodes(&self, max_age_secs: i64) -> Vec<Node> {
+        let inner = self.inner.read().unwrap();
+        inner
+            .iter()
+            .filter(|node| node.is_active(max_age_secs))
+            .cloned()
+            .collect()
+    }
+
+    /// Remove peers not heard from within the last `max_age_secs` seconds.
+    /// Returns the number of peers removed.
+    pub fn prune_stale(&self, max_age_secs: i64) -> usize {
+        let mut inner = self.inner.write().unwrap();
+        let before = inner.len();
+        inner.retain(|node| node.is_active(max_age_secs));
+        let pruned = before - inner.len();
+        if pruned > 0 {
+            info!("Pruned {pruned} stale peers");
+        }
+        pruned
+    }
+
     pub fn node_is_known(&self, addr: &str) -> bool {
+        if self.is_banned(addr) {
+            return false;
+        }
         let inner = self.inner.read().unwrap();
         if inner.iter().any(|x| x.get_addr().eq(addr)) {
             return true;
         }
         false
     }
+
+    /// Drop `addr` from the peer list and refuse it for `duration`
+    pub fn ban(&self, addr: &str, duration: Duration) {
+        self.evict_node(addr);
+        self.scores.write().unwrap().remove(addr);
+        let mut bans = self.bans.write().unwrap();
+        bans.insert(addr.to_string(), Instant::now() + duration);
+        info!("Banned peer {addr} for {}s", duration.as_secs());
+    }
+
+    /// Whether `addr` is under a ban that has not expired yet
+    pub fn is_banned(&self, addr: &str) -> bool {
+        let expires_at = match self.bans.read().unwrap().get(addr) {
+            Some(expires_at) => *expires_at,
+            None => return false,
+        };
+        if Instant::now() < expires_at {
+            return true;
+        }
+        self.bans.write().unwrap().remove(addr);
+        false
+    }
+
+    /// Add `score` to the misbehavior score of `addr`, banning it for
+    /// `DEFAULT_BAN_DURATION` once `BAN_SCORE_THRESHOLD` is reached. Returns true if banned.
+    pub fn misbehaving(&self, addr: &str, score: u32) -> bool {
+        let total = {
+            let mut scores = self.scores.write().unwrap();
+            let total = scores.entry(addr.to_string()).or_insert(0);
+            *total = total.saturating_add(score);
+            *total
+        };
+        info!("Peer {addr} misbehaved, score is now {total}");
+        if total >= BAN_SCORE_THRESHOLD {
+            self.ban(addr, DEFAULT_BAN_DURATION);
+            return true;
+        }
+        false
+    }
+
+    pub fn get_misbehavior_score(&self, addr: &str) -> u32 {
+        self.scores.read().unwrap().get(addr).copied().unwrap_or(0)
+    }
 }
 
 impl Default for Nodes {
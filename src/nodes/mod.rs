@@ -2,5 +2,5 @@ mod data;
 mod implementation;
 
 pub use data::node::Node;
-pub use data::nodes::Nodes;
+pub use data::nodes::{Nodes, BAN_SCORE_THRESHOLD, DEFAULT_BAN_DURATION};
 pub use implementation::node_impl;
@@ -1 +1,2 @@
 pub mod proof_of_work;
+pub mod pow_algorithm;
@@ -0,0 +1,11 @@
+use bincode::{Decode, Encode};
+
+/// Hash function a block's proof-of-work is computed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Encode, Decode)]
+pub enum PowAlgorithm {
+    // SHA256 of the block data, the scheme every block so far has been mined with
+    #[default]
+    Sha256,
+    // SHA256 of the SHA256 of the block data
+    DoubleSha256,
+}
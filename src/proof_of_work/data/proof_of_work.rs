@@ -1,13 +1,26 @@
-use crate::{block::Block, common::BincodeBigInt};
+use crate::{block::Block, common::BincodeBigInt, proof_of_work::PowAlgorithm};
 
 // Maximum number of nonce iterations to try
 pub const MAX_NONCE: i64 = i64::MAX;
 
+// Number of nonces tried between progress reports in `run_with_control`
+pub const PROGRESS_INTERVAL: i64 = 10_000;
+
 // Target difficulty - number of leading zeros in hash (adjustable)
 pub const TARGET_BITS: usize = 24;
 
+// Bounds for retargeted difficulty
+pub const MIN_TARGET_BITS: usize = 1;
+pub const MAX_TARGET_BITS: usize = 255;
+
+// Largest difficulty change (in bits) a single retarget may apply
+pub const MAX_RETARGET_STEP: usize = 2;
+
 #[derive(Clone, bincode::Encode, bincode::Decode)]
 pub struct ProofOfWork {
     pub(in crate::proof_of_work) block: Block,
     pub(in crate::proof_of_work) target: BincodeBigInt,
+    pub(in crate::proof_of_work) target_bits: usize,
+    // Hash function nonces are tried with
+    pub(in crate::proof_of_work) algorithm: PowAlgorithm,
 }
@@ -1 +1,2 @@
+pub mod pow_algorithm_impl;
 pub mod proof_of_work_impl;
@@ -0,0 +1,11 @@
+use crate::{proof_of_work::PowAlgorithm, util};
+
+impl PowAlgorithm {
+    /// Hash `data` the way this algorithm does
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            PowAlgorithm::Sha256 => util::sha256_digest(data),
+            PowAlgorithm::DoubleSha256 => util::sha256d(data),
+        }
+    }
+}
@@ -1,21 +1,82 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use data_encoding::HEXLOWER;
+use log::info;
 use num_bigint::{BigInt, Sign};
 
 use crate::{
-    block::Block,
+    block::{Block, BlockHeader},
     common::BincodeBigInt,
-    proof_of_work::{MAX_NONCE, ProofOfWork, TARGET_BITS},
-    util,
+    config::GLOBAL_CONFIG,
+    proof_of_work::{
+        MAX_NONCE, MAX_RETARGET_STEP, MAX_TARGET_BITS, MIN_TARGET_BITS, PROGRESS_INTERVAL,
+        PowAlgorithm, ProofOfWork,
+    },
 };
 
 impl ProofOfWork {
-    /// Create a new proof-of-work instance for the given block
-    pub fn new_proof_of_work(block: Block) -> ProofOfWork {
-        // Calculate target: 1 << (256 - TARGET_BITS)
-        let target_value = BigInt::from(1) << (256 - TARGET_BITS);
-        let target = BincodeBigInt::new(target_value);
+    /// Create a new proof-of-work instance for the given block and difficulty
+    pub fn new_proof_of_work(block: Block, target_bits: usize) -> ProofOfWork {
+        Self::new_proof_of_work_with_algorithm(block, target_bits, PowAlgorithm::default())
+    }
+
+    /// Like `new_proof_of_work`, hashing with `algorithm` instead of the default one.
+    /// `target_bits` is clamped to `MIN_TARGET_BITS..=MAX_TARGET_BITS`.
+    pub fn new_proof_of_work_with_algorithm(
+        block: Block,
+        target_bits: usize,
+        algorithm: PowAlgorithm,
+    ) -> ProofOfWork {
+        let target_bits = target_bits.clamp(MIN_TARGET_BITS, MAX_TARGET_BITS);
+        let target = BincodeBigInt::new(Self::target_for(target_bits));
 
-        ProofOfWork { block, target }
+        ProofOfWork {
+            block,
+            target,
+            target_bits,
+            algorithm,
+        }
+    }
+
+    /// Create a new proof-of-work instance using the configured difficulty, `TARGET_BITS`
+    /// unless `BLOCKCHAIN_TARGET_BITS` overrides it
+    pub fn new_proof_of_work_default(block: Block) -> ProofOfWork {
+        Self::new_proof_of_work(block, GLOBAL_CONFIG.get_target_bits())
+    }
+
+    /// Compute the difficulty for the next block from the timestamps of the most recent
+    /// blocks (oldest first), aiming for one block every `block_interval` seconds.
+    /// Each bit doubles the expected work, so the adjustment is log2(target / actual),
+    /// clamped to `MAX_RETARGET_STEP` bits per retarget.
+    ///
+    /// A standalone helper that nothing on the chain calls: blocks do not record the
+    /// difficulty they were mined at, so a chain mines and checks every block at its one
+    /// fixed `target_bits`.
+    pub fn calculate_target_bits(
+        current_bits: usize,
+        timestamps: &[i64],
+        block_interval: i64,
+    ) -> usize {
+        if timestamps.len() < 2 || block_interval <= 0 {
+            return current_bits;
+        }
+        let intervals = (timestamps.len() - 1) as f64;
+        let elapsed = (timestamps[timestamps.len() - 1] - timestamps[0]).max(1) as f64;
+        let actual_interval = elapsed / intervals;
+
+        let step = (block_interval as f64 / actual_interval).log2().round() as i64;
+        let step = step.clamp(-(MAX_RETARGET_STEP as i64), MAX_RETARGET_STEP as i64);
+        let new_bits =
+            (current_bits as i64 + step).clamp(MIN_TARGET_BITS as i64, MAX_TARGET_BITS as i64);
+        new_bits as usize
+    }
+
+    pub fn get_target_bits(&self) -> usize {
+        self.target_bits
+    }
+
+    pub fn get_algorithm(&self) -> PowAlgorithm {
+        self.algorithm
     }
 
     pub fn get_block(&self) -> &Block {
@@ -26,27 +87,67 @@ impl ProofOfWork {
         &self.target
     }
 
-    /// Prepare data for hashing by combining block fields with nonce
-    pub fn prepare_data(&self, nonce: i64) -> Vec<u8> {
+    // Calculate target: 1 << (256 - target_bits)
+    fn target_for(target_bits: usize) -> BigInt {
+        BigInt::from(1) << (256 - target_bits)
+    }
+
+    fn hash_data(
+        pre_block_hash: &str,
+        merkle_root: &[u8],
+        timestamp: i64,
+        target_bits: usize,
+        nonce: i64,
+    ) -> Vec<u8> {
         let mut data = Vec::new();
 
         // Combine block data with nonce
-        data.extend_from_slice(self.block.get_pre_block_hash().as_bytes());
-        data.extend_from_slice(&self.block.hash_transactions());
-        data.extend_from_slice(&self.block.get_timestamp().to_be_bytes());
-        data.extend_from_slice(&(TARGET_BITS as u64).to_be_bytes());
+        data.extend_from_slice(pre_block_hash.as_bytes());
+        data.extend_from_slice(merkle_root);
+        data.extend_from_slice(&timestamp.to_be_bytes());
+        data.extend_from_slice(&(target_bits as u64).to_be_bytes());
         data.extend_from_slice(&nonce.to_be_bytes());
 
         data
     }
 
+    /// Prepare data for hashing by combining block fields with nonce
+    pub fn prepare_data(&self, nonce: i64) -> Vec<u8> {
+        Self::hash_data(
+            self.block.get_pre_block_hash(),
+            &self.block.hash_transactions(),
+            self.block.get_timestamp(),
+            self.target_bits,
+            nonce,
+        )
+    }
+
     pub fn run(&self) -> (i64, String) {
+        let never_stop = AtomicBool::new(false);
+        self.run_with_control(&never_stop, |_| {})
+            .expect("mining without a stop signal always completes")
+    }
+
+    /// Mine like `run`, reporting the current nonce to `on_progress` every
+    /// `PROGRESS_INTERVAL` attempts. Returns `None` as soon as `should_stop` is set.
+    pub fn run_with_control(
+        &self,
+        should_stop: &AtomicBool,
+        on_progress: impl Fn(i64),
+    ) -> Option<(i64, String)> {
         let mut nonce = 0;
         let mut hash = Vec::new();
         println!("Mining the block");
         while nonce < MAX_NONCE {
+            if should_stop.load(Ordering::Relaxed) {
+                info!("Mining cancelled at nonce {nonce}");
+                return None;
+            }
+            if nonce > 0 && nonce % PROGRESS_INTERVAL == 0 {
+                on_progress(nonce);
+            }
             let data = self.prepare_data(nonce);
-            hash = util::sha256_digest(data.as_slice());
+            hash = self.algorithm.digest(data.as_slice());
             let hash_int = BigInt::from_bytes_be(Sign::Plus, hash.as_slice());
 
             if hash_int < *self.target.as_bigint() {
@@ -57,15 +158,40 @@ impl ProofOfWork {
             }
         }
         println!();
-        (nonce, HEXLOWER.encode(hash.as_slice()))
+        Some((nonce, HEXLOWER.encode(hash.as_slice())))
     }
 
     /// Validate that a block's hash satisfies the proof-of-work requirement
     pub fn validate(&self) -> bool {
         let data = self.prepare_data(self.block.get_nonce());
-        let hash = util::sha256_digest(data.as_slice());
+        let hash = self.algorithm.digest(data.as_slice());
         let hash_int = BigInt::from_bytes_be(Sign::Plus, hash.as_slice());
 
         hash_int < *self.target.as_bigint()
     }
+
+    /// Validate proof-of-work from a block header alone, without its transactions. Agrees
+    /// with `validate` on the full block.
+    pub fn validate_header(header: &BlockHeader, target_bits: usize) -> bool {
+        let hash = Self::header_digest(header, target_bits);
+        let hash_int = BigInt::from_bytes_be(Sign::Plus, hash.as_slice());
+
+        hash_int < Self::target_for(target_bits)
+    }
+
+    /// Hex hash a header mined at `target_bits` should carry, recomputed from its fields
+    pub fn header_hash(header: &BlockHeader, target_bits: usize) -> String {
+        HEXLOWER.encode(Self::header_digest(header, target_bits).as_slice())
+    }
+
+    fn header_digest(header: &BlockHeader, target_bits: usize) -> Vec<u8> {
+        let data = Self::hash_data(
+            header.get_pre_block_hash(),
+            header.get_merkle_root(),
+            header.get_timestamp(),
+            target_bits,
+            header.get_nonce(),
+        );
+        PowAlgorithm::default().digest(data.as_slice())
+    }
 }
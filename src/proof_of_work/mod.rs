@@ -2,4 +2,5 @@ mod data;
 mod implementation;
 
 // Re-export the main struct and constants
-pub use data::proof_of_work::{ProofOfWork, MAX_NONCE, TARGET_BITS};
+pub use data::proof_of_work::{ProofOfWork, MAX_NONCE, MAX_RETARGET_STEP, MAX_TARGET_BITS, MIN_TARGET_BITS, PROGRESS_INTERVAL, TARGET_BITS};
+pub use data::pow_algorithm::PowAlgorithm;
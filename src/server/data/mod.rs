@@ -1,2 +1,4 @@
+pub mod orphan_pool;
 pub mod server;
 pub mod server_enums;
+pub mod rejection_log;
@@ -0,0 +1,8 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use crate::Block;
+
+/// Blocks received before their parent, keyed by the missing parent's hash
+pub struct OrphanPool {
+    pub(in crate::server) inner: RwLock<HashMap<String, Vec<Block>>>,
+}
@@ -0,0 +1,9 @@
+use std::{collections::VecDeque, sync::RwLock};
+
+use crate::server::RejectReason;
+
+/// The most recently rejected blocks with why, oldest first, capped at `capacity`
+pub struct RejectionLog {
+    pub(in crate::server) inner: RwLock<VecDeque<(String, RejectReason)>>,
+    pub(in crate::server) capacity: usize,
+}
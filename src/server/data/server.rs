@@ -1,6 +1,15 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, atomic::AtomicUsize},
+    time::Duration,
+};
+
 use once_cell::sync::Lazy;
 
-use crate::{BlockInTransit, Blockchain, MemoryPool, Nodes};
+use crate::{
+    BlockInTransit, Blockchain, MemoryPool, Nodes,
+    server::{OrphanPool, RejectionLog},
+};
 
 pub const NODE_VERSION: usize = 1;
 pub const CENTRAL_NODE: &str = "127.0.0.1:2001";
@@ -19,8 +28,37 @@ pub static GLOBAL_MEMORY_POOL: Lazy<MemoryPool> = Lazy::new(MemoryPool::new);
 
 pub static GLOBAL_BLOCKS_IN_TRANSIT: Lazy<BlockInTransit> = Lazy::new(BlockInTransit::new);
 
+pub static GLOBAL_ORPHAN_POOL: Lazy<OrphanPool> = Lazy::new(OrphanPool::new);
+
+pub static GLOBAL_REJECTION_LOG: Lazy<RejectionLog> = Lazy::new(RejectionLog::new);
+
 pub const TCP_WRITE_TIMEOUT: u64 = 1000;
+// Wait before the second connection attempt to a peer, doubled before each later one
+pub const CONNECT_RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+
+// Most peer addresses sent or accepted in a single Addr package
+pub const MAX_ADDR_ENTRIES: usize = 1000;
+
+// Most block headers sent in a single Headers package
+pub const MAX_HEADERS_ENTRIES: usize = 2000;
+
+// Rejected blocks remembered for inspection
+pub const MAX_RECENT_REJECTIONS: usize = 100;
+
+// Misbehavior scores charged to peers; BAN_SCORE_THRESHOLD triggers a ban
+pub const MALFORMED_PACKAGE_SCORE: u32 = 50;
+pub const INVALID_CHAIN_SCORE: u32 = 100;
+pub const INVALID_BLOCK_SCORE: u32 = 100;
+
+// How often background threads wake up to check for shutdown
+pub const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct Server {
     pub(in crate::server) blockchain: Blockchain,
+    // Peer to download the whole chain from when the local chain is empty
+    pub(in crate::server) sync_from: Option<String>,
+    // Connection handler threads currently running
+    pub(in crate::server) active_connections: Arc<AtomicUsize>,
+    // Where the memory pool is loaded from on startup and saved to on shutdown
+    pub(in crate::server) mempool_file: Option<PathBuf>,
 }
@@ -12,8 +12,11 @@ pub enum Package {
         addr_from: String,
         block: Vec<u8>,
     },
+    // Ask for the hashes of the blocks after the newest `locator` entry the peer has, see
+    // `Blockchain::block_locator`
     GetBlocks {
         addr_from: String,
+        locator: Vec<String>,
     },
     GetData {
         addr_from: String,
@@ -33,5 +36,65 @@ pub enum Package {
         addr_from: String,
         version: usize,
         best_height: usize,
+        // The sender's network; peers on another network are disconnected
+        magic: u32,
     },
+    GetAddr {
+        addr_from: String,
+    },
+    Addr {
+        addr_from: String,
+        nodes: Vec<String>,
+    },
+    Ping {
+        nonce: u64,
+    },
+    Pong {
+        nonce: u64,
+    },
+    GetHeaders {
+        addr_from: String,
+        from_height: usize,
+    },
+    Headers {
+        addr_from: String,
+        headers: Vec<Vec<u8>>,
+    },
+    // Ask for the header of one block so its proof-of-work can be checked without its
+    // transactions
+    GetBlockHeader {
+        addr_from: String,
+        hash: Vec<u8>,
+    },
+    // Reply to `GetBlockHeader`; `header` is None when the block is unknown
+    BlockHeader {
+        addr_from: String,
+        hash: Vec<u8>,
+        header: Option<Vec<u8>>,
+    },
+    // Ask for the ids of every transaction waiting in the peer's memory pool
+    GetMempool {
+        addr_from: String,
+    },
+    // Reply to `GetMempool`
+    MempoolInv {
+        addr_from: String,
+        txids: Vec<Vec<u8>>,
+    },
+}
+
+/// Why a received block was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    // The hash is not the block's own or does not meet the proof-of-work target
+    BadProofOfWork,
+    // The height does not follow the parent's
+    BadParent,
+    // A transaction fails verification, or the coinbase is missing, misplaced or pays out
+    // too much
+    InvalidTransaction,
+    // Repeats a transaction within the block or one already on its chain
+    Duplicate,
+    // Sits at a checkpointed height but is not the checkpointed block
+    CheckpointMismatch,
 }
@@ -1 +1,4 @@
+pub mod orphan_pool_impl;
+pub mod rejection_log_impl;
+pub mod server_enums_impl;
 pub mod server_impl;
@@ -0,0 +1,59 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use crate::{Block, server::OrphanPool};
+
+impl OrphanPool {
+    pub fn new() -> OrphanPool {
+        OrphanPool {
+            inner: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Buffer `block` until its parent arrives. Returns false if it is already buffered.
+    pub fn add(&self, block: Block) -> bool {
+        let mut inner = self.inner.write().unwrap();
+        let children = inner
+            .entry(block.get_pre_block_hash().to_string())
+            .or_default();
+        if children
+            .iter()
+            .any(|child| child.get_hash() == block.get_hash())
+        {
+            return false;
+        }
+        children.push(block);
+        true
+    }
+
+    /// Remove and return every buffered block whose parent is `parent_hash`
+    pub fn take_children(&self, parent_hash: &str) -> Vec<Block> {
+        self.inner
+            .write()
+            .unwrap()
+            .remove(parent_hash)
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, block_hash: &str) -> bool {
+        self.inner
+            .read()
+            .unwrap()
+            .values()
+            .flatten()
+            .any(|block| block.get_hash() == block_hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for OrphanPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
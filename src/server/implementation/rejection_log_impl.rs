@@ -0,0 +1,48 @@
+use std::{collections::VecDeque, sync::RwLock};
+
+use log::info;
+
+use crate::server::{MAX_RECENT_REJECTIONS, RejectReason, RejectionLog};
+
+impl RejectionLog {
+    pub fn new() -> RejectionLog {
+        RejectionLog::with_capacity(MAX_RECENT_REJECTIONS)
+    }
+
+    pub fn with_capacity(capacity: usize) -> RejectionLog {
+        RejectionLog {
+            inner: RwLock::new(VecDeque::new()),
+            capacity,
+        }
+    }
+
+    /// Log the rejection of block `block_hash` and remember it, forgetting the oldest
+    /// rejection once `capacity` are kept
+    pub fn record(&self, block_hash: &str, reason: RejectReason, detail: &str) {
+        info!("Rejected block {block_hash} ({reason:?}): {detail}");
+        let mut inner = self.inner.write().unwrap();
+        inner.push_back((block_hash.to_string(), reason));
+        while inner.len() > self.capacity {
+            inner.pop_front();
+        }
+    }
+
+    /// Hashes of the remembered rejected blocks with why, oldest first
+    pub fn recent(&self) -> Vec<(String, RejectReason)> {
+        self.inner.read().unwrap().iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().unwrap().is_empty()
+    }
+}
+
+impl Default for RejectionLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
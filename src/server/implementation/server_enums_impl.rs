@@ -0,0 +1,24 @@
+use crate::server::Package;
+
+impl Package {
+    /// The listening address the sending peer reported, if the package carries one
+    pub fn get_addr_from(&self) -> Option<&str> {
+        match self {
+            Package::Block { addr_from, .. }
+            | Package::GetBlocks { addr_from, .. }
+            | Package::GetData { addr_from, .. }
+            | Package::Inv { addr_from, .. }
+            | Package::Tx { addr_from, .. }
+            | Package::Version { addr_from, .. }
+            | Package::GetAddr { addr_from }
+            | Package::Addr { addr_from, .. }
+            | Package::GetHeaders { addr_from, .. }
+            | Package::Headers { addr_from, .. }
+            | Package::GetBlockHeader { addr_from, .. }
+            | Package::BlockHeader { addr_from, .. }
+            | Package::GetMempool { addr_from }
+            | Package::MempoolInv { addr_from, .. } => Some(addr_from.as_str()),
+            Package::Ping { .. } | Package::Pong { .. } => None,
+        }
+    }
+}
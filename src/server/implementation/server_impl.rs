@@ -1,38 +1,242 @@
-use std::{net::TcpListener, thread};
+use std::{
+    io::ErrorKind,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-use log::error;
+use log::{error, info};
 
 use crate::{
-    Blockchain,
+    Block, Blockchain, UTXOSet,
+    config::GLOBAL_CONFIG,
     server::{
-        Server,
-        data::server::CENTRAL_NODE,
-        server_utils::{send_version, serve},
+        OpType, RejectReason, Server,
+        data::server::{
+            GLOBAL_MEMORY_POOL, GLOBAL_NODES, GLOBAL_ORPHAN_POOL, GLOBAL_REJECTION_LOG,
+            SHUTDOWN_POLL_INTERVAL,
+        },
+        server_utils::{
+            connect_block, filter_unknown_items, mine_pending_transactions, send_get_blocks,
+            send_version, serve,
+        },
     },
+    util,
 };
 
 impl Server {
     pub fn new(blockchain: Blockchain) -> Server {
-        Server { blockchain }
+        Server {
+            blockchain,
+            sync_from: None,
+            active_connections: Arc::new(AtomicUsize::new(0)),
+            mempool_file: None,
+        }
+    }
+
+    /// Create a server that, while its chain is empty, syncs every block from `sync_from`
+    pub fn new_with_sync_from(blockchain: Blockchain, sync_from: String) -> Server {
+        Server {
+            blockchain,
+            sync_from: Some(sync_from),
+            active_connections: Arc::new(AtomicUsize::new(0)),
+            mempool_file: None,
+        }
+    }
+
+    /// Persist the memory pool in `path` across restarts
+    pub fn with_mempool_file(mut self, path: PathBuf) -> Server {
+        self.mempool_file = Some(path);
+        self
     }
 
     pub fn run(&self, addr: &str) {
+        self.run_with_shutdown(addr, Arc::new(AtomicBool::new(false)));
+    }
+
+    /// Serve connections on `addr` until `shutdown` is set, then close open connections,
+    /// join their handler threads and return
+    pub fn run_with_shutdown(&self, addr: &str, shutdown: Arc<AtomicBool>) {
         let listener = TcpListener::bind(addr).unwrap();
+        listener.set_nonblocking(true).unwrap();
+        self.load_mempool();
+
+        let interval = Duration::from_secs(GLOBAL_CONFIG.get_heartbeat_interval_secs());
+        let heartbeat = self.spawn_heartbeat(interval, shutdown.clone());
 
-        if !addr.eq(CENTRAL_NODE) {
-            let best_height = self.blockchain.get_best_height();
-            send_version(CENTRAL_NODE, best_height);
+        if let Some(peer) = self.sync_from.as_deref()
+            && self.blockchain.is_empty()
+        {
+            info!("Local chain is empty, syncing from {peer}");
+            send_get_blocks(peer, self.blockchain.block_locator());
+        } else {
+            self.connect_to_seeds(addr);
         }
-        for stream in listener.incoming() {
-            let blockchain = self.blockchain.clone();
-            thread::spawn(move || match stream {
-                Ok(stream) => {
-                    let _ = serve(blockchain, stream);
+
+        let max_connections = GLOBAL_CONFIG.get_max_connections();
+        let mut handlers: Vec<(JoinHandle<()>, TcpStream)> = vec![];
+        while !shutdown.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, peer_addr)) => {
+                    if self.active_connections() >= max_connections {
+                        info!("Rejecting {peer_addr}: {max_connections} connections already open");
+                        let _ = stream.shutdown(Shutdown::Both);
+                        continue;
+                    }
+                    let _ = stream.set_nonblocking(false);
+                    let Ok(stream_handle) = stream.try_clone() else {
+                        error!("Error cloning connection stream");
+                        continue;
+                    };
+                    let blockchain = self.blockchain.clone();
+                    let active_connections = self.active_connections.clone();
+                    active_connections.fetch_add(1, Ordering::SeqCst);
+                    let handler = thread::spawn(move || {
+                        let _ = serve(blockchain, stream);
+                        active_connections.fetch_sub(1, Ordering::SeqCst);
+                    });
+                    handlers.push((handler, stream_handle));
+                    handlers.retain(|(handler, _)| !handler.is_finished());
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(SHUTDOWN_POLL_INTERVAL);
                 }
                 Err(e) => {
                     error!("Error accepting connection: {e}");
                 }
-            });
+            }
+        }
+
+        info!("Shutting down server on {addr}");
+        for (handler, stream) in handlers {
+            // Unblock handlers still waiting on a peer that keeps its connection open
+            let _ = stream.shutdown(Shutdown::Both);
+            let _ = handler.join();
+        }
+        let _ = heartbeat.join();
+        self.save_mempool();
+        if let Err(e) = self.blockchain.flush() {
+            error!("Error flushing blockchain on shutdown: {e}");
+        }
+    }
+
+    fn load_mempool(&self) {
+        let Some(path) = self.mempool_file.as_deref() else {
+            return;
+        };
+        let utxo_set = UTXOSet::new(self.blockchain.clone());
+        match GLOBAL_MEMORY_POOL.load_from_disk(path, &utxo_set) {
+            Ok(loaded) => info!(
+                "Loaded {loaded} pending transactions from {}",
+                path.display()
+            ),
+            Err(e) => error!("Error loading memory pool from {}: {e}", path.display()),
+        }
+    }
+
+    fn save_mempool(&self) {
+        let Some(path) = self.mempool_file.as_deref() else {
+            return;
+        };
+        match GLOBAL_MEMORY_POOL.save_to_disk(path) {
+            Ok(()) => info!(
+                "Saved {} pending transactions to {}",
+                GLOBAL_MEMORY_POOL.len(),
+                path.display()
+            ),
+            Err(e) => error!("Error saving memory pool to {}: {e}", path.display()),
+        }
+    }
+
+    /// Announce ourselves to every configured seed node other than `addr`, remembering
+    /// each one as a peer
+    fn connect_to_seeds(&self, addr: &str) {
+        let best_height = self.blockchain.get_best_height();
+        for seed in GLOBAL_CONFIG.get_seed_nodes() {
+            if seed.eq(addr) {
+                continue;
+            }
+            if seed.parse::<SocketAddr>().is_err() {
+                error!("Ignoring unparsable seed node {seed}");
+                continue;
+            }
+            GLOBAL_NODES.add_node(seed.clone());
+            send_version(seed.as_str(), best_height);
         }
     }
+
+    /// Number of connections currently being served
+    pub fn active_connections(&self) -> usize {
+        self.active_connections.load(Ordering::SeqCst)
+    }
+
+    /// The announced `items` of `op_type` this node does not have yet
+    pub fn filter_unknown_items(&self, op_type: OpType, items: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        filter_unknown_items(&self.blockchain, &op_type, items)
+    }
+
+    /// Number of received blocks still waiting for their parent
+    pub fn orphan_count(&self) -> usize {
+        GLOBAL_ORPHAN_POOL.len()
+    }
+
+    /// Hashes of the most recently rejected blocks with why, oldest first
+    pub fn recent_rejections(&self) -> Vec<(String, RejectReason)> {
+        GLOBAL_REJECTION_LOG.recent()
+    }
+
+    /// Add a block received out of band, buffering it if its parent is not known yet
+    pub fn submit_block(&self, block: Block) -> bool {
+        connect_block(
+            &self.blockchain,
+            &GLOBAL_ORPHAN_POOL,
+            &GLOBAL_MEMORY_POOL,
+            block,
+        )
+    }
+
+    /// Mine the pooled transactions into a block if there are enough of them and a mining
+    /// address is configured, as the node does whenever it accepts a transaction
+    pub fn try_mine(&self) -> Option<Block> {
+        mine_pending_transactions(&self.blockchain, &GLOBAL_MEMORY_POOL)
+    }
+
+    /// One-line node status: best height, mempool size, peer count and tip age
+    pub fn status_line(&self) -> String {
+        let tip_hash = self.blockchain.get_tip_hash();
+        let (best_height, tip_age) = match self.blockchain.get_block(tip_hash.as_bytes()) {
+            Some(tip) => (
+                tip.get_height().to_string(),
+                format!("{}s", util::current_timestamp() - tip.get_timestamp()),
+            ),
+            None => (String::from("unknown"), String::from("unknown")),
+        };
+        format!(
+            "Heartbeat: best_height = {best_height}, mempool = {}, peers = {}, tip_age = {tip_age}",
+            GLOBAL_MEMORY_POOL.len(),
+            GLOBAL_NODES.len(),
+        )
+    }
+
+    /// Log `status_line` and evict expired memory pool transactions every `interval` on a
+    /// background thread until `shutdown` is set
+    pub fn spawn_heartbeat(&self, interval: Duration, shutdown: Arc<AtomicBool>) -> JoinHandle<()> {
+        let server = Server::new(self.blockchain.clone());
+        thread::spawn(move || {
+            let mut last_beat = Instant::now();
+            while !shutdown.load(Ordering::SeqCst) {
+                thread::sleep(SHUTDOWN_POLL_INTERVAL.min(interval));
+                if last_beat.elapsed() >= interval {
+                    GLOBAL_MEMORY_POOL.evict_expired(GLOBAL_CONFIG.get_mempool_expiry_secs());
+                    info!("{}", server.status_line());
+                    last_beat = Instant::now();
+                }
+            }
+        })
+    }
 }
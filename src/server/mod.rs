@@ -3,6 +3,10 @@ mod implementation;
 pub mod server_utils;
 
 pub use data::server::*;
+pub use data::orphan_pool::OrphanPool;
+pub use data::rejection_log::RejectionLog;
+pub use implementation::orphan_pool_impl;
+pub use implementation::rejection_log_impl;
 pub use implementation::server_impl;
 pub use data::server_enums::*;
 pub use server_utils::*;
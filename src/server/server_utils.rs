@@ -1,39 +1,73 @@
 use std::{
     error::Error,
-    io::{BufReader, Write},
+    io::{self, BufReader, ErrorKind, Read, Write},
     net::{Shutdown, SocketAddr, TcpStream},
+    thread,
     time::Duration,
 };
 
+use bincode::error::DecodeError;
 use data_encoding::HEXLOWER;
 use log::{error, info};
 
 use crate::{
-    Block, Blockchain, Transaction, UTXOSet,
-    config::GLOBAL_CONFIG,
+    Block, BlockHeader, Blockchain, GENESIS_PRE_BLOCK_HASH, MemoryPool, ProofOfWork, ReplaceResult,
+    Transaction, UTXOSet,
+    config::{GLOBAL_CONFIG, MAX_PACKAGE_SIZE_CEILING},
     server::{
-        OpType, Package,
+        OpType, OrphanPool, Package, RejectReason,
         data::server::{
-            CENTRAL_NODE, GLOBAL_BLOCKS_IN_TRANSIT, GLOBAL_MEMORY_POOL, GLOBAL_NODES, NODE_VERSION,
-            TCP_WRITE_TIMEOUT, TRANSACTION_THRESHOLD,
+            CENTRAL_NODE, CONNECT_RETRY_BASE_DELAY, GLOBAL_BLOCKS_IN_TRANSIT, GLOBAL_MEMORY_POOL,
+            GLOBAL_NODES, GLOBAL_ORPHAN_POOL, GLOBAL_REJECTION_LOG, INVALID_BLOCK_SCORE,
+            INVALID_CHAIN_SCORE, MALFORMED_PACKAGE_SCORE, MAX_ADDR_ENTRIES, MAX_HEADERS_ENTRIES,
+            NODE_VERSION, TCP_WRITE_TIMEOUT, TRANSACTION_THRESHOLD,
         },
     },
+    util,
 };
 
+/// Connect to `addr`, retrying up to the configured number of attempts with a delay of
+/// `CONNECT_RETRY_BASE_DELAY` doubled after every failure. Returns the last error if every
+/// attempt fails.
+pub fn connect_with_retry(addr: SocketAddr) -> io::Result<TcpStream> {
+    let attempts = GLOBAL_CONFIG.get_connect_attempts();
+    let mut delay = CONNECT_RETRY_BASE_DELAY;
+    let mut attempt = 1;
+    loop {
+        match TcpStream::connect(addr) {
+            Ok(stream) => return Ok(stream),
+            Err(e) if attempt >= attempts => return Err(e),
+            Err(e) => {
+                info!("Connecting to {addr} failed ({e}), retrying in {delay:?}");
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
 pub fn send_data(addr: SocketAddr, pkg: Package) {
+    let _ = try_send_data(addr, pkg);
+}
+
+/// Like `send_data`, but reports whether `pkg` could be delivered to `addr`
+fn try_send_data(addr: SocketAddr, pkg: Package) -> io::Result<()> {
     info!("send package: {:?}", &pkg);
-    let stream = TcpStream::connect(addr);
-    if stream.is_err() {
-        error!("The {addr} is not valid");
+    let mut stream = match connect_with_retry(addr) {
+        Ok(stream) => stream,
+        Err(e) => {
+            error!("The {addr} is not valid");
 
-        GLOBAL_NODES.evict_node(addr.to_string().as_str());
-        return;
-    }
-    let mut stream = stream.unwrap();
+            GLOBAL_NODES.evict_node(addr.to_string().as_str());
+            return Err(e);
+        }
+    };
     let _ = stream.set_write_timeout(Option::from(Duration::from_millis(TCP_WRITE_TIMEOUT)));
-    let serialized = bincode::encode_to_vec(&pkg, bincode::config::standard()).unwrap();
-    let _ = stream.write_all(&serialized);
-    let _ = stream.flush();
+    let serialized = bincode::encode_to_vec(&pkg, bincode::config::standard())
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    stream.write_all(&serialized)?;
+    stream.flush()
 }
 
 pub fn send_inv(addr: &str, op_type: OpType, blocks: &[Vec<u8>]) {
@@ -61,16 +95,19 @@ pub fn send_block(addr: &str, block: &Block) {
     );
 }
 
-pub fn send_tx(addr: &str, tx: &Transaction) {
-    let socket_addr = addr.parse().unwrap();
+/// Send `tx` to `addr`, failing if the address is unparsable or the node cannot be reached
+pub fn send_tx(addr: &str, tx: &Transaction) -> io::Result<()> {
+    let socket_addr = addr
+        .parse()
+        .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
     let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
-    send_data(
+    try_send_data(
         socket_addr,
         Package::Tx {
             addr_from: node_addr,
             transaction: tx.serialize(),
         },
-    );
+    )
 }
 
 pub fn send_version(addr: &str, height: usize) {
@@ -82,6 +119,30 @@ pub fn send_version(addr: &str, height: usize) {
             addr_from: node_addr,
             version: NODE_VERSION,
             best_height: height,
+            magic: GLOBAL_CONFIG.get_network_magic(),
+        },
+    );
+}
+
+pub fn send_get_addr(addr: &str) {
+    let socket_addr = addr.parse().unwrap();
+    let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
+    send_data(
+        socket_addr,
+        Package::GetAddr {
+            addr_from: node_addr,
+        },
+    );
+}
+
+pub fn send_addr(addr: &str, nodes: &[String]) {
+    let socket_addr = addr.parse().unwrap();
+    let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
+    send_data(
+        socket_addr,
+        Package::Addr {
+            addr_from: node_addr,
+            nodes: nodes.to_vec(),
         },
     );
 }
@@ -99,44 +160,488 @@ pub fn send_get_data(addr: &str, op_type: OpType, id: &[u8]) {
     );
 }
 
-pub fn send_get_blocks(addr: &str) {
+/// Ask `addr` for the blocks after the newest `locator` hash it has
+pub fn send_get_blocks(addr: &str, locator: Vec<String>) {
     let socket_addr = addr.parse().unwrap();
     let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
     send_data(
         socket_addr,
         Package::GetBlocks {
             addr_from: node_addr,
+            locator,
+        },
+    );
+}
+
+pub fn send_get_headers(addr: &str, from_height: usize) {
+    let socket_addr = addr.parse().unwrap();
+    let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
+    send_data(
+        socket_addr,
+        Package::GetHeaders {
+            addr_from: node_addr,
+            from_height,
         },
     );
 }
 
+pub fn send_headers(addr: &str, headers: &[Vec<u8>]) {
+    let socket_addr = addr.parse().unwrap();
+    let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
+    send_data(
+        socket_addr,
+        Package::Headers {
+            addr_from: node_addr,
+            headers: headers.to_vec(),
+        },
+    );
+}
+
+pub fn send_get_block_header(addr: &str, hash: &[u8]) {
+    let socket_addr = addr.parse().unwrap();
+    let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
+    send_data(
+        socket_addr,
+        Package::GetBlockHeader {
+            addr_from: node_addr,
+            hash: hash.to_vec(),
+        },
+    );
+}
+
+pub fn send_block_header(addr: &str, hash: &[u8], header: Option<Vec<u8>>) {
+    let socket_addr = addr.parse().unwrap();
+    let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
+    send_data(
+        socket_addr,
+        Package::BlockHeader {
+            addr_from: node_addr,
+            hash: hash.to_vec(),
+            header,
+        },
+    );
+}
+
+pub fn send_get_mempool(addr: &str) {
+    let socket_addr = addr.parse().unwrap();
+    let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
+    send_data(
+        socket_addr,
+        Package::GetMempool {
+            addr_from: node_addr,
+        },
+    );
+}
+
+pub fn send_mempool_inv(addr: &str, txids: &[Vec<u8>]) {
+    let socket_addr = addr.parse().unwrap();
+    let node_addr = GLOBAL_CONFIG.get_node_addr().parse().unwrap();
+    send_data(
+        socket_addr,
+        Package::MempoolInv {
+            addr_from: node_addr,
+            txids: txids.to_vec(),
+        },
+    );
+}
+
+/// Checks that `header` is the block `hash` asked for and that its hash, recomputed from
+/// its fields, satisfies `target_bits` of proof-of-work. A genesis header is exempt from
+/// the proof-of-work checks, as genesis blocks are created without mining.
+pub fn verify_block_header(
+    hash: &[u8],
+    header: &BlockHeader,
+    target_bits: usize,
+) -> Result<(), String> {
+    if header.get_hash().as_bytes() != hash {
+        return Err(format!(
+            "header {} is not the requested block {}",
+            header.get_hash(),
+            String::from_utf8_lossy(hash)
+        ));
+    }
+    if header.get_height() == 0 && header.get_pre_block_hash() == GENESIS_PRE_BLOCK_HASH {
+        return Ok(());
+    }
+    if ProofOfWork::header_hash(header, target_bits) != header.get_hash() {
+        return Err(format!(
+            "header {} does not hash to its claimed hash",
+            header.get_hash()
+        ));
+    }
+    if !ProofOfWork::validate_header(header, target_bits) {
+        return Err(format!(
+            "header {} does not meet the proof-of-work target",
+            header.get_hash()
+        ));
+    }
+    Ok(())
+}
+
+/// Serialized headers of the main-chain blocks from `from_height` up, at most
+/// `MAX_HEADERS_ENTRIES` of them
+pub fn collect_headers(blockchain: &Blockchain, from_height: usize) -> Vec<Vec<u8>> {
+    let mut headers = vec![];
+    let mut height = from_height;
+    while headers.len() < MAX_HEADERS_ENTRIES {
+        match blockchain.get_block_by_height(height) {
+            Some(block) => headers.push(block.header_bytes()),
+            None => break,
+        }
+        height += 1;
+    }
+    headers
+}
+
+/// Checks that `headers` form a chain extending a block `blockchain` already has (or start
+/// a new one at genesis) and returns the hashes of the blocks still to download, in order
+pub fn accept_headers(
+    blockchain: &Blockchain,
+    headers: &[BlockHeader],
+) -> Result<Vec<Vec<u8>>, String> {
+    let Some(first) = headers.first() else {
+        return Ok(vec![]);
+    };
+    if first.get_height() == 0 {
+        if first.get_pre_block_hash() != GENESIS_PRE_BLOCK_HASH {
+            return Err(format!("genesis header {} has a parent", first.get_hash()));
+        }
+    } else {
+        match blockchain.get_block(first.get_pre_block_hash().as_bytes()) {
+            Some(parent) if parent.get_height() + 1 == first.get_height() => {}
+            Some(_) => return Err(format!("header {} has the wrong height", first.get_hash())),
+            None => {
+                return Err(format!(
+                    "header {} does not extend a known block",
+                    first.get_hash()
+                ));
+            }
+        }
+    }
+    for pair in headers.windows(2) {
+        if pair[1].get_pre_block_hash() != pair[0].get_hash()
+            || pair[1].get_height() != pair[0].get_height() + 1
+        {
+            return Err(format!(
+                "header {} does not follow {}",
+                pair[1].get_hash(),
+                pair[0].get_hash()
+            ));
+        }
+    }
+    Ok(headers
+        .iter()
+        .filter(|header| blockchain.get_block(header.get_hash().as_bytes()).is_none())
+        .map(|header| header.get_hash().as_bytes().to_vec())
+        .collect())
+}
+
+/// The announced `items` not already known locally: blocks missing from `blockchain`, or
+/// transactions neither pooled nor on the chain
+pub fn filter_unknown_items(
+    blockchain: &Blockchain,
+    op_type: &OpType,
+    items: &[Vec<u8>],
+) -> Vec<Vec<u8>> {
+    items
+        .iter()
+        .filter(|item| match op_type {
+            OpType::Block => blockchain.get_block(item).is_none(),
+            OpType::Tx => {
+                !GLOBAL_MEMORY_POOL.contains(HEXLOWER.encode(item).as_str())
+                    && blockchain.find_transaction(item).is_none()
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// Adds `tx` to `memory_pool` unless it is malformed or one of its inputs is no longer in
+/// the UTXO set. A double-spend of a pooled transaction is accepted only if it pays a
+/// strictly higher fee, and then replaces it.
+pub fn accept_tx(blockchain: &Blockchain, memory_pool: &MemoryPool, tx: Transaction) -> bool {
+    if let Err(reason) = tx.validate_structure() {
+        info!("Malformed transaction: {reason}");
+        return false;
+    }
+    let dust_threshold = GLOBAL_CONFIG.get_dust_threshold();
+    if let Some(idx) = tx
+        .get_vout()
+        .iter()
+        .position(|out| out.is_dust(dust_threshold))
+    {
+        info!("Dust transaction: output {idx} is below the threshold {dust_threshold}");
+        return false;
+    }
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    if !tx.is_coinbase() {
+        for vin in tx.get_vin() {
+            if !utxo_set.is_spendable(vin.get_txid(), vin.get_vout()) {
+                return false;
+            }
+        }
+    }
+    match memory_pool.try_replace(tx, &utxo_set) {
+        ReplaceResult::Added => true,
+        ReplaceResult::Replaced(replaced) => {
+            info!("Replaced pooled transactions {replaced:?}");
+            true
+        }
+        ReplaceResult::Rejected => false,
+        ReplaceResult::Invalid(reason) => {
+            info!("Invalid transaction: {reason}");
+            false
+        }
+    }
+}
+
+/// Adds `block` to `blockchain` if its parent is known, then connects any orphans waiting
+/// on it, keeping the UTXO set on the main chain and returning the transactions of blocks a
+/// reorganization abandons to `memory_pool`. A block whose parent is missing is buffered in
+/// `orphans` and false is returned, as it is for a block without exactly one coinbase, which
+/// is dropped.
+pub fn connect_block(
+    blockchain: &Blockchain,
+    orphans: &OrphanPool,
+    memory_pool: &MemoryPool,
+    block: Block,
+) -> bool {
+    if let Err((reason, detail)) = check_block_transactions(&block) {
+        GLOBAL_REJECTION_LOG.record(block.get_hash(), reason, &detail);
+        return false;
+    }
+    let expected_height = if block.get_pre_block_hash() == GENESIS_PRE_BLOCK_HASH {
+        0
+    } else {
+        match blockchain.get_block(block.get_pre_block_hash().as_bytes()) {
+            Some(parent) => parent.get_height() + 1,
+            None => {
+                orphans.add(block);
+                return false;
+            }
+        }
+    };
+    if let Err((reason, detail)) = check_block_against_chain(blockchain, &block, expected_height) {
+        GLOBAL_REJECTION_LOG.record(block.get_hash(), reason, &detail);
+        return false;
+    }
+
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let mut connected = vec![block];
+    while let Some(block) = connected.pop() {
+        add_to_chain(blockchain, &utxo_set, memory_pool, &block);
+        for child in orphans.take_children(block.get_hash()) {
+            // Orphans are only checked against their ancestors once those are known
+            match check_block_against_chain(blockchain, &child, block.get_height() + 1) {
+                Ok(()) => connected.push(child),
+                Err((reason, detail)) => {
+                    GLOBAL_REJECTION_LOG.record(child.get_hash(), reason, &detail)
+                }
+            }
+        }
+    }
+    true
+}
+
+// Store `block` and move the UTXO set along with the tip: apply the block when it extends
+// the tip, or follow the reorganization when it makes its branch the longest, returning the
+// transactions of the abandoned blocks to `memory_pool`
+fn add_to_chain(
+    blockchain: &Blockchain,
+    utxo_set: &UTXOSet,
+    memory_pool: &MemoryPool,
+    block: &Block,
+) {
+    let known = blockchain.get_block(block.get_hash().as_bytes()).is_some();
+    match blockchain.add_block(block) {
+        Some(reorg) => {
+            utxo_set.reorganize(&reorg);
+            // Oldest first, so a transaction is pooled after those it spends. Ones the new
+            // branch confirmed or double-spent no longer find their inputs and are dropped.
+            for tx in reorg
+                .get_disconnected()
+                .iter()
+                .rev()
+                .flat_map(|block| block.get_transactions())
+                .filter(|tx| !tx.is_coinbase())
+            {
+                memory_pool.try_replace(tx.clone(), utxo_set);
+            }
+        }
+        None if !known && blockchain.get_tip_hash() == block.get_hash() => utxo_set.update(block),
+        None => return,
+    }
+    utxo_set.prune_undo(block.get_height());
+}
+
+/// Checks what needs no chain: the coinbase and that no transaction repeats in `block`
+pub fn check_block_transactions(block: &Block) -> Result<(), (RejectReason, String)> {
+    block
+        .validate_coinbase()
+        .map_err(|detail| (RejectReason::InvalidTransaction, detail))?;
+    block
+        .validate_unique_txids()
+        .map_err(|detail| (RejectReason::Duplicate, detail))
+}
+
+/// Checks that `block` sits at `expected_height`, one above its parent, agrees with any
+/// checkpoint there, replays no transaction already confirmed below it, that every
+/// transaction in it verifies against the chain and that its coinbase claims no more than
+/// the subsidy plus the fees
+fn check_block_against_chain(
+    blockchain: &Blockchain,
+    block: &Block,
+    expected_height: usize,
+) -> Result<(), (RejectReason, String)> {
+    if block.get_height() != expected_height {
+        return Err((
+            RejectReason::BadParent,
+            format!(
+                "height {} does not follow its parent, expected {expected_height}",
+                block.get_height()
+            ),
+        ));
+    }
+    if !blockchain.is_checkpoint_valid(block.get_height(), block.get_hash()) {
+        return Err((
+            RejectReason::CheckpointMismatch,
+            format!(
+                "contradicts the checkpoint at height {}",
+                block.get_height()
+            ),
+        ));
+    }
+    blockchain
+        .validate_not_confirmed(block)
+        .map_err(|detail| (RejectReason::Duplicate, detail))?;
+    if !blockchain.verify_block_transactions(block) {
+        return Err((
+            RejectReason::InvalidTransaction,
+            String::from("a transaction is malformed, spends an unknown output or is badly signed"),
+        ));
+    }
+    blockchain
+        .validate_coinbase_value(block)
+        .map_err(|detail| (RejectReason::InvalidTransaction, detail))
+}
+
+/// Once `memory_pool` holds `TRANSACTION_THRESHOLD` transactions final for the next block
+/// and a mining address is configured, mine them, highest fee first, behind a coinbase
+/// paying that address. The mined block is added to `blockchain`, its transactions leave
+/// the pool and it is announced to every known peer.
+pub fn mine_pending_transactions(
+    blockchain: &Blockchain,
+    memory_pool: &MemoryPool,
+) -> Option<Block> {
+    let mining_address = GLOBAL_CONFIG.get_mining_addr()?;
+    // Time-locked transactions wait in the pool until the next block can include them
+    let mut txs = memory_pool.get_final(
+        blockchain
+            .try_get_best_height()
+            .map_or(0, |height| height + 1),
+        util::current_timestamp(),
+    );
+    if txs.len() < TRANSACTION_THRESHOLD {
+        return None;
+    }
+    txs.sort_by_key(|tx| std::cmp::Reverse(memory_pool.get_fee(&HEXLOWER.encode(tx.get_id()))));
+    txs.insert(0, Transaction::new_coinbase_tx(mining_address.as_str()));
+
+    let new_block = blockchain.mine_block(&txs);
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    utxo_set.reindex();
+    info!("New block {} is mined!", new_block.get_hash());
+
+    // Transactions trimmed by the block limits stay pooled for the next block
+    for tx in new_block.get_transactions() {
+        let txid_hex = HEXLOWER.encode(tx.get_id());
+        memory_pool.remove(txid_hex.as_str());
+    }
+
+    let node_addr = GLOBAL_CONFIG.get_node_addr();
+    for node in &GLOBAL_NODES.get_nodes() {
+        if node_addr.eq(node.get_addr().as_str()) {
+            continue;
+        }
+        send_inv(
+            node.get_addr().as_str(),
+            OpType::Block,
+            &[new_block.get_hash_bytes()],
+        );
+    }
+    Some(new_block)
+}
+
 pub fn serve(blockchain: Blockchain, stream: TcpStream) -> Result<(), Box<dyn Error>> {
     let peer_addr = stream.peer_addr()?;
+    let read_timeout_secs = GLOBAL_CONFIG.get_read_timeout_secs();
+    stream.set_read_timeout(Some(Duration::from_secs(read_timeout_secs)))?;
     let mut reader = BufReader::new(&stream);
+    let max_package_size = GLOBAL_CONFIG.get_max_package_size();
+    // Lengths declared past the ceiling fail before anything is allocated for them
+    let package_config = bincode::config::standard().with_limit::<MAX_PACKAGE_SIZE_CEILING>();
     loop {
-        let pkg_result = bincode::decode_from_reader(&mut reader, bincode::config::standard());
+        let mut package_reader = (&mut reader).take(max_package_size as u64);
+        let pkg_result = bincode::decode_from_std_read(&mut package_reader, package_config);
         match pkg_result {
             Ok(pkg) => {
                 let pkg: Package = pkg;
                 info!("Receive request from {peer_addr}: {pkg:?}");
+                if let Some(addr_from) = pkg.get_addr_from() {
+                    if GLOBAL_NODES.is_banned(addr_from) {
+                        info!("Dropping connection from banned peer {addr_from}");
+                        break;
+                    }
+                    GLOBAL_NODES.mark_seen(addr_from);
+                }
                 match pkg {
                     Package::Block { addr_from, block } => {
-                        let block = Block::deserialize(block.as_slice());
-                        blockchain.add_block(&block);
-                        info!("Added block {}", block.get_hash());
+                        let block = match Block::try_deserialize(block.as_slice()) {
+                            Ok(block) => block,
+                            Err(e) => {
+                                error!("Dropping malformed block from {addr_from}: {e}");
+                                GLOBAL_NODES
+                                    .misbehaving(addr_from.as_str(), MALFORMED_PACKAGE_SCORE);
+                                continue;
+                            }
+                        };
+                        let checked = verify_block_header(
+                            &block.get_hash_bytes(),
+                            &block.header(),
+                            blockchain.get_target_bits(),
+                        )
+                        .map_err(|detail| (RejectReason::BadProofOfWork, detail))
+                        .and_then(|()| check_block_transactions(&block));
+                        if let Err((reason, detail)) = checked {
+                            error!("Dropping invalid block from {addr_from}");
+                            GLOBAL_REJECTION_LOG.record(block.get_hash(), reason, &detail);
+                            GLOBAL_NODES.misbehaving(addr_from.as_str(), INVALID_BLOCK_SCORE);
+                            continue;
+                        }
+                        let block_hash = block.get_hash().to_string();
+                        if connect_block(
+                            &blockchain,
+                            &GLOBAL_ORPHAN_POOL,
+                            &GLOBAL_MEMORY_POOL,
+                            block,
+                        ) {
+                            info!("Added block {block_hash}");
+                        } else {
+                            info!("Buffered orphan block {block_hash}");
+                        }
 
                         if !GLOBAL_BLOCKS_IN_TRANSIT.is_empty() {
                             let block_hash = GLOBAL_BLOCKS_IN_TRANSIT.first().unwrap();
                             send_get_data(addr_from.as_str(), OpType::Block, &block_hash);
 
                             GLOBAL_BLOCKS_IN_TRANSIT.remove(block_hash.as_slice());
-                        } else {
-                            let utxo_set = UTXOSet::new(blockchain.clone());
-                            utxo_set.reindex();
+                        } else if !blockchain.verify_chain() {
+                            error!("Received chain from {addr_from} failed validation");
+                            GLOBAL_NODES.misbehaving(addr_from.as_str(), INVALID_CHAIN_SCORE);
                         }
                     }
-                    Package::GetBlocks { addr_from } => {
-                        let blocks = blockchain.get_block_hashes();
+                    Package::GetBlocks { addr_from, locator } => {
+                        let blocks = blockchain.get_block_hashes_after_locator(&locator);
                         send_inv(addr_from.as_str(), OpType::Block, &blocks);
                     }
                     Package::GetData {
@@ -152,7 +657,7 @@ pub fn serve(blockchain: Blockchain, stream: TcpStream) -> Result<(), Box<dyn Er
                         OpType::Tx => {
                             let txid_hex = HEXLOWER.encode(id.as_slice());
                             if let Some(tx) = GLOBAL_MEMORY_POOL.get(txid_hex.as_str()) {
-                                send_tx(addr_from.as_str(), &tx);
+                                let _ = send_tx(addr_from.as_str(), &tx);
                             }
                         }
                     },
@@ -160,31 +665,45 @@ pub fn serve(blockchain: Blockchain, stream: TcpStream) -> Result<(), Box<dyn Er
                         addr_from,
                         op_type,
                         items,
-                    } => match op_type {
-                        OpType::Block => {
-                            GLOBAL_BLOCKS_IN_TRANSIT.add_blocks(items.as_slice());
-
-                            let block_hash = items.first().unwrap();
-                            send_get_data(addr_from.as_str(), OpType::Block, block_hash);
-
-                            GLOBAL_BLOCKS_IN_TRANSIT.remove(block_hash);
-                        }
-                        OpType::Tx => {
-                            let txid = items.first().unwrap();
-                            let txid_hex = HEXLOWER.encode(txid);
-
-                            if !GLOBAL_MEMORY_POOL.contains(txid_hex.as_str()) {
-                                send_get_data(addr_from.as_str(), OpType::Tx, txid);
+                    } => {
+                        let items = filter_unknown_items(&blockchain, &op_type, &items);
+                        match op_type {
+                            OpType::Block => {
+                                let Some(block_hash) = items.first() else {
+                                    continue;
+                                };
+                                GLOBAL_BLOCKS_IN_TRANSIT.add_blocks(items.as_slice());
+                                send_get_data(addr_from.as_str(), OpType::Block, block_hash);
+                                GLOBAL_BLOCKS_IN_TRANSIT.remove(block_hash);
+                            }
+                            OpType::Tx => {
+                                for txid in &items {
+                                    send_get_data(addr_from.as_str(), OpType::Tx, txid);
+                                }
                             }
                         }
-                    },
+                    }
                     Package::Tx {
                         addr_from,
                         transaction,
                     } => {
-                        let tx = Transaction::deserialize(transaction.as_slice());
+                        let tx = match Transaction::try_deserialize(transaction.as_slice()) {
+                            Ok(tx) => tx,
+                            Err(e) => {
+                                error!("Dropping malformed transaction from {addr_from}: {e}");
+                                GLOBAL_NODES
+                                    .misbehaving(addr_from.as_str(), MALFORMED_PACKAGE_SCORE);
+                                continue;
+                            }
+                        };
+                        if tx.validate_structure().is_err() {
+                            GLOBAL_NODES.misbehaving(addr_from.as_str(), MALFORMED_PACKAGE_SCORE);
+                        }
                         let txid = tx.get_id_bytes();
-                        GLOBAL_MEMORY_POOL.add(tx.clone());
+                        if !accept_tx(&blockchain, &GLOBAL_MEMORY_POOL, tx.clone()) {
+                            info!("Rejected transaction {}", HEXLOWER.encode(txid));
+                            continue;
+                        }
 
                         let node_addr = GLOBAL_CONFIG.get_node_addr();
 
@@ -201,58 +720,182 @@ pub fn serve(blockchain: Blockchain, stream: TcpStream) -> Result<(), Box<dyn Er
                             }
                         }
 
-                        if GLOBAL_MEMORY_POOL.len() >= TRANSACTION_THRESHOLD
-                            && GLOBAL_CONFIG.is_miner()
-                        {
-                            let mining_address = GLOBAL_CONFIG.get_mining_addr().unwrap();
-                            let coinbase_tx = Transaction::new_coinbase_tx(mining_address.as_str());
-                            let mut txs = GLOBAL_MEMORY_POOL.get_all();
-                            txs.push(coinbase_tx);
-
-                            let new_block = blockchain.mine_block(&txs);
-                            let utxo_set = UTXOSet::new(blockchain.clone());
-                            utxo_set.reindex();
-                            info!("New block {} is mined!", new_block.get_hash());
-
-                            for tx in &txs {
-                                let txid_hex = HEXLOWER.encode(tx.get_id());
-                                GLOBAL_MEMORY_POOL.remove(txid_hex.as_str());
-                            }
-
-                            let nodes = GLOBAL_NODES.get_nodes();
-                            for node in &nodes {
-                                if node_addr.eq(node.get_addr().as_str()) {
-                                    continue;
-                                }
-                                send_inv(
-                                    node.get_addr().as_str(),
-                                    OpType::Block,
-                                    &[new_block.get_hash_bytes()],
-                                );
-                            }
-                        }
+                        mine_pending_transactions(&blockchain, &GLOBAL_MEMORY_POOL);
                     }
                     Package::Version {
                         addr_from,
                         version,
                         best_height,
+                        magic,
                     } => {
-                        info!("version = {version}, best_height = {best_height}");
-                        let local_best_height = blockchain.get_best_height();
-                        if local_best_height < best_height {
-                            send_get_blocks(addr_from.as_str());
+                        let local_magic = GLOBAL_CONFIG.get_network_magic();
+                        if magic != local_magic {
+                            info!(
+                                "Disconnecting {addr_from}: network magic {magic:#010x} does not match {local_magic:#010x}"
+                            );
+                            break;
                         }
-                        if local_best_height > best_height {
-                            send_version(addr_from.as_str(), blockchain.get_best_height());
+                        info!("version = {version}, best_height = {best_height}");
+                        // A taller peer is asked for the headers above our tip; the blocks
+                        // follow once they check out, or the full inventory if they fork
+                        if blockchain.is_empty() {
+                            send_get_headers(addr_from.as_str(), 0);
+                        } else {
+                            let local_best_height = blockchain.get_best_height();
+                            if local_best_height < best_height {
+                                send_get_headers(addr_from.as_str(), local_best_height + 1);
+                            }
+                            if local_best_height > best_height {
+                                send_version(addr_from.as_str(), blockchain.get_best_height());
+                            }
                         }
 
                         if !GLOBAL_NODES.node_is_known(peer_addr.to_string().as_str()) {
                             GLOBAL_NODES.add_node(addr_from);
                         }
                     }
+                    Package::GetHeaders {
+                        addr_from,
+                        from_height,
+                    } => {
+                        let headers = collect_headers(&blockchain, from_height);
+                        send_headers(addr_from.as_str(), &headers);
+                    }
+                    Package::Headers { addr_from, headers } => {
+                        let decoded: Result<Vec<BlockHeader>, _> = headers
+                            .iter()
+                            .map(|header| BlockHeader::try_deserialize(header))
+                            .collect();
+                        let headers = match decoded {
+                            Ok(headers) => headers,
+                            Err(e) => {
+                                error!("Dropping malformed headers from {addr_from}: {e}");
+                                GLOBAL_NODES
+                                    .misbehaving(addr_from.as_str(), MALFORMED_PACKAGE_SCORE);
+                                continue;
+                            }
+                        };
+                        match accept_headers(&blockchain, &headers) {
+                            Ok(missing) => {
+                                info!(
+                                    "Received {} headers, {} blocks missing",
+                                    headers.len(),
+                                    missing.len()
+                                );
+                                if let Some(block_hash) = missing.first() {
+                                    GLOBAL_BLOCKS_IN_TRANSIT.add_blocks(missing.as_slice());
+                                    send_get_data(addr_from.as_str(), OpType::Block, block_hash);
+                                    GLOBAL_BLOCKS_IN_TRANSIT.remove(block_hash);
+                                }
+                            }
+                            Err(e) => {
+                                // Likely a fork below our tip: ask for the blocks after the fork point
+                                info!("Headers from {addr_from} do not connect: {e}");
+                                send_get_blocks(addr_from.as_str(), blockchain.block_locator());
+                            }
+                        }
+                    }
+                    Package::GetBlockHeader { addr_from, hash } => {
+                        let header = blockchain
+                            .get_block(hash.as_slice())
+                            .map(|block| block.header_bytes());
+                        send_block_header(addr_from.as_str(), hash.as_slice(), header);
+                    }
+                    Package::BlockHeader {
+                        addr_from,
+                        hash,
+                        header,
+                    } => {
+                        let Some(header) = header else {
+                            info!(
+                                "Peer {addr_from} does not have block {}",
+                                String::from_utf8_lossy(&hash)
+                            );
+                            continue;
+                        };
+                        let header = match BlockHeader::try_deserialize(&header) {
+                            Ok(header) => header,
+                            Err(e) => {
+                                error!("Dropping malformed header from {addr_from}: {e}");
+                                GLOBAL_NODES
+                                    .misbehaving(addr_from.as_str(), MALFORMED_PACKAGE_SCORE);
+                                continue;
+                            }
+                        };
+                        match verify_block_header(&hash, &header, blockchain.get_target_bits()) {
+                            Ok(()) => info!(
+                                "Verified proof-of-work of block {} from {addr_from}",
+                                header.get_hash()
+                            ),
+                            Err(reason) => {
+                                error!("Invalid header from {addr_from}: {reason}");
+                                GLOBAL_NODES.misbehaving(addr_from.as_str(), INVALID_BLOCK_SCORE);
+                            }
+                        }
+                    }
+                    Package::Ping { nonce } => {
+                        // Answer on the same connection so the pinging side can tell it is alive
+                        let pong = Package::Pong { nonce };
+                        let serialized =
+                            bincode::encode_to_vec(&pong, bincode::config::standard())?;
+                        (&stream).write_all(&serialized)?;
+                        (&stream).flush()?;
+                    }
+                    Package::Pong { nonce } => {
+                        info!("Received pong {nonce} from {peer_addr}");
+                    }
+                    Package::GetMempool { addr_from } => {
+                        let txids: Vec<Vec<u8>> = GLOBAL_MEMORY_POOL
+                            .get_all()
+                            .iter()
+                            .map(|tx| tx.get_id().to_vec())
+                            .collect();
+                        send_mempool_inv(addr_from.as_str(), &txids);
+                    }
+                    Package::MempoolInv { addr_from, txids } => {
+                        let txids = filter_unknown_items(&blockchain, &OpType::Tx, &txids);
+                        info!(
+                            "{addr_from} has {} pending transactions we lack",
+                            txids.len()
+                        );
+                        for txid in &txids {
+                            send_get_data(addr_from.as_str(), OpType::Tx, txid);
+                        }
+                    }
+                    Package::GetAddr { addr_from } => {
+                        let nodes: Vec<String> = GLOBAL_NODES
+                            .get_nodes()
+                            .iter()
+                            .map(|node| node.get_addr())
+                            .filter(|addr| addr.ne(&addr_from))
+                            .take(MAX_ADDR_ENTRIES)
+                            .collect();
+                        send_addr(addr_from.as_str(), &nodes);
+                    }
+                    Package::Addr { addr_from, nodes } => {
+                        info!("Received {} peer addresses from {addr_from}", nodes.len());
+                        let node_addr = GLOBAL_CONFIG.get_node_addr();
+                        for addr in nodes.into_iter().take(MAX_ADDR_ENTRIES) {
+                            if addr.eq(&node_addr) || addr.parse::<SocketAddr>().is_err() {
+                                continue;
+                            }
+                            GLOBAL_NODES.add_node(addr);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                if matches!(e, DecodeError::LimitExceeded) || package_reader.limit() == 0 {
+                    error!(
+                        "Dropping connection from {peer_addr}: package exceeds {max_package_size} bytes"
+                    );
+                } else if let DecodeError::Io { inner, .. } = &e
+                    && matches!(inner.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+                {
+                    info!("Dropping connection from {peer_addr}: silent for {read_timeout_secs}s");
                 }
+                break;
             }
-            Err(_) => break,
         }
     }
     let _ = stream.shutdown(Shutdown::Both);
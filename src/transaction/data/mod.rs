@@ -1,3 +1,6 @@
 pub mod tx_input;
 pub mod tx_output;
 pub mod transaction;
+pub mod tx_estimate;
+pub mod sig_cache;
+pub mod multisig_lock;
@@ -0,0 +1,6 @@
+/// An m-of-n lock: spending the output takes signatures from `required` of the listed keys
+#[derive(Clone, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct MultiSigLock {
+    pub(in crate::transaction) required: usize,
+    pub(in crate::transaction) pub_key_hashes: Vec<Vec<u8>>,
+}
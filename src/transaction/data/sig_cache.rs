@@ -0,0 +1,30 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, atomic::AtomicU64},
+};
+
+use once_cell::sync::Lazy;
+
+// Maximum number of signature checks remembered by the global cache
+pub const SIG_CACHE_CAPACITY: usize = 10_000;
+
+pub static GLOBAL_SIG_CACHE: Lazy<SigCache> = Lazy::new(|| SigCache::new(SIG_CACHE_CAPACITY));
+
+/// A checked input: txid, input index and a hash over its signature, public key and
+/// signed data
+pub type SigCacheKey = (Vec<u8>, usize, Vec<u8>);
+
+/// Least-recently-used cache of signature verification results
+pub struct SigCache {
+    pub(in crate::transaction) capacity: usize,
+    pub(in crate::transaction) inner: Mutex<SigCacheEntries>,
+    pub(in crate::transaction) hits: AtomicU64,
+}
+
+pub(in crate::transaction) struct SigCacheEntries {
+    // Each result with the tick it was last used at
+    pub(in crate::transaction) results: HashMap<SigCacheKey, (bool, u64)>,
+    // Keys ordered from least to most recently used
+    pub(in crate::transaction) recency: BTreeMap<u64, SigCacheKey>,
+    pub(in crate::transaction) tick: u64,
+}
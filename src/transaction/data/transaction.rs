@@ -1,11 +1,15 @@
 use crate::transaction::{TXInput, TXOutput};
 
 pub const SUBSIDY: i32 = 10;
+// Lock times below this are block heights, at or above it unix timestamps
+pub const LOCK_TIME_THRESHOLD: i64 = 500_000_000;
 
 #[derive(Clone, bincode::Encode, bincode::Decode)]
 pub struct Transaction {
     pub(in crate::transaction) id: Vec<u8>,
     pub vin: Vec<TXInput>,
     pub vout: Vec<TXOutput>,
+    // Earliest block height or timestamp the transaction can be mined at, 0 for none
+    pub(in crate::transaction) lock_time: i64,
 }
 
@@ -0,0 +1,15 @@
+// Byte lengths of the signature and public key a signed input carries, used to size
+// transactions without signing them
+pub const SIGNATURE_LEN: usize = 64;
+pub const PUBLIC_KEY_LEN: usize = 65;
+pub const COMPRESSED_PUBLIC_KEY_LEN: usize = 33;
+
+/// What a transaction would spend and weigh, computed without signing or sending it
+#[derive(Debug, Clone)]
+pub struct TxEstimate {
+    pub(in crate::transaction) input_count: usize,
+    pub(in crate::transaction) total_input: i64,
+    pub(in crate::transaction) change: i64,
+    pub(in crate::transaction) size: usize,
+    pub(in crate::transaction) fee: i64,
+}
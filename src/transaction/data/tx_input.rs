@@ -4,4 +4,6 @@ pub struct TXInput {
     pub vout: usize,
     pub signature: Vec<u8>,
     pub pub_key: Vec<u8>,
+    // (pub_key, signature) pairs for an input spending a multisig output
+    pub multisig_signatures: Vec<(Vec<u8>, Vec<u8>)>,
 }
@@ -1,6 +1,19 @@
+use crate::transaction::MultiSigLock;
+
+// Length of the all-zero pub_key_hash burn outputs carry. No address hash or multisig lock
+// hash has this length, so no key can ever unlock one.
+pub const BURN_PUB_KEY_HASH_LEN: usize = 21;
+
+// Most bytes a data output may carry
+pub const MAX_DATA_OUTPUT_LEN: usize = 80;
+
 #[derive(Clone, bincode::Encode, bincode::Decode)]
 pub struct TXOutput {
     pub value: i32,
     pub pub_key_hash: Vec<u8>,
+    // Set for m-of-n outputs, whose pub_key_hash is the lock's hash; None for single-sig
+    pub multisig: Option<MultiSigLock>,
+    // Payload of a zero-value data output, which no key can spend; None for value outputs
+    pub data: Option<Vec<u8>>,
 }
 
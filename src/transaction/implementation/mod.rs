@@ -1,3 +1,6 @@
 pub mod tx_input_impl;
 pub mod tx_output_impl;
 pub mod transaction_impl;
+pub mod tx_estimate_impl;
+pub mod sig_cache_impl;
+pub mod multisig_lock_impl;
@@ -0,0 +1,43 @@
+use bincode::config::standard;
+
+use crate::{transaction::MultiSigLock, util};
+
+impl MultiSigLock {
+    /// Lock requiring `required` signatures from distinct keys among `pub_key_hashes`
+    pub fn new(required: usize, pub_key_hashes: Vec<Vec<u8>>) -> Result<MultiSigLock, String> {
+        if required == 0 || required > pub_key_hashes.len() {
+            return Err(format!(
+                "cannot require {required} of {} signatures",
+                pub_key_hashes.len()
+            ));
+        }
+        let mut unique = pub_key_hashes.clone();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != pub_key_hashes.len() {
+            return Err(String::from("multisig keys must be distinct"));
+        }
+        Ok(MultiSigLock {
+            required,
+            pub_key_hashes,
+        })
+    }
+
+    pub fn get_required(&self) -> usize {
+        self.required
+    }
+
+    pub fn get_pub_key_hashes(&self) -> &[Vec<u8>] {
+        self.pub_key_hashes.as_slice()
+    }
+
+    pub fn contains(&self, pub_key_hash: &[u8]) -> bool {
+        self.pub_key_hashes.iter().any(|hash| hash.eq(pub_key_hash))
+    }
+
+    /// Hash160 of the lock, stored as the pub_key_hash of outputs it locks
+    pub fn lock_hash(&self) -> Vec<u8> {
+        let bytes = bincode::encode_to_vec(self, standard()).unwrap();
+        util::hash160(bytes.as_slice())
+    }
+}
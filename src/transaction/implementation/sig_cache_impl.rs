@@ -0,0 +1,70 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::transaction::data::sig_cache::{SigCache, SigCacheEntries, SigCacheKey};
+
+impl SigCache {
+    pub fn new(capacity: usize) -> SigCache {
+        SigCache {
+            capacity,
+            inner: Mutex::new(SigCacheEntries {
+                results: HashMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+            }),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// The cached result for `key`, marking it as most recently used
+    pub fn get(&self, key: &SigCacheKey) -> Option<bool> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let (valid, last_used) = inner.results.get_mut(key)?;
+        let valid = *valid;
+        let previous = std::mem::replace(last_used, tick);
+        inner.recency.remove(&previous);
+        inner.recency.insert(tick, key.clone());
+        self.hits.fetch_add(1, Ordering::SeqCst);
+        Some(valid)
+    }
+
+    /// Remember `valid` for `key`, evicting the least recently used entry when full
+    pub fn insert(&self, key: SigCacheKey, valid: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        if let Some((_, previous)) = inner.results.insert(key.clone(), (valid, tick)) {
+            inner.recency.remove(&previous);
+        }
+        inner.recency.insert(tick, key);
+        while inner.results.len() > self.capacity {
+            let Some((_, oldest)) = inner.recency.pop_first() else {
+                break;
+            };
+            inner.results.remove(&oldest);
+        }
+    }
+
+    /// Number of lookups answered from the cache
+    pub fn get_hits(&self) -> u64 {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().unwrap().results.is_empty()
+    }
+}
@@ -1,14 +1,51 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
 use bincode::config::standard;
 use data_encoding::HEXLOWER;
 use uuid::Uuid;
 
 use crate::{
-    transaction::{data::transaction::SUBSIDY, Transaction}, util::{self, ecdsa_p256_sha256_sign_verify, sha256_digest}, wallet::{wallet_util::hash_pub_key, Wallets}, Blockchain, TXInput, TXOutput, UTXOSet
+    Blockchain, TXInput, TXOutput, UTXOSet,
+    config::GLOBAL_CONFIG,
+    transaction::{
+        GLOBAL_SIG_CACHE, MultiSigLock, SigCacheKey, Transaction, TxEstimate,
+        data::{
+            transaction::{LOCK_TIME_THRESHOLD, SUBSIDY},
+            tx_estimate::{COMPRESSED_PUBLIC_KEY_LEN, PUBLIC_KEY_LEN, SIGNATURE_LEN},
+        },
+    },
+    util::{self, ecdsa_p256_sha256_sign_verify, sha256_digest},
+    wallet::{
+        ADDRESS_CHECK_SUM_LEN, Wallet, Wallets,
+        wallet_util::{hash_pub_key, validate_address},
+    },
 };
 
 impl Transaction {
     pub fn new(id: Vec<u8>, vin: Vec<TXInput>, vout: Vec<TXOutput>) -> Transaction {
-        Transaction { id, vin, vout }
+        Transaction {
+            id,
+            vin,
+            vout,
+            lock_time: 0,
+        }
+    }
+
+    /// Build a transaction with its inputs sorted by txid then vout and its outputs by
+    /// value then pub_key_hash, and its id computed from that order, so equivalent
+    /// transactions assembled in any order get the same id
+    pub fn new_canonical(mut vin: Vec<TXInput>, mut vout: Vec<TXOutput>) -> Transaction {
+        vin.sort_by(|a, b| a.txid.cmp(&b.txid).then(a.vout.cmp(&b.vout)));
+        vout.sort_by_cached_key(|out| {
+            let encoded = bincode::encode_to_vec(out, standard()).unwrap();
+            (out.value, out.pub_key_hash.clone(), encoded)
+        });
+        let mut tx = Transaction::new(vec![], vin, vout);
+        tx.id = tx.hash();
+        tx
     }
 
     pub fn new_utxo_transaction(
@@ -17,14 +54,64 @@ impl Transaction {
         amount: i32,
         utxo_set: &UTXOSet,
     ) -> Transaction {
+        let outputs = [(to.to_string(), amount)];
+        Transaction::new_utxo_transaction_multi(from, &outputs, utxo_set)
+            .unwrap_or_else(|e| panic!("Error: {e}"))
+    }
+
+    /// Build and sign one transaction from the local wallet of `from` paying every
+    /// `(address, amount)` in `outputs`, sending any change back to `from`
+    pub fn new_utxo_transaction_multi(
+        from: &str,
+        outputs: &[(String, i32)],
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction, String> {
         let wallets = Wallets::new();
-        let wallet = wallets.get_wallet(from).expect("unable to found wallet");
-        let public_key_hash = hash_pub_key(wallet.get_public_key());
+        let wallet = wallets
+            .get_wallet(from)
+            .ok_or_else(|| format!("No local wallet for address {from}"))?;
+        Transaction::new_utxo_transaction_from_wallet(wallet, outputs, utxo_set)
+    }
 
+    /// Same as `new_utxo_transaction_multi`, spending from `wallet` directly
+    pub fn new_utxo_transaction_from_wallet(
+        wallet: &Wallet,
+        outputs: &[(String, i32)],
+        utxo_set: &UTXOSet,
+    ) -> Result<Transaction, String> {
+        if outputs.is_empty() {
+            return Err(String::from("Transaction needs at least one recipient"));
+        }
+        let dust_threshold = GLOBAL_CONFIG.get_dust_threshold();
+        let mut amount: i32 = 0;
+        for (address, value) in outputs {
+            if !validate_address(address.as_str()) {
+                return Err(format!("Recipient address {address} is not valid"));
+            }
+            if *value <= 0 {
+                return Err(format!(
+                    "Amount for {address} must be positive, got {value}"
+                ));
+            }
+            if *value < dust_threshold {
+                return Err(format!(
+                    "Amount for {address} is below the dust threshold {dust_threshold}, got {value}"
+                ));
+            }
+            amount = amount
+                .checked_add(*value)
+                .ok_or_else(|| String::from("Total output value overflows"))?;
+        }
+
+        let from = wallet.get_address();
+        let public_key = wallet.get_address_public_key();
+        let public_key_hash = hash_pub_key(public_key.as_slice());
         let (accumulated, valid_outputs) =
             utxo_set.find_spendable_outputs(public_key_hash.as_slice(), amount);
         if accumulated < amount {
-            panic!("Error: Not enough funds")
+            return Err(format!(
+                "Not enough funds: have {accumulated}, need {amount}"
+            ));
         }
 
         let mut inputs = vec![];
@@ -35,44 +122,140 @@ impl Transaction {
                     txid: txid.clone(),
                     vout: out,
                     signature: vec![],
-                    pub_key: wallet.get_public_key().to_vec(),
+                    pub_key: public_key.clone(),
+                    multisig_signatures: vec![],
                 };
                 inputs.push(input);
             }
         }
 
-        let mut outputs = vec![TXOutput::new(amount, to)];
+        let mut vout: Vec<TXOutput> = outputs
+            .iter()
+            .map(|(address, value)| TXOutput::new(*value, address.as_str()))
+            .collect();
 
-        if accumulated > amount {
-            outputs.push(TXOutput::new(accumulated - amount, from)) // to: 币收入
+        // Change too small to be worth an output is left to the miner as fee
+        if accumulated - amount >= dust_threshold.max(1) {
+            vout.push(TXOutput::new(accumulated - amount, from.as_str())) // to: 币收入
         }
 
         let mut tx = Transaction {
             id: vec![],
             vin: inputs,
-            vout: outputs,
+            vout,
+            lock_time: 0,
         };
 
         tx.id = tx.hash();
 
         tx.sign(utxo_set.get_blockchain(), wallet.get_pkcs8());
 
-        tx
+        Ok(tx)
+    }
+
+    /// Work out the inputs, change, size and fee of paying `amount` from `from` to `to`
+    /// without signing, sending or storing anything
+    pub fn estimate(
+        from: &str,
+        to: &str,
+        amount: i32,
+        utxo_set: &UTXOSet,
+    ) -> Result<TxEstimate, String> {
+        if !validate_address(from) {
+            return Err(format!("Sender address {from} is not valid"));
+        }
+        if !validate_address(to) {
+            return Err(format!("Recipient address {to} is not valid"));
+        }
+        if amount <= 0 {
+            return Err(format!("Amount must be positive, got {amount}"));
+        }
+        let payload = util::base58_decode(from);
+        let public_key_hash = &payload[1..payload.len() - ADDRESS_CHECK_SUM_LEN];
+        let (accumulated, selected) =
+            utxo_set.find_spendable_outputs_detailed(public_key_hash, amount);
+        if accumulated < amount as i64 {
+            return Err(format!(
+                "Not enough funds: have {accumulated}, need {amount}"
+            ));
+        }
+
+        // Placeholders of the same length as the real id, signatures and public key
+        let public_key_len = if GLOBAL_CONFIG.is_compressed_pubkeys_enabled() {
+            COMPRESSED_PUBLIC_KEY_LEN
+        } else {
+            PUBLIC_KEY_LEN
+        };
+        let inputs = selected
+            .iter()
+            .map(|(txid_hex, vout, _)| TXInput {
+                txid: HEXLOWER.decode(txid_hex.as_bytes()).unwrap(),
+                vout: *vout,
+                signature: vec![0; SIGNATURE_LEN],
+                pub_key: vec![0; public_key_len],
+                multisig_signatures: vec![],
+            })
+            .collect();
+        let change = accumulated - amount as i64;
+        let mut outputs = vec![TXOutput::new(amount, to)];
+        if change > 0 {
+            outputs.push(TXOutput::new(change as i32, from));
+        }
+        let total_output: i64 = outputs.iter().map(|out| out.get_value() as i64).sum();
+        let tx = Transaction {
+            id: vec![0; sha256_digest(&[]).len()],
+            vin: inputs,
+            vout: outputs,
+            lock_time: 0,
+        };
+
+        Ok(TxEstimate {
+            input_count: selected.len(),
+            total_input: accumulated,
+            change,
+            size: tx.serialize().len(),
+            fee: accumulated - total_output,
+        })
     }
 
     pub fn new_coinbase_tx(to: &str) -> Transaction {
-        let txout = TXOutput::new(SUBSIDY, to);
+        Self::new_coinbase_tx_with_reward(to, SUBSIDY)
+    }
+
+    /// A coinbase paying `reward` instead of the block subsidy, e.g. a genesis reward
+    pub fn new_coinbase_tx_with_reward(to: &str, reward: i32) -> Transaction {
+        Self::new_coinbase_tx_with_signature(to, reward, Uuid::new_v4().as_bytes().to_vec())
+    }
+
+    /// A coinbase paying `reward` whose input carries `extra_nonce` as 8 little-endian bytes
+    /// followed by `message`, e.g. a miner tag. Changing the extra nonce changes the txid and
+    /// so the merkle root, giving the miner more search space once the nonce runs out.
+    pub fn new_coinbase_tx_with_data(
+        to: &str,
+        reward: i32,
+        message: &[u8],
+        extra_nonce: u64,
+    ) -> Transaction {
+        let mut signature = extra_nonce.to_le_bytes().to_vec();
+        signature.extend_from_slice(message);
+        Self::new_coinbase_tx_with_signature(to, reward, signature)
+    }
+
+    fn new_coinbase_tx_with_signature(to: &str, reward: i32, signature: Vec<u8>) -> Transaction {
+        let txout = TXOutput::new(reward, to);
         let tx_input = TXInput {
             txid: vec![],
             vout: 0,
-            signature: Uuid::new_v4().as_bytes().to_vec(),
+            signature,
             pub_key: vec![],
+            multisig_signatures: vec![],
         };
 
         let mut tx = Transaction {
             id: vec![],
             vin: vec![tx_input],
             vout: vec![txout],
+            lock_time: 0,
         };
 
         tx.id = tx.hash();
@@ -83,6 +266,30 @@ impl Transaction {
         &self.id
     }
 
+    pub fn get_lock_time(&self) -> i64 {
+        self.lock_time
+    }
+
+    /// Keep the transaction out of blocks until `lock_time`: a block height below
+    /// `LOCK_TIME_THRESHOLD`, a unix timestamp otherwise, 0 for no lock. Set it before signing;
+    /// it is covered by the id and signatures computed afterwards.
+    pub fn set_lock_time(&mut self, lock_time: i64) {
+        self.lock_time = lock_time;
+    }
+
+    /// True if the transaction may be included in a block at `block_height` mined at
+    /// `block_time`
+    pub fn is_final(&self, block_height: usize, block_time: i64) -> bool {
+        if self.lock_time <= 0 {
+            return true;
+        }
+        if self.lock_time < LOCK_TIME_THRESHOLD {
+            self.lock_time <= block_height as i64
+        } else {
+            self.lock_time <= block_time
+        }
+    }
+
     fn trimmed_copy(&self) -> Transaction {
         let mut inputs = vec![];
         let mut outputs = vec![];
@@ -97,6 +304,7 @@ impl Transaction {
             id: self.id.clone(),
             vin: inputs,
             vout: outputs,
+            lock_time: self.lock_time,
         }
     }
 
@@ -105,21 +313,31 @@ impl Transaction {
             id: vec![],
             vin: self.vin.clone(),
             vout: self.vout.clone(),
+            lock_time: self.lock_time,
         };
         sha256_digest(tx_copy.serialize().as_slice())
     }
 
     fn sign(&mut self, blockchain: &Blockchain, pkcs8: &[u8]) {
-        let mut tx_copy = self.trimmed_copy();
-
-        for (idx, vin) in self.vin.iter_mut().enumerate() {
+        let mut prev_pub_key_hashes = vec![];
+        for vin in &self.vin {
             let prev_tx_option = blockchain.find_transaction(vin.get_txid());
             if prev_tx_option.is_none() {
                 panic!("ERROR: Previous transaction is not correct")
             }
             let prev_tx = prev_tx_option.unwrap();
+            prev_pub_key_hashes.push(prev_tx.vout[vin.vout].pub_key_hash.clone());
+        }
+        self.sign_inputs(pkcs8, &prev_pub_key_hashes);
+    }
+
+    /// Sign every input given the pub_key_hash of the output it spends, in input order
+    fn sign_inputs(&mut self, pkcs8: &[u8], prev_pub_key_hashes: &[Vec<u8>]) {
+        let mut tx_copy = self.trimmed_copy();
+
+        for (idx, vin) in self.vin.iter_mut().enumerate() {
             tx_copy.vin[idx].signature = vec![];
-            tx_copy.vin[idx].pub_key = prev_tx.vout[vin.vout].pub_key_hash.clone();
+            tx_copy.vin[idx].pub_key = prev_pub_key_hashes[idx].clone();
             tx_copy.id = tx_copy.hash();
             tx_copy.vin[idx].pub_key = vec![];
 
@@ -128,27 +346,141 @@ impl Transaction {
         }
     }
 
+    /// Write the bundle returned by `UTXOSet::build_unsigned` to `path`, so a signer on
+    /// another machine can pick it up
+    pub fn save_bundle(path: &Path, tx: &Transaction, prev_outputs: &[TXOutput]) -> io::Result<()> {
+        let bytes = bincode::encode_to_vec((tx, prev_outputs), standard())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, bytes)
+    }
+
+    /// Read a bundle written by `save_bundle`
+    pub fn load_bundle(path: &Path) -> io::Result<(Transaction, Vec<TXOutput>)> {
+        let bytes = fs::read(path)?;
+        let (bundle, _) = bincode::decode_from_slice(&bytes, standard())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(bundle)
+    }
+
+    /// Sign an unsigned transaction from `UTXOSet::build_unsigned` without access to the
+    /// chain. `prev_outputs` are the outputs spent by each input, in input order. Inputs
+    /// carry the public key in the wallet's encoding, compressed when that is enabled.
+    pub fn sign_offline(&mut self, pkcs8: &[u8], prev_outputs: &[TXOutput]) -> Result<(), String> {
+        if prev_outputs.len() != self.vin.len() {
+            return Err(format!(
+                "expected {} previous outputs, got {}",
+                self.vin.len(),
+                prev_outputs.len()
+            ));
+        }
+        let public_key = Wallet::from_pkcs8(pkcs8.to_vec())
+            .map_err(|_| String::from("invalid pkcs8 key"))?
+            .get_address_public_key();
+        for vin in self.vin.iter_mut() {
+            vin.pub_key = public_key.clone();
+        }
+        self.id = self.hash();
+
+        let prev_pub_key_hashes: Vec<Vec<u8>> = prev_outputs
+            .iter()
+            .map(|out| out.pub_key_hash.clone())
+            .collect();
+        self.sign_inputs(pkcs8, &prev_pub_key_hashes);
+        Ok(())
+    }
+
+    /// Sign with an explicit key, looking up the output each input spends in `prev_txs`,
+    /// keyed by txid hex. Fails without signing if any input's previous transaction or
+    /// output is missing.
+    pub fn sign_with(
+        &mut self,
+        pkcs8: &[u8],
+        prev_txs: &HashMap<String, Transaction>,
+    ) -> Result<(), String> {
+        let mut prev_outputs = vec![];
+        for vin in &self.vin {
+            let txid_hex = HEXLOWER.encode(vin.get_txid());
+            let prev_tx = prev_txs
+                .get(&txid_hex)
+                .ok_or_else(|| format!("missing previous transaction {txid_hex}"))?;
+            let prev_out = prev_tx.vout.get(vin.vout).ok_or_else(|| {
+                format!("previous transaction {txid_hex} has no output {}", vin.vout)
+            })?;
+            prev_outputs.push(prev_out.clone());
+        }
+        self.sign_offline(pkcs8, &prev_outputs)
+    }
+
+    /// Add the signature of `pkcs8` to input `idx`, which spends the multisig output
+    /// `prev_output`. The input verifies once enough of the lock's participants have signed.
+    pub fn sign_multisig_input(
+        &mut self,
+        idx: usize,
+        pkcs8: &[u8],
+        prev_output: &TXOutput,
+    ) -> Result<(), String> {
+        let lock = prev_output
+            .get_multisig()
+            .ok_or_else(|| String::from("the spent output is not a multisig output"))?;
+        if idx >= self.vin.len() {
+            return Err(format!("transaction has no input {idx}"));
+        }
+        let public_key =
+            util::public_key_from_pkcs8(pkcs8).ok_or_else(|| String::from("invalid pkcs8 key"))?;
+        if !lock.contains(&hash_pub_key(&public_key)) {
+            return Err(String::from("the key is not part of the multisig lock"));
+        }
+        // Multisig inputs carry the lock hash as their pub_key, so the spend never reads as a
+        // coinbase
+        self.vin[idx].pub_key = prev_output.pub_key_hash.clone();
+        if self.id.is_empty() {
+            self.id = self.hash();
+        }
+
+        let mut tx_copy = self.trimmed_copy();
+        tx_copy.vin[idx].pub_key = prev_output.pub_key_hash.clone();
+        let signature = util::ecdsa_p256_sha256_sign_digest(pkcs8, &tx_copy.hash());
+        self.vin[idx]
+            .multisig_signatures
+            .push((public_key, signature));
+        Ok(())
+    }
+
     pub fn verify(&self, blockchain: &Blockchain) -> bool {
+        self.verify_with(|txid| match blockchain.find_transaction(txid) {
+            Some(prev_tx) => Some(prev_tx),
+            None => panic!("ERROR: Previous transaction is not correct"),
+        })
+    }
+
+    /// Verify against the previous transactions in `prev_txs`, keyed by txid hex, instead of
+    /// the chain. False if any input's previous transaction is missing.
+    pub fn verify_against(&self, prev_txs: &HashMap<String, Transaction>) -> bool {
+        self.verify_with(|txid| prev_txs.get(&HEXLOWER.encode(txid)).cloned())
+    }
+
+    fn verify_with(&self, find_prev_tx: impl Fn(&[u8]) -> Option<Transaction>) -> bool {
         if self.is_coinbase() {
             return true;
         }
         let mut tx_copy = self.trimmed_copy();
         for (idx, vin) in self.vin.iter().enumerate() {
-            let prev_tx_option = blockchain.find_transaction(vin.get_txid());
-            if prev_tx_option.is_none() {
-                panic!("ERROR: Previous transaction is not correct")
-            }
-            let prev_tx = prev_tx_option.unwrap();
+            let Some(prev_tx) = find_prev_tx(vin.get_txid()) else {
+                return false;
+            };
+            // A pruned previous transaction no longer carries the output
+            let Some(prev_out) = prev_tx.vout.get(vin.vout) else {
+                return false;
+            };
             tx_copy.vin[idx].signature = vec![];
-            tx_copy.vin[idx].pub_key = prev_tx.vout[vin.vout].pub_key_hash.clone();
+            tx_copy.vin[idx].pub_key = prev_out.pub_key_hash.clone();
             tx_copy.id = tx_copy.hash();
             tx_copy.vin[idx].pub_key = vec![];
 
-            let verify = ecdsa_p256_sha256_sign_verify(
-                vin.pub_key.as_slice(),
-                vin.signature.as_slice(),
-                tx_copy.get_id(),
-            );
+            let verify = match prev_out.get_multisig() {
+                Some(lock) => self.verify_multisig(idx, vin, lock, tx_copy.get_id()),
+                None => self.verify_signature(idx, &vin.pub_key, &vin.signature, tx_copy.get_id()),
+            };
             if !verify {
                 return false;
             }
@@ -156,6 +488,103 @@ impl Transaction {
         true
     }
 
+    /// Check one signature for input `idx`, consulting the signature cache first
+    fn verify_signature(
+        &self,
+        idx: usize,
+        pub_key: &[u8],
+        signature: &[u8],
+        signed_data: &[u8],
+    ) -> bool {
+        let key = Self::sig_cache_key(&self.id, idx, pub_key, signature, signed_data);
+        GLOBAL_SIG_CACHE.get(&key).unwrap_or_else(|| {
+            let verify = ecdsa_p256_sha256_sign_verify(pub_key, signature, signed_data);
+            GLOBAL_SIG_CACHE.insert(key, verify);
+            verify
+        })
+    }
+
+    /// True if `vin` carries valid signatures from at least `required` distinct keys of
+    /// `lock` and none from outside it
+    fn verify_multisig(
+        &self,
+        idx: usize,
+        vin: &TXInput,
+        lock: &MultiSigLock,
+        signed_data: &[u8],
+    ) -> bool {
+        let mut signers: Vec<Vec<u8>> = vec![];
+        for (pub_key, signature) in vin.get_multisig_signatures() {
+            let pub_key_hash = hash_pub_key(pub_key);
+            if !lock.contains(&pub_key_hash) || signers.contains(&pub_key_hash) {
+                return false;
+            }
+            if !self.verify_signature(idx, pub_key, signature, signed_data) {
+                return false;
+            }
+            signers.push(pub_key_hash);
+        }
+        signers.len() >= lock.get_required()
+    }
+
+    /// Signature cache key for input `idx` of `txid`, covering everything the check depends on
+    fn sig_cache_key(
+        txid: &[u8],
+        idx: usize,
+        pub_key: &[u8],
+        signature: &[u8],
+        signed_data: &[u8],
+    ) -> SigCacheKey {
+        let mut material = vec![];
+        material.extend_from_slice(signature);
+        material.extend_from_slice(pub_key);
+        material.extend_from_slice(signed_data);
+        (txid.to_vec(), idx, sha256_digest(material.as_slice()))
+    }
+
+    /// Cheap structural checks to run before signature verification
+    pub fn validate_structure(&self) -> Result<(), String> {
+        if self.is_coinbase() {
+            if !self.vin[0].txid.is_empty() {
+                return Err(String::from("coinbase input must not reference a txid"));
+            }
+        } else {
+            if self.vin.is_empty() {
+                return Err(String::from("transaction has no inputs"));
+            }
+            if self.vout.is_empty() {
+                return Err(String::from("transaction has no outputs"));
+            }
+        }
+        let mut total: i64 = 0;
+        for (idx, out) in self.vout.iter().enumerate() {
+            if out.value < 0 {
+                return Err(format!("output {idx} has negative value {}", out.value));
+            }
+            total = total
+                .checked_add(out.value as i64)
+                .ok_or_else(|| String::from("total output value overflows"))?;
+        }
+        Ok(())
+    }
+
+    /// Placeholder keeping the id, which is all the block's merkle root commits to, and the
+    /// inputs, so the outputs they spent stay spent when the UTXO set is rebuilt from the chain
+    pub fn pruned(&self) -> Transaction {
+        Transaction {
+            id: self.id.clone(),
+            vin: self.vin.clone(),
+            vout: vec![],
+            lock_time: 0,
+        }
+    }
+
+    /// Whether this is a placeholder left by `Blockchain::prune_below`. No valid transaction
+    /// is without outputs.
+    pub fn is_pruned(&self) -> bool {
+        self.vout.is_empty()
+    }
+
     pub fn get_vin(&self) -> &[TXInput] {
         self.vin.as_slice()
     }
@@ -184,6 +613,28 @@ impl Transaction {
         Ok(tx)
     }
 
+    /// Hex of the serialized transaction, as printed by `getrawtx`
+    pub fn to_raw_hex(&self) -> String {
+        HEXLOWER.encode(self.serialize().as_slice())
+    }
+
+    /// Parse the hex printed by `to_raw_hex`, failing on malformed hex, bytes that do not
+    /// decode to a transaction or bytes left over after one
+    pub fn from_raw_hex(hex: &str) -> Result<Transaction, String> {
+        let bytes = HEXLOWER
+            .decode(hex.trim().to_ascii_lowercase().as_bytes())
+            .map_err(|e| format!("invalid hex: {e}"))?;
+        let (tx, read): (Transaction, usize) = bincode::decode_from_slice(&bytes, standard())
+            .map_err(|e| format!("not a transaction: {e}"))?;
+        if read != bytes.len() {
+            return Err(format!(
+                "{} trailing bytes after the transaction",
+                bytes.len() - read
+            ));
+        }
+        Ok(tx)
+    }
+
     pub fn get_id_bytes(&self) -> &[u8] {
         &self.id
     }
@@ -0,0 +1,25 @@
+use crate::transaction::TxEstimate;
+
+impl TxEstimate {
+    pub fn get_input_count(&self) -> usize {
+        self.input_count
+    }
+
+    pub fn get_total_input(&self) -> i64 {
+        self.total_input
+    }
+
+    pub fn get_change(&self) -> i64 {
+        self.change
+    }
+
+    /// Serialized size in bytes of the signed transaction
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Input value not paid to any output
+    pub fn get_fee(&self) -> i64 {
+        self.fee
+    }
+}
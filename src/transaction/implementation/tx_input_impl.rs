@@ -7,6 +7,7 @@ impl TXInput {
             vout,
             signature: vec![],
             pub_key: vec![],
+            multisig_signatures: vec![],
         }
     }
 
@@ -22,6 +23,10 @@ impl TXInput {
         self.pub_key.as_slice()
     }
 
+    pub fn get_multisig_signatures(&self) -> &[(Vec<u8>, Vec<u8>)] {
+        self.multisig_signatures.as_slice()
+    }
+
     // pub fn uses_key(&self, pub_key_hash: &[u8]) -> bool {
     //     let locking_hash = wallet::hash_pub_key(self.pub_key.as_slice());
     //     return locking_hash.eq(pub_key_hash);
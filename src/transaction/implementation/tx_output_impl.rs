@@ -1,4 +1,7 @@
-use crate::{transaction::TXOutput, util, wallet};
+use crate::{
+    transaction::{MultiSigLock, TXOutput, data::tx_output::{BURN_PUB_KEY_HASH_LEN, MAX_DATA_OUTPUT_LEN}},
+    util, wallet,
+};
 
 impl TXOutput {
    
@@ -6,12 +9,64 @@ impl TXOutput {
         let mut output = TXOutput {
             value,
             pub_key_hash: vec![],
+            multisig: None,
+            data: None,
         };
         output.lock(address);
         
         output
     }
 
+    /// An output spendable only with signatures satisfying `lock`
+    pub fn new_multisig(value: i32, lock: MultiSigLock) -> TXOutput {
+        TXOutput {
+            value,
+            pub_key_hash: lock.lock_hash(),
+            multisig: Some(lock),
+            data: None,
+        }
+    }
+
+    /// An output destroying `value`, locked to a hash no key can match
+    pub fn new_burn(value: i32) -> TXOutput {
+        TXOutput {
+            value,
+            pub_key_hash: vec![0; BURN_PUB_KEY_HASH_LEN],
+            multisig: None,
+            data: None,
+        }
+    }
+
+    /// A zero-value output carrying `data`, at most `MAX_DATA_OUTPUT_LEN` bytes of it
+    pub fn new_data(data: &[u8]) -> Result<TXOutput, String> {
+        if data.len() > MAX_DATA_OUTPUT_LEN {
+            return Err(format!(
+                "data output carries {} bytes, at most {MAX_DATA_OUTPUT_LEN} allowed",
+                data.len()
+            ));
+        }
+        Ok(TXOutput {
+            value: 0,
+            pub_key_hash: vec![],
+            multisig: None,
+            data: Some(data.to_vec()),
+        })
+    }
+
+    pub fn get_data(&self) -> Option<&[u8]> {
+        self.data.as_deref()
+    }
+
+    pub fn is_data(&self) -> bool {
+        self.data.is_some()
+    }
+
+    pub fn is_burn(&self) -> bool {
+        self.multisig.is_none()
+            && self.pub_key_hash.len() == BURN_PUB_KEY_HASH_LEN
+            && self.pub_key_hash.iter().all(|byte| *byte == 0)
+    }
+
     pub fn get_value(&self) -> i32 {
         self.value
     }
@@ -34,7 +89,24 @@ impl TXOutput {
         self.pub_key_hash = pub_key_hash;
     }
 
+    pub fn get_multisig(&self) -> Option<&MultiSigLock> {
+        self.multisig.as_ref()
+    }
+
+    /// Whether any key can ever spend this output; burn and data outputs stay unspent
+    pub fn is_spendable(&self) -> bool {
+        !self.is_burn() && !self.is_data()
+    }
+
+    /// Whether this spendable output is worth less than `threshold`. Burn and data outputs
+    /// are never dust.
+    pub fn is_dust(&self, threshold: i32) -> bool {
+        self.is_spendable() && self.value < threshold
+    }
+
+    /// A multisig output is locked with its lock hash, never with a single participant's key.
+    /// Burn and data outputs are locked with nothing.
     pub fn is_locked_with_key(&self, pub_key_hash: &[u8]) -> bool {
-        self.pub_key_hash.eq(pub_key_hash)
+        self.is_spendable() && self.pub_key_hash.eq(pub_key_hash)
     }
 }
@@ -2,7 +2,11 @@ mod data;
 mod implementation;
 
 // Re-export specific types instead of wildcards
-pub use data::transaction::Transaction;
+pub use data::transaction::{LOCK_TIME_THRESHOLD, SUBSIDY, Transaction};
 pub use data::tx_input::TXInput;
-pub use data::tx_output::TXOutput;
+pub use data::tx_output::{BURN_PUB_KEY_HASH_LEN, MAX_DATA_OUTPUT_LEN, TXOutput};
 
+pub use data::tx_estimate::{COMPRESSED_PUBLIC_KEY_LEN, PUBLIC_KEY_LEN, SIGNATURE_LEN, TxEstimate};
+pub use data::multisig_lock::MultiSigLock;
+
+pub use data::sig_cache::{GLOBAL_SIG_CACHE, SIG_CACHE_CAPACITY, SigCache, SigCacheKey};
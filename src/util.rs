@@ -1,7 +1,21 @@
 use std::path::PathBuf;
 
 use crypto::{digest::Digest, sha2::Sha256};
-use ring::signature::{ECDSA_P256_SHA256_FIXED, ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair};
+use p256::{
+    FieldBytes, PublicKey, Scalar,
+    elliptic_curve::{ff::PrimeField, scalar::IsHigh, sec1::ToSec1Point},
+};
+use ring::signature::{
+    ECDSA_P256_SHA256_FIXED, ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair,
+};
+
+use crate::{
+    transaction::{COMPRESSED_PUBLIC_KEY_LEN, SIGNATURE_LEN},
+    wallet::ADDRESS_CHECK_SUM_LEN,
+};
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub fn current_timestamp() -> i64 {
     std::time::SystemTime::now()
@@ -18,6 +32,11 @@ pub fn sha256_digest(data: &[u8]) -> Vec<u8> {
     out.to_vec()
 }
 
+/// SHA256 applied twice, as Bitcoin hashes blocks and transactions
+pub fn sha256d(data: &[u8]) -> Vec<u8> {
+    sha256_digest(sha256_digest(data).as_slice())
+}
+
 pub fn base58_encode(data: &[u8]) -> String {
     bs58::encode(data).into_string()
 }
@@ -26,6 +45,34 @@ pub fn base58_decode(data: &str) -> Vec<u8> {
     bs58::decode(data).into_vec().unwrap_or_else(|_| vec![])
 }
 
+/// Base58 of `version`, `payload` and the first `ADDRESS_CHECK_SUM_LEN` bytes of their double
+/// SHA256
+pub fn base58check_encode(version: u8, payload: &[u8]) -> String {
+    let mut data = vec![version];
+    data.extend_from_slice(payload);
+    let checksum = base58check_checksum(data.as_slice());
+    data.extend(checksum);
+    base58_encode(data.as_slice())
+}
+
+/// The version byte and payload of a base58check string, or None if it is not valid base58,
+/// is too short or its checksum does not match
+pub fn base58check_decode(data: &str) -> Option<(u8, Vec<u8>)> {
+    let bytes = bs58::decode(data).into_vec().ok()?;
+    if bytes.len() < 1 + ADDRESS_CHECK_SUM_LEN {
+        return None;
+    }
+    let (body, checksum) = bytes.split_at(bytes.len() - ADDRESS_CHECK_SUM_LEN);
+    if base58check_checksum(body) != checksum {
+        return None;
+    }
+    Some((body[0], body[1..].to_vec()))
+}
+
+fn base58check_checksum(data: &[u8]) -> Vec<u8> {
+    sha256d(data)[..ADDRESS_CHECK_SUM_LEN].to_vec()
+}
+
 pub fn current_dir() -> PathBuf {
     std::env::current_dir().unwrap()
 }
@@ -33,10 +80,51 @@ pub fn current_dir() -> PathBuf {
 pub fn ecdsa_p256_sha256_sign_digest(pkcs8: &[u8], message: &[u8]) -> Vec<u8> {
     let rng = ring::rand::SystemRandom::new();
     let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8, &rng).unwrap();
-    key_pair.sign(&rng, message).unwrap().as_ref().to_vec()
+    let signature = key_pair.sign(&rng, message).unwrap().as_ref().to_vec();
+    normalize_signature(&signature).unwrap_or(signature)
+}
+
+// The s half of a fixed-size r || s signature, or None if it is not a valid scalar
+fn signature_s(signature: &[u8]) -> Option<Scalar> {
+    if signature.len() != SIGNATURE_LEN {
+        return None;
+    }
+    let bytes = FieldBytes::try_from(&signature[SIGNATURE_LEN / 2..]).ok()?;
+    Scalar::from_repr(bytes).into()
+}
+
+/// Whether a fixed-size r || s signature has s in the lower half of the curve order. Both
+/// s and its negation verify, so only the low one is accepted to keep signatures, and the
+/// txids covering them, from being changed by a third party.
+pub fn is_low_s_signature(signature: &[u8]) -> bool {
+    signature_s(signature).is_some_and(|s| !bool::from(s.is_high()))
 }
 
+/// The low-s form of a fixed-size r || s signature, or None if it is malformed
+pub fn normalize_signature(signature: &[u8]) -> Option<Vec<u8>> {
+    let s = signature_s(signature)?;
+    if !bool::from(s.is_high()) {
+        return Some(signature.to_vec());
+    }
+    let mut normalized = signature[..SIGNATURE_LEN / 2].to_vec();
+    normalized.extend_from_slice(&(-s).to_repr());
+    Some(normalized)
+}
+
+/// Verify a low-s `signature` over `message` with a P-256 public key in either SEC1 form
 pub fn ecdsa_p256_sha256_sign_verify(public_key: &[u8], signature: &[u8], message: &[u8]) -> bool {
+    if !is_low_s_signature(signature) {
+        return false;
+    }
+    // ring only accepts uncompressed keys
+    let public_key = if public_key.len() == COMPRESSED_PUBLIC_KEY_LEN {
+        match uncompressed_public_key(public_key) {
+            Some(public_key) => public_key,
+            None => return false,
+        }
+    } else {
+        public_key.to_vec()
+    };
     let peer_public_key =
         ring::signature::UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, public_key);
     let result = peer_public_key.verify(message, signature.as_ref());
@@ -49,6 +137,24 @@ pub fn new_key_pair() -> Vec<u8> {
     pkcs8.as_ref().to_vec()
 }
 
+pub fn public_key_from_pkcs8(pkcs8: &[u8]) -> Option<Vec<u8>> {
+    let rng = ring::rand::SystemRandom::new();
+    let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8, &rng).ok()?;
+    Some(key_pair.public_key().as_ref().to_vec())
+}
+
+/// The 33-byte SEC1 compressed form of a P-256 public key, or None if it is not a valid key
+pub fn compressed_public_key(public_key: &[u8]) -> Option<Vec<u8>> {
+    let public_key = PublicKey::from_sec1_bytes(public_key).ok()?;
+    Some(public_key.to_sec1_point(true).as_bytes().to_vec())
+}
+
+/// The 65-byte SEC1 uncompressed form of a P-256 public key, or None if it is not a valid key
+pub fn uncompressed_public_key(public_key: &[u8]) -> Option<Vec<u8>> {
+    let public_key = PublicKey::from_sec1_bytes(public_key).ok()?;
+    Some(public_key.to_sec1_point(false).as_bytes().to_vec())
+}
+
 pub fn ripemd160_digest(data: &[u8]) -> Vec<u8> {
     let mut ripemd160 = crypto::ripemd160::Ripemd160::new();
     ripemd160.input(data);
@@ -58,3 +164,55 @@ pub fn ripemd160_digest(data: &[u8]) -> Vec<u8> {
     buf
 }
 
+/// RIPEMD160 of the SHA256 of `data`, the digest addresses are built from
+pub fn hash160(data: &[u8]) -> Vec<u8> {
+    ripemd160_digest(sha256_digest(data).as_slice())
+}
+
+/// Append `value` to `out` as a compact varint: one byte below 0xfd, otherwise a 0xfd,
+/// 0xfe or 0xff marker followed by the value in 2, 4 or 8 little-endian bytes
+pub fn write_varint(value: u64, out: &mut Vec<u8>) {
+    match value {
+        0..0xfd => out.push(value as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(value as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+/// The varint at the start of `bytes` and how many bytes it took, or None if it is cut
+/// short or not in its shortest form
+pub fn read_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let (&marker, rest) = bytes.split_first()?;
+    let (value, len, min) = match marker {
+        0xfd => (
+            u16::from_le_bytes(rest.get(..2)?.try_into().ok()?) as u64,
+            3,
+            0xfd,
+        ),
+        0xfe => (
+            u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as u64,
+            5,
+            0x1_0000,
+        ),
+        0xff => (
+            u64::from_le_bytes(rest.get(..8)?.try_into().ok()?),
+            9,
+            0x1_0000_0000,
+        ),
+        _ => return Some((marker as u64, 1)),
+    };
+    if value < min {
+        return None;
+    }
+    Some((value, len))
+}
@@ -0,0 +1,82 @@
+//! Fixtures for tests written against this crate, enabled by the `testing` feature
+
+use std::{ops::Deref, path::Path};
+
+use data_encoding::HEXLOWER;
+use tempfile::TempDir;
+
+use crate::{Block, Blockchain, ChainParams, GENESIS_PRE_BLOCK_HASH, Transaction, UTXOSet, util};
+
+/// A blockchain kept in a temporary directory that is removed when the fixture is dropped
+pub struct TempBlockchain {
+    blockchain: Blockchain,
+    // Declared after the chain so the database is closed before its directory goes
+    temp_dir: TempDir,
+}
+
+impl TempBlockchain {
+    /// An empty chain with no genesis block
+    pub fn new() -> TempBlockchain {
+        let temp_dir = TempDir::new().expect("unable to create temp directory");
+        let db = sled::open(temp_dir.path()).expect("unable to open database");
+        TempBlockchain {
+            blockchain: Blockchain::new_with_empty_tip(db),
+            temp_dir,
+        }
+    }
+
+    /// A chain whose genesis block pays `address`, with its UTXO set indexed
+    pub fn with_genesis(address: &str) -> TempBlockchain {
+        let temp_dir = TempDir::new().expect("unable to create temp directory");
+        let db = sled::open(temp_dir.path()).expect("unable to open database");
+        let blockchain = Blockchain::create_blockchain_in(db, &ChainParams::new(address));
+        UTXOSet::new(blockchain.clone()).reindex();
+        TempBlockchain {
+            blockchain,
+            temp_dir,
+        }
+    }
+
+    pub fn get_blockchain(&self) -> &Blockchain {
+        &self.blockchain
+    }
+
+    pub fn get_utxo_set(&self) -> UTXOSet {
+        UTXOSet::new(self.blockchain.clone())
+    }
+
+    /// Directory the chain's database lives in
+    pub fn get_path(&self) -> &Path {
+        self.temp_dir.path()
+    }
+}
+
+impl Default for TempBlockchain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for TempBlockchain {
+    type Target = Blockchain;
+
+    fn deref(&self) -> &Self::Target {
+        &self.blockchain
+    }
+}
+
+/// Add a block of `transactions` on the tip of `blockchain` without proof of work, and
+/// apply it to the UTXO set. Its hash is derived from its contents so it is unique.
+pub fn mine_test_block(blockchain: &Blockchain, transactions: &[Transaction]) -> Block {
+    let (pre_block_hash, height) = if blockchain.is_empty() {
+        (String::from(GENESIS_PRE_BLOCK_HASH), 0)
+    } else {
+        (blockchain.get_tip_hash(), blockchain.get_best_height() + 1)
+    };
+    let mut block = Block::new_block_without_proof_of_work(pre_block_hash, transactions, height);
+    let hash = util::sha256_digest(block.serialize().as_slice());
+    block.set_hash_for_test(HEXLOWER.encode(hash.as_slice()).as_str());
+    blockchain.add_block(&block);
+    UTXOSet::new(blockchain.clone()).update(&block);
+    block
+}
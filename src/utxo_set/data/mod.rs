@@ -1 +1,2 @@
+pub mod utxo_diff;
 pub mod utxo_set;
@@ -0,0 +1,12 @@
+use crate::TXOutput;
+
+/// The outputs `UTXOSet::apply` added to and removed from the set for one block
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct UtxoDiff {
+    pub(in crate::utxo_set) block_hash: String,
+    pub(in crate::utxo_set) block_height: usize,
+    // (txid, vout, output) of every output the block's transactions created, in block order
+    pub(in crate::utxo_set) created: Vec<(Vec<u8>, usize, TXOutput)>,
+    // (txid, vout, output) of every output the block's inputs spent, in spending order
+    pub(in crate::utxo_set) spent: Vec<(Vec<u8>, usize, TXOutput)>,
+}
@@ -1,7 +1,28 @@
-use crate::Blockchain;
+use crate::{Blockchain, TXOutput};
 
+// Maps txid to its UtxoEntry
 pub const UTXO_TREE: &str = "chainstate";
+// Maps block hash to the encoded UtxoDiff that block applied, for rolling it back
+pub const UTXO_UNDO_TREE: &str = "chainstate_undo";
+// Blocks below the tip whose undo records are kept. A reorg deeper than this reverts the
+// older blocks from the chain instead.
+pub const MAX_UNDO_DEPTH: usize = 100;
+// Maps pub_key_hash to the big-endian i64 total value of its unspent outputs
+pub const BALANCE_INDEX_TREE: &str = "chainstate_balances";
+// Maps coinbase txid to the big-endian u64 height of the block that created it
+pub const COINBASE_HEIGHT_TREE: &str = "chainstate_coinbase_heights";
+
+// The outputs of one transaction at their original vout positions, None once spent. The
+// entry is removed when every output is spent.
+pub(in crate::utxo_set) type UtxoEntry = Vec<Option<TXOutput>>;
+
+// The UTXO and coinbase height entries an exported snapshot holds, as raw key/value pairs
+pub(in crate::utxo_set) type Snapshot = (Vec<(Vec<u8>, Vec<u8>)>, Vec<(Vec<u8>, Vec<u8>)>);
+// Length of the sha256 checksum appended to an exported snapshot
+pub(in crate::utxo_set) const SNAPSHOT_CHECKSUM_LEN: usize = 32;
 
 pub struct UTXOSet {
    pub(in crate::utxo_set) blockchain: Blockchain,
+   // Blocks a coinbase output must wait before it can be selected for spending
+   pub(in crate::utxo_set) coinbase_maturity: usize,
 }
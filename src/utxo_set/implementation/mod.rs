@@ -1 +1,2 @@
+pub mod utxo_diff_impl;
 pub mod utxo_set_impl;
@@ -0,0 +1,15 @@
+use crate::{TXOutput, utxo_set::UtxoDiff};
+
+impl UtxoDiff {
+    pub fn get_block_hash(&self) -> &str {
+        self.block_hash.as_str()
+    }
+
+    pub fn get_created(&self) -> &[(Vec<u8>, usize, TXOutput)] {
+        self.created.as_slice()
+    }
+
+    pub fn get_spent(&self) -> &[(Vec<u8>, usize, TXOutput)] {
+        self.spent.as_slice()
+    }
+}
@@ -1,16 +1,45 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+};
 
 use bincode::config::standard;
 use data_encoding::HEXLOWER;
+use log::{error, info};
+use sled::Tree;
 
 use crate::{
-    Block, Blockchain, TXOutput,
-    utxo_set::data::utxo_set::{UTXO_TREE, UTXOSet},
+    Block, Blockchain, MemoryPool, Reorg, TXInput, TXOutput, Transaction,
+    config::GLOBAL_CONFIG,
+    util,
+    utxo_set::{
+        UtxoDiff,
+        data::utxo_set::{
+            BALANCE_INDEX_TREE, COINBASE_HEIGHT_TREE, MAX_UNDO_DEPTH, SNAPSHOT_CHECKSUM_LEN,
+            Snapshot, UTXO_TREE, UTXO_UNDO_TREE, UTXOSet, UtxoEntry,
+        },
+    },
+    wallet::{ADDRESS_CHECK_SUM_LEN, wallet_util::validate_address},
 };
 
 impl UTXOSet {
     pub fn new(blockchain: Blockchain) -> UTXOSet {
-        UTXOSet { blockchain }
+        UTXOSet {
+            blockchain,
+            coinbase_maturity: GLOBAL_CONFIG.get_coinbase_maturity(),
+        }
+    }
+
+    /// Only select coinbase outputs for spending once `depth` blocks, counting the one
+    /// carrying the spend, have been mined on top of the block that created them
+    pub fn with_coinbase_maturity(mut self, depth: usize) -> UTXOSet {
+        self.coinbase_maturity = depth;
+        self
+    }
+
+    pub fn get_coinbase_maturity(&self) -> usize {
+        self.coinbase_maturity
     }
 
     pub fn get_blockchain(&self) -> &Blockchain {
@@ -22,31 +51,112 @@ impl UTXOSet {
         pub_key_hash: &[u8],
         amount: i32,
     ) -> (i32, HashMap<String, Vec<usize>>) {
+        let (accumulated, selected) = self.find_spendable_outputs_detailed(pub_key_hash, amount);
         let mut unspent_outputs: HashMap<String, Vec<usize>> = HashMap::new();
-        let mut accmulated = 0;
+        for (txid_hex, vout, _) in selected {
+            unspent_outputs.entry(txid_hex).or_default().push(vout);
+        }
+        (accumulated as i32, unspent_outputs)
+    }
+
+    /// Select outputs locked to `pub_key_hash` until their value covers `amount`, skipping
+    /// immature coinbase outputs. Returns the accumulated value and the
+    /// `(txid_hex, vout, value)` of every selected output.
+    pub fn find_spendable_outputs_detailed(
+        &self,
+        pub_key_hash: &[u8],
+        amount: i32,
+    ) -> (i64, Vec<(String, usize, i32)>) {
+        let mut selected = vec![];
+        let mut accumulated: i64 = 0;
         let db = self.blockchain.get_db();
         let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
+        let next_height = self.next_block_height();
         for item in utxo_tree.iter() {
             let (k, v) = item.unwrap();
+            if !self.is_mature(k.as_ref(), next_height) {
+                continue;
+            }
             let txid_hex = HEXLOWER.encode(k.to_vec().as_slice());
-            let (outs, _): (Vec<TXOutput>, _) =
-                bincode::decode_from_slice(v.to_vec().as_slice(), standard())
-                    .expect("unable to deserialize TXOutput");
-            for (idx, out) in outs.iter().enumerate() {
-                if out.is_locked_with_key(pub_key_hash) && accmulated < amount {
-                    accmulated += out.get_value();
-                    if unspent_outputs.contains_key(txid_hex.as_str()) {
-                        unspent_outputs
-                            .get_mut(txid_hex.as_str())
-                            .unwrap()
-                            .push(idx);
-                    } else {
-                        unspent_outputs.insert(txid_hex.clone(), vec![idx]);
-                    }
+            let outs = Self::decode_entry(v.as_ref());
+            for (idx, out) in Self::unspent(&outs) {
+                if out.is_locked_with_key(pub_key_hash) && accumulated < amount as i64 {
+                    accumulated += out.get_value() as i64;
+                    selected.push((txid_hex.clone(), idx, out.get_value()));
                 }
             }
         }
-        (accmulated, unspent_outputs)
+        (accumulated, selected)
+    }
+
+    /// Build an unsigned transaction paying `outputs` from `from_pub_key_hash`, plus the
+    /// previous outputs its inputs spend so an offline signer needs nothing else.
+    pub fn build_unsigned(
+        &self,
+        from_pub_key_hash: &[u8],
+        outputs: &[TXOutput],
+    ) -> Result<(Transaction, Vec<TXOutput>), String> {
+        let amount: i32 = outputs.iter().map(|out| out.get_value()).sum();
+        let (accumulated, valid_outputs) = self.find_spendable_outputs(from_pub_key_hash, amount);
+        if accumulated < amount {
+            return Err(format!("Not enough funds: have {accumulated}, need {amount}"));
+        }
+
+        let db = self.blockchain.get_db();
+        let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
+        let mut inputs = vec![];
+        let mut prev_outputs = vec![];
+        for (txid_hex, outs) in valid_outputs {
+            let txid = HEXLOWER.decode(txid_hex.as_bytes()).unwrap();
+            let outs_bytes = utxo_tree.get(txid.as_slice()).unwrap().unwrap();
+            let stored = Self::decode_entry(outs_bytes.as_ref());
+            for out in outs {
+                inputs.push(TXInput::new(txid.as_slice(), out));
+                prev_outputs.push(stored[out].clone().unwrap());
+            }
+        }
+
+        let mut vout = outputs.to_vec();
+        if accumulated > amount {
+            vout.push(TXOutput {
+                value: accumulated - amount,
+                pub_key_hash: from_pub_key_hash.to_vec(),
+                multisig: None,
+                data: None,
+            });
+        }
+
+        Ok((Transaction::new(vec![], inputs, vout), prev_outputs))
+    }
+
+    /// Height of the block that created coinbase `txid`, or None for other transactions
+    pub fn get_coinbase_height(&self, txid: &[u8]) -> Option<usize> {
+        let db = self.blockchain.get_db();
+        let coinbase_tree = db.open_tree(COINBASE_HEIGHT_TREE).unwrap();
+        coinbase_tree
+            .get(txid)
+            .unwrap()
+            .map(|bytes| u64::from_be_bytes(bytes.as_ref().try_into().unwrap()) as usize)
+    }
+
+    // Whether the outputs of `txid` may go into a block at `next_height`
+    fn is_mature(&self, txid: &[u8], next_height: usize) -> bool {
+        if self.coinbase_maturity == 0 {
+            return true;
+        }
+        match self.get_coinbase_height(txid) {
+            Some(height) => next_height.saturating_sub(height) >= self.coinbase_maturity,
+            None => true,
+        }
+    }
+
+    fn next_block_height(&self) -> usize {
+        if self.coinbase_maturity == 0 {
+            return 0;
+        }
+        self.blockchain
+            .try_get_best_height()
+            .map_or(0, |height| height + 1)
     }
 
     pub fn find_utxo(&self, pub_key_hash: &[u8]) -> Vec<TXOutput> {
@@ -55,10 +165,8 @@ impl UTXOSet {
         let mut utxos = vec![];
         for item in utxo_tree.iter() {
             let (_, v) = item.unwrap();
-            let (outs, _): (Vec<TXOutput>, _) =
-                bincode::decode_from_slice(v.to_vec().as_slice(), standard())
-                    .expect("unable to deserialize TXOutput");
-            for out in outs.iter() {
+            let outs = Self::decode_entry(v.as_ref());
+            for (_, out) in Self::unspent(&outs) {
                 if out.is_locked_with_key(pub_key_hash) {
                     utxos.push(out.clone())
                 }
@@ -67,6 +175,45 @@ impl UTXOSet {
         utxos
     }
 
+    /// Balance of each valid address in `addresses`, keyed and ordered by address
+    pub fn get_balances(&self, addresses: &[String]) -> BTreeMap<String, i32> {
+        let mut balances = BTreeMap::new();
+        for address in addresses {
+            if !validate_address(address.as_str()) {
+                continue;
+            }
+            let payload = util::base58_decode(address.as_str());
+            let pub_key_hash = &payload[1..payload.len() - ADDRESS_CHECK_SUM_LEN];
+            let balance = self
+                .find_utxo(pub_key_hash)
+                .iter()
+                .map(|utxo| utxo.get_value())
+                .sum();
+            balances.insert(address.clone(), balance);
+        }
+        balances
+    }
+
+    /// Whether output `vout` of `txid` is still present in the UTXO set and can be spent
+    pub fn is_spendable(&self, txid: &[u8], vout: usize) -> bool {
+        self.get_output(txid, vout).is_some()
+    }
+
+    /// Output `vout` of `txid`, if it is still present in the UTXO set. `vout` is the
+    /// output's position in the transaction, as inputs reference it; spending a sibling never
+    /// moves it. Burn and data outputs are kept but never returned.
+    pub fn get_output(&self, txid: &[u8], vout: usize) -> Option<TXOutput> {
+        let db = self.blockchain.get_db();
+        let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
+        let outs_bytes = utxo_tree.get(txid).unwrap()?;
+        let mut outs = Self::decode_entry(outs_bytes.as_ref());
+        if vout < outs.len() {
+            outs.swap_remove(vout).filter(|out| out.is_spendable())
+        } else {
+            None
+        }
+    }
+
     pub fn count_transactions(&self) -> i32 {
         let db = self.blockchain.get_db();
         let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
@@ -77,51 +224,466 @@ impl UTXOSet {
         counter
     }
 
-    pub fn reindex(&self) {
+    /// Sum of the values of every unspent output that is not burned
+    pub fn total_supply(&self) -> i64 {
+        let db = self.blockchain.get_db();
+        let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
+        let mut supply = 0;
+        for item in utxo_tree.iter() {
+            let (_, v) = item.unwrap();
+            let outs = Self::decode_entry(v.as_ref());
+            for (_, out) in Self::unspent(&outs).filter(|(_, out)| !out.is_burn()) {
+                supply += out.get_value() as i64;
+            }
+        }
+        supply
+    }
+
+    /// Checks that applying `block` grew the supply from `supply_before` by exactly the
+    /// value its coinbase minted less the value it burned and the fees its transactions paid,
+    /// which leave the set unless the coinbase claims them. If the transactions it spends
+    /// cannot be found to work the fees out, only checks that the supply grew by no more.
+    pub fn check_supply_invariant(&self, supply_before: i64, block: &Block) -> bool {
+        let minted: i64 = block
+            .get_transactions()
+            .iter()
+            .filter(|tx| tx.is_coinbase())
+            .flat_map(|tx| tx.get_vout())
+            .map(|out| out.get_value() as i64)
+            .sum();
+        let burned: i64 = block
+            .get_transactions()
+            .iter()
+            .flat_map(|tx| tx.get_vout())
+            .filter(|out| out.is_burn())
+            .map(|out| out.get_value() as i64)
+            .sum();
+        let ceiling = supply_before + minted - burned;
+        let actual = self.total_supply();
+        let (expected, holds) = match self.blockchain.block_fees(block) {
+            Ok(fees) => ((ceiling - fees).to_string(), actual == ceiling - fees),
+            Err(_) => (format!("at most {ceiling}"), actual <= ceiling),
+        };
+        if !holds {
+            error!(
+                "SUPPLY INVARIANT VIOLATED after block {}: expected {expected}, found {actual}",
+                block.get_hash()
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Rebuild the UTXO set, its balance index and coinbase heights from the chain.
+    /// Returns how many transactions still have unspent outputs.
+    pub fn reindex(&self) -> usize {
         let db = self.blockchain.get_db();
         let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
         utxo_tree.clear().unwrap();
+        db.open_tree(BALANCE_INDEX_TREE).unwrap().clear().unwrap();
+        let coinbase_tree = db.open_tree(COINBASE_HEIGHT_TREE).unwrap();
+        coinbase_tree.clear().unwrap();
+        // Undo records describe the set being replaced
+        db.open_tree(UTXO_UNDO_TREE).unwrap().clear().unwrap();
+        for block in self.blockchain.iterator() {
+            for tx in block
+                .get_transactions()
+                .iter()
+                .filter(|tx| tx.is_coinbase())
+            {
+                let height = block.get_height() as u64;
+                coinbase_tree
+                    .insert(tx.get_id(), &height.to_be_bytes())
+                    .unwrap();
+            }
+        }
 
         let utxo_map = self.blockchain.find_utxo();
         for (txid_hex, outs) in &utxo_map {
             let txid = HEXLOWER.decode(txid_hex.as_bytes()).unwrap();
-            let value = bincode::encode_to_vec(outs, standard()).unwrap();
-            let _ = utxo_tree.insert(txid.as_slice(), value).unwrap();
+            self.write_entry(&utxo_tree, txid.as_slice(), Self::encode_entry(outs));
+        }
+        utxo_map.len()
+    }
+
+    /// Write every UTXO entry and coinbase height to `path`, followed by a checksum of
+    /// them, so a new node can load the set without replaying the chain.
+    /// Returns how many transactions the snapshot holds.
+    pub fn export_snapshot(&self, path: &Path) -> io::Result<usize> {
+        let db = self.blockchain.get_db();
+        let collect = |tree_name: &str| -> Vec<(Vec<u8>, Vec<u8>)> {
+            db.open_tree(tree_name)
+                .unwrap()
+                .iter()
+                .map(|item| {
+                    let (k, v) = item.unwrap();
+                    (k.to_vec(), v.to_vec())
+                })
+                .collect()
+        };
+        let snapshot: Snapshot = (collect(UTXO_TREE), collect(COINBASE_HEIGHT_TREE));
+        let mut bytes = bincode::encode_to_vec(&snapshot, standard())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let checksum = util::sha256_digest(bytes.as_slice());
+        bytes.extend(checksum);
+        fs::write(path, bytes)?;
+        Ok(snapshot.0.len())
+    }
+
+    /// Replace the UTXO set, its balance index and coinbase heights with the snapshot at
+    /// `path` and drop the undo records, leaving them all untouched if the file is truncated
+    /// or its checksum does not match. Returns how many transactions were loaded.
+    pub fn import_snapshot(&self, path: &Path) -> io::Result<usize> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < SNAPSHOT_CHECKSUM_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "UTXO snapshot is truncated",
+            ));
+        }
+        let (payload, checksum) = bytes.split_at(bytes.len() - SNAPSHOT_CHECKSUM_LEN);
+        if util::sha256_digest(payload) != checksum {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "UTXO snapshot checksum does not match",
+            ));
         }
+        let ((utxo_entries, coinbase_heights), _): (Snapshot, _) =
+            bincode::decode_from_slice(payload, standard())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let db = self.blockchain.get_db();
+        let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
+        utxo_tree.clear().unwrap();
+        db.open_tree(BALANCE_INDEX_TREE).unwrap().clear().unwrap();
+        let coinbase_tree = db.open_tree(COINBASE_HEIGHT_TREE).unwrap();
+        coinbase_tree.clear().unwrap();
+        // Undo records describe the set being replaced
+        db.open_tree(UTXO_UNDO_TREE).unwrap().clear().unwrap();
+        for (txid, height) in coinbase_heights {
+            coinbase_tree.insert(txid, height).unwrap();
+        }
+        let loaded = utxo_entries.len();
+        for (txid, outs_bytes) in utxo_entries {
+            self.write_entry(&utxo_tree, txid.as_slice(), Some(outs_bytes));
+        }
+        Ok(loaded)
+    }
+
+    /// Total value of the unspent outputs locked to `pub_key_hash`, read from the balance
+    /// index that `update`, `rollback` and `reindex` maintain
+    pub fn get_balance(&self, pub_key_hash: &[u8]) -> i64 {
+        let db = self.blockchain.get_db();
+        let balance_tree = db.open_tree(BALANCE_INDEX_TREE).unwrap();
+        match balance_tree.get(pub_key_hash).unwrap() {
+            Some(bytes) => i64::from_be_bytes(bytes.as_ref().try_into().unwrap()),
+            None => 0,
+        }
+    }
+
+    /// Confirmed balance of `pub_key_hash` and the net change the transactions waiting in
+    /// `mempool` would make to it once mined: what they pay to it less the outputs of it they
+    /// spend, whether confirmed or created by another pooled transaction
+    pub fn get_balance_with_mempool(
+        &self,
+        pub_key_hash: &[u8],
+        mempool: &MemoryPool,
+    ) -> (i64, i64) {
+        let confirmed = self.get_balance(pub_key_hash);
+        let pooled = mempool.get_all();
+        let pooled_outputs: HashMap<&[u8], &[TXOutput]> = pooled
+            .iter()
+            .map(|tx| (tx.get_id(), tx.get_vout()))
+            .collect();
+
+        let mut pending = 0;
+        for tx in &pooled {
+            if !tx.is_coinbase() {
+                for vin in tx.get_vin() {
+                    let spent = self.get_output(vin.get_txid(), vin.get_vout()).or_else(|| {
+                        pooled_outputs
+                            .get(vin.get_txid())
+                            .and_then(|outs| outs.get(vin.get_vout()))
+                            .cloned()
+                    });
+                    if let Some(out) = spent
+                        && out.is_locked_with_key(pub_key_hash)
+                    {
+                        pending -= out.get_value() as i64;
+                    }
+                }
+            }
+            for out in tx.get_vout() {
+                if out.is_locked_with_key(pub_key_hash) {
+                    pending += out.get_value() as i64;
+                }
+            }
+        }
+        (confirmed, pending)
     }
 
     pub fn update(&self, block: &Block) {
+        self.apply(block);
+    }
+
+    /// Same as `update`, returning the outputs `block` created and spent. An output both
+    /// created and spent within the block appears in both lists.
+    pub fn apply(&self, block: &Block) -> UtxoDiff {
+        if GLOBAL_CONFIG.is_supply_check_enabled() {
+            let supply_before = self.total_supply();
+            let diff = self.apply_block(block);
+            self.check_supply_invariant(supply_before, block);
+            diff
+        } else {
+            self.apply_block(block)
+        }
+    }
+
+    /// Undo `apply` from the diff it returned, without the chain: remove the outputs it
+    /// created and put back the exact outputs it spent at their vout positions
+    pub fn revert_diff(&self, diff: UtxoDiff) {
+        let db = self.blockchain.get_db();
+        let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
+        let coinbase_tree = db.open_tree(COINBASE_HEIGHT_TREE).unwrap();
+        let mut created_txids: Vec<&[u8]> = vec![];
+        for (txid, _, _) in &diff.created {
+            if !created_txids.contains(&txid.as_slice()) {
+                self.write_entry(&utxo_tree, txid, None);
+                coinbase_tree.remove(txid).unwrap();
+                created_txids.push(txid);
+            }
+        }
+        for (txid, vout, out) in &diff.spent {
+            if created_txids.contains(&txid.as_slice()) {
+                continue;
+            }
+            self.restore_output(&utxo_tree, txid, *vout, out.clone());
+        }
+        let undo_tree = db.open_tree(UTXO_UNDO_TREE).unwrap();
+        undo_tree.remove(diff.block_hash.as_str()).unwrap();
+    }
+
+    /// Undo `update(block)` from its undo record, which holds the exact outputs the block
+    /// created and spent. Returns false if no undo data was recorded for the block.
+    pub fn rollback(&self, block: &Block) -> bool {
+        let db = self.blockchain.get_db();
+        let undo_tree = db.open_tree(UTXO_UNDO_TREE).unwrap();
+        let Some(undo_bytes) = undo_tree.get(block.get_hash()).unwrap() else {
+            return false;
+        };
+        let (diff, _): (UtxoDiff, _) = bincode::decode_from_slice(undo_bytes.as_ref(), standard())
+            .expect("unable to deserialize undo data");
+        self.revert_diff(diff);
+        true
+    }
+
+    /// Undo `update(block)` without undo data: remove the outputs the block created and
+    /// re-add the outputs its inputs spent, taken by vout from the spent transactions in the
+    /// chain. Returns false, leaving the set untouched, if a spent transaction or output cannot
+    /// be found, as when `prune_below` has discarded it.
+    pub fn revert(&self, block: &Block) -> bool {
+        let mut diff = UtxoDiff {
+            block_hash: block.get_hash().to_string(),
+            block_height: block.get_height(),
+            created: vec![],
+            spent: vec![],
+        };
+        for tx in block.get_transactions() {
+            for (idx, out) in tx.get_vout().iter().enumerate() {
+                diff.created.push((tx.get_id().to_vec(), idx, out.clone()));
+            }
+            if tx.is_coinbase() {
+                continue;
+            }
+            for vin in tx.get_vin() {
+                let prev_tx = block
+                    .get_transactions()
+                    .iter()
+                    .find(|candidate| candidate.get_id().eq(vin.get_txid()))
+                    .cloned()
+                    .or_else(|| self.blockchain.find_transaction(vin.get_txid()));
+                let Some(prev_tx) = prev_tx else {
+                    return false;
+                };
+                // A pruned transaction no longer carries the spent output
+                let Some(out) = prev_tx.get_vout().get(vin.get_vout()) else {
+                    return false;
+                };
+                diff.spent
+                    .push((vin.get_txid().to_vec(), vin.get_vout(), out.clone()));
+            }
+        }
+        self.revert_diff(diff);
+        true
+    }
+
+    /// Drop the undo records of blocks more than `MAX_UNDO_DEPTH` below `tip_height`.
+    /// Returns how many were dropped.
+    pub fn prune_undo(&self, tip_height: usize) -> usize {
+        let undo_tree = self.blockchain.get_db().open_tree(UTXO_UNDO_TREE).unwrap();
+        let mut pruned = 0;
+        for item in undo_tree.iter() {
+            let (block_hash, undo_bytes) = item.unwrap();
+            let (diff, _): (UtxoDiff, _) =
+                bincode::decode_from_slice(undo_bytes.as_ref(), standard())
+                    .expect("unable to deserialize undo data");
+            if diff.block_height + MAX_UNDO_DEPTH < tip_height {
+                undo_tree.remove(block_hash).unwrap();
+                pruned += 1;
+            }
+        }
+        pruned
+    }
+
+    /// Move the UTXO set onto the new main chain: roll back the disconnected blocks, then
+    /// apply the connected ones. Blocks without undo data are reverted from the chain, and a
+    /// full reindex is the last resort.
+    pub fn reorganize(&self, reorg: &Reorg) {
+        for block in reorg.get_disconnected() {
+            if !self.rollback(block) && !self.revert(block) {
+                error!(
+                    "Unable to undo block {}, reindexing UTXO set",
+                    block.get_hash()
+                );
+                self.reindex();
+                return;
+            }
+        }
+        for block in reorg.get_connected() {
+            self.update(block);
+        }
+        info!(
+            "Reorganized UTXO set: {} blocks disconnected, {} connected",
+            reorg.get_disconnected().len(),
+            reorg.get_connected().len()
+        );
+    }
+
+    fn apply_block(&self, block: &Block) -> UtxoDiff {
+        let mut diff = UtxoDiff {
+            block_hash: block.get_hash().to_string(),
+            block_height: block.get_height(),
+            created: vec![],
+            spent: vec![],
+        };
         let db = self.blockchain.get_db();
         let utxo_tree = db.open_tree(UTXO_TREE).unwrap();
+        let coinbase_tree = db.open_tree(COINBASE_HEIGHT_TREE).unwrap();
         for tx in block.get_transactions() {
+            if tx.is_coinbase() {
+                let height = block.get_height() as u64;
+                coinbase_tree
+                    .insert(tx.get_id(), &height.to_be_bytes())
+                    .unwrap();
+            }
             if !tx.is_coinbase() {
                 for vin in tx.get_vin() {
-                    let mut updated_outs = vec![];
                     let outs_bytes = utxo_tree.get(vin.get_txid()).unwrap().unwrap();
-                    let (outs, _): (Vec<TXOutput>, _) =
-                        bincode::decode_from_slice(outs_bytes.as_ref(), standard())
-                            .expect("unable to deserialize TXOutput");
-                    for (idx, out) in outs.iter().enumerate() {
-                        if idx != vin.get_vout() {
-                            updated_outs.push(out.clone())
-                        }
-                    }
-                    if updated_outs.is_empty() {
-                        let _ = utxo_tree.remove(vin.get_txid()).unwrap();
-                    } else {
-                        let outs_bytes = bincode::encode_to_vec(&updated_outs, standard())
-                            .expect("unable to serialize TXOutput");
-                        utxo_tree.insert(vin.get_txid(), outs_bytes).unwrap();
+                    let mut outs = Self::decode_entry(outs_bytes.as_ref());
+                    if let Some(out) = outs.get_mut(vin.get_vout()).and_then(Option::take) {
+                        diff.spent
+                            .push((vin.get_txid().to_vec(), vin.get_vout(), out));
                     }
+                    self.write_entry(&utxo_tree, vin.get_txid(), Self::encode_entry(&outs));
                 }
             }
             let mut new_outputs = vec![];
-            for out in tx.get_vout() {
-                new_outputs.push(out.clone())
+            for (idx, out) in tx.get_vout().iter().enumerate() {
+                new_outputs.push(Some(out.clone()));
+                diff.created.push((tx.get_id().to_vec(), idx, out.clone()));
             }
-            let outs_bytes = bincode::encode_to_vec(&new_outputs, standard())
-                .expect("unable to serialize TXOutput");
-            let _ = utxo_tree.insert(tx.get_id(), outs_bytes).unwrap();
+            self.write_entry(&utxo_tree, tx.get_id(), Self::encode_entry(&new_outputs));
         }
+        self.record_undo(&diff);
+        diff
+    }
+
+    // Replace the UTXO entry for `txid` (removing it for None) and move the value of the
+    // outputs it held and now holds in the balance index
+    fn write_entry(&self, utxo_tree: &Tree, txid: &[u8], outs_bytes: Option<Vec<u8>>) {
+        let decode = |bytes: &[u8]| -> Vec<TXOutput> {
+            Self::decode_entry(bytes).into_iter().flatten().collect()
+        };
+        let previous = match &outs_bytes {
+            Some(bytes) => utxo_tree.insert(txid, bytes.as_slice()).unwrap(),
+            None => utxo_tree.remove(txid).unwrap(),
+        };
+
+        let mut deltas: HashMap<Vec<u8>, i64> = HashMap::new();
+        for out in previous
+            .map(|bytes| decode(bytes.as_ref()))
+            .unwrap_or_default()
+        {
+            *deltas.entry(out.get_pub_key_hash().to_vec()).or_default() -= out.get_value() as i64;
+        }
+        for out in outs_bytes
+            .map(|bytes| decode(bytes.as_slice()))
+            .unwrap_or_default()
+        {
+            *deltas.entry(out.get_pub_key_hash().to_vec()).or_default() += out.get_value() as i64;
+        }
+
+        let balance_tree = self
+            .blockchain
+            .get_db()
+            .open_tree(BALANCE_INDEX_TREE)
+            .unwrap();
+        for (pub_key_hash, delta) in deltas {
+            if delta == 0 {
+                continue;
+            }
+            let balance = self.get_balance(pub_key_hash.as_slice()) + delta;
+            if balance == 0 {
+                balance_tree.remove(pub_key_hash).unwrap();
+            } else {
+                balance_tree
+                    .insert(pub_key_hash, &balance.to_be_bytes())
+                    .unwrap();
+            }
+        }
+    }
+
+    // Put `out` back at position `vout` of the UTXO entry for `txid`
+    fn restore_output(&self, utxo_tree: &Tree, txid: &[u8], vout: usize, out: TXOutput) {
+        let mut outs = match utxo_tree.get(txid).unwrap() {
+            Some(outs_bytes) => Self::decode_entry(outs_bytes.as_ref()),
+            None => vec![],
+        };
+        if outs.len() <= vout {
+            outs.resize(vout + 1, None);
+        }
+        outs[vout] = Some(out);
+        self.write_entry(utxo_tree, txid, Self::encode_entry(&outs));
+    }
+
+    fn decode_entry(bytes: &[u8]) -> UtxoEntry {
+        bincode::decode_from_slice(bytes, standard())
+            .expect("unable to deserialize TXOutput")
+            .0
+    }
+
+    // The encoded entry, or None once every output is spent and the entry should go
+    fn encode_entry(outs: &[Option<TXOutput>]) -> Option<Vec<u8>> {
+        if outs.iter().all(Option::is_none) {
+            return None;
+        }
+        Some(bincode::encode_to_vec(outs, standard()).expect("unable to serialize TXOutput"))
+    }
+
+    // The unspent outputs of an entry with their vout positions
+    fn unspent(outs: &[Option<TXOutput>]) -> impl Iterator<Item = (usize, &TXOutput)> {
+        outs.iter()
+            .enumerate()
+            .filter_map(|(idx, out)| out.as_ref().map(|out| (idx, out)))
+    }
+
+    // Save `diff` as the undo record of its block
+    fn record_undo(&self, diff: &UtxoDiff) {
+        let undo_tree = self.blockchain.get_db().open_tree(UTXO_UNDO_TREE).unwrap();
+        let undo_bytes =
+            bincode::encode_to_vec(diff, standard()).expect("unable to serialize undo data");
+        undo_tree
+            .insert(diff.block_hash.as_str(), undo_bytes)
+            .unwrap();
     }
 }
@@ -1,5 +1,8 @@
 mod data;
 mod implementation;
 
-pub use data::utxo_set::{UTXOSet, UTXO_TREE};
+pub use data::utxo_diff::UtxoDiff;
+pub use data::utxo_set::{
+    UTXOSet, BALANCE_INDEX_TREE, COINBASE_HEIGHT_TREE, MAX_UNDO_DEPTH, UTXO_TREE, UTXO_UNDO_TREE,
+};
 pub use implementation::utxo_set_impl;
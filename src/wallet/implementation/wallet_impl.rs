@@ -1,10 +1,12 @@
+use p256::{SecretKey, pkcs8::EncodePrivateKey};
 use ring::rand::SystemRandom;
 use ring::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair};
 
-use crate::util;
-use crate::wallet::implementation::wallet_util::{checksum, hash_pub_key};
+use crate::{config::GLOBAL_CONFIG, util};
+use crate::wallet::implementation::wallet_util::{
+    convert_address, convert_address_with_version, hash_pub_key,
+};
 use crate::wallet::Wallet;
-use crate::wallet::data::wallet::VERSION;
 
 impl Wallet {
     pub fn new() -> Wallet {
@@ -17,21 +19,75 @@ impl Wallet {
         Wallet { pkcs8, public_key }
     }
 
+    /// Derive a wallet deterministically from `seed`: the same seed always yields the same keys.
+    /// The private scalar is SHA256(SHA256(seed) || counter) for the first counter that gives
+    /// a valid P-256 key.
+    pub fn from_seed(seed: &[u8]) -> Wallet {
+        let seed_hash = util::sha256_digest(seed);
+        let secret_key = (0u32..)
+            .find_map(|counter| {
+                let mut input = seed_hash.clone();
+                input.extend(counter.to_be_bytes());
+                SecretKey::from_slice(util::sha256_digest(input.as_slice()).as_slice()).ok()
+            })
+            .unwrap();
+        let pkcs8 = secret_key.to_pkcs8_der().unwrap().as_bytes().to_vec();
+        Wallet::from_pkcs8(pkcs8).unwrap()
+    }
+
+    /// Rebuild a wallet from a key produced by `export_private_key`
+    pub fn from_private_key(encoded: &str) -> Result<Wallet, String> {
+        let pkcs8 = bs58::decode(encoded.trim())
+            .into_vec()
+            .map_err(|e| format!("Private key is not valid base58: {e}"))?;
+        Wallet::from_pkcs8(pkcs8)
+    }
+
+    pub(crate) fn from_pkcs8(pkcs8: Vec<u8>) -> Result<Wallet, String> {
+        let rng = SystemRandom::new();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+                .map_err(|e| format!("Private key is not a valid PKCS#8 document: {e}"))?;
+        let public_key = key_pair.public_key().as_ref().to_vec();
+        Ok(Wallet { pkcs8, public_key })
+    }
+
+    /// Base58-encoded PKCS#8 private key, suitable for `from_private_key`
+    pub fn export_private_key(&self) -> String {
+        util::base58_encode(self.pkcs8.as_slice())
+    }
+
     pub fn get_address(&self) -> String {
-        let pub_key_hash = hash_pub_key(self.public_key.as_slice());
-        let mut payload: Vec<u8> = vec![];
-        payload.push(VERSION);
-        payload.extend(pub_key_hash.as_slice());
-        let checksum = checksum(payload.as_slice());
-        payload.extend(checksum.as_slice());
-        // version + pub_key_hash + checksum
-        util::base58_encode(payload.as_slice())
+        let pub_key_hash = hash_pub_key(self.get_address_public_key().as_slice());
+        convert_address(pub_key_hash.as_slice())
+    }
+
+    /// This wallet's address under `version` rather than the configured address version
+    pub fn get_address_with_version(&self, version: u8) -> String {
+        let pub_key_hash = hash_pub_key(self.get_address_public_key().as_slice());
+        convert_address_with_version(pub_key_hash.as_slice(), version)
     }
 
+    /// The uncompressed 65-byte public key
     pub fn get_public_key(&self) -> &[u8] {
         self.public_key.as_slice()
     }
 
+    /// The 33-byte SEC1 compressed form of the public key
+    pub fn get_compressed_public_key(&self) -> Vec<u8> {
+        util::compressed_public_key(self.public_key.as_slice()).unwrap()
+    }
+
+    /// The public key the address is derived from and inputs carry: compressed when
+    /// compressed public keys are enabled, uncompressed otherwise
+    pub fn get_address_public_key(&self) -> Vec<u8> {
+        if GLOBAL_CONFIG.is_compressed_pubkeys_enabled() {
+            self.get_compressed_public_key()
+        } else {
+            self.public_key.clone()
+        }
+    }
+
     pub fn get_pkcs8(&self) -> &[u8] {
         self.pkcs8.as_slice()
     }
@@ -1,52 +1,25 @@
-use crate::{util, wallet::data::wallet::{ADDRESS_CHECK_SUM_LEN, VERSION}};
+use crate::{config::GLOBAL_CONFIG, util, wallet::data::wallet::ADDRESS_CHECK_SUM_LEN};
 
+/// Whether `address` has a valid checksum and the configured address version
 pub fn validate_address(address: &str) -> bool {
-    // Handle empty or invalid base58
-    if address.is_empty() {
-        return false;
-    }
-    
-    let payload = util::base58_decode(address);
-    
-    // Check minimum length: version (1) + at least some pub_key_hash + checksum (4)
-    if payload.len() < 1 + ADDRESS_CHECK_SUM_LEN {
-        return false;
-    }
-    
-    // Check if payload is empty (invalid base58 decode)
-    if payload.is_empty() {
-        return false;
-    }
-    
-    let actual_checksum = payload[payload.len() - ADDRESS_CHECK_SUM_LEN..].to_vec();
-    let version = payload[0];
-    
-    // Check version
-    if version != VERSION {
-        return false;
-    }
-    
-    let pub_key_hash = payload[1..payload.len() - ADDRESS_CHECK_SUM_LEN].to_vec();
+    validate_address_with_version(address, GLOBAL_CONFIG.get_address_version())
+}
 
-    let mut target_vec = vec![];
-    target_vec.push(version);
-    target_vec.extend(pub_key_hash);
-    let target_checksum = checksum(target_vec.as_slice());
-    actual_checksum.eq(target_checksum.as_slice())
+pub fn validate_address_with_version(address: &str, expected_version: u8) -> bool {
+    matches!(util::base58check_decode(address), Some((version, _)) if version == expected_version)
 }
 
+/// Encode `pub_hash_key` as an address with the configured address version
 pub fn convert_address(pub_hash_key: &[u8]) -> String {
-    let mut payload: Vec<u8> = vec![];
-    payload.push(VERSION);
-    payload.extend(pub_hash_key);
-    let checksum = checksum(payload.as_slice());
-    payload.extend(checksum.as_slice());
-    util::base58_encode(payload.as_slice())
+    convert_address_with_version(pub_hash_key, GLOBAL_CONFIG.get_address_version())
+}
+
+pub fn convert_address_with_version(pub_hash_key: &[u8], version: u8) -> String {
+    util::base58check_encode(version, pub_hash_key)
 }
 
 pub fn hash_pub_key(pub_key: &[u8]) -> Vec<u8> {
-    let pub_key_sha256 = util::sha256_digest(pub_key);
-    util::ripemd160_digest(pub_key_sha256.as_slice())
+    util::hash160(pub_key)
 }
 
 pub fn checksum(payload: &[u8]) -> Vec<u8> {
@@ -32,9 +32,32 @@ impl Wallets {
     }
 
     pub fn create_wallet(&mut self) -> String {
+        let address = self.insert_new_wallet();
+        self.save_to_file();
+
+        address
+    }
+
+    /// Create `count` wallets, saving the wallet file once after all of them are added
+    pub fn create_wallets(&mut self, count: usize) -> Vec<String> {
+        let addresses = (0..count).map(|_| self.insert_new_wallet()).collect();
+        self.save_to_file();
+
+        addresses
+    }
+
+    fn insert_new_wallet(&mut self) -> String {
         let wallet = Wallet::new();
         let address = wallet.get_address();
         self.wallets.insert(address.clone(), wallet);
+
+        address
+    }
+
+    /// Store an existing wallet, e.g. one restored from a private key
+    pub fn add_wallet(&mut self, wallet: Wallet) -> String {
+        let address = wallet.get_address();
+        self.wallets.insert(address.clone(), wallet);
         self.save_to_file();
 
         address
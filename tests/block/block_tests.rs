@@ -1,5 +1,5 @@
 use crate::test_helpers::*;
-use rust_blockchain::{Block, TXInput, TXOutput, Transaction, util};
+use rust_blockchain::{Block, BlockHeader, TXInput, TXOutput, Transaction, util};
 
 #[ignore]
 #[test]
@@ -623,12 +623,15 @@ fn test_large_transaction_data_without_proof_of_work() {
         txid: vec![0u8; 1000], // 1KB txid
         vout: 0,
         signature: vec![255u8; 2000], // 2KB signature
-        pub_key: vec![128u8; 500],    // 500B public key
+        pub_key: vec![128u8; 500],    // 500B public key,
+        multisig_signatures: vec![],
     };
 
     let large_tx_output = TXOutput {
         value: i32::MAX,
-        pub_key_hash: vec![42u8; 1000], // 1KB hash
+        pub_key_hash: vec![42u8; 1000], // 1KB hash,
+        multisig: None,
+        data: None,
     };
 
     let large_transaction = Transaction::new(
@@ -684,3 +687,137 @@ fn test_block_memory_layout_and_cloning() {
     // Verify they are separate instances (if we could modify them)
     // This test mainly ensures Clone trait works correctly
 }
+
+// Tests for Block::try_deserialize()
+#[test]
+fn test_block_try_deserialize_success() {
+    let block = create_test_block("try_deserialize_parent".to_string(), 3);
+    let result = Block::try_deserialize(&block.serialize());
+
+    assert!(result.is_ok());
+    let decoded = result.unwrap();
+    assert_eq!(decoded.get_hash(), block.get_hash());
+    assert_eq!(decoded.get_pre_block_hash(), block.get_pre_block_hash());
+    assert_eq!(decoded.get_height(), 3);
+    assert_eq!(decoded.get_transactions().len(), block.get_transactions().len());
+}
+
+#[test]
+fn test_block_try_deserialize_empty_data() {
+    assert!(Block::try_deserialize(&[]).is_err());
+}
+
+#[test]
+fn test_block_try_deserialize_truncated_data() {
+    let serialized = create_default_test_block().serialize();
+    let truncated = &serialized[..serialized.len() / 2];
+    assert!(Block::try_deserialize(truncated).is_err());
+}
+
+#[test]
+fn test_block_try_deserialize_invalid_data() {
+    assert!(Block::try_deserialize(&[0xff, 0xff, 0xff, 0xff]).is_err());
+}
+
+// Tests for Block::header() and Block::header_bytes()
+#[test]
+fn test_header_bytes_round_trip() {
+    let block = create_test_block("header_parent".to_string(), 4);
+    let header = BlockHeader::try_deserialize(&block.header_bytes()).unwrap();
+
+    assert_eq!(header, block.header());
+    assert_eq!(header.get_timestamp(), block.get_timestamp());
+    assert_eq!(header.get_pre_block_hash(), "header_parent");
+    assert_eq!(header.get_hash(), block.get_hash());
+    assert_eq!(header.get_nonce(), block.get_nonce());
+    assert_eq!(header.get_height(), 4);
+}
+
+#[test]
+fn test_header_bytes_leave_out_transactions() {
+    let block = create_test_block("header_size_parent".to_string(), 1);
+    let mut bigger = block.clone();
+    bigger.set_transactions_for_test(&[
+        create_test_transaction(vec![5, 5, 5, 5]),
+        create_test_transaction(vec![6, 6, 6, 6]),
+    ]);
+
+    assert!(bigger.header_bytes().len() < bigger.serialize().len());
+    assert_eq!(bigger.header_bytes().len(), block.header_bytes().len());
+    assert!(BlockHeader::try_deserialize(&[0xff, 0xff]).is_err());
+
+    // The merkle root still commits the header to the transactions left out
+    assert_eq!(block.header().get_merkle_root(), block.hash_transactions().as_slice());
+    assert_ne!(bigger.header().get_merkle_root(), block.header().get_merkle_root());
+}
+
+// Tests for Block::validate_coinbase()
+#[test]
+fn test_validate_coinbase_accepts_single_leading_coinbase() {
+    let block = create_test_block("coinbase_parent".to_string(), 1);
+    assert!(block.validate_coinbase().is_ok());
+
+    let genesis = create_test_genesis_block();
+    assert!(genesis.validate_coinbase().is_ok());
+}
+
+#[test]
+fn test_validate_coinbase_rejects_block_without_coinbase() {
+    let mut block = create_test_block("no_coinbase_parent".to_string(), 1);
+    block.set_transactions_for_test(&[create_test_transaction(vec![5, 5, 5, 5])]);
+    let err = block.validate_coinbase().unwrap_err();
+    assert!(err.contains("does not start with a coinbase"), "unexpected error: {err}");
+
+    block.set_transactions_for_test(&[]);
+    assert!(block.validate_coinbase().is_err());
+}
+
+#[test]
+fn test_validate_coinbase_rejects_coinbase_after_first_position() {
+    let mut block = create_test_block("late_coinbase_parent".to_string(), 1);
+    block.set_transactions_for_test(&[create_test_transaction(vec![5, 5, 5, 5]), create_test_coinbase(b"late")]);
+    assert!(block.validate_coinbase().is_err());
+}
+
+#[test]
+fn test_validate_coinbase_rejects_two_coinbases() {
+    let mut block = create_test_block("two_coinbases_parent".to_string(), 1);
+    block.set_transactions_for_test(&[
+        create_test_coinbase(b"first"),
+        create_test_transaction(vec![5, 5, 5, 5]),
+        create_test_coinbase(b"second"),
+    ]);
+    let err = block.validate_coinbase().unwrap_err();
+    assert!(err.contains("second coinbase at index 2"), "unexpected error: {err}");
+}
+
+// Tests for Block::validate_unique_txids()
+#[test]
+fn test_validate_unique_txids_rejects_intra_block_duplicate() {
+    let mut block = create_test_block("duplicate_txid_parent".to_string(), 1);
+    assert!(block.validate_unique_txids().is_ok());
+
+    let coinbase = block.get_transactions()[0].clone();
+    let tx = create_test_transaction(vec![6, 6, 6, 6]);
+    block.set_transactions_for_test(&[coinbase, tx.clone(), tx]);
+    let err = block.validate_unique_txids().unwrap_err();
+    assert!(err.contains("06060606"), "unexpected error: {err}");
+}
+
+// Tests for Block::prune_transactions()
+#[test]
+fn test_prune_transactions_keeps_merkle_root() {
+    let mut block = create_test_block("prune_parent".to_string(), 1);
+    let merkle_root = block.hash_transactions();
+    let hash = block.get_hash().to_string();
+
+    assert_eq!(block.prune_transactions(|tx| !tx.is_coinbase()), 1);
+    assert!(!block.get_transactions()[0].is_pruned());
+    assert!(block.get_transactions()[1].is_pruned());
+    assert_eq!(block.hash_transactions(), merkle_root);
+    assert_eq!(block.get_hash(), hash);
+
+    // Placeholders are never pruned twice
+    assert_eq!(block.prune_transactions(|_| true), 1);
+    assert_eq!(block.hash_transactions(), merkle_root);
+}
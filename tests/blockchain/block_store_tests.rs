@@ -0,0 +1,263 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use rust_blockchain::{
+    Block, BlockStore, Blockchain, BlockchainError, MemoryBlockStore, SledBlockStore, StoreBatch, Transaction, UTXOSet,
+    wallet::{Wallet, wallet_util::hash_pub_key},
+};
+use tempfile::TempDir;
+
+use crate::test_helpers::*;
+
+/// The same empty chain on each backend, with the directory backing the sled one
+fn empty_chains() -> Vec<(&'static str, Blockchain, Option<TempDir>)> {
+    let (db, temp_dir) = setup_temp_test_db();
+    vec![
+        ("sled", Blockchain::new_with_empty_tip(db), Some(temp_dir)),
+        ("memory", Blockchain::new_in_memory(), None),
+    ]
+}
+
+/// A genesis and `len - 1` blocks on top of it, not yet stored
+fn linked_blocks(len: usize) -> Vec<Block> {
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("store_genesis_hash");
+    let mut blocks = vec![genesis];
+    for height in 1..len {
+        let pre_hash = blocks[height - 1].get_hash().to_string();
+        blocks.push(mine_test_block_at_low_difficulty(create_test_block(pre_hash, height)));
+    }
+    blocks
+}
+
+// =============================================================================
+// SHARED SCENARIO TESTS
+// =============================================================================
+
+#[test]
+fn test_add_block_and_lookups_agree_across_stores() {
+    let blocks = linked_blocks(4);
+    for (name, blockchain, _temp_dir) in empty_chains() {
+        assert!(blockchain.is_empty(), "{name}");
+        for block in &blocks {
+            assert!(blockchain.add_block(block).is_none(), "{name}");
+        }
+
+        assert_eq!(blockchain.get_tip_hash(), blocks[3].get_hash(), "{name}");
+        assert_eq!(blockchain.get_best_height(), 3, "{name}");
+        for block in &blocks {
+            let stored = blockchain.get_block(block.get_hash().as_bytes()).unwrap();
+            assert_eq!(stored.get_hash(), block.get_hash(), "{name}");
+            assert_eq!(blockchain.get_hash_at_height(block.get_height()).as_deref(), Some(block.get_hash()), "{name}");
+        }
+        let walked: Vec<String> = blockchain.iterator().map(|block| block.get_hash().to_string()).collect();
+        let expected: Vec<String> = blocks.iter().rev().map(|block| block.get_hash().to_string()).collect();
+        assert_eq!(walked, expected, "{name}");
+        assert!(blockchain.verify_chain_with_target_bits(TEST_TARGET_BITS), "{name}");
+    }
+}
+
+#[test]
+fn test_reorg_agrees_across_stores() {
+    let blocks = linked_blocks(3);
+    for (name, blockchain, _temp_dir) in empty_chains() {
+        for block in &blocks {
+            blockchain.add_block(block);
+        }
+        // A longer branch forking off the block at height 1
+        let mut fork2 = create_test_block(blocks[1].get_hash().to_string(), 2);
+        fork2.set_hash_for_test("store_fork_2");
+        let mut fork3 = create_test_block(fork2.get_hash().to_string(), 3);
+        fork3.set_hash_for_test("store_fork_3");
+        assert!(blockchain.add_block(&fork2).is_none(), "{name}");
+        let reorg = blockchain.add_block(&fork3).expect("the longer branch should reorganize");
+
+        assert_eq!(reorg.get_disconnected().len(), 1, "{name}");
+        assert_eq!(reorg.get_connected().len(), 2, "{name}");
+        assert_eq!(blockchain.get_tip_hash(), fork3.get_hash(), "{name}");
+        assert_eq!(blockchain.get_hash_at_height(2).as_deref(), Some(fork2.get_hash()), "{name}");
+
+        // The abandoned block is still stored and drawn as a side branch
+        assert!(blockchain.get_block(blocks[2].get_hash().as_bytes()).is_some(), "{name}");
+        assert!(blockchain.export_dot().contains(blocks[2].get_hash()), "{name}");
+    }
+}
+
+#[test]
+fn test_add_blocks_agrees_across_stores() {
+    let blocks = linked_blocks(5);
+    for (name, blockchain, _temp_dir) in empty_chains() {
+        assert_eq!(blockchain.add_blocks(&blocks[..3]), Ok(()), "{name}");
+        let mut gapped = blocks[3..].to_vec();
+        gapped.remove(0);
+        assert!(blockchain.add_blocks(&gapped).is_err(), "{name}");
+        assert_eq!(blockchain.get_best_height(), 2, "{name}");
+
+        assert_eq!(blockchain.add_blocks(&blocks[3..]), Ok(()), "{name}");
+        assert_eq!(blockchain.get_tip_hash(), blocks[4].get_hash(), "{name}");
+        blockchain.reindex_heights();
+        assert_eq!(blockchain.get_block_by_height(3).unwrap().get_hash(), blocks[3].get_hash(), "{name}");
+    }
+}
+
+#[test]
+fn test_utxo_set_agrees_across_stores() {
+    let wallet = Wallet::from_seed(b"block-store-utxo");
+    for (name, blockchain, _temp_dir) in empty_chains() {
+        let mut genesis = Block::generate_genesis_block(&Transaction::new_coinbase_tx(&wallet.get_address()));
+        genesis.set_hash_for_test("store_utxo_genesis");
+        blockchain.add_block(&genesis);
+        let utxo_set = UTXOSet::new(blockchain.clone());
+        utxo_set.reindex();
+
+        assert_eq!(utxo_set.get_balance(&hash_pub_key(wallet.get_public_key())), 10, "{name}");
+        assert_eq!(utxo_set.count_transactions(), 1, "{name}");
+    }
+}
+
+#[test]
+fn test_purge_orphans_agrees_across_stores() {
+    let blocks = linked_blocks(3);
+    for (name, blockchain, _temp_dir) in empty_chains() {
+        for block in &blocks {
+            blockchain.add_block(block);
+        }
+        let mut side_block = create_test_block(blocks[0].get_hash().to_string(), 1);
+        side_block.set_hash_for_test("store_purged_side_block");
+        blockchain.add_block(&side_block);
+        assert_eq!(blockchain.find_orphan_blocks(), vec!["store_purged_side_block"], "{name}");
+
+        assert_eq!(blockchain.purge_orphans().unwrap(), 1, "{name}");
+        assert!(blockchain.get_block(b"store_purged_side_block").is_none(), "{name}");
+        assert!(blockchain.find_orphan_blocks().is_empty(), "{name}");
+        for block in &blocks {
+            assert!(blockchain.get_block(block.get_hash().as_bytes()).is_some(), "{name}");
+        }
+        assert_eq!(blockchain.get_tip_hash(), blocks[2].get_hash(), "{name}");
+        assert_eq!(blockchain.purge_orphans().unwrap(), 0, "{name}");
+    }
+}
+
+// =============================================================================
+// BACKEND TESTS
+// =============================================================================
+
+#[test]
+fn test_memory_store_batch_fill_height_keeps_existing_entry() {
+    let store = MemoryBlockStore::new();
+    let mut batch = StoreBatch::new();
+    batch.insert_block("a", vec![1]);
+    batch.set_height(0, "a");
+    batch.set_tip("a");
+    store.write(&batch).unwrap();
+
+    let mut batch = StoreBatch::new();
+    batch.fill_height(0, "b");
+    batch.fill_height(1, "c");
+    store.write(&batch).unwrap();
+
+    assert_eq!(store.get_block(b"a").unwrap(), Some(vec![1]));
+    assert_eq!(store.get_block(b"b").unwrap(), None);
+    assert_eq!(store.get_hash_at_height(0).unwrap().as_deref(), Some("a"));
+    assert_eq!(store.get_hash_at_height(1).unwrap().as_deref(), Some("c"));
+    assert_eq!(store.get_tip().unwrap().as_deref(), Some("a"));
+
+    store.clear_heights().unwrap();
+    assert_eq!(store.get_hash_at_height(0).unwrap(), None);
+    assert_eq!(store.get_all_blocks().unwrap(), vec![vec![1]]);
+}
+
+#[test]
+fn test_sled_store_excludes_tip_from_blocks() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let store = SledBlockStore::new(db);
+    let mut batch = StoreBatch::new();
+    batch.insert_block("a", vec![1]);
+    batch.set_tip("a");
+    store.write(&batch).unwrap();
+
+    assert_eq!(store.get_tip().unwrap().as_deref(), Some("a"));
+    assert_eq!(store.get_all_blocks().unwrap(), vec![vec![1]]);
+}
+
+#[test]
+fn test_new_with_store_resumes_from_recorded_tip() {
+    let blocks = linked_blocks(3);
+    let store: Arc<dyn BlockStore> = Arc::new(MemoryBlockStore::new());
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_store(db.clone(), store.clone());
+    assert!(blockchain.is_empty());
+    blockchain.add_blocks(&blocks).unwrap();
+
+    let reopened = Blockchain::new_with_store(db, store);
+    assert_eq!(reopened.get_tip_hash(), blocks[2].get_hash());
+    assert_eq!(reopened.get_best_height(), 2);
+}
+
+// =============================================================================
+// ATOMIC WRITE TESTS
+// =============================================================================
+
+/// A sled store whose writes fail, without applying anything, while `failing` is set
+struct FailingStore {
+    inner: SledBlockStore,
+    failing: AtomicBool,
+}
+
+impl BlockStore for FailingStore {
+    fn get_block(&self, block_hash: &[u8]) -> Result<Option<Vec<u8>>, BlockchainError> {
+        self.inner.get_block(block_hash)
+    }
+
+    fn get_all_blocks(&self) -> Result<Vec<Vec<u8>>, BlockchainError> {
+        self.inner.get_all_blocks()
+    }
+
+    fn get_tip(&self) -> Result<Option<String>, BlockchainError> {
+        self.inner.get_tip()
+    }
+
+    fn get_hash_at_height(&self, height: usize) -> Result<Option<String>, BlockchainError> {
+        self.inner.get_hash_at_height(height)
+    }
+
+    fn write(&self, batch: &StoreBatch) -> Result<(), BlockchainError> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(BlockchainError::DbError(String::from("injected write failure")));
+        }
+        self.inner.write(batch)
+    }
+
+    fn clear_heights(&self) -> Result<(), BlockchainError> {
+        self.inner.clear_heights()
+    }
+}
+
+#[test]
+fn test_add_block_writes_block_and_tip_together() {
+    let blocks = linked_blocks(3);
+    let (db, _temp_dir) = setup_temp_test_db();
+    let store = Arc::new(FailingStore { inner: SledBlockStore::new(db.clone()), failing: AtomicBool::new(false) });
+    let blockchain = Blockchain::new_with_store(db.clone(), store.clone());
+    blockchain.add_block(&blocks[0]);
+    blockchain.add_block(&blocks[1]);
+
+    // Neither the block nor the tip move when the write fails
+    store.failing.store(true, Ordering::SeqCst);
+    assert!(blockchain.add_block(&blocks[2]).is_none());
+    assert_eq!(blockchain.get_tip_hash(), blocks[1].get_hash());
+    assert!(blockchain.get_block(blocks[2].get_hash().as_bytes()).is_none());
+    assert_eq!(blockchain.get_hash_at_height(2), None);
+    let on_disk = SledBlockStore::new(db.clone());
+    assert_eq!(on_disk.get_tip().unwrap().as_deref(), Some(blocks[1].get_hash()));
+    assert_eq!(on_disk.get_block(blocks[2].get_hash().as_bytes()).unwrap(), None);
+
+    // Both are visible once the write goes through
+    store.failing.store(false, Ordering::SeqCst);
+    blockchain.add_block(&blocks[2]);
+    assert_eq!(on_disk.get_tip().unwrap().as_deref(), Some(blocks[2].get_hash()));
+    assert!(on_disk.get_block(blocks[2].get_hash().as_bytes()).unwrap().is_some());
+    assert_eq!(on_disk.get_hash_at_height(2).unwrap().as_deref(), Some(blocks[2].get_hash()));
+}
@@ -1,4 +1,4 @@
-use rust_blockchain::{Block, BlockchainIterator, BLOCKS_TREE};
+use rust_blockchain::{Block, Blockchain, BlockchainIterator, BLOCKS_TREE};
 use crate::test_helpers::*;
 
 #[test]
@@ -512,3 +512,70 @@ fn test_iterator_with_corrupted_data() {
 }
 
 
+
+// =============================================================================
+// FORWARD ITERATOR TESTS
+// =============================================================================
+
+fn build_chain(blockchain: &Blockchain, len: usize) -> Vec<Block> {
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("forward_genesis_hash");
+    let mut blocks = vec![genesis];
+    for height in 1..len {
+        let pre_hash = blocks[height - 1].get_hash().to_string();
+        blocks.push(create_test_block(pre_hash, height));
+    }
+    for block in &blocks {
+        blockchain.add_block(block);
+    }
+    blocks
+}
+
+#[test]
+fn test_forward_iterator_yields_increasing_heights() {
+    let blockchain = Blockchain::new_in_memory();
+    let blocks = build_chain(&blockchain, 5);
+
+    let heights: Vec<usize> = blockchain.forward_iterator().map(|block| block.get_height()).collect();
+    assert_eq!(heights, vec![0, 1, 2, 3, 4]);
+
+    let hashes: Vec<String> = blockchain.forward_iterator().map(|block| block.get_hash().to_string()).collect();
+    let expected: Vec<String> = blocks.iter().map(|block| block.get_hash().to_string()).collect();
+    assert_eq!(hashes, expected);
+}
+
+#[test]
+fn test_forward_iterator_reverses_backward_iterator() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    build_chain(&blockchain, 4);
+
+    let mut backward: Vec<String> = blockchain.iterator().map(|block| block.get_hash().to_string()).collect();
+    assert_eq!(backward.len(), 4);
+    backward.reverse();
+    let forward: Vec<String> = blockchain.forward_iterator().map(|block| block.get_hash().to_string()).collect();
+    assert_eq!(forward, backward);
+}
+
+#[test]
+fn test_forward_iterator_empty_chain() {
+    let blockchain = Blockchain::new_in_memory();
+    assert_eq!(blockchain.forward_iterator().count(), 0);
+}
+
+#[test]
+fn test_forward_iterator_only_follows_main_chain() {
+    let blockchain = Blockchain::new_in_memory();
+    let blocks = build_chain(&blockchain, 3);
+
+    // A shorter side branch is stored but not on the way from the tip to genesis
+    let mut side = create_test_block(blocks[0].get_hash().to_string(), 1);
+    side.set_hash_for_test("forward_side_branch");
+    blockchain.add_block(&side);
+
+    let mut iterator = blockchain.forward_iterator();
+    assert_eq!(iterator.next().unwrap().get_hash(), blocks[0].get_hash());
+    assert_eq!(iterator.next().unwrap().get_hash(), blocks[1].get_hash());
+    assert_eq!(iterator.next().unwrap().get_hash(), blocks[2].get_hash());
+    assert!(iterator.next().is_none());
+}
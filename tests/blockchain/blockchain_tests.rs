@@ -1,6 +1,10 @@
 use crate::test_helpers::*;
 use rust_blockchain::{
-    BLOCKS_TREE, Block, Blockchain, TIP_BLOCK_HASH_KEY, TXInput, TXOutput, Transaction,
+    BLOCKS_TREE, Block, Blockchain, HEIGHT_INDEX_TREE, TIP_BLOCK_HASH_KEY, TXInput, TXOutput,
+    BlockchainError, ChainParams, ProofOfWork, Transaction, UTXOSet,
+    LOCATOR_DENSE_LEN, MINED_BLOCK_SIZE_SLACK, SCHEMA_VERSION, SCHEMA_VERSION_KEY, SUBSIDY,
+    GENESIS_PRE_BLOCK_HASH, UTXO_TREE, UTXO_UNDO_TREE, config::GLOBAL_CONFIG,
+    wallet::{Wallet, wallet_util::hash_pub_key},
 };
 
 #[test]
@@ -1014,8 +1018,8 @@ fn test_find_utxo_single_coinbase_transaction() {
 
     let outputs = utxo.get(&coinbase_id_hex).unwrap();
     assert_eq!(outputs.len(), 1);
-    assert_eq!(outputs[0].value, 50);
-    assert_eq!(outputs[0].pub_key_hash, vec![1, 2, 3, 4]);
+    assert_eq!(outputs[0].as_ref().unwrap().value, 50);
+    assert_eq!(outputs[0].as_ref().unwrap().pub_key_hash, vec![1, 2, 3, 4]);
 }
 
 #[test]
@@ -1072,11 +1076,11 @@ fn test_find_utxo_multiple_coinbase_transactions() {
 
     let outputs1 = utxo.get(&coinbase_id1_hex).unwrap();
     assert_eq!(outputs1.len(), 1);
-    assert_eq!(outputs1[0].value, 50);
+    assert_eq!(outputs1[0].as_ref().unwrap().value, 50);
 
     let outputs2 = utxo.get(&coinbase_id2_hex).unwrap();
     assert_eq!(outputs2.len(), 1);
-    assert_eq!(outputs2[0].value, 25);
+    assert_eq!(outputs2[0].as_ref().unwrap().value, 25);
 }
 
 #[test]
@@ -1134,7 +1138,7 @@ fn test_find_utxo_with_spending_transaction() {
     assert_eq!(outputs.len(), 2);
 
     // Check the two outputs from the spending transaction
-    let mut values: Vec<i32> = outputs.iter().map(|o| o.value).collect();
+    let mut values: Vec<i32> = outputs.iter().flatten().map(|o| o.value).collect();
     values.sort();
     assert_eq!(values, vec![40, 60]);
 }
@@ -1156,14 +1160,20 @@ fn test_find_utxo_partial_spending() {
             TXOutput {
                 value: 30,
                 pub_key_hash: vec![1, 1, 1],
+                multisig: None,
+                data: None,
             },
             TXOutput {
                 value: 40,
                 pub_key_hash: vec![2, 2, 2],
+                multisig: None,
+                data: None,
             },
             TXOutput {
                 value: 50,
                 pub_key_hash: vec![3, 3, 3],
+                multisig: None,
+                data: None,
             },
         ],
     );
@@ -1208,9 +1218,8 @@ fn test_find_utxo_partial_spending() {
 
     let utxo = blockchain.find_utxo();
 
-    // The find_utxo implementation has complex behavior due to bugs.
-    // We actually get 2 UTXOs: one from the original transaction (partial outputs)
-    // and one from the spending transaction
+    // Both outputs of the spending transaction and the two unspent outputs of the
+    // original transaction remain
     assert_eq!(utxo.len(), 2);
     assert!(utxo.contains_key(&multi_id_hex)); // Original transaction partially included
     assert!(utxo.contains_key(&partial_id_hex)); // Spending transaction included
@@ -1218,15 +1227,15 @@ fn test_find_utxo_partial_spending() {
     // Check that we get the expected total values
     let mut all_values: Vec<i32> = utxo
         .values()
-        .flat_map(|outputs| outputs.iter())
+        .flat_map(|outputs| outputs.iter().flatten())
         .map(|o| o.value)
         .collect();
     all_values.sort();
-    assert_eq!(all_values, vec![5, 30, 35]); // Values we actually observe
+    assert_eq!(all_values, vec![5, 30, 35, 50]);
 
-    // Total unspent value should be 70 (5 + 30 + 35)
+    // Total unspent value should be 120 (5 + 30 + 35 + 50)
     let total_unspent: i32 = all_values.iter().sum();
-    assert_eq!(total_unspent, 70);
+    assert_eq!(total_unspent, 120);
 }
 
 #[test]
@@ -1243,11 +1252,17 @@ fn test_find_utxo_complex_transaction_chain() {
         vec![(60, vec![2, 2, 2]), (40, vec![3, 3, 3])],
     );
 
-    // Create second spending transaction that spends from tx1
+    // Create second spending transaction that spends from tx1, with its own id since it
+    // has the same shape as tx1
     let tx2 = create_spending_transaction(
         vec![(tx1.get_id().to_vec(), 0)], // Spend the 60-value output from tx1
         vec![(30, vec![4, 4, 4]), (30, vec![5, 5, 5])],
     );
+    let tx2 = Transaction::new(
+        rust_blockchain::util::sha256_digest(b"complex_chain_tx2"),
+        tx2.vin,
+        tx2.vout,
+    );
 
     // Store transaction ID before moving the transaction
     let tx2_id = tx2.get_id().to_vec();
@@ -1300,23 +1315,21 @@ fn test_find_utxo_complex_transaction_chain() {
 
     let utxo = blockchain.find_utxo();
 
-    // The find_utxo function has a bug where it processes blocks in reverse order
-    // and has issues with the 'continue 'outer logic. Based on actual behavior:
-    // We find 2 UTXOs: one with value 70 (from tx3) and one with value 100 (from coinbase)
+    // Only tx3's output and the unspent 30-value output of tx2 remain
     assert_eq!(utxo.len(), 2);
 
     // Check that we have the expected values (70 and 100)
     let mut values: Vec<i32> = utxo
         .values()
-        .flat_map(|outputs| outputs.iter())
+        .flat_map(|outputs| outputs.iter().flatten())
         .map(|output| output.value)
         .collect();
     values.sort();
-    assert_eq!(values, vec![70, 100]);
+    assert_eq!(values, vec![30, 70]);
 
-    // Total unspent value should be 170 (70 + 100)
+    // Total unspent value should be 100 (30 + 70)
     let total_unspent: i32 = values.iter().sum();
-    assert_eq!(total_unspent, 170);
+    assert_eq!(total_unspent, 100);
 }
 
 #[test]
@@ -1443,7 +1456,7 @@ fn test_find_utxo_no_unspent_outputs() {
 
     let total_unspent: i32 = utxo
         .values()
-        .flat_map(|outputs| outputs.iter())
+        .flat_map(|outputs| outputs.iter().flatten())
         .map(|output| output.value)
         .sum();
     assert_eq!(total_unspent, 50); // All value should still be conserved
@@ -1889,3 +1902,1443 @@ fn test_find_transaction_large_blockchain() {
     let found_non_existent = blockchain.find_transaction(&non_existent_id);
     assert!(found_non_existent.is_none());
 }
+
+// =============================================================================
+// EMPTY CHAIN AND VERIFY CHAIN TESTS
+// =============================================================================
+
+// Stores a linked chain of `len` blocks (genesis first) and returns them
+fn store_linked_chain(blockchain: &Blockchain, len: usize) -> Vec<Block> {
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("linked_genesis_hash");
+    let mut blocks = vec![genesis];
+    for height in 1..len {
+        let pre_hash = blocks[height - 1].get_hash().to_string();
+        blocks.push(mine_test_block_at_low_difficulty(create_test_block(pre_hash, height)));
+    }
+    for block in &blocks {
+        blockchain.add_block(block);
+    }
+    blocks
+}
+
+#[test]
+fn test_add_block_to_empty_chain_sets_tip() {
+    let test_db = TestDatabase::new("add_block_empty_chain");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(blockchain.is_empty());
+
+    let block = create_test_block("parent_hash".to_string(), 3);
+    blockchain.add_block(&block);
+
+    assert!(!blockchain.is_empty());
+    assert_eq!(blockchain.get_tip_hash(), block.get_hash());
+    assert_eq!(blockchain.get_best_height(), 3);
+    let blocks_tree = test_db.get_db().open_tree(BLOCKS_TREE).unwrap();
+    assert_eq!(
+        blocks_tree.get(TIP_BLOCK_HASH_KEY).unwrap().unwrap().as_ref(),
+        block.get_hash().as_bytes()
+    );
+}
+
+#[test]
+fn test_verify_chain_accepts_linked_chain_received_tip_first() {
+    let test_db = TestDatabase::new("verify_chain_tip_first");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+
+    // Peers announce blocks tip first, so sync stores them in reverse order
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("reverse_genesis_hash");
+    let block1 = mine_test_block_at_low_difficulty(create_test_block(genesis.get_hash().to_string(), 1));
+    let block2 = mine_test_block_at_low_difficulty(create_test_block(block1.get_hash().to_string(), 2));
+    for block in [&block2, &block1, &genesis] {
+        blockchain.add_block(block);
+    }
+
+    assert_eq!(blockchain.get_tip_hash(), block2.get_hash());
+    assert!(blockchain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+}
+
+#[test]
+fn test_verify_chain_rejects_broken_chains() {
+    let test_db = TestDatabase::new("verify_chain_broken");
+    let empty = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(!empty.verify_chain_with_target_bits(TEST_TARGET_BITS));
+
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 3);
+    assert!(blockchain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+
+    // A block whose hash does not meet the difficulty, though it links correctly
+    let unmined = create_test_block(blocks[2].get_hash().to_string(), 3);
+    let unmined_chain = Blockchain::new_with_tip(test_db.get_db().clone(), String::new());
+    unmined_chain.add_block(&unmined);
+    assert!(!unmined_chain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+
+    // Missing parent
+    let orphan = create_test_block("unknown_parent".to_string(), 3);
+    let orphan_chain = Blockchain::new_with_tip(test_db.get_db().clone(), String::new());
+    orphan_chain.add_block(&orphan);
+    assert!(!orphan_chain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+
+    // Height gap between a block and its parent
+    let gapped = create_test_block(blocks[2].get_hash().to_string(), 5);
+    let gapped_chain = Blockchain::new_with_tip(test_db.get_db().clone(), String::new());
+    gapped_chain.add_block(&gapped);
+    assert!(!gapped_chain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+
+    // Height reaches 0 on a block that is not a genesis block
+    let fake_root = create_test_block("not_genesis".to_string(), 0);
+    let child = create_test_block(fake_root.get_hash().to_string(), 1);
+    let fake_chain = Blockchain::new_with_tip(test_db.get_db().clone(), String::new());
+    fake_chain.add_block(&fake_root);
+    fake_chain.add_block(&child);
+    assert!(!fake_chain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+}
+
+// =============================================================================
+// VALIDATE CHAIN TESTS
+// =============================================================================
+
+const VALIDATE_TARGET_BITS: usize = 8;
+
+fn mine_test_block(pre_hash: &str, height: usize, txs: &[Transaction]) -> Block {
+    let mut block = Block::new_block_without_proof_of_work(pre_hash.to_string(), txs, height);
+    let (nonce, hash) = ProofOfWork::new_proof_of_work(block.clone(), VALIDATE_TARGET_BITS).run();
+    block.set_nonce_for_test(nonce);
+    block.set_hash_for_test(&hash);
+    block
+}
+
+/// A genesis paying `wallet` and a mined block spending part of it with a signed transaction
+fn store_validated_chain(blockchain: &Blockchain, wallet: &Wallet) -> Vec<Block> {
+    let mut genesis = Block::generate_genesis_block(&Transaction::new_coinbase_tx(&wallet.get_address()));
+    genesis.set_hash_for_test("validate_genesis");
+    blockchain.add_block(&genesis);
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    utxo_set.reindex();
+
+    let recipient = Wallet::from_seed(b"validate-recipient").get_address();
+    let spend = match Transaction::new_utxo_transaction_from_wallet(wallet, &[(recipient, 3)], &utxo_set) {
+        Ok(tx) => tx,
+        Err(e) => panic!("funded wallet should be able to pay: {e}"),
+    };
+    let coinbase = Transaction::new_coinbase_tx(&wallet.get_address());
+    let block1 = mine_test_block(genesis.get_hash(), 1, &[coinbase, spend]);
+    blockchain.add_block(&block1);
+    let block2 = mine_test_block(block1.get_hash(), 2, &[Transaction::new_coinbase_tx(&wallet.get_address())]);
+    blockchain.add_block(&block2);
+    vec![genesis, block1, block2]
+}
+
+#[test]
+fn test_validate_chain_accepts_valid_chain() {
+    let test_db = TestDatabase::new("validate_chain_valid");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    store_validated_chain(&blockchain, &Wallet::from_seed(b"validate-valid"));
+
+    assert_eq!(blockchain.validate_chain_with_target_bits(VALIDATE_TARGET_BITS), Ok(()));
+    // The blocks were mined far below the default difficulty
+    assert!(blockchain.validate_chain().is_err());
+}
+
+#[test]
+fn test_validate_chain_rejects_extra_coinbase() {
+    let test_db = TestDatabase::new("validate_chain_extra_coinbase");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let wallet = Wallet::from_seed(b"validate-extra-coinbase");
+    let blocks = store_validated_chain(&blockchain, &wallet);
+
+    let inflated = mine_test_block(blocks[2].get_hash(), 3, &[
+        Transaction::new_coinbase_tx(&wallet.get_address()),
+        Transaction::new_coinbase_tx(&wallet.get_address()),
+    ]);
+    blockchain.add_block(&inflated);
+
+    let err = blockchain
+        .validate_chain_with_target_bits(VALIDATE_TARGET_BITS)
+        .unwrap_err();
+    assert!(err.contains(inflated.get_hash()), "unexpected error: {err}");
+    assert!(err.contains("second coinbase"), "unexpected error: {err}");
+}
+
+#[test]
+fn test_validate_chain_rejects_empty_chain() {
+    let test_db = TestDatabase::new("validate_chain_empty");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(blockchain.validate_chain().is_err());
+}
+
+#[test]
+fn test_validate_chain_rejects_tampered_block_hash() {
+    let test_db = TestDatabase::new("validate_chain_tampered");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_validated_chain(&blockchain, &Wallet::from_seed(b"validate-tampered"));
+
+    // Overwrite block 1 in place with a forged hash, keeping its key and its child's link
+    let mut tampered = blocks[1].clone();
+    tampered.set_hash_for_test(&"0".repeat(64));
+    let blocks_tree = test_db.get_db().open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree.insert(blocks[1].get_hash(), tampered.serialize()).unwrap();
+
+    let err = blockchain
+        .validate_chain_with_target_bits(VALIDATE_TARGET_BITS)
+        .unwrap_err();
+    assert!(err.contains(blocks[2].get_hash()), "unexpected error: {err}");
+    assert!(err.contains("does not link"), "unexpected error: {err}");
+
+    // A tampered tip is caught by its hash no longer matching its contents
+    let mut tampered_tip = blocks[2].clone();
+    tampered_tip.set_hash_for_test(&"0".repeat(64));
+    blocks_tree.insert(blocks[2].get_hash(), tampered_tip.serialize()).unwrap();
+    let err = blockchain
+        .validate_chain_with_target_bits(VALIDATE_TARGET_BITS)
+        .unwrap_err();
+    assert!(err.contains("does not match its contents"), "unexpected error: {err}");
+}
+
+// =============================================================================
+// GET BLOCK BY HEIGHT TESTS
+// =============================================================================
+
+#[test]
+fn test_get_block_by_height_returns_each_block() {
+    let test_db = TestDatabase::new("get_block_by_height");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 5);
+
+    for block in &blocks {
+        let found = blockchain
+            .get_block_by_height(block.get_height())
+            .expect("Block should exist at this height");
+        assert_eq!(found.get_hash(), block.get_hash());
+        assert_eq!(found.get_height(), block.get_height());
+    }
+}
+
+#[test]
+fn test_get_block_by_height_out_of_range() {
+    let test_db = TestDatabase::new("get_block_by_height_out_of_range");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(blockchain.get_block_by_height(0).is_none());
+
+    store_linked_chain(&blockchain, 3);
+    assert!(blockchain.get_block_by_height(3).is_none());
+    assert!(blockchain.get_block_by_height(usize::MAX).is_none());
+}
+
+// =============================================================================
+// BLOCK HASH PAGINATION TESTS
+// =============================================================================
+
+#[test]
+fn test_get_block_hashes_from_pages_through_chain() {
+    let test_db = TestDatabase::new("block_hashes_from_pages");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 6);
+    let hashes: Vec<String> = blocks.iter().map(|block| block.get_hash().to_string()).collect();
+
+    // Page through in twos from genesis
+    let first_page = blockchain.get_block_hashes_from(&hashes[0], 2);
+    assert_eq!(first_page, hashes[1..3]);
+    let second_page = blockchain.get_block_hashes_from(first_page.last().unwrap(), 2);
+    assert_eq!(second_page, hashes[3..5]);
+    let last_page = blockchain.get_block_hashes_from(second_page.last().unwrap(), 2);
+    assert_eq!(last_page, hashes[5..6]);
+
+    // Nothing is newer than the tip, and a zero limit returns nothing
+    assert!(blockchain.get_block_hashes_from(&hashes[5], 2).is_empty());
+    assert!(blockchain.get_block_hashes_from(&hashes[0], 0).is_empty());
+    // A limit beyond the chain returns the rest
+    assert_eq!(blockchain.get_block_hashes_from(&hashes[2], 100), hashes[3..]);
+}
+
+#[test]
+fn test_get_block_hashes_from_unknown_or_side_start() {
+    let test_db = TestDatabase::new("block_hashes_from_unknown");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(blockchain.get_block_hashes_from("no_such_hash", 5).is_empty());
+
+    let blocks = store_linked_chain(&blockchain, 3);
+    assert!(blockchain.get_block_hashes_from("no_such_hash", 5).is_empty());
+
+    // A block on a losing branch is not a starting point
+    let mut side_block = create_test_block(blocks[0].get_hash().to_string(), 1);
+    side_block.set_hash_for_test("pagination_side_block");
+    blockchain.add_block(&side_block);
+    assert!(blockchain.get_block_hashes_from("pagination_side_block", 5).is_empty());
+}
+
+#[test]
+fn test_get_last_n_blocks_on_chain_longer_than_n() {
+    let test_db = TestDatabase::new("last_n_blocks_longer");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 6);
+
+    let recent = blockchain.get_last_n_blocks(3);
+    let heights: Vec<usize> = recent.iter().map(|block| block.get_height()).collect();
+    assert_eq!(heights, vec![5, 4, 3]);
+    assert_eq!(recent[0].get_hash(), blocks[5].get_hash());
+    assert!(blockchain.get_last_n_blocks(0).is_empty());
+}
+
+#[test]
+fn test_get_last_n_blocks_stops_at_genesis() {
+    let test_db = TestDatabase::new("last_n_blocks_shorter");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(blockchain.get_last_n_blocks(5).is_empty());
+
+    store_linked_chain(&blockchain, 3);
+    let recent = blockchain.get_last_n_blocks(10);
+    let heights: Vec<usize> = recent.iter().map(|block| block.get_height()).collect();
+    assert_eq!(heights, vec![2, 1, 0]);
+    assert_eq!(recent[2].get_hash(), "linked_genesis_hash");
+}
+
+// =============================================================================
+// HEIGHT INDEX TESTS
+// =============================================================================
+
+fn assert_height_index_matches(blockchain: &Blockchain, blocks: &[Block]) {
+    for block in blocks {
+        assert_eq!(
+            blockchain.get_hash_at_height(block.get_height()).as_deref(),
+            Some(block.get_hash())
+        );
+    }
+    assert!(blockchain.get_hash_at_height(blocks.len()).is_none());
+}
+
+#[test]
+fn test_height_index_updated_by_add_block() {
+    let test_db = TestDatabase::new("height_index_add_block");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 5);
+
+    assert_height_index_matches(&blockchain, &blocks);
+}
+
+#[test]
+fn test_height_index_filled_when_synced_tip_first() {
+    let test_db = TestDatabase::new("height_index_tip_first");
+    let source = TestDatabase::new("height_index_tip_first_source");
+    let blocks = store_linked_chain(
+        &Blockchain::new_with_empty_tip(source.get_db().clone()),
+        4,
+    );
+
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    for block in blocks.iter().rev() {
+        blockchain.add_block(block);
+    }
+    assert_height_index_matches(&blockchain, &blocks);
+}
+
+#[test]
+fn test_height_index_ignores_side_block_at_existing_height() {
+    let test_db = TestDatabase::new("height_index_side_block");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 3);
+
+    // A competing block at height 2 that does not extend the chain
+    let mut side_block = create_test_block(blocks[1].get_hash().to_string(), 2);
+    side_block.set_hash_for_test("side_block_hash");
+    blockchain.add_block(&side_block);
+
+    assert_height_index_matches(&blockchain, &blocks);
+}
+
+#[test]
+fn test_reindex_heights_rebuilds_index() {
+    let test_db = TestDatabase::new("height_index_reindex");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 5);
+
+    // Corrupt the index: drop one entry and point another at the wrong block
+    let height_tree = test_db.get_db().open_tree(HEIGHT_INDEX_TREE).unwrap();
+    height_tree.remove(2u64.to_be_bytes()).unwrap();
+    height_tree.insert(3u64.to_be_bytes(), "wrong_hash").unwrap();
+    height_tree.insert(9u64.to_be_bytes(), "stale_hash").unwrap();
+
+    blockchain.reindex_heights();
+
+    assert_height_index_matches(&blockchain, &blocks);
+    assert!(blockchain.get_hash_at_height(9).is_none());
+    assert_eq!(
+        blockchain.get_block_by_height(3).unwrap().get_hash(),
+        blocks[3].get_hash()
+    );
+}
+
+#[test]
+fn test_get_block_by_height_without_index_falls_back_to_walk() {
+    let test_db = TestDatabase::new("height_index_fallback");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 3);
+
+    test_db
+        .get_db()
+        .open_tree(HEIGHT_INDEX_TREE)
+        .unwrap()
+        .clear()
+        .unwrap();
+
+    assert!(blockchain.get_hash_at_height(1).is_none());
+    assert_eq!(
+        blockchain.get_block_by_height(1).unwrap().get_hash(),
+        blocks[1].get_hash()
+    );
+}
+
+#[test]
+fn test_get_block_returns_none_for_corrupt_entry() {
+    let test_db = TestDatabase::new("get_block_corrupt_entry");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 2);
+
+    let blocks_tree = test_db.get_db().open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree.insert("corrupt_block_hash", vec![0xff, 0xff, 0xff]).unwrap();
+
+    assert!(blockchain.get_block(b"corrupt_block_hash").is_none());
+    assert!(blockchain.get_block(b"missing_block_hash").is_none());
+    // The tip pointer shares the tree with the blocks but is not one
+    assert!(blockchain.get_block(TIP_BLOCK_HASH_KEY.as_bytes()).is_none());
+    assert_eq!(
+        blockchain.get_block(blocks[1].get_hash().as_bytes()).unwrap().get_hash(),
+        blocks[1].get_hash()
+    );
+}
+
+#[test]
+fn test_find_transaction_in_each_stored_block() {
+    let test_db = TestDatabase::new("find_transaction_each_block");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+
+    let mut pre_hash = String::from(rust_blockchain::GENESIS_PRE_BLOCK_HASH);
+    let mut txids = vec![];
+    for height in 0..3 {
+        let tx = create_coinbase_transaction(10 + height as i32, vec![height as u8]);
+        txids.push(tx.get_id().to_vec());
+        let mut block = create_test_block(pre_hash.clone(), height);
+        block.set_transactions_for_test(std::slice::from_ref(&tx));
+        block.set_hash_for_test(format!("lookup_block_{height}").as_str());
+        blockchain.add_block(&block);
+        pre_hash = block.get_hash().to_string();
+    }
+
+    for (height, txid) in txids.iter().enumerate() {
+        let found = blockchain.find_transaction(txid).unwrap();
+        assert_eq!(found.get_id(), txid.as_slice());
+        assert_eq!(found.get_vout()[0].get_value(), 10 + height as i32);
+    }
+    assert!(blockchain.find_transaction(&[0xde, 0xad]).is_none());
+}
+
+#[test]
+fn test_export_dot_has_one_node_per_block_and_parent_edges() {
+    let test_db = TestDatabase::new("export_dot_linear");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 3);
+
+    let dot = blockchain.export_dot();
+    assert!(dot.starts_with("digraph blockchain {"));
+    assert!(dot.trim_end().ends_with('}'));
+    assert_eq!(dot.matches("[label=").count(), 3);
+    assert_eq!(dot.matches(" -> ").count(), 2);
+    for block in &blocks {
+        assert!(dot.contains(format!("\"{}\" [label=", block.get_hash()).as_str()));
+        assert!(dot.contains(format!("height {}", block.get_height()).as_str()));
+    }
+    assert!(dot.contains(format!("\"{}\" -> \"{}\";", blocks[1].get_hash(), blocks[0].get_hash()).as_str()));
+    assert!(dot.contains(format!("\"{}\" -> \"{}\";", blocks[2].get_hash(), blocks[1].get_hash()).as_str()));
+    assert!(!dot.contains("dashed"));
+}
+
+#[test]
+fn test_export_dot_shows_side_branch() {
+    let test_db = TestDatabase::new("export_dot_fork");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 3);
+
+    let mut side = create_test_block(blocks[0].get_hash().to_string(), 1);
+    side.set_hash_for_test("dot_side_branch_hash");
+    blockchain.add_block(&side);
+    assert_eq!(blockchain.get_tip_hash(), blocks[2].get_hash());
+
+    let dot = blockchain.export_dot();
+    assert_eq!(dot.matches("[label=").count(), 4);
+    assert_eq!(dot.matches(" -> ").count(), 3);
+    assert!(dot.contains("\"dot_side_branch_hash\" [label=\"dot_side\\nheight 1\\n"));
+    assert!(dot.contains(format!("\"dot_side_branch_hash\" -> \"{}\";", blocks[0].get_hash()).as_str()));
+    assert_eq!(dot.matches("style=dashed").count(), 1);
+}
+
+#[test]
+fn test_try_get_best_height_reports_missing_tip() {
+    let test_db = TestDatabase::new("try_best_height_missing_tip");
+    let empty = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(matches!(empty.try_get_best_height(), Err(BlockchainError::TipNotFound)));
+
+    let dangling = Blockchain::new_with_tip(test_db.get_db().clone(), "no_such_block".to_string());
+    assert!(matches!(dangling.try_get_best_height(), Err(BlockchainError::TipNotFound)));
+}
+
+#[test]
+fn test_try_get_best_height_reports_corrupt_tip() {
+    let test_db = TestDatabase::new("try_best_height_corrupt_tip");
+    let blocks_tree = test_db.get_db().open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree.insert("corrupt_tip", vec![0xff, 0xff]).unwrap();
+    let blockchain = Blockchain::new_with_tip(test_db.get_db().clone(), "corrupt_tip".to_string());
+
+    let err = blockchain.try_get_best_height().unwrap_err();
+    assert!(matches!(err, BlockchainError::Deserialize(_)));
+    assert!(err.to_string().starts_with("unable to deserialize block"));
+}
+
+#[test]
+fn test_try_get_best_height_and_try_iterator_on_valid_chain() {
+    let test_db = TestDatabase::new("try_methods_valid_chain");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(matches!(blockchain.try_iterator(), Err(BlockchainError::TipNotFound)));
+
+    let blocks = store_linked_chain(&blockchain, 3);
+    assert_eq!(blockchain.try_get_best_height().unwrap(), 2);
+    let hashes: Vec<String> = blockchain
+        .try_iterator()
+        .unwrap()
+        .map(|block| block.get_hash().to_string())
+        .collect();
+    assert_eq!(hashes, vec![blocks[2].get_hash(), blocks[1].get_hash(), blocks[0].get_hash()]);
+}
+
+// =============================================================================
+// PARALLEL TRANSACTION VERIFICATION TESTS
+// =============================================================================
+
+/// A funded chain for `wallet` and a block of several transactions it signed
+fn block_of_signed_transactions(blockchain: &Blockchain, wallet: &Wallet) -> Block {
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let mut txs = vec![Transaction::new_coinbase_tx(&wallet.get_address())];
+    for amount in 1..=6 {
+        let recipient = Wallet::from_seed(format!("parallel-recipient-{amount}").as_bytes()).get_address();
+        txs.push(Transaction::new_utxo_transaction_from_wallet(wallet, &[(recipient, amount)], &utxo_set).unwrap());
+    }
+    Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &txs, 1)
+}
+
+#[test]
+fn test_verify_block_transactions_parallel_matches_sequential() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"parallel-verify-sender");
+    let blockchain = create_funded_blockchain(&db, &wallet.get_address());
+    let block = block_of_signed_transactions(&blockchain, &wallet);
+    assert_eq!(block.get_transactions().len(), 7);
+
+    assert!(blockchain.verify_block_transactions(&block));
+    assert!(blockchain.verify_block_transactions_sequential(&block));
+}
+
+#[test]
+fn test_verify_block_transactions_rejects_bad_signature() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"parallel-verify-tampered");
+    let blockchain = create_funded_blockchain(&db, &wallet.get_address());
+    let block = block_of_signed_transactions(&blockchain, &wallet);
+
+    let mut txs = block.get_transactions().to_vec();
+    txs[3].vin[0].signature[0] ^= 0xff;
+    let tampered = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &txs, 1);
+
+    assert!(!blockchain.verify_block_transactions(&tampered));
+    assert!(!blockchain.verify_block_transactions_sequential(&tampered));
+}
+
+#[test]
+fn test_verify_block_transactions_rejects_malformed_transactions() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"parallel-verify-malformed");
+    let blockchain = create_funded_blockchain(&db, &wallet.get_address());
+    let block = block_of_signed_transactions(&blockchain, &wallet);
+    let signed = block.get_transactions().to_vec();
+
+    // Spending a transaction that is not on the chain fails instead of panicking
+    let orphan_spend = create_spending_transaction(vec![(b"missing_tx".to_vec(), 0)], vec![(1, vec![7; 20])]);
+    let mut txs = signed.clone();
+    txs.push(orphan_spend);
+    let orphaned = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &txs, 1);
+    assert!(!blockchain.verify_block_transactions(&orphaned));
+    assert!(!blockchain.verify_block_transactions_sequential(&orphaned));
+
+    // An output with a negative value fails the structural checks
+    let mut txs = signed;
+    txs[5].vout[0].value = -1;
+    let negative = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &txs, 1);
+    assert!(!blockchain.verify_block_transactions(&negative));
+    assert!(!blockchain.verify_block_transactions_sequential(&negative));
+}
+
+// =============================================================================
+// BATCH INSERTION TESTS
+// =============================================================================
+
+/// `len` linked blocks continuing from `pre_hash` at `start_height`, not yet stored
+fn unstored_blocks(pre_hash: &str, start_height: usize, len: usize) -> Vec<Block> {
+    let mut blocks: Vec<Block> = vec![];
+    for height in start_height..start_height + len {
+        let pre_hash = blocks.last().map_or(pre_hash.to_string(), |block| block.get_hash().to_string());
+        blocks.push(mine_test_block_at_low_difficulty(create_test_block(pre_hash, height)));
+    }
+    blocks
+}
+
+#[test]
+fn test_add_blocks_stores_contiguous_batch() {
+    let test_db = TestDatabase::new("add_blocks_contiguous");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("batch_genesis_hash");
+    let mut batch = vec![genesis];
+    batch.extend(unstored_blocks("batch_genesis_hash", 1, 4));
+
+    assert_eq!(blockchain.add_blocks(&batch), Ok(()));
+    assert_eq!(blockchain.get_tip_hash(), batch[4].get_hash());
+    assert_eq!(blockchain.get_best_height(), 4);
+    for block in &batch {
+        let stored = blockchain.get_block_by_height(block.get_height()).unwrap();
+        assert_eq!(stored.get_hash(), block.get_hash());
+    }
+    assert!(blockchain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+
+    // A second batch continues from the new tip
+    let more = unstored_blocks(batch[4].get_hash(), 5, 3);
+    assert_eq!(blockchain.add_blocks(&more), Ok(()));
+    assert_eq!(blockchain.get_tip_hash(), more[2].get_hash());
+    assert_eq!(blockchain.get_best_height(), 7);
+    assert!(blockchain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+
+    // An empty batch changes nothing
+    assert_eq!(blockchain.add_blocks(&[]), Ok(()));
+    assert_eq!(blockchain.get_tip_hash(), more[2].get_hash());
+}
+
+#[test]
+fn test_add_blocks_rejects_batch_with_gap() {
+    let test_db = TestDatabase::new("add_blocks_gap");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let stored = store_linked_chain(&blockchain, 2);
+    let tip_hash = blockchain.get_tip_hash();
+
+    let mut batch = unstored_blocks(stored[1].get_hash(), 2, 4);
+    batch.remove(2);
+    let err = blockchain.add_blocks(&batch).unwrap_err();
+    assert!(err.contains(batch[2].get_hash()), "unexpected error: {err}");
+    assert!(err.contains("does not follow"), "unexpected error: {err}");
+
+    // Nothing from the rejected batch was stored
+    assert_eq!(blockchain.get_tip_hash(), tip_hash);
+    assert_eq!(blockchain.get_best_height(), 1);
+    for block in &batch {
+        assert!(blockchain.get_block(block.get_hash().as_bytes()).is_none());
+    }
+}
+
+#[test]
+fn test_add_blocks_rejects_batch_not_extending_tip() {
+    let test_db = TestDatabase::new("add_blocks_not_tip");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let stored = store_linked_chain(&blockchain, 3);
+
+    // Linked to a block below the tip
+    let fork = unstored_blocks(stored[1].get_hash(), 2, 2);
+    assert!(blockchain.add_blocks(&fork).is_err());
+
+    // Linked to the tip but at the wrong height
+    let skipped_height = unstored_blocks(stored[2].get_hash(), 4, 2);
+    let err = blockchain.add_blocks(&skipped_height).unwrap_err();
+    assert!(err.contains("instead of 3"), "unexpected error: {err}");
+    assert_eq!(blockchain.get_tip_hash(), stored[2].get_hash());
+}
+
+// =============================================================================
+// BLOCK LIMIT TESTS
+// =============================================================================
+
+/// A funded chain and seven candidate transactions for its next block, coinbase first
+fn block_candidates(db: &sled::Db, seed: &[u8]) -> (Blockchain, Vec<Transaction>) {
+    let wallet = Wallet::from_seed(seed);
+    let blockchain = create_funded_blockchain(db, &wallet.get_address());
+    let txs = block_of_signed_transactions(&blockchain, &wallet).get_transactions().to_vec();
+    assert!(txs[0].is_coinbase());
+    (blockchain, txs)
+}
+
+fn unmined_size_bound(blockchain: &Blockchain, txs: &[Transaction]) -> usize {
+    Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), txs, 1).serialize().len() + MINED_BLOCK_SIZE_SLACK
+}
+
+#[test]
+fn test_select_block_transactions_within_limits_keeps_all() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, txs) = block_candidates(&db, b"block-limit-all");
+
+    let selected = blockchain.select_block_transactions(&txs, usize::MAX, usize::MAX);
+    let selected_ids: Vec<&[u8]> = selected.iter().map(|tx| tx.get_id()).collect();
+    let ids: Vec<&[u8]> = txs.iter().map(|tx| tx.get_id()).collect();
+    assert_eq!(selected_ids, ids);
+}
+
+#[test]
+fn test_select_block_transactions_trims_to_tx_count() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, txs) = block_candidates(&db, b"block-limit-count");
+
+    // The coinbase takes one of the three slots
+    let selected = blockchain.select_block_transactions(&txs, usize::MAX, 3);
+    assert_eq!(selected.len(), 3);
+    assert!(selected[0].is_coinbase());
+    assert_eq!(selected[1].get_id(), txs[1].get_id());
+    assert_eq!(selected[2].get_id(), txs[2].get_id());
+}
+
+#[test]
+fn test_select_block_transactions_always_keeps_coinbase() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, txs) = block_candidates(&db, b"block-limit-coinbase");
+
+    for selected in [
+        blockchain.select_block_transactions(&txs, usize::MAX, 0),
+        blockchain.select_block_transactions(&txs, 0, usize::MAX),
+    ] {
+        assert_eq!(selected.len(), 1);
+        assert!(selected[0].is_coinbase());
+    }
+}
+
+#[test]
+fn test_select_block_transactions_trims_to_block_size() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, txs) = block_candidates(&db, b"block-limit-size");
+    let limit = unmined_size_bound(&blockchain, &txs[..3]);
+
+    let selected = blockchain.select_block_transactions(&txs, limit, usize::MAX);
+    assert_eq!(selected.len(), 3);
+    assert!(selected[0].is_coinbase());
+    assert!(unmined_size_bound(&blockchain, &selected) <= limit);
+
+    // One byte less only leaves room for a single spend
+    let selected = blockchain.select_block_transactions(&txs, limit - 1, usize::MAX);
+    assert_eq!(selected.len(), 2);
+}
+
+#[ignore] // Mining at the default difficulty can take a very long time; run with BLOCKCHAIN_TARGET_BITS=8
+#[test]
+fn test_mine_block_with_limits_stays_within_bounds() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, txs) = block_candidates(&db, b"block-limit-mine");
+    let limit = unmined_size_bound(&blockchain, &txs[..3]);
+
+    let block = blockchain.mine_block_with_limits(&txs, limit, 4);
+    assert!(block.get_transactions().len() <= 4);
+    assert!(block.serialize().len() <= limit);
+    assert!(block.get_transactions().iter().any(|tx| tx.is_coinbase()));
+    assert_eq!(blockchain.get_tip_hash(), block.get_hash());
+}
+
+// =============================================================================
+// COINBASE VALUE TESTS
+// =============================================================================
+
+/// A funded chain and a signed spend of its genesis reward paying `fee`
+fn fee_paying_spend(db: &sled::Db, seed: &[u8], fee: i32) -> (Blockchain, Wallet, Transaction) {
+    let wallet = Wallet::from_seed(seed);
+    let blockchain = create_funded_blockchain(db, &wallet.get_address());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let recipient = Wallet::from_seed(b"fee-recipient").get_address();
+    let (unsigned, prev_outputs) = utxo_set
+        .build_unsigned(&hash_pub_key(wallet.get_public_key()), &[TXOutput::new(SUBSIDY - fee, &recipient)])
+        .unwrap();
+    let mut spend = Transaction::new(vec![], unsigned.get_vin().to_vec(), vec![TXOutput::new(SUBSIDY - fee, &recipient)]);
+    spend.sign_offline(wallet.get_pkcs8(), &prev_outputs).unwrap();
+    (blockchain, wallet, spend)
+}
+
+#[test]
+fn test_transaction_fee_and_block_fees() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, wallet, spend) = fee_paying_spend(&db, b"fee-sum", 3);
+    let coinbase = Transaction::new_coinbase_tx(&wallet.get_address());
+
+    assert_eq!(blockchain.transaction_fee(&spend, &[]), Ok(3));
+    assert_eq!(blockchain.transaction_fee(&coinbase, &[]), Ok(0));
+    let block = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &[coinbase, spend], 1);
+    assert_eq!(blockchain.block_fees(&block), Ok(3));
+
+    // Inputs that cannot be found make the fee unknown
+    let unknown = create_spending_transaction(vec![(vec![9, 9, 9], 0)], vec![(1, vec![1])]);
+    assert!(blockchain.transaction_fee(&unknown, &[]).is_err());
+}
+
+#[test]
+fn test_validate_coinbase_value_accepts_subsidy_plus_fees() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, wallet, spend) = fee_paying_spend(&db, b"fee-accept", 3);
+    let miner_hash = hash_pub_key(wallet.get_public_key());
+
+    for claimed in [SUBSIDY, SUBSIDY + 3] {
+        let coinbase = create_coinbase_transaction(claimed, miner_hash.clone());
+        let block = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &[coinbase, spend.clone()], 1);
+        assert_eq!(blockchain.validate_coinbase_value(&block), Ok(()));
+    }
+}
+
+#[test]
+fn test_validate_coinbase_value_rejects_over_claim() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, wallet, spend) = fee_paying_spend(&db, b"fee-reject", 3);
+    let miner_hash = hash_pub_key(wallet.get_public_key());
+
+    let coinbase = create_coinbase_transaction(SUBSIDY + 4, miner_hash.clone());
+    let block = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &[coinbase, spend], 1);
+    let err = blockchain.validate_coinbase_value(&block).unwrap_err();
+    assert!(err.contains("claims 14"), "unexpected error: {err}");
+
+    // Without fees only the subsidy may be claimed
+    let coinbase = create_coinbase_transaction(SUBSIDY + 1, miner_hash);
+    let block = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &[coinbase], 1);
+    assert!(blockchain.validate_coinbase_value(&block).is_err());
+}
+
+#[test]
+fn test_validate_chain_rejects_over_valued_coinbase() {
+    let test_db = TestDatabase::new("validate_chain_over_valued");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let wallet = Wallet::from_seed(b"validate-over-valued");
+    let blocks = store_validated_chain(&blockchain, &wallet);
+
+    let coinbase = create_coinbase_transaction(SUBSIDY * 2, hash_pub_key(wallet.get_public_key()));
+    let inflated = mine_test_block(blocks[2].get_hash(), 3, &[coinbase]);
+    blockchain.add_block(&inflated);
+
+    let err = blockchain
+        .validate_chain_with_target_bits(VALIDATE_TARGET_BITS)
+        .unwrap_err();
+    assert!(err.contains(inflated.get_hash()), "unexpected error: {err}");
+    assert!(err.contains("more than subsidy"), "unexpected error: {err}");
+}
+
+// =============================================================================
+// CHAIN STATS TESTS
+// =============================================================================
+
+#[test]
+fn test_stats_on_empty_chain() {
+    let blockchain = Blockchain::new_in_memory();
+    let stats = blockchain.stats();
+    assert_eq!(stats.get_block_count(), 0);
+    assert_eq!(stats.get_transaction_count(), 0);
+    assert_eq!(stats.get_coinbase_issuance(), 0);
+    assert_eq!(stats.get_circulating_supply(), 0);
+}
+
+#[test]
+fn test_stats_match_constructed_chain() {
+    let test_db = TestDatabase::new("chain_stats_constructed");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    store_validated_chain(&blockchain, &Wallet::from_seed(b"chain-stats"));
+
+    // Genesis, a block with a coinbase and a spend, and a coinbase-only block
+    let stats = blockchain.stats();
+    assert_eq!(stats.get_block_count(), 3);
+    assert_eq!(stats.get_transaction_count(), 4);
+    assert_eq!(stats.get_coinbase_issuance(), 3 * SUBSIDY as i64);
+    assert_eq!(stats.get_circulating_supply(), 3 * SUBSIDY as i64);
+
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    utxo_set.reindex();
+    assert_eq!(stats.get_circulating_supply(), utxo_set.total_supply());
+}
+
+#[test]
+fn test_stats_supply_excludes_spent_outputs_and_fees() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (blockchain, wallet, spend) = fee_paying_spend(&db, b"chain-stats-fee", 3);
+    let coinbase = Transaction::new_coinbase_tx(&wallet.get_address());
+    let block = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &[coinbase, spend], 1);
+    blockchain.add_block(&block);
+
+    // The genesis reward is spent into 7 for the recipient; the 3 left over went unclaimed
+    let stats = blockchain.stats();
+    assert_eq!(stats.get_block_count(), 2);
+    assert_eq!(stats.get_transaction_count(), 3);
+    assert_eq!(stats.get_coinbase_issuance(), 2 * SUBSIDY as i64);
+    assert_eq!(stats.get_circulating_supply(), 2 * SUBSIDY as i64 - 3);
+}
+
+// =============================================================================
+// PRUNING TESTS
+// =============================================================================
+
+/// A validated chain with its UTXO set built: the genesis reward is spent in block 1, every
+/// other output is unspent
+fn store_pruning_chain(test_db: &TestDatabase, seed: &[u8]) -> (Blockchain, Vec<Block>) {
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_validated_chain(&blockchain, &Wallet::from_seed(seed));
+    UTXOSet::new(blockchain.clone()).reindex();
+    (blockchain, blocks)
+}
+
+#[test]
+fn test_prune_below_discards_only_fully_spent_transactions() {
+    let test_db = TestDatabase::new("prune_spent_only");
+    let (blockchain, blocks) = store_pruning_chain(&test_db, b"prune-spent-only");
+    let genesis_coinbase = blocks[0].get_transactions()[0].clone();
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let supply_before = utxo_set.total_supply();
+
+    assert_eq!(blockchain.prune_below(3).unwrap(), 1);
+
+    let pruned = blockchain.find_transaction(genesis_coinbase.get_id()).unwrap();
+    assert!(pruned.is_pruned());
+    assert_eq!(pruned.get_id(), genesis_coinbase.get_id());
+
+    // Everything the UTXO set still references keeps its full data
+    for block in &blocks[1..] {
+        for tx in block.get_transactions() {
+            let stored = blockchain.find_transaction(tx.get_id()).unwrap();
+            assert!(!stored.is_pruned());
+            assert_eq!(stored.get_vout().len(), tx.get_vout().len());
+        }
+    }
+    utxo_set.reindex();
+    assert_eq!(utxo_set.total_supply(), supply_before);
+
+    // Pruning again finds nothing left to discard
+    assert_eq!(blockchain.prune_below(3).unwrap(), 0);
+}
+
+#[test]
+fn test_prune_below_keeps_chain_structurally_valid() {
+    let test_db = TestDatabase::new("prune_structure");
+    let (blockchain, blocks) = store_pruning_chain(&test_db, b"prune-structure");
+    let merkle_roots: Vec<Vec<u8>> = blocks.iter().map(|block| block.hash_transactions()).collect();
+
+    blockchain.prune_below(3).unwrap();
+
+    assert_eq!(blockchain.validate_chain_structure_with_target_bits(VALIDATE_TARGET_BITS), Ok(()));
+    for (block, merkle_root) in blocks.iter().zip(&merkle_roots) {
+        let stored = blockchain.get_block(block.get_hash().as_bytes()).unwrap();
+        assert_eq!(&stored.hash_transactions(), merkle_root);
+    }
+    // Full validation needs the spent outputs that were discarded
+    assert!(blockchain.validate_chain_with_target_bits(VALIDATE_TARGET_BITS).is_err());
+}
+
+#[test]
+fn test_prune_below_leaves_blocks_at_or_above_height() {
+    let test_db = TestDatabase::new("prune_height");
+    let (blockchain, _blocks) = store_pruning_chain(&test_db, b"prune-height");
+
+    assert_eq!(blockchain.prune_below(0).unwrap(), 0);
+    assert_eq!(blockchain.validate_chain_with_target_bits(VALIDATE_TARGET_BITS), Ok(()));
+}
+
+#[test]
+fn test_prune_below_never_prunes_utxo_set_entries() {
+    let test_db = TestDatabase::new("prune_utxo_guard");
+    let (blockchain, blocks) = store_pruning_chain(&test_db, b"prune-utxo-guard");
+    let genesis_coinbase = &blocks[0].get_transactions()[0];
+
+    // A UTXO set that still lists the spent genesis reward protects it
+    let utxo_tree = test_db.get_db().open_tree(rust_blockchain::UTXO_TREE).unwrap();
+    let entry: Vec<Option<TXOutput>> = genesis_coinbase.get_vout().iter().cloned().map(Some).collect();
+    let outs = bincode::encode_to_vec(entry, bincode::config::standard()).unwrap();
+    utxo_tree.insert(genesis_coinbase.get_id(), outs).unwrap();
+
+    assert_eq!(blockchain.prune_below(3).unwrap(), 0);
+    assert!(!blockchain.find_transaction(genesis_coinbase.get_id()).unwrap().is_pruned());
+}
+
+#[test]
+fn test_prune_below_then_reindex_keeps_balances() {
+    let test_db = TestDatabase::new("prune_reindex_balances");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let funder = Wallet::from_seed(b"prune-reindex-funder");
+    let recipient = Wallet::from_seed(b"prune-reindex-recipient");
+    let relay = Wallet::from_seed(b"prune-reindex-relay");
+    let miner = Wallet::from_seed(b"prune-reindex-miner").get_address();
+    let mut genesis = Block::generate_genesis_block(&Transaction::new_coinbase_tx(&funder.get_address()));
+    genesis.set_hash_for_test("prune_reindex_genesis");
+    blockchain.add_block(&genesis);
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    utxo_set.reindex();
+
+    // Pays the recipient 3 and keeps its change unspent, so it is never pruned
+    let pay = Transaction::new_utxo_transaction_from_wallet(&funder, &[(recipient.get_address(), 3)], &utxo_set).unwrap();
+    // Spends the 3 and is itself fully spent, so it is pruned along with the record of that spend
+    let forward = |from: &Wallet, to: &Wallet| {
+        Transaction::new_utxo_transaction_from_wallet(from, &[(to.get_address(), 3)], &utxo_set).unwrap()
+    };
+    let mut pre_hash = genesis.get_hash().to_string();
+    for height in 1..=3 {
+        let spend = match height {
+            1 => pay.clone(),
+            2 => forward(&recipient, &relay),
+            _ => forward(&relay, &recipient),
+        };
+        let block = mine_test_block(&pre_hash, height, &[Transaction::new_coinbase_tx(&miner), spend]);
+        blockchain.add_block(&block);
+        utxo_set.reindex();
+        pre_hash = block.get_hash().to_string();
+    }
+    let balances = || {
+        [&funder, &recipient, &relay].map(|wallet| utxo_set.get_balance(&hash_pub_key(wallet.get_public_key())))
+    };
+    let balances_before = balances();
+    assert_eq!(balances_before, [SUBSIDY as i64 - 3, 3, 0]);
+
+    // The genesis reward and the recipient's forward to the relay are fully spent
+    assert_eq!(blockchain.prune_below(3).unwrap(), 2);
+    utxo_set.reindex();
+    assert_eq!(balances(), balances_before);
+    assert!(!utxo_set.is_spendable(pay.get_id(), 0));
+}
+
+// =============================================================================
+// CHAIN PARAMS TESTS
+// =============================================================================
+
+#[test]
+fn test_chain_params_defaults() {
+    let params = ChainParams::new("params_default_address");
+    assert_eq!(params.get_genesis_address(), "params_default_address");
+    assert_eq!(params.get_genesis_reward(), SUBSIDY);
+    assert_eq!(params.get_target_bits(), GLOBAL_CONFIG.get_target_bits());
+
+    let params = params.with_genesis_reward(50).with_target_bits(8);
+    assert_eq!(params.get_genesis_reward(), 50);
+    assert_eq!(params.get_target_bits(), 8);
+}
+
+#[test]
+fn test_create_blockchain_with_different_params() {
+    let main_wallet = Wallet::from_seed(b"params-main");
+    let test_wallet = Wallet::from_seed(b"params-test");
+    let main_params = ChainParams::new(&main_wallet.get_address());
+    let test_params = ChainParams::new(&test_wallet.get_address())
+        .with_genesis_reward(1_000)
+        .with_target_bits(8);
+
+    let (main_db, _main_dir) = setup_temp_test_db();
+    let (test_db, _test_dir) = setup_temp_test_db();
+    let main_chain = Blockchain::create_blockchain_in(main_db, &main_params);
+    let test_chain = Blockchain::create_blockchain_in(test_db, &test_params);
+
+    let main_genesis = main_chain.get_block(main_chain.get_tip_hash().as_bytes()).unwrap();
+    let test_genesis = test_chain.get_block(test_chain.get_tip_hash().as_bytes()).unwrap();
+    let main_out = &main_genesis.get_transactions()[0].get_vout()[0];
+    let test_out = &test_genesis.get_transactions()[0].get_vout()[0];
+
+    assert_eq!(main_out.get_value(), SUBSIDY);
+    assert_eq!(test_out.get_value(), 1_000);
+    assert!(main_out.is_locked_with_key(&hash_pub_key(main_wallet.get_public_key())));
+    assert!(test_out.is_locked_with_key(&hash_pub_key(test_wallet.get_public_key())));
+    assert_ne!(main_genesis.hash_transactions(), test_genesis.hash_transactions());
+
+    // Each chain remembers the parameters it was created with
+    assert_eq!(main_chain.get_chain_params(), Some(main_params));
+    assert_eq!(test_chain.get_chain_params(), Some(test_params));
+
+    // A larger genesis reward is still a valid chain
+    assert_eq!(test_chain.validate_chain(), Ok(()));
+}
+
+#[test]
+fn test_create_blockchain_in_keeps_existing_chain() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let first = Blockchain::create_blockchain_in(db.clone(), &ChainParams::new("params_first"));
+    let reopened = Blockchain::create_blockchain_in(db, &ChainParams::new("params_second").with_genesis_reward(99));
+
+    assert_eq!(reopened.get_tip_hash(), first.get_tip_hash());
+    assert_eq!(reopened.get_chain_params().unwrap().get_genesis_address(), "params_first");
+
+    // Chains not created from parameters have none recorded
+    let (other_db, _other_dir) = setup_temp_test_db();
+    assert_eq!(Blockchain::new_with_empty_tip(other_db).get_chain_params(), None);
+}
+
+#[test]
+fn test_chain_mines_and_validates_at_its_target_bits() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let params = ChainParams::new("params_target_bits").with_target_bits(TEST_TARGET_BITS);
+    let blockchain = Blockchain::create_blockchain_in(db, &params);
+    assert_eq!(blockchain.get_target_bits(), TEST_TARGET_BITS);
+
+    let block = blockchain.mine_block(&[create_coinbase_transaction(SUBSIDY, vec![0x7b])]);
+    assert!(ProofOfWork::new_proof_of_work(block, TEST_TARGET_BITS).validate());
+    assert!(blockchain.verify_chain());
+    assert_eq!(blockchain.validate_chain(), Ok(()));
+
+    // A chain without parameters uses the configured difficulty
+    let (other_db, _other_dir) = setup_temp_test_db();
+    let other = Blockchain::new_with_empty_tip(other_db);
+    assert_eq!(other.get_target_bits(), GLOBAL_CONFIG.get_target_bits());
+}
+
+// =============================================================================
+// DUPLICATE TRANSACTION TESTS
+// =============================================================================
+
+#[test]
+fn test_validate_not_confirmed_checks_only_ancestors() {
+    let test_db = TestDatabase::new("validate_not_confirmed");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("not_confirmed_genesis");
+    blockchain.add_block(&genesis);
+    let block1 = create_test_block(genesis.get_hash().to_string(), 1);
+    blockchain.add_block(&block1);
+    let confirmed = block1.get_transactions()[1].clone();
+
+    let fresh = create_test_block(block1.get_hash().to_string(), 2);
+    assert_eq!(blockchain.validate_not_confirmed(&fresh), Ok(()));
+
+    let mut replay = create_test_block(block1.get_hash().to_string(), 2);
+    replay.set_transactions_for_test(&[replay.get_transactions()[0].clone(), confirmed.clone()]);
+    let err = blockchain.validate_not_confirmed(&replay).unwrap_err();
+    assert!(err.contains(block1.get_hash()), "unexpected error: {err}");
+
+    // A competing block at height 1 does not descend from block1, so it may include the same transaction
+    let mut sibling = create_test_block(genesis.get_hash().to_string(), 1);
+    sibling.set_hash_for_test("not_confirmed_sibling");
+    sibling.set_transactions_for_test(&[sibling.get_transactions()[0].clone(), confirmed]);
+    assert_eq!(blockchain.validate_not_confirmed(&sibling), Ok(()));
+}
+
+// =============================================================================
+// TIP NOTIFICATION TESTS
+// =============================================================================
+
+#[test]
+fn test_subscribe_receives_tip_updates() {
+    let blockchain = Blockchain::new_in_memory();
+    let updates = blockchain.subscribe();
+
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("tip_update_genesis");
+    blockchain.add_block(&genesis);
+    let block_1 = create_test_block("tip_update_genesis".to_string(), 1);
+    // Subscribers share the clones of a chain
+    blockchain.clone().add_block(&block_1);
+
+    let first = updates.try_recv().unwrap();
+    assert_eq!((first.get_hash(), first.get_height()), ("tip_update_genesis", 0));
+    let second = updates.try_recv().unwrap();
+    assert_eq!((second.get_hash(), second.get_height()), (block_1.get_hash(), 1));
+
+    // A block that does not move the tip sends nothing
+    let mut side = create_test_block("tip_update_genesis".to_string(), 1);
+    side.set_hash_for_test("tip_update_side");
+    blockchain.add_block(&side);
+    assert!(blockchain.get_block(b"tip_update_side").is_some());
+    assert!(updates.try_recv().is_err());
+}
+
+#[test]
+fn test_tip_updates_skip_dropped_subscribers() {
+    let blockchain = Blockchain::new_in_memory();
+    drop(blockchain.subscribe());
+    let updates = blockchain.subscribe();
+
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("dropped_subscriber_genesis");
+    blockchain.add_block(&genesis);
+
+    assert_eq!(updates.try_recv().unwrap().get_hash(), "dropped_subscriber_genesis");
+}
+
+// =============================================================================
+// CONFIRMATION TESTS
+// =============================================================================
+
+// Genesis plus `len - 1` linked test blocks in memory, oldest first
+fn in_memory_chain(len: usize) -> (Blockchain, Vec<Block>) {
+    let blockchain = Blockchain::new_in_memory();
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("confirmations_genesis");
+    let mut blocks = vec![genesis];
+    for height in 1..len {
+        let pre_hash = blocks[height - 1].get_hash().to_string();
+        blocks.push(mine_test_block_at_low_difficulty(create_test_block(pre_hash, height)));
+    }
+    for block in &blocks {
+        blockchain.add_block(block);
+    }
+    (blockchain, blocks)
+}
+
+#[test]
+fn test_get_confirmations_counts_from_containing_block() {
+    let (blockchain, blocks) = in_memory_chain(4);
+
+    let genesis_txid = blocks[0].get_transactions()[0].get_id();
+    assert_eq!(blockchain.get_confirmations(genesis_txid), Some(4));
+
+    let tip_txid = blocks[3].get_transactions()[1].get_id();
+    assert_eq!(blockchain.get_confirmations(tip_txid), Some(1));
+    assert_eq!(blockchain.find_transaction_height(tip_txid), Some(3));
+
+    let middle_txid = blocks[2].get_transactions()[0].get_id();
+    assert_eq!(blockchain.get_confirmations(middle_txid), Some(2));
+}
+
+#[test]
+fn test_get_confirmations_unknown_transaction() {
+    let (blockchain, _) = in_memory_chain(2);
+    assert_eq!(blockchain.get_confirmations(b"not_a_txid"), None);
+    assert_eq!(Blockchain::new_in_memory().get_confirmations(b"not_a_txid"), None);
+}
+
+// =============================================================================
+// CLOSE TESTS
+// =============================================================================
+
+#[test]
+fn test_close_releases_database_for_reopen() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let params = ChainParams::new("close_reopen_address");
+
+    let blockchain = Blockchain::create_blockchain_in(sled::open(temp_dir.path()).unwrap(), &params);
+    let block = create_test_block(blockchain.get_tip_hash(), 1);
+    blockchain.add_block(&block);
+    let tip_hash = blockchain.get_tip_hash();
+    assert_eq!(tip_hash, block.get_hash());
+    blockchain.close().unwrap();
+
+    // No sleep or retry: the lock is gone as soon as close returns
+    let reopened = Blockchain::create_blockchain_in(sled::open(temp_dir.path()).unwrap(), &params);
+    assert_eq!(reopened.get_tip_hash(), tip_hash);
+    assert_eq!(reopened.get_best_height(), 1);
+    reopened.close().unwrap();
+}
+
+// =============================================================================
+// SCHEMA VERSION TESTS
+// =============================================================================
+
+fn stored_schema_version(db: &sled::Db) -> Option<u32> {
+    db.open_tree(BLOCKS_TREE)
+        .unwrap()
+        .get(SCHEMA_VERSION_KEY)
+        .unwrap()
+        .map(|bytes| u32::from_be_bytes(bytes.as_ref().try_into().unwrap()))
+}
+
+#[test]
+fn test_new_store_is_stamped_with_schema_version() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"schema-fresh");
+    let blockchain = Blockchain::create_blockchain_in(db.clone(), &ChainParams::new(&wallet.get_address()));
+    assert_eq!(stored_schema_version(&db), Some(SCHEMA_VERSION));
+
+    // The version key is not mistaken for a block
+    assert_eq!(blockchain.get_block_hashes().len(), 1);
+    assert_eq!(blockchain.stats().get_block_count(), 1);
+}
+
+#[test]
+fn test_version_one_store_is_migrated_on_open() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"schema-version-one");
+    let pub_key_hash = hash_pub_key(wallet.get_public_key());
+    let blockchain = create_funded_blockchain(&db, &wallet.get_address());
+    let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree.insert(SCHEMA_VERSION_KEY, &1u32.to_be_bytes()).unwrap();
+
+    // Version 1 UTXO entries and undo records do not decode as version 2 ones
+    db.open_tree(UTXO_TREE).unwrap().clear().unwrap();
+    db.open_tree(UTXO_UNDO_TREE).unwrap().insert(b"stale", &[1u8, 2, 3]).unwrap();
+
+    assert_eq!(Blockchain::check_schema_version(&db).unwrap(), 1);
+    assert_eq!(stored_schema_version(&db), Some(SCHEMA_VERSION));
+    let utxo_set = UTXOSet::new(blockchain);
+    assert_eq!(utxo_set.get_balance(&pub_key_hash), SUBSIDY as i64);
+    assert!(db.open_tree(UTXO_UNDO_TREE).unwrap().is_empty());
+    assert_eq!(Blockchain::check_schema_version(&db).unwrap(), SCHEMA_VERSION);
+}
+
+// The block layout of stores written before schema versioning
+#[derive(bincode::Encode)]
+struct V0Block {
+    timestamp: i64,
+    pre_block_hash: String,
+    hash: String,
+    transactions: Vec<V0Transaction>,
+    nonce: i64,
+    height: usize,
+}
+
+#[derive(bincode::Encode)]
+struct V0Transaction {
+    id: Vec<u8>,
+    vin: Vec<V0Input>,
+    vout: Vec<V0Output>,
+}
+
+#[derive(bincode::Encode)]
+struct V0Input {
+    txid: Vec<u8>,
+    vout: usize,
+    signature: Vec<u8>,
+    pub_key: Vec<u8>,
+}
+
+#[derive(bincode::Encode)]
+struct V0Output {
+    value: i32,
+    pub_key_hash: Vec<u8>,
+}
+
+#[test]
+fn test_version_zero_store_is_refused() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"schema-version-zero");
+    let coinbase = V0Transaction {
+        id: vec![7; 32],
+        vin: vec![V0Input { txid: vec![], vout: 0, signature: vec![], pub_key: b"reward".to_vec() }],
+        vout: vec![V0Output { value: SUBSIDY, pub_key_hash: hash_pub_key(wallet.get_public_key()) }],
+    };
+    let genesis = V0Block {
+        timestamp: 1,
+        pre_block_hash: GENESIS_PRE_BLOCK_HASH.to_string(),
+        hash: String::from("v0_genesis_hash"),
+        transactions: vec![coinbase],
+        nonce: 0,
+        height: 0,
+    };
+    let encoded = bincode::encode_to_vec(&genesis, bincode::config::standard()).unwrap();
+    let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree.insert(genesis.hash.as_bytes(), encoded.as_slice()).unwrap();
+    blocks_tree.insert(TIP_BLOCK_HASH_KEY, genesis.hash.as_bytes()).unwrap();
+
+    let err = Blockchain::check_schema_version(&db).unwrap_err();
+    assert!(matches!(err, BlockchainError::ResyncRequired(0)));
+    assert!(err.to_string().contains("sync the chain again"));
+    assert_eq!(stored_schema_version(&db), None);
+    assert_eq!(blocks_tree.get(genesis.hash.as_bytes()).unwrap().unwrap().as_ref(), encoded.as_slice());
+
+    let params = ChainParams::new(&wallet.get_address());
+    let opened = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        Blockchain::create_blockchain_in(db.clone(), &params)
+    }));
+    assert!(opened.is_err());
+}
+
+#[test]
+fn test_future_schema_version_is_refused() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db.clone());
+    store_linked_chain(&blockchain, 2);
+    let future = SCHEMA_VERSION + 1;
+    let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree.insert(SCHEMA_VERSION_KEY, &future.to_be_bytes()).unwrap();
+
+    let err = Blockchain::check_schema_version(&db).unwrap_err();
+    assert!(matches!(err, BlockchainError::UnsupportedSchema(version) if version == future));
+    assert!(err.to_string().contains("newer than the supported version"));
+    assert_eq!(stored_schema_version(&db), Some(future));
+
+    let wallet = Wallet::from_seed(b"schema-future");
+    let params = ChainParams::new(&wallet.get_address());
+    let opened = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        Blockchain::create_blockchain_in(db.clone(), &params)
+    }));
+    assert!(opened.is_err());
+}
+
+// =============================================================================
+// BLOCK LOCATOR TESTS
+// =============================================================================
+
+#[test]
+fn test_block_locator_is_dense_near_tip_and_sparse_toward_genesis() {
+    let test_db = TestDatabase::new("block_locator_long_chain");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(blockchain.block_locator().is_empty());
+    let blocks = store_linked_chain(&blockchain, 100);
+
+    let locator = blockchain.block_locator();
+    let heights: Vec<usize> = locator
+        .iter()
+        .map(|hash| blockchain.get_block(hash.as_bytes()).unwrap().get_height())
+        .collect();
+    assert_eq!(
+        heights,
+        vec![99, 98, 97, 96, 95, 94, 93, 92, 91, 90, 88, 84, 76, 60, 28, 0]
+    );
+    assert_eq!(locator[0], blocks[99].get_hash());
+    assert_eq!(locator.last().unwrap(), "linked_genesis_hash");
+
+    // One height apart for the dense part, then every gap is twice the one before
+    let gaps: Vec<usize> = heights.windows(2).map(|pair| pair[0] - pair[1]).collect();
+    assert!(gaps[..LOCATOR_DENSE_LEN - 1].iter().all(|&gap| gap == 1));
+    assert!(gaps[LOCATOR_DENSE_LEN..gaps.len() - 1]
+        .windows(2)
+        .all(|pair| pair[1] == pair[0] * 2));
+}
+
+#[test]
+fn test_get_block_hashes_after_locator_starts_at_fork_point() {
+    let test_db = TestDatabase::new("block_hashes_after_locator");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let blocks = store_linked_chain(&blockchain, 6);
+    let mut side_block = create_test_block(blocks[2].get_hash().to_string(), 3);
+    side_block.set_hash_for_test("locator_side_block");
+    blockchain.add_block(&side_block);
+
+    // Unknown and side-chain hashes are skipped for the newest main-chain one
+    let locator = vec![
+        "unknown_hash".to_string(),
+        "locator_side_block".to_string(),
+        blocks[3].get_hash().to_string(),
+        blocks[0].get_hash().to_string(),
+    ];
+    let hashes = blockchain.get_block_hashes_after_locator(&locator);
+    assert_eq!(hashes, vec![blocks[5].get_hash_bytes(), blocks[4].get_hash_bytes()]);
+
+    let tip_locator = blockchain.block_locator();
+    assert!(blockchain.get_block_hashes_after_locator(&tip_locator).is_empty());
+
+    // Without a shared block the peer gets every hash
+    assert_eq!(
+        blockchain.get_block_hashes_after_locator(&["unknown_hash".to_string()]),
+        blockchain.get_block_hashes()
+    );
+    assert_eq!(blockchain.get_block_hashes_after_locator(&[]).len(), 6);
+}
+
+// =============================================================================
+// ORPHAN BLOCK TESTS
+// =============================================================================
+
+#[test]
+fn test_find_orphan_blocks_reports_side_branch_only() {
+    let test_db = TestDatabase::new("find_orphan_blocks");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    assert!(blockchain.find_orphan_blocks().is_empty());
+    let blocks = store_linked_chain(&blockchain, 4);
+    assert!(blockchain.find_orphan_blocks().is_empty());
+
+    let mut side_block = create_test_block(blocks[1].get_hash().to_string(), 2);
+    side_block.set_hash_for_test("orphan_side_block");
+    blockchain.add_block(&side_block);
+    let mut side_child = create_test_block("orphan_side_block".to_string(), 3);
+    side_child.set_hash_for_test("orphan_side_child");
+    blockchain.add_block(&side_child);
+    assert_eq!(blockchain.get_tip_hash(), blocks[3].get_hash());
+
+    let orphans = blockchain.find_orphan_blocks();
+    assert_eq!(orphans, vec!["orphan_side_block", "orphan_side_child"]);
+    for block in &blocks {
+        assert!(!orphans.contains(&block.get_hash().to_string()));
+    }
+}
@@ -1,2 +1,3 @@
 mod blockchain_tests;
-mod blockchain_iterator_tests; 
+mod blockchain_iterator_tests;
+mod block_store_tests;
@@ -0,0 +1,75 @@
+//! Checkpoint tests. They configure checkpoints on the global config, which every chain in
+//! the process honors, so they run as their own test binary.
+
+use rust_blockchain::{
+    BLOCKS_TREE, Block, Blockchain, GENESIS_PRE_BLOCK_HASH, TXInput, TXOutput, Transaction,
+    config::GLOBAL_CONFIG,
+};
+
+// Shared by every test, since tests run in parallel against the one global config
+const CHECKPOINTS: &str = "1:checkpointed_block_1,3:checkpointed_block_3";
+
+// An unmined block at `height` on top of `pre_hash`, carrying `hash`
+fn block_with_hash(pre_hash: &str, height: usize, hash: &str) -> Block {
+    let coinbase = Transaction::new(
+        hash.as_bytes().to_vec(),
+        vec![TXInput::new(&[], 0)],
+        vec![TXOutput::new(10, "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2")],
+    );
+    let mut block =
+        Block::new_block_without_proof_of_work(pre_hash.to_string(), &[coinbase], height);
+    block.set_hash_for_test(hash);
+    block
+}
+
+#[test]
+fn test_blocks_must_match_checkpoints() {
+    GLOBAL_CONFIG.set_checkpoints(CHECKPOINTS);
+    let blockchain = Blockchain::new_in_memory();
+    assert!(blockchain.is_checkpoint_valid(1, "checkpointed_block_1"));
+    assert!(!blockchain.is_checkpoint_valid(1, "other_block_1"));
+    assert!(blockchain.is_checkpoint_valid(2, "any_block_2"));
+
+    blockchain.add_block(&block_with_hash(GENESIS_PRE_BLOCK_HASH, 0, "genesis_block"));
+
+    // A block conflicting with the checkpoint is not stored
+    let conflicting = block_with_hash("genesis_block", 1, "other_block_1");
+    assert!(blockchain.add_block(&conflicting).is_none());
+    assert!(blockchain.get_block(b"other_block_1").is_none());
+    assert_eq!(blockchain.get_tip_hash(), "genesis_block");
+
+    // The checkpointed block and an unconstrained child of it are
+    blockchain.add_block(&block_with_hash("genesis_block", 1, "checkpointed_block_1"));
+    blockchain.add_block(&block_with_hash("checkpointed_block_1", 2, "block_2"));
+    assert_eq!(blockchain.get_tip_hash(), "block_2");
+
+    // A batch is rejected as a whole
+    let batch = [
+        block_with_hash("block_2", 3, "other_block_3"),
+        block_with_hash("other_block_3", 4, "block_4"),
+    ];
+    assert!(blockchain.add_blocks(&batch).is_err());
+    assert!(blockchain.get_block(b"block_4").is_none());
+    assert_eq!(blockchain.get_tip_hash(), "block_2");
+}
+
+#[test]
+fn test_fork_contradicting_checkpoint_cannot_reorganize() {
+    GLOBAL_CONFIG.set_checkpoints(CHECKPOINTS);
+    let db = sled::Config::new().temporary(true).open().unwrap();
+    // A side branch written before any checkpoint named its height
+    let side_1 = block_with_hash("fork_genesis", 1, "fork_side_1");
+    let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree
+        .insert(side_1.get_hash(), side_1.serialize())
+        .unwrap();
+
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    blockchain.add_block(&block_with_hash(GENESIS_PRE_BLOCK_HASH, 0, "fork_genesis"));
+    blockchain.add_block(&block_with_hash("fork_genesis", 1, "checkpointed_block_1"));
+
+    let longer_side = block_with_hash("fork_side_1", 2, "fork_side_2");
+    assert!(blockchain.add_block(&longer_side).is_none());
+    assert!(blockchain.get_block(b"fork_side_2").is_none());
+    assert_eq!(blockchain.get_tip_hash(), "checkpointed_block_1");
+}
@@ -0,0 +1,30 @@
+use rust_blockchain::command::{BLOCK_HASH_HEX_LEN, BlockId};
+
+#[test]
+fn test_block_id_parses_digits_as_height() {
+    assert_eq!("0".parse::<BlockId>(), Ok(BlockId::Height(0)));
+    assert_eq!("42".parse::<BlockId>(), Ok(BlockId::Height(42)));
+    assert_eq!(" 7 ".parse::<BlockId>(), Ok(BlockId::Height(7)));
+    assert!("99999999999999999999999".parse::<BlockId>().is_err());
+}
+
+#[test]
+fn test_block_id_parses_hex_as_hash() {
+    let hash = "00ab".repeat(BLOCK_HASH_HEX_LEN / 4);
+    assert_eq!(hash.parse::<BlockId>(), Ok(BlockId::Hash(hash.clone())));
+    assert_eq!(hash.to_uppercase().parse::<BlockId>(), Ok(BlockId::Hash(hash)));
+    assert_eq!("deadbeef".parse::<BlockId>(), Ok(BlockId::Hash(String::from("deadbeef"))));
+
+    // A full-length hash made only of digits is still a hash
+    let digits = "1".repeat(BLOCK_HASH_HEX_LEN);
+    assert_eq!(digits.parse::<BlockId>(), Ok(BlockId::Hash(digits.clone())));
+}
+
+#[test]
+fn test_block_id_rejects_other_input() {
+    assert!("".parse::<BlockId>().is_err());
+    assert!("   ".parse::<BlockId>().is_err());
+    assert!("-1".parse::<BlockId>().is_err());
+    let err = "not-a-block".parse::<BlockId>().unwrap_err();
+    assert!(err.contains("neither a block height nor a hex hash"));
+}
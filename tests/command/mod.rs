@@ -0,0 +1 @@
+mod block_id_tests;
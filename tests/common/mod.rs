@@ -1,2 +1,4 @@
 mod util_tests;
 mod bincode_bigint_tests; 
+#[cfg(feature = "testing")]
+mod testing_tests;
@@ -0,0 +1,37 @@
+use rust_blockchain::util::testing::{TempBlockchain, mine_test_block};
+use rust_blockchain::wallet::{Wallet, wallet_util::hash_pub_key};
+use rust_blockchain::{SUBSIDY, Transaction};
+
+#[test]
+fn test_temp_blockchain_mines_blocks_on_its_tip() {
+    let miner = Wallet::from_seed(b"testing-fixture-miner");
+    let chain = TempBlockchain::with_genesis(&miner.get_address());
+    assert!(chain.get_path().exists());
+    let genesis_hash = chain.get_tip_hash();
+
+    let block = mine_test_block(&chain, &[Transaction::new_coinbase_tx(&miner.get_address())]);
+    assert_eq!(block.get_height(), 1);
+    assert_eq!(block.get_pre_block_hash(), genesis_hash);
+    assert_eq!(chain.get_tip_hash(), block.get_hash());
+    assert_eq!(chain.get_best_height(), 1);
+
+    let balance = chain
+        .get_utxo_set()
+        .get_balance(hash_pub_key(miner.get_public_key()).as_slice());
+    assert!(balance > SUBSIDY as i64);
+}
+
+#[test]
+fn test_temp_blockchain_starts_empty_and_cleans_up() {
+    let chain = TempBlockchain::new();
+    assert!(chain.is_empty());
+    let path = chain.get_path().to_path_buf();
+
+    let miner = Wallet::from_seed(b"testing-fixture-empty").get_address();
+    let genesis = mine_test_block(chain.get_blockchain(), &[Transaction::new_coinbase_tx(&miner)]);
+    assert_eq!(genesis.get_height(), 0);
+    assert_eq!(chain.get_tip_hash(), genesis.get_hash());
+
+    drop(chain);
+    assert!(!path.exists());
+}
@@ -1,4 +1,5 @@
-use rust_blockchain::util::{current_timestamp, sha256_digest, base58_encode, base58_decode, current_dir, ecdsa_p256_sha256_sign_digest, ecdsa_p256_sha256_sign_verify, new_key_pair, ripemd160_digest};
+use rust_blockchain::util::{current_timestamp, sha256_digest, base58_encode, base58_decode, current_dir, ecdsa_p256_sha256_sign_digest, ecdsa_p256_sha256_sign_verify, is_low_s_signature, new_key_pair, normalize_signature, ripemd160_digest, hash160, base58check_encode, base58check_decode, write_varint, read_varint, sha256d};
+use data_encoding::HEXLOWER;
 
 #[test]
 fn test_current_timestamp() {
@@ -1156,3 +1157,175 @@ fn test_ripemd160_digest_incremental_changes() {
     // At least half the bytes should be different (avalanche effect)
     assert!(different_bytes >= 10, "Expected avalanche effect, only {different_bytes} bytes different");
 }
+
+#[test]
+fn test_hash160_known_vectors() {
+    assert_eq!(HEXLOWER.encode(&hash160(b"")), "b472a266d0bd89c13706a4132ccfb16f7c3b9fcb");
+
+    // The compressed secp256k1 generator point and its well-known hash160
+    let generator = HEXLOWER
+        .decode(b"0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+        .unwrap();
+    assert_eq!(HEXLOWER.encode(&hash160(&generator)), "751e76e8199196d454941c45d1b3a323f1433bd6");
+}
+
+#[test]
+fn test_hash160_is_ripemd160_of_sha256() {
+    let data = b"hash160 composition";
+    assert_eq!(hash160(data), ripemd160_digest(&sha256_digest(data)));
+    assert_eq!(hash160(data).len(), 20);
+}
+
+#[test]
+fn test_base58check_round_trip() {
+    for (version, payload) in [(0x00u8, vec![]), (0x00, vec![0u8; 20]), (0x05, b"payload".to_vec()), (0xff, vec![0xab; 64])] {
+        let encoded = base58check_encode(version, &payload);
+        assert_eq!(base58check_decode(&encoded), Some((version, payload)));
+    }
+}
+
+#[test]
+fn test_base58check_encode_appends_double_sha256_checksum() {
+    let encoded = base58check_encode(0x00, &[1, 2, 3]);
+    let bytes = base58_decode(&encoded);
+    assert_eq!(&bytes[..4], &[0, 1, 2, 3]);
+    assert_eq!(&bytes[4..], &sha256_digest(&sha256_digest(&[0, 1, 2, 3]))[..4]);
+}
+
+#[test]
+fn test_base58check_decode_rejects_corrupted_checksum() {
+    let mut bytes = base58_decode(&base58check_encode(0x00, b"checksummed"));
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0x01;
+    assert_eq!(base58check_decode(&base58_encode(&bytes)), None);
+
+    // A corrupted payload no longer matches its checksum either
+    let mut bytes = base58_decode(&base58check_encode(0x00, b"checksummed"));
+    bytes[1] ^= 0x01;
+    assert_eq!(base58check_decode(&base58_encode(&bytes)), None);
+}
+
+#[test]
+fn test_base58check_decode_rejects_malformed_input() {
+    assert_eq!(base58check_decode(""), None);
+    assert_eq!(base58check_decode("0OIl"), None);
+    // Shorter than a version byte plus checksum
+    assert_eq!(base58check_decode(&base58_encode(&[0, 1, 2, 3])), None);
+}
+
+// =============================================================================
+// VARINT TESTS
+// =============================================================================
+
+fn varint_bytes(value: u64) -> Vec<u8> {
+    let mut out = vec![];
+    write_varint(value, &mut out);
+    out
+}
+
+#[test]
+fn test_varint_round_trips_at_width_boundaries() {
+    let cases: [(u64, usize); 8] = [
+        (0, 1),
+        (0xfc, 1),
+        (0xfd, 3),
+        (0xffff, 3),
+        (0x1_0000, 5),
+        (0xffff_ffff, 5),
+        (0x1_0000_0000, 9),
+        (u64::MAX, 9),
+    ];
+    for (value, len) in cases {
+        let bytes = varint_bytes(value);
+        assert_eq!(bytes.len(), len, "width of {value:#x}");
+        assert_eq!(read_varint(&bytes), Some((value, len)));
+    }
+    assert_eq!(varint_bytes(0xfd), vec![0xfd, 0xfd, 0x00]);
+    assert_eq!(varint_bytes(0x1_0000), vec![0xfe, 0x00, 0x00, 0x01, 0x00]);
+}
+
+#[test]
+fn test_read_varint_ignores_trailing_bytes() {
+    let mut bytes = varint_bytes(300);
+    bytes.extend_from_slice(&[0xaa, 0xbb]);
+    assert_eq!(read_varint(&bytes), Some((300, 3)));
+}
+
+#[test]
+fn test_read_varint_rejects_truncated_and_overlong_input() {
+    assert_eq!(read_varint(&[]), None);
+    assert_eq!(read_varint(&[0xfd, 0x01]), None);
+    assert_eq!(read_varint(&[0xfe, 0x01, 0x02, 0x03]), None);
+    // Values that fit a shorter form must use it
+    assert_eq!(read_varint(&[0xfd, 0x10, 0x00]), None);
+    assert_eq!(read_varint(&[0xfe, 0xff, 0xff, 0x00, 0x00]), None);
+    assert_eq!(read_varint(&[0xff, 0x01, 0, 0, 0, 0, 0, 0, 0]), None);
+}
+
+// =============================================================================
+// DOUBLE SHA256 TESTS
+// =============================================================================
+
+#[test]
+fn test_sha256d_known_vectors() {
+    assert_eq!(
+        HEXLOWER.encode(&sha256d(b"")),
+        "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456"
+    );
+    assert_eq!(
+        HEXLOWER.encode(&sha256d(b"abc")),
+        "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358"
+    );
+}
+
+#[test]
+fn test_sha256d_is_sha256_of_sha256() {
+    let data = b"double hashed";
+    assert_eq!(sha256d(data), sha256_digest(&sha256_digest(data)));
+    assert_ne!(sha256d(data), sha256_digest(data));
+}
+
+// =============================================================================
+// LOW-S SIGNATURE TESTS
+// =============================================================================
+
+// The same signature with s replaced by its negation, which is just as valid an ECDSA
+// signature but in the high half of the curve order
+fn high_s_form(signature: &[u8]) -> Vec<u8> {
+    use p256::{FieldBytes, Scalar, elliptic_curve::ff::PrimeField};
+
+    let s = Scalar::from_repr(FieldBytes::try_from(&signature[32..]).unwrap()).unwrap();
+    let mut malleated = signature[..32].to_vec();
+    malleated.extend_from_slice(&(-s).to_repr());
+    malleated
+}
+
+#[test]
+fn test_ecdsa_p256_sha256_sign_digest_always_produces_low_s() {
+    let pkcs8 = new_key_pair();
+    for round in 0..64u8 {
+        let message = [round; 32];
+        let signature = ecdsa_p256_sha256_sign_digest(&pkcs8, &message);
+        assert_eq!(signature.len(), 64);
+        assert!(is_low_s_signature(&signature), "round {round} produced a high-s signature");
+    }
+}
+
+#[test]
+fn test_ecdsa_p256_sha256_sign_verify_rejects_high_s_signature() {
+    let pkcs8 = new_key_pair();
+    let public_key = rust_blockchain::util::public_key_from_pkcs8(&pkcs8).unwrap();
+    let message = b"malleability test message";
+    let signature = ecdsa_p256_sha256_sign_digest(&pkcs8, message);
+    assert!(ecdsa_p256_sha256_sign_verify(&public_key, &signature, message));
+
+    let malleated = high_s_form(&signature);
+    assert_ne!(malleated, signature);
+    assert!(!is_low_s_signature(&malleated));
+    assert!(!ecdsa_p256_sha256_sign_verify(&public_key, &malleated, message));
+
+    // Normalizing recovers the original signature
+    assert_eq!(normalize_signature(&malleated), Some(signature.clone()));
+    assert_eq!(normalize_signature(&signature), Some(signature));
+    assert_eq!(normalize_signature(&[0; 10]), None);
+}
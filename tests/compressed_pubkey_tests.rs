@@ -0,0 +1,97 @@
+//! Compressed public key tests. They switch wallets on the global config to compressed keys,
+//! which changes every wallet address in the process, so they run as their own test binary.
+
+use std::collections::HashMap;
+
+use data_encoding::HEXLOWER;
+use rust_blockchain::{
+    Blockchain, COMPRESSED_PUBLIC_KEY_LEN, ChainParams, TXOutput, Transaction, UTXOSet,
+    config::GLOBAL_CONFIG,
+    wallet::{Wallet, wallet_util::hash_pub_key},
+};
+use tempfile::TempDir;
+
+#[test]
+fn test_wallet_address_uses_compressed_key_when_enabled() {
+    GLOBAL_CONFIG.set_compressed_pubkeys(true);
+    let wallet = Wallet::from_seed(b"compressed-address");
+    assert_eq!(
+        wallet.get_address_public_key(),
+        wallet.get_compressed_public_key()
+    );
+
+    let (_, pub_key_hash) =
+        rust_blockchain::util::base58check_decode(&wallet.get_address()).unwrap();
+    assert_eq!(
+        pub_key_hash,
+        hash_pub_key(wallet.get_compressed_public_key().as_slice())
+    );
+    assert_ne!(pub_key_hash, hash_pub_key(wallet.get_public_key()));
+}
+
+#[test]
+fn test_transaction_from_compressed_key_wallet_verifies() {
+    GLOBAL_CONFIG.set_compressed_pubkeys(true);
+    let funder = Wallet::from_seed(b"compressed-funder");
+    let recipient = Wallet::from_seed(b"compressed-recipient").get_address();
+    let temp_dir = TempDir::new().unwrap();
+    let db = sled::open(temp_dir.path()).unwrap();
+    let blockchain = Blockchain::create_blockchain_in(db, &ChainParams::new(&funder.get_address()));
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    utxo_set.reindex();
+
+    let outputs = [(recipient, 3)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&funder, &outputs, &utxo_set).unwrap();
+    assert!(
+        tx.get_vin()
+            .iter()
+            .all(|vin| vin.get_pub_key().len() == COMPRESSED_PUBLIC_KEY_LEN)
+    );
+    assert!(tx.verify(&blockchain));
+
+    let estimate =
+        Transaction::estimate(&funder.get_address(), &outputs[0].0, 3, &utxo_set).unwrap();
+    assert_eq!(estimate.get_size(), tx.serialize().len());
+}
+
+#[test]
+fn test_offline_signing_uses_compressed_key_when_enabled() {
+    GLOBAL_CONFIG.set_compressed_pubkeys(true);
+    let funder = Wallet::from_seed(b"compressed-offline-funder");
+    let recipient = Wallet::from_seed(b"compressed-offline-recipient").get_address();
+    let temp_dir = TempDir::new().unwrap();
+    let db = sled::open(temp_dir.path()).unwrap();
+    let blockchain = Blockchain::create_blockchain_in(db, &ChainParams::new(&funder.get_address()));
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    utxo_set.reindex();
+    let funder_hash = hash_pub_key(funder.get_compressed_public_key().as_slice());
+
+    let (mut offline, prev_outputs) = utxo_set
+        .build_unsigned(&funder_hash, &[TXOutput::new(3, &recipient)])
+        .unwrap();
+    let mut with_prev_txs = offline.clone();
+    offline
+        .sign_offline(funder.get_pkcs8(), &prev_outputs)
+        .unwrap();
+
+    let prev_txs: HashMap<String, Transaction> = with_prev_txs
+        .get_vin()
+        .iter()
+        .map(|vin| {
+            let prev_tx = blockchain.find_transaction(vin.get_txid()).unwrap();
+            (HEXLOWER.encode(vin.get_txid()), prev_tx)
+        })
+        .collect();
+    with_prev_txs
+        .sign_with(funder.get_pkcs8(), &prev_txs)
+        .unwrap();
+
+    for tx in [&offline, &with_prev_txs] {
+        assert!(
+            tx.get_vin()
+                .iter()
+                .all(|vin| vin.get_pub_key() == funder.get_compressed_public_key().as_slice())
+        );
+        assert!(tx.verify(&blockchain));
+    }
+}
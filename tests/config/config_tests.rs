@@ -1,5 +1,10 @@
 use std::{env, sync::Arc, thread};
-use rust_blockchain::config::{Config, DEFAULT_NODE_ADDR};
+use rust_blockchain::config::{
+    Config, DEFAULT_COINBASE_MATURITY, DEFAULT_HEARTBEAT_INTERVAL_SECS, DEFAULT_MAX_BLOCK_SIZE,
+    DEFAULT_MAX_BLOCK_TXS, DEFAULT_MAX_CONNECTIONS, DEFAULT_MEMPOOL_EXPIRY_SECS, DEFAULT_NODE_ADDR,
+    DEFAULT_TARGET_BITS, DEFAULT_ADDRESS_VERSION, DEFAULT_NETWORK_MAGIC, DEFAULT_MAX_PACKAGE_SIZE, MAX_PACKAGE_SIZE_CEILING, DEFAULT_READ_TIMEOUT_SECS, DEFAULT_DUST_THRESHOLD, DEFAULT_CONNECT_ATTEMPTS,
+};
+use rust_blockchain::server::CENTRAL_NODE;
 
 #[test]
 fn test_config_new_with_default_node_address() {
@@ -279,3 +284,239 @@ fn test_environment_variable_priority() {
         None => unsafe { env::remove_var("NODE_ADDRESS"); },
     }
 } 
+
+#[test]
+fn test_supply_check_toggle() {
+    let config = Config::new();
+
+    config.set_supply_check(true);
+    assert!(config.is_supply_check_enabled());
+
+    config.set_supply_check(false);
+    assert!(!config.is_supply_check_enabled());
+}
+
+#[test]
+fn test_heartbeat_interval_default_and_override() {
+    let config = Config::new();
+    if env::var("HEARTBEAT_INTERVAL").is_err() {
+        assert_eq!(config.get_heartbeat_interval_secs(), DEFAULT_HEARTBEAT_INTERVAL_SECS);
+    }
+
+    config.set_heartbeat_interval_secs(5);
+    assert_eq!(config.get_heartbeat_interval_secs(), 5);
+    config.set_heartbeat_interval_secs(0);
+    assert_eq!(config.get_heartbeat_interval_secs(), DEFAULT_HEARTBEAT_INTERVAL_SECS);
+}
+
+#[test]
+fn test_max_connections_default_and_override() {
+    let config = Config::new();
+    if env::var("MAX_CONNECTIONS").is_err() {
+        assert_eq!(config.get_max_connections(), DEFAULT_MAX_CONNECTIONS);
+    }
+
+    config.set_max_connections(4);
+    assert_eq!(config.get_max_connections(), 4);
+}
+
+#[test]
+fn test_set_node_addr_round_trip() {
+    let config = Config::new();
+
+    assert_eq!(config.set_node_addr("127.0.0.1:4100".to_string()), Ok(()));
+    assert_eq!(config.get_node_addr(), "127.0.0.1:4100");
+
+    assert_eq!(config.set_node_addr("[::1]:4101".to_string()), Ok(()));
+    assert_eq!(config.get_node_addr(), "[::1]:4101");
+}
+
+#[test]
+fn test_set_node_addr_rejects_unparseable_address() {
+    let config = Config::new();
+    config.set_node_addr("127.0.0.1:4102".to_string()).unwrap();
+
+    for addr in ["not-an-address", "127.0.0.1", "127.0.0.1:99999", ""] {
+        assert!(config.set_node_addr(addr.to_string()).is_err(), "{addr} should be rejected");
+    }
+    // A rejected address leaves the previous one in place
+    assert_eq!(config.get_node_addr(), "127.0.0.1:4102");
+}
+
+#[test]
+fn test_seed_nodes_parse_and_filter() {
+    let config = Config::new();
+
+    config.set_seed_nodes(" 127.0.0.1:3001, ,127.0.0.1:3002,127.0.0.1:3001,, 127.0.0.1:3003 ");
+    assert_eq!(
+        config.get_seed_nodes(),
+        vec!["127.0.0.1:3001", "127.0.0.1:3002", "127.0.0.1:3003"]
+    );
+
+    // Nothing usable falls back to the central node
+    config.set_seed_nodes(" , ,");
+    assert_eq!(config.get_seed_nodes(), vec![CENTRAL_NODE]);
+    if env::var("SEED_NODES").is_err() {
+        assert_eq!(Config::new().get_seed_nodes(), vec![CENTRAL_NODE]);
+    }
+}
+
+#[test]
+fn test_mempool_expiry_default_and_override() {
+    let config = Config::new();
+    if env::var("MEMPOOL_EXPIRY").is_err() {
+        assert_eq!(config.get_mempool_expiry_secs(), DEFAULT_MEMPOOL_EXPIRY_SECS);
+    }
+
+    config.set_mempool_expiry_secs(90);
+    assert_eq!(config.get_mempool_expiry_secs(), 90);
+}
+
+#[test]
+fn test_coinbase_maturity_default_and_override() {
+    let config = Config::new();
+    if env::var("COINBASE_MATURITY").is_err() {
+        assert_eq!(config.get_coinbase_maturity(), DEFAULT_COINBASE_MATURITY);
+    }
+
+    config.set_coinbase_maturity(100);
+    assert_eq!(config.get_coinbase_maturity(), 100);
+}
+
+#[test]
+fn test_block_limits_default_and_override() {
+    let config = Config::new();
+    if env::var("MAX_BLOCK_SIZE").is_err() {
+        assert_eq!(config.get_max_block_size(), DEFAULT_MAX_BLOCK_SIZE);
+    }
+    if env::var("MAX_BLOCK_TXS").is_err() {
+        assert_eq!(config.get_max_block_txs(), DEFAULT_MAX_BLOCK_TXS);
+    }
+
+    config.set_max_block_size(4096);
+    config.set_max_block_txs(10);
+    assert_eq!(config.get_max_block_size(), 4096);
+    assert_eq!(config.get_max_block_txs(), 10);
+}
+
+#[test]
+fn test_target_bits_default_and_override() {
+    let config = Config::new();
+    if env::var("BLOCKCHAIN_TARGET_BITS").is_err() {
+        assert_eq!(config.get_target_bits(), DEFAULT_TARGET_BITS);
+    }
+    assert_eq!(DEFAULT_TARGET_BITS, rust_blockchain::TARGET_BITS);
+
+    config.set_target_bits(4);
+    assert_eq!(config.get_target_bits(), 4);
+
+    // Difficulties no target can be built from fall back to the default
+    config.set_target_bits(0);
+    assert_eq!(config.get_target_bits(), DEFAULT_TARGET_BITS);
+    config.set_target_bits(256);
+    assert_eq!(config.get_target_bits(), DEFAULT_TARGET_BITS);
+}
+
+#[test]
+fn test_address_version_default_and_override() {
+    let config = Config::new();
+    if env::var("ADDRESS_VERSION").is_err() {
+        assert_eq!(config.get_address_version(), DEFAULT_ADDRESS_VERSION);
+    }
+    assert_eq!(DEFAULT_ADDRESS_VERSION, rust_blockchain::wallet::VERSION);
+
+    config.set_address_version(0x6f);
+    assert_eq!(config.get_address_version(), 0x6f);
+}
+
+#[test]
+fn test_network_magic_default_and_override() {
+    let config = Config::new();
+    if env::var("NETWORK_MAGIC").is_err() {
+        assert_eq!(config.get_network_magic(), DEFAULT_NETWORK_MAGIC);
+    }
+
+    config.set_network_magic(0x0b11_0907);
+    assert_eq!(config.get_network_magic(), 0x0b11_0907);
+}
+
+#[test]
+fn test_max_package_size_default_and_ceiling() {
+    let config = Config::new();
+    if env::var("MAX_PACKAGE_SIZE").is_err() {
+        assert_eq!(config.get_max_package_size(), DEFAULT_MAX_PACKAGE_SIZE);
+    }
+
+    config.set_max_package_size(1024);
+    assert_eq!(config.get_max_package_size(), 1024);
+    config.set_max_package_size(0);
+    assert_eq!(config.get_max_package_size(), DEFAULT_MAX_PACKAGE_SIZE);
+    config.set_max_package_size(MAX_PACKAGE_SIZE_CEILING * 2);
+    assert_eq!(config.get_max_package_size(), MAX_PACKAGE_SIZE_CEILING);
+}
+
+#[test]
+fn test_read_timeout_default_and_override() {
+    let config = Config::new();
+    if env::var("READ_TIMEOUT").is_err() {
+        assert_eq!(config.get_read_timeout_secs(), DEFAULT_READ_TIMEOUT_SECS);
+    }
+
+    config.set_read_timeout_secs(5);
+    assert_eq!(config.get_read_timeout_secs(), 5);
+    config.set_read_timeout_secs(0);
+    assert_eq!(config.get_read_timeout_secs(), DEFAULT_READ_TIMEOUT_SECS);
+}
+
+#[test]
+fn test_dust_threshold_default_and_override() {
+    let config = Config::new();
+    if env::var("DUST_THRESHOLD").is_err() {
+        assert_eq!(config.get_dust_threshold(), DEFAULT_DUST_THRESHOLD);
+    }
+
+    config.set_dust_threshold(546);
+    assert_eq!(config.get_dust_threshold(), 546);
+}
+
+#[test]
+fn test_checkpoints_parse_and_skip_malformed_pairs() {
+    let config = Config::new();
+    config.set_checkpoints(" 10:abc, oops, 20: def ,x:ghi,30:, 10:jkl");
+
+    let checkpoints = config.get_checkpoints();
+    assert_eq!(checkpoints.len(), 2);
+    assert_eq!(checkpoints[&10], "jkl");
+    assert_eq!(checkpoints[&20], "def");
+
+    if env::var("CHECKPOINTS").is_err() {
+        assert!(Config::new().get_checkpoints().is_empty());
+    }
+}
+
+#[test]
+fn test_compressed_pubkeys_toggle() {
+    let config = Config::new();
+    if env::var("COMPRESSED_PUBKEYS").is_err() {
+        assert!(!config.is_compressed_pubkeys_enabled());
+    }
+
+    config.set_compressed_pubkeys(true);
+    assert!(config.is_compressed_pubkeys_enabled());
+
+    config.set_compressed_pubkeys(false);
+    assert!(!config.is_compressed_pubkeys_enabled());
+}
+
+#[test]
+fn test_connect_attempts_default_and_override() {
+    let config = Config::new();
+    if env::var("CONNECT_ATTEMPTS").is_err() {
+        assert_eq!(config.get_connect_attempts(), DEFAULT_CONNECT_ATTEMPTS);
+    }
+
+    config.set_connect_attempts(5);
+    assert_eq!(config.get_connect_attempts(), 5);
+    config.set_connect_attempts(0);
+    assert_eq!(config.get_connect_attempts(), DEFAULT_CONNECT_ATTEMPTS);
+}
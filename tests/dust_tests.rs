@@ -0,0 +1,72 @@
+//! Dust threshold tests. They raise the dust threshold on the global config, which every
+//! transaction builder and memory pool in the process honors, so they run as their own
+//! test binary.
+
+use rust_blockchain::{
+    Blockchain, ChainParams, MemoryPool, SUBSIDY, Transaction, UTXOSet, config::GLOBAL_CONFIG,
+    server::accept_tx, wallet::Wallet,
+};
+use tempfile::TempDir;
+
+// Shared by every test, since tests run in parallel against the one global config
+const DUST_THRESHOLD: i32 = 5;
+
+// A chain whose genesis pays `funder`, kept in a temporary directory
+fn funded_chain(funder: &Wallet) -> (Blockchain, UTXOSet, TempDir) {
+    GLOBAL_CONFIG.set_dust_threshold(DUST_THRESHOLD);
+    let temp_dir = TempDir::new().unwrap();
+    let db = sled::open(temp_dir.path()).unwrap();
+    let blockchain =
+        Blockchain::create_blockchain_in(db, &ChainParams::new(&funder.get_address()));
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    utxo_set.reindex();
+    (blockchain, utxo_set, temp_dir)
+}
+
+#[test]
+fn test_builder_rejects_amount_below_dust_threshold() {
+    let funder = Wallet::from_seed(b"dust-builder-funder");
+    let recipient = Wallet::from_seed(b"dust-builder-recipient").get_address();
+    let (_blockchain, utxo_set, _temp_dir) = funded_chain(&funder);
+
+    let below = [(recipient.clone(), DUST_THRESHOLD - 1)];
+    let err = Transaction::new_utxo_transaction_from_wallet(&funder, &below, &utxo_set)
+        .err()
+        .unwrap();
+    assert!(err.contains("dust threshold"));
+
+    let at = [(recipient, DUST_THRESHOLD)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&funder, &at, &utxo_set).unwrap();
+    assert_eq!(tx.get_vout()[0].get_value(), DUST_THRESHOLD);
+}
+
+#[test]
+fn test_builder_leaves_dust_change_as_fee() {
+    let funder = Wallet::from_seed(b"dust-change-funder");
+    let recipient = Wallet::from_seed(b"dust-change-recipient").get_address();
+    let (_blockchain, utxo_set, _temp_dir) = funded_chain(&funder);
+
+    // Paying all but a dust-sized remainder creates no change output
+    let outputs = [(recipient, SUBSIDY - DUST_THRESHOLD + 1)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&funder, &outputs, &utxo_set).unwrap();
+    assert_eq!(tx.get_vout().len(), 1);
+}
+
+#[test]
+fn test_accept_tx_rejects_output_below_dust_threshold() {
+    let funder = Wallet::from_seed(b"dust-accept-funder");
+    let recipient = Wallet::from_seed(b"dust-accept-recipient").get_address();
+    let (blockchain, utxo_set, _temp_dir) = funded_chain(&funder);
+
+    let outputs = [(recipient, DUST_THRESHOLD)];
+    let at_threshold =
+        Transaction::new_utxo_transaction_from_wallet(&funder, &outputs, &utxo_set).unwrap();
+    let mut below_threshold = at_threshold.clone();
+    below_threshold.vout[0].value = DUST_THRESHOLD - 1;
+
+    let memory_pool = MemoryPool::new();
+    assert!(!accept_tx(&blockchain, &memory_pool, below_threshold));
+    assert!(memory_pool.is_empty());
+    assert!(accept_tx(&blockchain, &memory_pool, at_threshold));
+    assert_eq!(memory_pool.len(), 1);
+}
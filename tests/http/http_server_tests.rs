@@ -0,0 +1,136 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use data_encoding::HEXLOWER;
+use rust_blockchain::{Blockchain, http::HttpServer, wallet::Wallet};
+
+use crate::test_helpers::{create_funded_blockchain, setup_temp_test_db};
+
+// Starts an HttpServer for `blockchain` on a free local port and returns its address
+fn start_server(blockchain: Blockchain) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    HttpServer::new(blockchain).spawn(listener);
+    addr
+}
+
+// Sends a raw request and returns the status code and body of the response
+fn request(addr: &str, request_line: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "{request_line}\r\nHost: {addr}\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    assert!(head.contains("Content-Type: application/json"));
+    (status, body.to_string())
+}
+
+fn get(addr: &str, path: &str) -> (u16, String) {
+    request(addr, format!("GET {path} HTTP/1.1").as_str())
+}
+
+#[test]
+fn test_height_endpoint() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"http-height");
+    let blockchain = create_funded_blockchain(&db, wallet.get_address().as_str());
+    let tip_hash = blockchain.get_tip_hash();
+    let addr = start_server(blockchain);
+
+    let (status, body) = get(&addr, "/height");
+    assert_eq!(status, 200);
+    assert_eq!(body, format!("{{\"height\":0,\"tip\":\"{tip_hash}\"}}"));
+}
+
+#[test]
+fn test_height_endpoint_on_empty_chain() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let addr = start_server(Blockchain::new_with_empty_tip(db));
+
+    let (status, body) = get(&addr, "/height");
+    assert_eq!(status, 200);
+    assert_eq!(body, "{\"height\":null,\"tip\":null}");
+}
+
+#[test]
+fn test_block_endpoint() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"http-block");
+    let blockchain = create_funded_blockchain(&db, wallet.get_address().as_str());
+    let genesis = blockchain.get_block(blockchain.get_tip_hash().as_bytes()).unwrap();
+    let coinbase_txid = HEXLOWER.encode(genesis.get_transactions()[0].get_id());
+    let addr = start_server(blockchain);
+
+    let (status, body) = get(&addr, format!("/block/{}", genesis.get_hash()).as_str());
+    assert_eq!(status, 200);
+    assert!(body.starts_with(format!("{{\"hash\":\"{}\"", genesis.get_hash()).as_str()));
+    assert!(body.contains("\"height\":0"));
+    assert!(body.contains(format!("\"transactions\":[\"{coinbase_txid}\"]").as_str()));
+
+    let (status, body) = get(&addr, "/block/unknown_hash");
+    assert_eq!(status, 404);
+    assert!(body.contains("\"error\""));
+}
+
+#[test]
+fn test_tx_endpoint() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"http-tx");
+    let blockchain = create_funded_blockchain(&db, wallet.get_address().as_str());
+    let genesis = blockchain.get_block(blockchain.get_tip_hash().as_bytes()).unwrap();
+    let txid_hex = HEXLOWER.encode(genesis.get_transactions()[0].get_id());
+    let addr = start_server(blockchain);
+
+    let (status, body) = get(&addr, format!("/tx/{txid_hex}").as_str());
+    assert_eq!(status, 200);
+    assert!(body.starts_with(format!("{{\"txid\":\"{txid_hex}\",\"coinbase\":true").as_str()));
+    assert!(body.contains(
+        format!("\"vout\":[{{\"value\":10,\"address\":\"{}\"}}]", wallet.get_address()).as_str()
+    ));
+
+    assert_eq!(get(&addr, "/tx/00ff").0, 404);
+    assert_eq!(get(&addr, "/tx/not-hex").0, 400);
+}
+
+#[test]
+fn test_balance_endpoint() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"http-balance");
+    let other = Wallet::from_seed(b"http-balance-other").get_address();
+    let blockchain = create_funded_blockchain(&db, wallet.get_address().as_str());
+    let addr = start_server(blockchain);
+
+    let (status, body) = get(&addr, format!("/balance/{}", wallet.get_address()).as_str());
+    assert_eq!(status, 200);
+    assert_eq!(body, format!("{{\"address\":\"{}\",\"balance\":10}}", wallet.get_address()));
+
+    let (status, body) = get(&addr, format!("/balance/{other}").as_str());
+    assert_eq!(status, 200);
+    assert!(body.ends_with("\"balance\":0}"));
+
+    assert_eq!(get(&addr, "/balance/not-an-address").0, 400);
+}
+
+#[test]
+fn test_unknown_paths_and_methods() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let addr = start_server(Blockchain::new_with_empty_tip(db));
+
+    assert_eq!(get(&addr, "/").0, 404);
+    assert_eq!(get(&addr, "/blocks/all").0, 404);
+    assert_eq!(request(&addr, "POST /height HTTP/1.1").0, 405);
+    assert_eq!(request(&addr, "garbage").0, 400);
+}
+
+#[test]
+fn test_json_string_escapes_special_characters() {
+    use rust_blockchain::http::http_utils::json_string;
+
+    assert_eq!(json_string("plain"), "\"plain\"");
+    assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+    assert_eq!(json_string("line\nbreak\u{1}"), "\"line\\nbreak\\u0001\"");
+}
@@ -0,0 +1 @@
+mod http_server_tests;
@@ -1,9 +1,11 @@
 use crate::test_helpers::{
-    create_coinbase_transaction, create_multiple_test_transactions, create_spending_transaction,
-    create_test_transaction,
+    create_coinbase_transaction, create_funded_blockchain, create_independent_test_transaction,
+    create_multiple_test_transactions, create_spending_transaction, create_test_transaction,
+    setup_temp_test_db,
 };
 use data_encoding::HEXLOWER;
-use rust_blockchain::{MemoryPool, TXInput};
+use rust_blockchain::wallet::Wallet;
+use rust_blockchain::{MemoryPool, ReplaceResult, TXInput, Transaction, UTXOSet, LOCK_TIME_THRESHOLD};
 use std::sync::Arc;
 use std::thread;
 
@@ -454,7 +456,7 @@ fn test_memory_pool_concurrent_add() {
     for i in 0..10 {
         let pool_clone = Arc::clone(&pool);
         let handle = thread::spawn(move || {
-            let tx = create_test_transaction(vec![i as u8; 4]);
+            let tx = create_independent_test_transaction(vec![i as u8; 4]);
             pool_clone.add(tx);
         });
         handles.push(handle);
@@ -474,7 +476,7 @@ fn test_memory_pool_concurrent_read_write() {
 
     // Add some initial transactions
     for i in 0..5 {
-        let tx = create_test_transaction(vec![i as u8; 4]);
+        let tx = create_independent_test_transaction(vec![i as u8; 4]);
         pool.add(tx);
     }
 
@@ -499,7 +501,7 @@ fn test_memory_pool_concurrent_read_write() {
     for i in 10..13 {
         let pool_clone = Arc::clone(&pool);
         let handle = thread::spawn(move || {
-            let tx = create_test_transaction(vec![i as u8; 4]);
+            let tx = create_independent_test_transaction(vec![i as u8; 4]);
             pool_clone.add(tx);
         });
         handles.push(handle);
@@ -539,7 +541,7 @@ fn test_memory_pool_concurrent_add_remove() {
     for i in 20..25 {
         let pool_clone = Arc::clone(&pool);
         let handle = thread::spawn(move || {
-            let tx = create_test_transaction(vec![i as u8; 4]);
+            let tx = create_independent_test_transaction(vec![i as u8; 4]);
             pool_clone.add(tx);
         });
         handles.push(handle);
@@ -675,3 +677,357 @@ fn test_memory_pool_hex_encoding_consistency() {
     assert!(retrieved_tx.is_some());
     assert_eq!(retrieved_tx.unwrap().get_id(), tx.get_id());
 }
+
+// =============================================================================
+// MEMORY POOL DOUBLE-SPEND TESTS
+// =============================================================================
+
+#[test]
+fn test_memory_pool_conflicts_with_detects_shared_input() {
+    let pool = MemoryPool::new();
+    let tx1 = create_spending_transaction(vec![(vec![9, 9, 9], 0)], vec![(100, vec![1, 1, 1])]);
+    let mut tx2 = tx1.clone();
+    tx2.vout[0].value = 90;
+    let tx2 = Transaction::new(vec![2, 2, 2, 2], tx2.vin, tx2.vout);
+
+    assert!(pool.conflicts_with(&tx2).is_none());
+    pool.add(tx1.clone());
+
+    assert_eq!(pool.conflicts_with(&tx2), Some(HEXLOWER.encode(tx1.get_id())));
+    // A transaction never conflicts with itself
+    assert!(pool.conflicts_with(&tx1).is_none());
+}
+
+#[test]
+fn test_memory_pool_conflicts_with_different_vout() {
+    let pool = MemoryPool::new();
+    let tx1 = create_spending_transaction(vec![(vec![9, 9, 9], 0)], vec![(100, vec![1, 1, 1])]);
+    let tx2 = Transaction::new(
+        vec![2, 2, 2, 2],
+        vec![TXInput {
+            vout: 1,
+            ..tx1.vin[0].clone()
+        }],
+        tx1.vout.clone(),
+    );
+
+    pool.add(tx1);
+    assert!(pool.conflicts_with(&tx2).is_none());
+    assert!(pool.add(tx2));
+    assert_eq!(pool.len(), 2);
+}
+
+#[test]
+fn test_memory_pool_add_rejects_conflicting_transaction() {
+    let pool = MemoryPool::new();
+    let tx1 = create_spending_transaction(vec![(vec![9, 9, 9], 0)], vec![(100, vec![1, 1, 1])]);
+    let tx2 = Transaction::new(vec![2, 2, 2, 2], tx1.vin.clone(), tx1.vout.clone());
+
+    assert!(pool.add(tx1.clone()));
+    assert!(!pool.add(tx2.clone()));
+
+    assert_eq!(pool.len(), 1);
+    assert!(pool.contains(&HEXLOWER.encode(tx1.get_id())));
+    assert!(!pool.contains(&HEXLOWER.encode(tx2.get_id())));
+}
+
+#[test]
+fn test_memory_pool_add_with_fee_keeps_higher_fee() {
+    let pool = MemoryPool::new();
+    let low = create_spending_transaction(vec![(vec![9, 9, 9], 0)], vec![(100, vec![1, 1, 1])]);
+    let high = Transaction::new(vec![2, 2, 2, 2], low.vin.clone(), low.vout.clone());
+    let low_hex = HEXLOWER.encode(low.get_id());
+    let high_hex = HEXLOWER.encode(high.get_id());
+
+    assert!(pool.add_with_fee(low.clone(), 1));
+    assert!(pool.add_with_fee(high.clone(), 5));
+
+    assert_eq!(pool.len(), 1);
+    assert!(!pool.contains(&low_hex));
+    assert!(pool.contains(&high_hex));
+    assert_eq!(pool.get_fee(&high_hex), Some(5));
+
+    // The evicted lower-fee transaction cannot come back
+    assert!(!pool.add_with_fee(low, 1));
+    assert_eq!(pool.len(), 1);
+    assert!(pool.contains(&high_hex));
+}
+
+#[test]
+fn test_memory_pool_coinbase_transactions_never_conflict() {
+    let pool = MemoryPool::new();
+    let cb1 = create_coinbase_transaction(50, vec![1, 1, 1]);
+    let cb2 = Transaction::new(vec![7, 7, 7, 7], cb1.vin.clone(), cb1.vout.clone());
+
+    assert!(pool.add(cb1));
+    assert!(pool.add(cb2));
+    assert_eq!(pool.len(), 2);
+}
+
+// =============================================================================
+// EXPIRY TESTS
+// =============================================================================
+
+#[test]
+fn test_memory_pool_evict_expired_keeps_fresh_transactions() {
+    let pool = MemoryPool::new();
+    let stale = create_independent_test_transaction(vec![8, 0, 8, 0]);
+    let fresh = create_independent_test_transaction(vec![8, 1, 8, 1]);
+    let stale_hex = HEXLOWER.encode(stale.get_id());
+    let fresh_hex = HEXLOWER.encode(fresh.get_id());
+    assert!(pool.add(stale));
+    assert!(pool.add(fresh));
+
+    // Backdate one transaction two hours
+    let added_at = pool.get_added_at(&stale_hex).unwrap();
+    pool.set_added_at_for_test(&stale_hex, added_at - 7200);
+
+    assert_eq!(pool.evict_expired(3600), 1);
+    assert!(!pool.contains(&stale_hex));
+    assert!(pool.contains(&fresh_hex));
+    assert_eq!(pool.evict_expired(3600), 0);
+    assert!(pool.get_added_at(&stale_hex).is_none());
+}
+
+// =============================================================================
+// PERSISTENCE TESTS
+// =============================================================================
+
+/// A UTXO set holding a single spendable output, the funded genesis coinbase output
+fn funded_utxo_set(db: &sled::Db, seed: &[u8]) -> (UTXOSet, Vec<u8>) {
+    let blockchain = create_funded_blockchain(db, &Wallet::from_seed(seed).get_address());
+    let genesis = blockchain.get_block(blockchain.get_tip_hash().as_bytes()).unwrap();
+    let coinbase_id = genesis.get_transactions()[0].get_id().to_vec();
+    (UTXOSet::new(blockchain), coinbase_id)
+}
+
+#[test]
+fn test_memory_pool_save_and_load_round_trip() {
+    let (db, temp_dir) = setup_temp_test_db();
+    let (utxo_set, coinbase_id) = funded_utxo_set(&db, b"mempool-round-trip");
+    let path = temp_dir.path().join("mempool.dat");
+
+    let pool = MemoryPool::new();
+    let spend = create_spending_transaction(vec![(coinbase_id, 0)], vec![(10, vec![1])]);
+    let coinbase = create_coinbase_transaction(50, vec![2]);
+    let spend_hex = HEXLOWER.encode(spend.get_id());
+    let coinbase_hex = HEXLOWER.encode(coinbase.get_id());
+    assert!(pool.add_with_fee(spend, 3));
+    assert!(pool.add(coinbase));
+    pool.save_to_disk(&path).unwrap();
+
+    let restored = MemoryPool::new();
+    assert_eq!(restored.load_from_disk(&path, &utxo_set).unwrap(), 2);
+    assert_eq!(restored.len(), 2);
+    assert_eq!(restored.get_fee(&spend_hex), Some(3));
+    assert_eq!(restored.get_fee(&coinbase_hex), Some(0));
+    assert_eq!(restored.get_added_at(&spend_hex), pool.get_added_at(&spend_hex));
+
+    // No saved pool yet is not an error
+    let missing = temp_dir.path().join("missing.dat");
+    assert_eq!(MemoryPool::new().load_from_disk(&missing, &utxo_set).unwrap(), 0);
+}
+
+#[test]
+fn test_memory_pool_load_drops_stale_transactions() {
+    let (db, temp_dir) = setup_temp_test_db();
+    let (utxo_set, coinbase_id) = funded_utxo_set(&db, b"mempool-stale");
+    let path = temp_dir.path().join("mempool.dat");
+
+    let pool = MemoryPool::new();
+    let fresh = create_spending_transaction(vec![(coinbase_id.clone(), 0)], vec![(10, vec![1])]);
+    // Spends an output that was never in, or has left, the UTXO set
+    let stale = create_spending_transaction(
+        vec![(vec![9, 9, 9, 9], 0)],
+        vec![(5, vec![2]), (5, vec![3])],
+    );
+    let beyond_outputs = create_spending_transaction(
+        vec![(coinbase_id, 1)],
+        vec![(1, vec![4]), (1, vec![5]), (1, vec![6])],
+    );
+    let fresh_hex = HEXLOWER.encode(fresh.get_id());
+    assert!(pool.add(fresh));
+    assert!(pool.add(stale));
+    assert!(pool.add(beyond_outputs));
+    pool.save_to_disk(&path).unwrap();
+
+    let restored = MemoryPool::new();
+    assert_eq!(restored.load_from_disk(&path, &utxo_set).unwrap(), 1);
+    assert_eq!(restored.len(), 1);
+    assert!(restored.contains(&fresh_hex));
+}
+
+#[test]
+fn test_memory_pool_load_rejects_corrupt_file() {
+    let (db, temp_dir) = setup_temp_test_db();
+    let (utxo_set, _) = funded_utxo_set(&db, b"mempool-corrupt");
+    let path = temp_dir.path().join("mempool.dat");
+    std::fs::write(&path, [0xff, 0xff, 0xff]).unwrap();
+
+    let pool = MemoryPool::new();
+    assert!(pool.load_from_disk(&path, &utxo_set).is_err());
+    assert!(pool.is_empty());
+}
+
+// =============================================================================
+// REPLACE-BY-FEE TESTS
+// =============================================================================
+
+#[test]
+fn test_try_replace_adds_transaction_without_conflict() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (utxo_set, coinbase_id) = funded_utxo_set(&db, b"rbf-no-conflict");
+
+    let pool = MemoryPool::new();
+    let tx = create_spending_transaction(vec![(coinbase_id, 0)], vec![(6, vec![1]), (2, vec![2])]);
+    let txid_hex = HEXLOWER.encode(tx.get_id());
+    assert_eq!(pool.try_replace(tx, &utxo_set), ReplaceResult::Added);
+    // The fee is what the inputs leave over the outputs
+    assert_eq!(pool.get_fee(&txid_hex), Some(2));
+}
+
+#[test]
+fn test_try_replace_replaces_lower_fee_conflict() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (utxo_set, coinbase_id) = funded_utxo_set(&db, b"rbf-replace");
+
+    let pool = MemoryPool::new();
+    let original = create_spending_transaction(vec![(coinbase_id.clone(), 0)], vec![(9, vec![1])]);
+    let original_hex = HEXLOWER.encode(original.get_id());
+    assert_eq!(pool.try_replace(original, &utxo_set), ReplaceResult::Added);
+
+    let bumped = create_spending_transaction(
+        vec![(coinbase_id, 0)],
+        vec![(5, vec![1]), (2, vec![2])],
+    );
+    let bumped_hex = HEXLOWER.encode(bumped.get_id());
+    assert_eq!(
+        pool.try_replace(bumped, &utxo_set),
+        ReplaceResult::Replaced(vec![original_hex.clone()])
+    );
+    assert!(!pool.contains(&original_hex));
+    assert_eq!(pool.get_fee(&bumped_hex), Some(3));
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn test_try_replace_rejects_lower_or_equal_fee() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (utxo_set, coinbase_id) = funded_utxo_set(&db, b"rbf-reject");
+
+    let pool = MemoryPool::new();
+    let original = create_spending_transaction(vec![(coinbase_id.clone(), 0)], vec![(7, vec![1])]);
+    let original_hex = HEXLOWER.encode(original.get_id());
+    assert_eq!(pool.try_replace(original, &utxo_set), ReplaceResult::Added);
+
+    let lower = create_spending_transaction(
+        vec![(coinbase_id.clone(), 0)],
+        vec![(4, vec![1]), (4, vec![2])],
+    );
+    assert_eq!(pool.try_replace(lower, &utxo_set), ReplaceResult::Rejected);
+    let equal = create_spending_transaction(
+        vec![(coinbase_id, 0)],
+        vec![(5, vec![1]), (1, vec![2]), (1, vec![3])],
+    );
+    assert_eq!(pool.try_replace(equal, &utxo_set), ReplaceResult::Rejected);
+
+    assert_eq!(pool.len(), 1);
+    assert_eq!(pool.get_fee(&original_hex), Some(3));
+}
+
+#[test]
+fn test_try_replace_rejects_invalid_transactions() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let (utxo_set, coinbase_id) = funded_utxo_set(&db, b"rbf-invalid");
+
+    let pool = MemoryPool::new();
+    let unknown_input = create_spending_transaction(vec![(vec![4, 4, 4, 4], 0)], vec![(1, vec![1])]);
+    assert!(matches!(pool.try_replace(unknown_input, &utxo_set), ReplaceResult::Invalid(_)));
+    let overspend = create_spending_transaction(
+        vec![(coinbase_id, 0)],
+        vec![(8, vec![1]), (8, vec![2])],
+    );
+    assert!(matches!(pool.try_replace(overspend, &utxo_set), ReplaceResult::Invalid(_)));
+    assert!(pool.is_empty());
+}
+
+// =============================================================================
+// LOCK TIME TESTS
+// =============================================================================
+
+#[test]
+fn test_get_final_excludes_height_locked_transaction_until_its_height() {
+    let pool = MemoryPool::new();
+    let unlocked = create_independent_test_transaction(vec![71]);
+    let mut locked = create_independent_test_transaction(vec![72]);
+    locked.set_lock_time(10);
+    let locked_hex = HEXLOWER.encode(locked.get_id());
+    pool.add(unlocked.clone());
+    pool.add(locked);
+
+    let before: Vec<Vec<u8>> = pool.get_final(9, 0).iter().map(|tx| tx.get_id().to_vec()).collect();
+    assert_eq!(before, vec![unlocked.get_id().to_vec()]);
+    // The locked transaction stays pooled while it waits
+    assert!(pool.contains(&locked_hex));
+
+    let after = pool.get_final(10, 0);
+    assert_eq!(after.len(), 2);
+    assert!(after.iter().any(|tx| HEXLOWER.encode(tx.get_id()) == locked_hex));
+}
+
+#[test]
+fn test_get_final_excludes_time_locked_transaction_until_its_time() {
+    let pool = MemoryPool::new();
+    let unlock_at = LOCK_TIME_THRESHOLD + 5_000;
+    let mut locked = create_independent_test_transaction(vec![73]);
+    locked.set_lock_time(unlock_at);
+    pool.add(locked);
+
+    assert!(pool.get_final(usize::MAX, unlock_at - 1).is_empty());
+    assert_eq!(pool.get_final(0, unlock_at).len(), 1);
+}
+
+// =============================================================================
+// LISTING TESTS
+// =============================================================================
+
+#[test]
+fn test_get_txids_with_fees_lists_every_pooled_transaction_by_fee() {
+    let pool = MemoryPool::new();
+    assert!(pool.get_txids_with_fees().is_empty());
+
+    let txs = create_multiple_test_transactions(3);
+    pool.add_with_fee(txs[0].clone(), 5);
+    pool.add_with_fee(txs[1].clone(), 20);
+    pool.add(txs[2].clone());
+
+    let listed = pool.get_txids_with_fees();
+    assert_eq!(
+        listed,
+        vec![
+            (HEXLOWER.encode(txs[1].get_id()), 20),
+            (HEXLOWER.encode(txs[0].get_id()), 5),
+            (HEXLOWER.encode(txs[2].get_id()), 0),
+        ]
+    );
+    assert_eq!(listed.len(), pool.len());
+}
+
+#[test]
+fn test_get_txids_with_fees_after_reloading_saved_pool() {
+    let (db, temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"list-mempool");
+    let blockchain = create_funded_blockchain(&db, &wallet.get_address());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let pool = MemoryPool::new();
+    let coinbase = create_coinbase_transaction(10, vec![1, 2, 3]);
+    pool.add_with_fee(coinbase.clone(), 3);
+    let path = temp_dir.path().join("mempool.dat");
+    pool.save_to_disk(&path).unwrap();
+
+    // What listmempool prints comes from the pool the node saved on shutdown
+    let reloaded = MemoryPool::new();
+    assert_eq!(reloaded.load_from_disk(&path, &utxo_set).unwrap(), 1);
+    assert_eq!(reloaded.get_txids_with_fees(), vec![(HEXLOWER.encode(coinbase.get_id()), 3)]);
+}
@@ -0,0 +1,88 @@
+//! Transaction relay tests. They send transactions through the node's Tx handler, which
+//! adds them to the global memory pool, so they run as their own test binary instead of
+//! alongside the tests in `mod.rs` that count its entries.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use bincode::config::standard;
+use data_encoding::HEXLOWER;
+use rust_blockchain::{
+    Blockchain, ChainParams, SUBSIDY, TXInput, TXOutput, Transaction, UTXOSet,
+    server::{GLOBAL_MEMORY_POOL, Package, serve},
+    wallet::Wallet,
+};
+use tempfile::TempDir;
+
+/// Serve `tx` to the node's Tx handler as the peer `addr_from`, waiting until it is done
+fn serve_tx(blockchain: &Blockchain, addr_from: &str, tx: &Transaction) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_chain = blockchain.clone();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _ = serve(server_chain, stream);
+    });
+    let pkg = Package::Tx {
+        addr_from: addr_from.to_string(),
+        transaction: tx.serialize(),
+    };
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(&bincode::encode_to_vec(&pkg, standard()).unwrap())
+        .unwrap();
+    drop(stream);
+    handle.join().unwrap();
+}
+
+/// A signed transaction spending the genesis coinbase, paying `value` to `to`
+fn spend_genesis(wallet: &Wallet, blockchain: &Blockchain, value: i32, to: &str) -> Transaction {
+    let coinbase = blockchain
+        .get_block_by_height(0)
+        .unwrap()
+        .get_transactions()[0]
+        .clone();
+    let mut tx = Transaction::new(
+        vec![],
+        vec![TXInput::new(coinbase.get_id(), 0)],
+        vec![TXOutput::new(value, to)],
+    );
+    let prev_txs = HashMap::from([(HEXLOWER.encode(coinbase.get_id()), coinbase)]);
+    tx.sign_with(wallet.get_pkcs8(), &prev_txs).unwrap();
+    tx
+}
+
+#[test]
+fn test_relayed_higher_fee_double_spend_replaces_original() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = sled::open(temp_dir.path().join("chain")).unwrap();
+    let wallet = Wallet::from_seed(b"relay-replace-wallet");
+    let recipient = Wallet::from_seed(b"relay-replace-recipient").get_address();
+    let blockchain = Blockchain::create_blockchain_in(db, &ChainParams::new(&wallet.get_address()));
+    UTXOSet::new(blockchain.clone()).reindex();
+    let peer = "127.0.0.1:3999";
+
+    let original = spend_genesis(&wallet, &blockchain, SUBSIDY - 1, &recipient);
+    let original_id = HEXLOWER.encode(original.get_id());
+    serve_tx(&blockchain, peer, &original);
+    assert!(GLOBAL_MEMORY_POOL.contains(&original_id));
+    assert_eq!(GLOBAL_MEMORY_POOL.get_fee(&original_id), Some(1));
+
+    // The same fee does not displace it
+    let same_fee = spend_genesis(&wallet, &blockchain, SUBSIDY - 1, &wallet.get_address());
+    serve_tx(&blockchain, peer, &same_fee);
+    assert!(GLOBAL_MEMORY_POOL.contains(&original_id));
+    assert!(!GLOBAL_MEMORY_POOL.contains(&HEXLOWER.encode(same_fee.get_id())));
+
+    let replacement = spend_genesis(&wallet, &blockchain, SUBSIDY - 3, &recipient);
+    let replacement_id = HEXLOWER.encode(replacement.get_id());
+    serve_tx(&blockchain, peer, &replacement);
+    assert!(GLOBAL_MEMORY_POOL.contains(&replacement_id));
+    assert_eq!(GLOBAL_MEMORY_POOL.get_fee(&replacement_id), Some(3));
+    assert!(!GLOBAL_MEMORY_POOL.contains(&original_id));
+    assert_eq!(GLOBAL_MEMORY_POOL.len(), 1);
+}
@@ -1,7 +1,10 @@
 mod block;
 mod blockchain;
+mod command;
 mod common;
 mod config;
+#[cfg(feature = "http")]
+mod http;
 mod memory_pool;
 mod nodes;
 mod proof_of_work;
@@ -1,6 +1,8 @@
-use rust_blockchain::nodes::Nodes;
+use rust_blockchain::nodes::{BAN_SCORE_THRESHOLD, Nodes};
+use rust_blockchain::util::current_timestamp;
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 
 #[cfg(test)]
 #[test]
@@ -444,3 +446,134 @@ fn test_special_characters() {
         assert!(nodes.node_is_known(addr));
     }
 }
+
+// =============================================================================
+// BANNING AND SCORING TESTS
+// =============================================================================
+
+#[test]
+fn test_ban_excludes_node() {
+    let nodes = Nodes::new();
+    let addr = "127.0.0.1:9100";
+    nodes.add_node(addr.to_string());
+    assert!(nodes.node_is_known(addr));
+
+    nodes.ban(addr, Duration::from_secs(60));
+    assert!(nodes.is_banned(addr));
+    assert!(!nodes.node_is_known(addr));
+    assert!(nodes.get_nodes().iter().all(|node| node.get_addr() != addr));
+
+    // A banned peer cannot be re-added while the ban lasts
+    nodes.add_node(addr.to_string());
+    assert!(!nodes.node_is_known(addr));
+    assert_eq!(nodes.len(), 0);
+}
+
+#[test]
+fn test_ban_expires() {
+    let nodes = Nodes::new();
+    let addr = "127.0.0.1:9101";
+    nodes.ban(addr, Duration::from_millis(50));
+    assert!(nodes.is_banned(addr));
+
+    thread::sleep(Duration::from_millis(100));
+    assert!(!nodes.is_banned(addr));
+
+    nodes.add_node(addr.to_string());
+    assert!(nodes.node_is_known(addr));
+}
+
+#[test]
+fn test_misbehaving_bans_at_threshold() {
+    let nodes = Nodes::new();
+    let addr = "127.0.0.1:9102";
+    nodes.add_node(addr.to_string());
+
+    assert!(!nodes.misbehaving(addr, BAN_SCORE_THRESHOLD / 2));
+    assert_eq!(nodes.get_misbehavior_score(addr), BAN_SCORE_THRESHOLD / 2);
+    assert!(nodes.node_is_known(addr));
+
+    assert!(nodes.misbehaving(addr, BAN_SCORE_THRESHOLD / 2));
+    assert!(nodes.is_banned(addr));
+    assert!(!nodes.node_is_known(addr));
+    // The score resets once the ban is applied
+    assert_eq!(nodes.get_misbehavior_score(addr), 0);
+}
+
+#[test]
+fn test_misbehaving_scores_are_per_peer() {
+    let nodes = Nodes::new();
+    nodes.misbehaving("127.0.0.1:9103", 10);
+    nodes.misbehaving("127.0.0.1:9103", 15);
+    nodes.misbehaving("127.0.0.1:9104", 5);
+
+    assert_eq!(nodes.get_misbehavior_score("127.0.0.1:9103"), 25);
+    assert_eq!(nodes.get_misbehavior_score("127.0.0.1:9104"), 5);
+    assert_eq!(nodes.get_misbehavior_score("127.0.0.1:9105"), 0);
+    assert!(!nodes.is_banned("127.0.0.1:9103"));
+}
+
+// =============================================================================
+// LAST-SEEN AND PRUNING TESTS
+// =============================================================================
+
+#[test]
+fn test_add_node_sets_last_seen_to_now() {
+    let nodes = Nodes::new();
+    let before = current_timestamp();
+    nodes.add_node("127.0.0.1:9200".to_string());
+
+    let last_seen = nodes.get_last_seen("127.0.0.1:9200").unwrap();
+    assert!(last_seen >= before && last_seen <= current_timestamp());
+    assert!(nodes.get_last_seen("127.0.0.1:9201").is_none());
+}
+
+#[test]
+fn test_mark_seen_refreshes_last_seen() {
+    let nodes = Nodes::new();
+    let addr = "127.0.0.1:9202";
+    nodes.add_node(addr.to_string());
+    nodes.set_last_seen_for_test(addr, current_timestamp() - 1_000);
+
+    nodes.mark_seen(addr);
+    assert!(nodes.get_last_seen(addr).unwrap() >= current_timestamp() - 1);
+
+    // Unknown peers are not added by mark_seen
+    nodes.mark_seen("127.0.0.1:9203");
+    assert!(!nodes.node_is_known("127.0.0.1:9203"));
+}
+
+#[test]
+fn test_get_active_nodes_excludes_stale() {
+    let nodes = Nodes::new();
+    nodes.add_node("127.0.0.1:9204".to_string());
+    nodes.add_node("127.0.0.1:9205".to_string());
+    nodes.set_last_seen_for_test("127.0.0.1:9205", current_timestamp() - 600);
+
+    let active = nodes.get_active_nodes(300);
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].get_addr(), "127.0.0.1:9204");
+    // get_active_nodes does not remove anything
+    assert_eq!(nodes.len(), 2);
+}
+
+#[test]
+fn test_prune_stale_removes_only_stale_nodes() {
+    let nodes = Nodes::new();
+    let now = current_timestamp();
+    nodes.add_node("127.0.0.1:9206".to_string());
+    nodes.add_node("127.0.0.1:9207".to_string());
+    nodes.add_node("127.0.0.1:9208".to_string());
+    nodes.set_last_seen_for_test("127.0.0.1:9206", now - 1_000);
+    nodes.set_last_seen_for_test("127.0.0.1:9207", now - 10);
+    nodes.set_last_seen_for_test("127.0.0.1:9208", now - 5_000);
+
+    assert_eq!(nodes.prune_stale(60), 2);
+    assert_eq!(nodes.len(), 1);
+    assert!(nodes.node_is_known("127.0.0.1:9207"));
+    assert!(!nodes.node_is_known("127.0.0.1:9206"));
+    assert!(!nodes.node_is_known("127.0.0.1:9208"));
+
+    assert_eq!(nodes.prune_stale(60), 0);
+    assert_eq!(nodes.len(), 1);
+}
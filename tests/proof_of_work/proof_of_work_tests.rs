@@ -1,5 +1,7 @@
-use rust_blockchain::{ProofOfWork, Block};
+use rust_blockchain::{ProofOfWork, PowAlgorithm, Block, BlockHeader, MAX_RETARGET_STEP, MAX_TARGET_BITS, MIN_TARGET_BITS, PROGRESS_INTERVAL};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use num_bigint::BigInt;
+use rust_blockchain::util::{sha256_digest, sha256d};
 use crate::test_helpers::*;
 
 const TARGET_BITS: usize = 24;
@@ -8,7 +10,7 @@ const MAX_NONCE: i64 = i64::MAX;
 #[test]
 fn test_new_proof_of_work() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block.clone());
+    let pow = ProofOfWork::new_proof_of_work_default(block.clone());
 
     // Verify that the target is calculated correctly
     let expected_target = BigInt::from(1) << (256 - TARGET_BITS);
@@ -23,7 +25,7 @@ fn test_new_proof_of_work() {
 #[test]
 fn test_new_proof_of_work_target_calculation() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block);
+    let pow = ProofOfWork::new_proof_of_work_default(block);
 
     // Test that target is a valid BigInt and has the expected magnitude
     let target = pow.get_target().as_bigint();
@@ -34,10 +36,23 @@ fn test_new_proof_of_work_target_calculation() {
     assert_eq!(target, &expected_target);
 }
 
+#[test]
+fn test_new_proof_of_work_clamps_out_of_range_target_bits() {
+    let block = create_default_test_block();
+
+    let too_hard = ProofOfWork::new_proof_of_work(block.clone(), 300);
+    assert_eq!(too_hard.get_target_bits(), MAX_TARGET_BITS);
+    assert_eq!(*too_hard.get_target().as_bigint(), BigInt::from(1) << (256 - MAX_TARGET_BITS));
+
+    let too_easy = ProofOfWork::new_proof_of_work(block, 0);
+    assert_eq!(too_easy.get_target_bits(), MIN_TARGET_BITS);
+    assert_eq!(*too_easy.get_target().as_bigint(), BigInt::from(1) << (256 - MIN_TARGET_BITS));
+}
+
 #[test]
 fn test_prepare_data() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block.clone());
+    let pow = ProofOfWork::new_proof_of_work_default(block.clone());
     let nonce = 12345;
 
     let data = pow.prepare_data(nonce);
@@ -56,7 +71,7 @@ fn test_prepare_data() {
 #[test]
 fn test_prepare_data_different_nonces() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block);
+    let pow = ProofOfWork::new_proof_of_work_default(block);
 
     let data1 = pow.prepare_data(100);
     let data2 = pow.prepare_data(200);
@@ -74,7 +89,7 @@ fn test_prepare_data_different_nonces() {
 #[test]
 fn test_prepare_data_deterministic() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block);
+    let pow = ProofOfWork::new_proof_of_work_default(block);
     let nonce = 42;
 
     let data1 = pow.prepare_data(nonce);
@@ -87,7 +102,7 @@ fn test_prepare_data_deterministic() {
 #[test]
 fn test_validate_with_default_nonce() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block);
+    let pow = ProofOfWork::new_proof_of_work_default(block);
 
     // With TARGET_BITS = 24, it's extremely unlikely that the default nonce (0) will produce a valid hash
     let is_valid = pow.validate();
@@ -100,7 +115,7 @@ fn test_validate_with_default_nonce() {
 #[test]
 fn test_validate_deterministic() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block);
+    let pow = ProofOfWork::new_proof_of_work_default(block);
 
     let result1 = pow.validate();
     let result2 = pow.validate();
@@ -119,8 +134,8 @@ fn test_validate_different_blocks() {
     let block2 =
         Block::new_block_without_proof_of_work("hash2".to_string(), &[transaction2], 2);
 
-    let pow1 = ProofOfWork::new_proof_of_work(block1);
-    let pow2 = ProofOfWork::new_proof_of_work(block2);
+    let pow1 = ProofOfWork::new_proof_of_work_default(block1);
+    let pow2 = ProofOfWork::new_proof_of_work_default(block2);
 
     let result1 = pow1.validate();
     let result2 = pow2.validate();
@@ -132,11 +147,11 @@ fn test_validate_different_blocks() {
     assert_eq!(result2, pow2.validate()); // Deterministic for same input
 }
 
-#[ignore] // This test can take a very long time to complete
+#[ignore] // This test can take a very long time to complete; run with BLOCKCHAIN_TARGET_BITS=8
 #[test]
 fn test_run_mining_process() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block.clone());
+    let pow = ProofOfWork::new_proof_of_work_default(block.clone());
 
     let (nonce, hash) = pow.run();
 
@@ -155,7 +170,7 @@ fn test_run_mining_process() {
 #[test]
 fn test_proof_of_work_clone() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block);
+    let pow = ProofOfWork::new_proof_of_work_default(block);
 
     let cloned_pow = pow.clone();
 
@@ -171,7 +186,7 @@ fn test_proof_of_work_clone() {
 #[test]
 fn test_proof_of_work_serialization() {
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block);
+    let pow = ProofOfWork::new_proof_of_work_default(block);
 
     // Test that the contained block can be serialized
     let block_serialized = pow.get_block().serialize();
@@ -199,8 +214,168 @@ fn test_max_nonce_constant() {
     
     // Test that nonce range is reasonable
     let block = create_default_test_block();
-    let pow = ProofOfWork::new_proof_of_work(block);
+    let pow = ProofOfWork::new_proof_of_work_default(block);
     
     // Prepare data with max nonce should not panic
     let _data = pow.prepare_data(MAX_NONCE);
 } 
+
+#[test]
+fn test_new_proof_of_work_explicit_target() {
+    let block = create_default_test_block();
+    let pow = ProofOfWork::new_proof_of_work(block, 8);
+
+    assert_eq!(pow.get_target_bits(), 8);
+    assert_eq!(*pow.get_target().as_bigint(), BigInt::from(1) << 248);
+
+    // The difficulty is committed into the hashed data
+    let default_pow = ProofOfWork::new_proof_of_work_default(create_default_test_block());
+    assert_eq!(default_pow.get_target_bits(), TARGET_BITS);
+    assert_ne!(pow.prepare_data(0)[..], default_pow.prepare_data(0)[..]);
+}
+
+#[test]
+fn test_run_with_easy_target_validates() {
+    let block = create_default_test_block();
+    let pow = ProofOfWork::new_proof_of_work(block, 8);
+    let (nonce, hash) = pow.run();
+
+    // The found nonce must reproduce the returned hash and satisfy the target
+    let data = pow.prepare_data(nonce);
+    let digest = rust_blockchain::util::sha256_digest(&data);
+    assert_eq!(data_encoding::HEXLOWER.encode(&digest), hash);
+    let hash_int = BigInt::from_bytes_be(num_bigint::Sign::Plus, &digest);
+    assert!(hash_int < *pow.get_target().as_bigint());
+}
+
+#[test]
+fn test_calculate_target_bits_rises_when_blocks_too_fast() {
+    // Blocks every 2 seconds against a 10 second target
+    let timestamps = [100, 102, 104, 106, 108];
+    let new_bits = ProofOfWork::calculate_target_bits(16, &timestamps, 10);
+    assert!(new_bits > 16);
+    assert!(new_bits <= 16 + MAX_RETARGET_STEP);
+}
+
+#[test]
+fn test_calculate_target_bits_falls_when_blocks_too_slow() {
+    // Blocks every 40 seconds against a 10 second target
+    let timestamps = [0, 40, 80, 120];
+    let new_bits = ProofOfWork::calculate_target_bits(16, &timestamps, 10);
+    assert_eq!(new_bits, 14);
+}
+
+#[test]
+fn test_calculate_target_bits_on_schedule_and_edge_cases() {
+    assert_eq!(ProofOfWork::calculate_target_bits(16, &[0, 10, 20, 30], 10), 16);
+    // Not enough history to retarget
+    assert_eq!(ProofOfWork::calculate_target_bits(16, &[0], 10), 16);
+    assert_eq!(ProofOfWork::calculate_target_bits(16, &[], 10), 16);
+    // Clamped to the configured bounds and step size
+    assert_eq!(ProofOfWork::calculate_target_bits(MIN_TARGET_BITS, &[0, 1000], 10), MIN_TARGET_BITS);
+    assert_eq!(ProofOfWork::calculate_target_bits(200, &[0, 0, 0], 10), 200 + MAX_RETARGET_STEP);
+    assert_eq!(ProofOfWork::calculate_target_bits(MAX_TARGET_BITS, &[0, 0], 10), MAX_TARGET_BITS);
+}
+
+#[test]
+fn test_run_with_control_cancels_and_returns_none() {
+    // An unreachable target guarantees mining only ends through cancellation
+    let pow = ProofOfWork::new_proof_of_work(create_default_test_block(), MAX_TARGET_BITS);
+    let should_stop = AtomicBool::new(false);
+    let reports = AtomicI64::new(0);
+    let last_nonce = AtomicI64::new(0);
+
+    let result = pow.run_with_control(&should_stop, |nonce| {
+        last_nonce.store(nonce, Ordering::SeqCst);
+        if reports.fetch_add(1, Ordering::SeqCst) + 1 == 3 {
+            should_stop.store(true, Ordering::SeqCst);
+        }
+    });
+
+    assert!(result.is_none());
+    assert_eq!(reports.load(Ordering::SeqCst), 3);
+    assert_eq!(last_nonce.load(Ordering::SeqCst), 3 * PROGRESS_INTERVAL);
+}
+
+#[test]
+fn test_run_with_control_finds_same_nonce_as_run() {
+    let pow = ProofOfWork::new_proof_of_work(create_default_test_block(), 8);
+    let should_stop = AtomicBool::new(false);
+
+    let controlled = pow.run_with_control(&should_stop, |_| {});
+    assert_eq!(controlled, Some(pow.run()));
+}
+
+#[test]
+fn test_run_with_control_already_stopped() {
+    let pow = ProofOfWork::new_proof_of_work(create_default_test_block(), 8);
+    let should_stop = AtomicBool::new(true);
+
+    assert!(pow.run_with_control(&should_stop, |_| {}).is_none());
+}
+
+#[test]
+fn test_validate_header_matches_full_block() {
+    let mut block = create_default_test_block();
+    let (nonce, hash) = ProofOfWork::new_proof_of_work(block.clone(), 8).run();
+    block.set_nonce_for_test(nonce);
+    block.set_hash_for_test(&hash);
+    assert!(ProofOfWork::validate_header(&block.header(), 8));
+
+    // Valid and invalid nonces alike get the same verdict from the header as from the block
+    for nonce in 0..64 {
+        block.set_nonce_for_test(nonce);
+        let full = ProofOfWork::new_proof_of_work(block.clone(), 8).validate();
+        let header = BlockHeader::try_deserialize(&block.header_bytes()).unwrap();
+        assert_eq!(ProofOfWork::validate_header(&header, 8), full, "nonce {nonce}");
+    }
+}
+
+#[test]
+fn test_validate_header_depends_on_transactions() {
+    let mut block = create_default_test_block();
+    let (nonce, _) = ProofOfWork::new_proof_of_work(block.clone(), 12).run();
+    block.set_nonce_for_test(nonce);
+    assert!(ProofOfWork::validate_header(&block.header(), 12));
+
+    // Swapping the transactions changes the merkle root the nonce was mined against
+    let mut swapped = block.clone();
+    swapped.set_transactions_for_test(&[create_test_transaction(vec![9, 9, 9, 9])]);
+    assert_eq!(
+        ProofOfWork::validate_header(&swapped.header(), 12),
+        ProofOfWork::new_proof_of_work(swapped, 12).validate()
+    );
+}
+
+// =============================================================================
+// ALGORITHM TESTS
+// =============================================================================
+
+#[test]
+fn test_default_algorithm_hashes_as_before() {
+    let block = create_default_test_block();
+    let pow = ProofOfWork::new_proof_of_work(block.clone(), 8);
+    assert_eq!(pow.get_algorithm(), PowAlgorithm::Sha256);
+
+    // The mined hash is still the single SHA256 of the nonce's data
+    let (nonce, hash) = pow.run();
+    let expected = sha256_digest(&pow.prepare_data(nonce));
+    assert_eq!(hash, data_encoding::HEXLOWER.encode(&expected));
+
+    let explicit = ProofOfWork::new_proof_of_work_with_algorithm(block, 8, PowAlgorithm::Sha256);
+    assert_eq!(explicit.run(), (nonce, hash));
+}
+
+#[test]
+fn test_double_sha256_algorithm_mines_different_valid_blocks() {
+    let mut block = create_default_test_block();
+    let double = ProofOfWork::new_proof_of_work_with_algorithm(block.clone(), 8, PowAlgorithm::DoubleSha256);
+    let (nonce, hash) = double.run();
+
+    let data = double.prepare_data(nonce);
+    assert_eq!(hash, data_encoding::HEXLOWER.encode(&sha256d(&data)));
+    assert_ne!(hash, data_encoding::HEXLOWER.encode(&sha256_digest(&data)));
+
+    block.set_nonce_for_test(nonce);
+    assert!(ProofOfWork::new_proof_of_work_with_algorithm(block, 8, PowAlgorithm::DoubleSha256).validate());
+}
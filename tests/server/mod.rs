@@ -1,2 +1,5 @@
+mod orphan_pool_tests;
+mod rejection_log_tests;
 mod server_enums_tests;
-mod server_utils_tests; 
+mod server_impl_tests;
+mod server_utils_tests;
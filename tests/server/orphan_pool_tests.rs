@@ -0,0 +1,243 @@
+use data_encoding::HEXLOWER;
+use rust_blockchain::{
+    Block, Blockchain, GENESIS_PRE_BLOCK_HASH, SUBSIDY, Transaction, UTXOSet,
+    memory_pool::MemoryPool,
+    server::{OrphanPool, connect_block},
+    wallet::{Wallet, wallet_util::hash_pub_key},
+};
+
+use crate::test_helpers::{
+    TEST_TARGET_BITS, TestDatabase, create_coinbase_only_block, create_funded_blockchain, create_test_block,
+    create_test_genesis_block, create_test_transaction, mine_test_block_at_low_difficulty, setup_temp_test_db,
+};
+
+fn genesis_block(hash: &str) -> Block {
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test(hash);
+    genesis
+}
+
+// =============================================================================
+// ORPHAN POOL TESTS
+// =============================================================================
+
+#[test]
+fn test_orphan_pool_add_and_take_children() {
+    let pool = OrphanPool::new();
+    assert!(pool.is_empty());
+
+    let child = create_test_block("missing_parent".to_string(), 1);
+    assert!(pool.add(child.clone()));
+    assert!(!pool.add(child.clone()));
+    assert_eq!(pool.len(), 1);
+    assert!(pool.contains(child.get_hash()));
+
+    assert!(pool.take_children("other_parent").is_empty());
+    let children = pool.take_children("missing_parent");
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].get_hash(), child.get_hash());
+    assert!(pool.is_empty());
+    assert!(!pool.contains(child.get_hash()));
+}
+
+#[test]
+fn test_orphan_pool_groups_siblings_by_parent() {
+    let pool = OrphanPool::new();
+    let first = create_test_block("shared_parent".to_string(), 1);
+    let mut second = create_test_block("shared_parent".to_string(), 1);
+    second.set_hash_for_test("second_sibling");
+
+    pool.add(first);
+    pool.add(second);
+    assert_eq!(pool.len(), 2);
+    assert_eq!(pool.take_children("shared_parent").len(), 2);
+    assert!(pool.is_empty());
+}
+
+// =============================================================================
+// CONNECT BLOCK TESTS
+// =============================================================================
+
+#[test]
+fn test_connect_block_child_before_parent() {
+    let test_db = TestDatabase::new("connect_child_before_parent");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let orphans = OrphanPool::new();
+    let memory_pool = MemoryPool::new();
+
+    let genesis = genesis_block("orphan_genesis");
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, genesis.clone()));
+
+    let parent = mine_test_block_at_low_difficulty(create_coinbase_only_block(genesis.get_hash().to_string(), 1));
+    let child = mine_test_block_at_low_difficulty(create_coinbase_only_block(parent.get_hash().to_string(), 2));
+
+    assert!(!connect_block(&blockchain, &orphans, &memory_pool, child.clone()));
+    assert_eq!(orphans.len(), 1);
+    assert!(blockchain.get_block(child.get_hash().as_bytes()).is_none());
+    assert_eq!(blockchain.get_tip_hash(), genesis.get_hash());
+
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, parent.clone()));
+    assert!(orphans.is_empty());
+    assert!(blockchain.get_block(child.get_hash().as_bytes()).is_some());
+    assert_eq!(blockchain.get_tip_hash(), child.get_hash());
+    assert!(blockchain.verify_chain_with_target_bits(TEST_TARGET_BITS));
+}
+
+#[test]
+fn test_connect_block_connects_orphans_recursively() {
+    let test_db = TestDatabase::new("connect_orphans_recursively");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let orphans = OrphanPool::new();
+    let memory_pool = MemoryPool::new();
+
+    let genesis = genesis_block("recursive_genesis");
+    let block1 = create_coinbase_only_block(genesis.get_hash().to_string(), 1);
+    let block2 = create_coinbase_only_block(block1.get_hash().to_string(), 2);
+    let block3 = create_coinbase_only_block(block2.get_hash().to_string(), 3);
+
+    // Blocks arrive tip first, as during a sync, on a node with no chain yet
+    for block in [&block3, &block2, &block1] {
+        assert!(!connect_block(&blockchain, &orphans, &memory_pool, block.clone()));
+    }
+    assert_eq!(orphans.len(), 3);
+    assert!(blockchain.is_empty());
+
+    assert_eq!(genesis.get_pre_block_hash(), GENESIS_PRE_BLOCK_HASH);
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, genesis));
+    assert!(orphans.is_empty());
+    assert_eq!(blockchain.get_tip_hash(), block3.get_hash());
+    assert_eq!(blockchain.get_best_height(), 3);
+    assert_eq!(blockchain.get_hash_at_height(2).as_deref(), Some(block2.get_hash()));
+}
+
+#[test]
+fn test_connect_block_rejects_block_without_single_coinbase() {
+    let test_db = TestDatabase::new("connect_rejects_coinbase");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let orphans = OrphanPool::new();
+    let memory_pool = MemoryPool::new();
+    let genesis = genesis_block("coinbase_check_genesis");
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, genesis.clone()));
+
+    let mut no_coinbase = create_test_block(genesis.get_hash().to_string(), 1);
+    no_coinbase.set_transactions_for_test(&[no_coinbase.get_transactions()[1].clone()]);
+    let mut two_coinbases = create_test_block("unknown_parent".to_string(), 1);
+    let coinbase = two_coinbases.get_transactions()[0].clone();
+    two_coinbases.set_transactions_for_test(&[coinbase.clone(), coinbase]);
+
+    // Invalid blocks are neither stored nor buffered as orphans
+    for block in [no_coinbase, two_coinbases] {
+        assert!(!connect_block(&blockchain, &orphans, &memory_pool, block.clone()));
+        assert!(blockchain.get_block(block.get_hash().as_bytes()).is_none());
+    }
+    assert!(orphans.is_empty());
+    assert_eq!(blockchain.get_tip_hash(), genesis.get_hash());
+}
+
+#[test]
+fn test_connect_block_rejects_duplicate_transactions() {
+    let test_db = TestDatabase::new("connect_rejects_duplicates");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let orphans = OrphanPool::new();
+    let memory_pool = MemoryPool::new();
+    let genesis = genesis_block("duplicate_check_genesis");
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, genesis.clone()));
+    let block1 = create_coinbase_only_block(genesis.get_hash().to_string(), 1);
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, block1.clone()));
+
+    // The same transaction twice in one block
+    let mut doubled = create_test_block(block1.get_hash().to_string(), 2);
+    let tx = create_test_transaction(vec![8, 8, 8, 8]);
+    doubled.set_transactions_for_test(&[doubled.get_transactions()[0].clone(), tx.clone(), tx]);
+    // A transaction already confirmed in block1
+    let mut replay = create_test_block(block1.get_hash().to_string(), 2);
+    replay.set_transactions_for_test(&[block1.get_transactions()[0].clone()]);
+
+    for block in [doubled, replay] {
+        assert!(!connect_block(&blockchain, &orphans, &memory_pool, block.clone()));
+        assert!(blockchain.get_block(block.get_hash().as_bytes()).is_none());
+    }
+    assert_eq!(blockchain.get_tip_hash(), block1.get_hash());
+}
+
+#[test]
+fn test_connect_block_drops_orphan_replaying_confirmed_transaction() {
+    let test_db = TestDatabase::new("connect_drops_replaying_orphan");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let orphans = OrphanPool::new();
+    let memory_pool = MemoryPool::new();
+    let genesis = genesis_block("orphan_replay_genesis");
+    let block1 = create_coinbase_only_block(genesis.get_hash().to_string(), 1);
+    let mut replay = create_test_block(block1.get_hash().to_string(), 2);
+    replay.set_transactions_for_test(&[block1.get_transactions()[0].clone()]);
+
+    // The replay is only detectable once the block it repeats is connected
+    assert!(!connect_block(&blockchain, &orphans, &memory_pool, replay.clone()));
+    assert!(!connect_block(&blockchain, &orphans, &memory_pool, block1.clone()));
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, genesis));
+
+    assert!(orphans.is_empty());
+    assert_eq!(blockchain.get_tip_hash(), block1.get_hash());
+    assert!(blockchain.get_block(replay.get_hash().as_bytes()).is_none());
+}
+
+#[test]
+fn test_connect_block_moves_utxo_set_through_reorg() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"connect-reorg-funder");
+    let blockchain = create_funded_blockchain(&db, &wallet.get_address());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let orphans = OrphanPool::new();
+    let memory_pool = MemoryPool::new();
+    let funder_hash = hash_pub_key(wallet.get_public_key());
+    let recipient = Wallet::from_seed(b"connect-reorg-recipient");
+    let recipient_hash = hash_pub_key(recipient.get_public_key());
+
+    let spend =
+        Transaction::new_utxo_transaction_from_wallet(&wallet, &[(recipient.get_address(), 3)], &utxo_set).unwrap();
+    let genesis_hash = blockchain.get_tip_hash();
+    let mut main_block = create_coinbase_only_block(genesis_hash.clone(), 1);
+    // Its own coinbase and hash, so it differs from the fork block at the same height
+    let coinbase = Transaction::new_coinbase_tx(&Wallet::from_seed(b"connect-reorg-miner").get_address());
+    main_block.set_transactions_for_test(&[coinbase, spend.clone()]);
+    main_block.set_hash_for_test("connect_reorg_main");
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, main_block.clone()));
+    assert_eq!(utxo_set.get_balance(&funder_hash), SUBSIDY as i64 - 3);
+    assert_eq!(utxo_set.get_balance(&recipient_hash), 3);
+
+    // A side branch only moves the UTXO set once it overtakes the main chain
+    let fork1 = create_coinbase_only_block(genesis_hash, 1);
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, fork1.clone()));
+    assert_eq!(utxo_set.get_balance(&recipient_hash), 3);
+    let fork2 = create_coinbase_only_block(fork1.get_hash().to_string(), 2);
+    assert!(connect_block(&blockchain, &orphans, &memory_pool, fork2.clone()));
+
+    assert_eq!(blockchain.get_tip_hash(), fork2.get_hash());
+    assert_eq!(utxo_set.get_balance(&funder_hash), SUBSIDY as i64);
+    assert_eq!(utxo_set.get_balance(&recipient_hash), 0);
+    assert!(!utxo_set.is_spendable(main_block.get_transactions()[0].get_id(), 0));
+    assert!(utxo_set.is_spendable(fork2.get_transactions()[0].get_id(), 0));
+    // The spend the abandoned block confirmed is pending again
+    assert!(memory_pool.contains(&HEXLOWER.encode(spend.get_id())));
+}
+
+#[test]
+fn test_connect_block_rejects_badly_signed_transaction() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let wallet = Wallet::from_seed(b"connect-bad-signature");
+    let blockchain = create_funded_blockchain(&db, &wallet.get_address());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let orphans = OrphanPool::new();
+    let memory_pool = MemoryPool::new();
+    let genesis_hash = blockchain.get_tip_hash();
+
+    let mut spend = Transaction::new_utxo_transaction_from_wallet(&wallet, &[(wallet.get_address(), 3)], &utxo_set).unwrap();
+    spend.vin[0].signature[0] ^= 0xff;
+    let mut block = create_coinbase_only_block(genesis_hash.clone(), 1);
+    block.set_transactions_for_test(&[block.get_transactions()[0].clone(), spend]);
+
+    assert!(!connect_block(&blockchain, &orphans, &memory_pool, block.clone()));
+    assert!(blockchain.get_block(block.get_hash().as_bytes()).is_none());
+    assert_eq!(blockchain.get_tip_hash(), genesis_hash);
+    assert_eq!(utxo_set.get_balance(&hash_pub_key(wallet.get_public_key())), SUBSIDY as i64);
+}
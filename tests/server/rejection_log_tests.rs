@@ -0,0 +1,48 @@
+use rust_blockchain::server::{MAX_RECENT_REJECTIONS, RejectReason, RejectionLog};
+
+// =============================================================================
+// REJECTION LOG TESTS
+// =============================================================================
+
+#[test]
+fn test_rejection_log_records_oldest_first() {
+    let log = RejectionLog::new();
+    assert!(log.is_empty());
+
+    log.record("block_a", RejectReason::BadProofOfWork, "no work");
+    log.record("block_b", RejectReason::Duplicate, "replays a transaction");
+
+    assert_eq!(
+        log.recent(),
+        vec![
+            ("block_a".to_string(), RejectReason::BadProofOfWork),
+            ("block_b".to_string(), RejectReason::Duplicate),
+        ]
+    );
+}
+
+#[test]
+fn test_rejection_log_forgets_oldest_past_capacity() {
+    let log = RejectionLog::with_capacity(2);
+    log.record("block_a", RejectReason::BadParent, "skips a height");
+    log.record("block_b", RejectReason::InvalidTransaction, "no coinbase");
+    log.record("block_c", RejectReason::Duplicate, "repeats a transaction");
+
+    assert_eq!(log.len(), 2);
+    let hashes: Vec<String> = log.recent().into_iter().map(|(hash, _)| hash).collect();
+    assert_eq!(hashes, vec!["block_b", "block_c"]);
+}
+
+#[test]
+fn test_rejection_log_default_capacity() {
+    let log = RejectionLog::default();
+    for i in 0..MAX_RECENT_REJECTIONS + 5 {
+        log.record(
+            &format!("block_{i}"),
+            RejectReason::BadProofOfWork,
+            "no work",
+        );
+    }
+    assert_eq!(log.len(), MAX_RECENT_REJECTIONS);
+    assert_eq!(log.recent()[0].0, "block_5");
+}
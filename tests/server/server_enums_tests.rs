@@ -96,6 +96,7 @@ fn test_package_encode_decode_block() {
 fn test_package_encode_decode_get_blocks() {
     let package = Package::GetBlocks {
         addr_from: "localhost:3001".to_string(),
+        locator: vec!["tip_hash".to_string(), "genesis_hash".to_string()],
     };
     let config = config::standard();
     
@@ -106,8 +107,9 @@ fn test_package_encode_decode_get_blocks() {
     assert_eq!(encoded[0], 1);
     
     match decoded {
-        Package::GetBlocks { addr_from } => {
+        Package::GetBlocks { addr_from, locator } => {
             assert_eq!(addr_from, "localhost:3001");
+            assert_eq!(locator, vec!["tip_hash", "genesis_hash"]);
         }
         _ => panic!("Expected Package::GetBlocks, got {decoded:?}"),
     }
@@ -198,6 +200,7 @@ fn test_package_encode_decode_version() {
         addr_from: "localhost:3005".to_string(),
         version: 1,
         best_height: 42,
+        magic: 0x0b11_0907,
     };
     let config = config::standard();
     
@@ -208,21 +211,183 @@ fn test_package_encode_decode_version() {
     assert_eq!(encoded[0], 5);
     
     match decoded {
-        Package::Version { addr_from, version, best_height } => {
+        Package::Version { addr_from, version, best_height, magic } => {
             assert_eq!(addr_from, "localhost:3005");
             assert_eq!(version, 1);
             assert_eq!(best_height, 42);
+            assert_eq!(magic, 0x0b11_0907);
         }
         _ => panic!("Expected Package::Version, got {decoded:?}"),
     }
 }
 
+#[test]
+fn test_package_encode_decode_get_addr() {
+    let package = Package::GetAddr {
+        addr_from: "localhost:3006".to_string(),
+    };
+    let config = config::standard();
+
+    let encoded = encode_to_vec(&package, config).unwrap();
+    let (decoded, _): (Package, usize) = decode_from_slice(&encoded, config).unwrap();
+
+    // Package::GetAddr should have discriminant 6
+    assert_eq!(encoded[0], 6);
+
+    match decoded {
+        Package::GetAddr { addr_from } => assert_eq!(addr_from, "localhost:3006"),
+        _ => panic!("Expected Package::GetAddr, got {decoded:?}"),
+    }
+}
+
+#[test]
+fn test_package_encode_decode_addr() {
+    let package = Package::Addr {
+        addr_from: "localhost:3007".to_string(),
+        nodes: vec!["127.0.0.1:4001".to_string(), "127.0.0.1:4002".to_string()],
+    };
+    let config = config::standard();
+
+    let encoded = encode_to_vec(&package, config).unwrap();
+    let (decoded, _): (Package, usize) = decode_from_slice(&encoded, config).unwrap();
+
+    // Package::Addr should have discriminant 7
+    assert_eq!(encoded[0], 7);
+
+    match decoded {
+        Package::Addr { addr_from, nodes } => {
+            assert_eq!(addr_from, "localhost:3007");
+            assert_eq!(nodes, vec!["127.0.0.1:4001", "127.0.0.1:4002"]);
+        }
+        _ => panic!("Expected Package::Addr, got {decoded:?}"),
+    }
+}
+
+#[test]
+fn test_package_encode_decode_ping_pong() {
+    let config = config::standard();
+
+    let ping = encode_to_vec(&Package::Ping { nonce: 7 }, config).unwrap();
+    let pong = encode_to_vec(&Package::Pong { nonce: 7 }, config).unwrap();
+
+    // Package::Ping and Package::Pong should have discriminants 8 and 9
+    assert_eq!(ping[0], 8);
+    assert_eq!(pong[0], 9);
+
+    match decode_from_slice::<Package, _>(&ping, config).unwrap().0 {
+        Package::Ping { nonce } => assert_eq!(nonce, 7),
+        other => panic!("Expected Package::Ping, got {other:?}"),
+    }
+    match decode_from_slice::<Package, _>(&pong, config).unwrap().0 {
+        Package::Pong { nonce } => assert_eq!(nonce, 7),
+        other => panic!("Expected Package::Pong, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_package_encode_decode_headers() {
+    let config = config::standard();
+
+    let get_headers = encode_to_vec(
+        &Package::GetHeaders { addr_from: "localhost:3010".to_string(), from_height: 5 },
+        config,
+    )
+    .unwrap();
+    let headers = encode_to_vec(
+        &Package::Headers { addr_from: "localhost:3011".to_string(), headers: vec![vec![1, 2], vec![3]] },
+        config,
+    )
+    .unwrap();
+
+    // Package::GetHeaders and Package::Headers should have discriminants 10 and 11
+    assert_eq!(get_headers[0], 10);
+    assert_eq!(headers[0], 11);
+
+    match decode_from_slice::<Package, _>(&get_headers, config).unwrap().0 {
+        Package::GetHeaders { addr_from, from_height } => {
+            assert_eq!(addr_from, "localhost:3010");
+            assert_eq!(from_height, 5);
+        }
+        other => panic!("Expected Package::GetHeaders, got {other:?}"),
+    }
+    match decode_from_slice::<Package, _>(&headers, config).unwrap().0 {
+        Package::Headers { addr_from, headers } => {
+            assert_eq!(addr_from, "localhost:3011");
+            assert_eq!(headers, vec![vec![1, 2], vec![3]]);
+        }
+        other => panic!("Expected Package::Headers, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_package_encode_decode_block_header() {
+    let config = config::standard();
+
+    let get_block_header = encode_to_vec(
+        &Package::GetBlockHeader { addr_from: "localhost:3012".to_string(), hash: vec![4, 5] },
+        config,
+    )
+    .unwrap();
+    let block_header = encode_to_vec(
+        &Package::BlockHeader { addr_from: "localhost:3013".to_string(), hash: vec![4, 5], header: Some(vec![6]) },
+        config,
+    )
+    .unwrap();
+
+    // Package::GetBlockHeader and Package::BlockHeader should have discriminants 12 and 13
+    assert_eq!(get_block_header[0], 12);
+    assert_eq!(block_header[0], 13);
+
+    match decode_from_slice::<Package, _>(&get_block_header, config).unwrap().0 {
+        Package::GetBlockHeader { addr_from, hash } => {
+            assert_eq!(addr_from, "localhost:3012");
+            assert_eq!(hash, vec![4, 5]);
+        }
+        other => panic!("Expected Package::GetBlockHeader, got {other:?}"),
+    }
+    match decode_from_slice::<Package, _>(&block_header, config).unwrap().0 {
+        Package::BlockHeader { addr_from, hash, header } => {
+            assert_eq!(addr_from, "localhost:3013");
+            assert_eq!(hash, vec![4, 5]);
+            assert_eq!(header, Some(vec![6]));
+        }
+        other => panic!("Expected Package::BlockHeader, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_package_encode_decode_mempool() {
+    let config = config::standard();
+
+    let get_mempool = encode_to_vec(&Package::GetMempool { addr_from: "localhost:3014".to_string() }, config).unwrap();
+    let mempool_inv = encode_to_vec(
+        &Package::MempoolInv { addr_from: "localhost:3015".to_string(), txids: vec![vec![7, 8], vec![9]] },
+        config,
+    )
+    .unwrap();
+
+    // Package::GetMempool and Package::MempoolInv should have discriminants 14 and 15
+    assert_eq!(get_mempool[0], 14);
+    assert_eq!(mempool_inv[0], 15);
+
+    match decode_from_slice::<Package, _>(&get_mempool, config).unwrap().0 {
+        Package::GetMempool { addr_from } => assert_eq!(addr_from, "localhost:3014"),
+        other => panic!("Expected Package::GetMempool, got {other:?}"),
+    }
+    let decoded = decode_from_slice::<Package, _>(&mempool_inv, config).unwrap().0;
+    assert_eq!(decoded.get_addr_from(), Some("localhost:3015"));
+    match decoded {
+        Package::MempoolInv { txids, .. } => assert_eq!(txids, vec![vec![7, 8], vec![9]]),
+        other => panic!("Expected Package::MempoolInv, got {other:?}"),
+    }
+}
+
 #[test]
 fn test_package_decode_invalid_discriminant() {
     let config = config::standard();
     
-    // Test invalid discriminant (6 is not a valid Package variant)
-    let invalid_encoded = vec![6];
+    // Test invalid discriminant (16 is not a valid Package variant)
+    let invalid_encoded = vec![16];
     let result: Result<(Package, usize), _> = decode_from_slice(&invalid_encoded, config);
     
     assert!(result.is_err());
@@ -242,6 +407,7 @@ fn test_package_with_empty_data() {
         },
         Package::GetBlocks {
             addr_from: "".to_string(),
+            locator: vec![],
         },
         Package::GetData {
             addr_from: "".to_string(),
@@ -261,6 +427,7 @@ fn test_package_with_empty_data() {
             addr_from: "".to_string(),
             version: 0,
             best_height: 0,
+            magic: 0,
         },
     ];
     
@@ -276,8 +443,9 @@ fn test_package_with_empty_data() {
                 assert_eq!(a1, a2);
                 assert_eq!(b1, b2);
             }
-            (Package::GetBlocks { addr_from: a1 }, Package::GetBlocks { addr_from: a2 }) => {
+            (Package::GetBlocks { addr_from: a1, locator: l1 }, Package::GetBlocks { addr_from: a2, locator: l2 }) => {
                 assert_eq!(a1, a2);
+                assert_eq!(l1, l2);
             }
             (Package::GetData { addr_from: a1, op_type: o1, id: i1 }, Package::GetData { addr_from: a2, op_type: o2, id: i2 }) => {
                 assert_eq!(a1, a2);
@@ -299,10 +467,11 @@ fn test_package_with_empty_data() {
                 assert_eq!(a1, a2);
                 assert_eq!(t1, t2);
             }
-            (Package::Version { addr_from: a1, version: v1, best_height: h1 }, Package::Version { addr_from: a2, version: v2, best_height: h2 }) => {
+            (Package::Version { addr_from: a1, version: v1, best_height: h1, magic: m1 }, Package::Version { addr_from: a2, version: v2, best_height: h2, magic: m2 }) => {
                 assert_eq!(a1, a2);
                 assert_eq!(v1, v2);
                 assert_eq!(h1, h2);
+                assert_eq!(m1, m2);
             }
             _ => panic!("Package variant mismatch"),
         }
@@ -361,11 +530,11 @@ fn test_discriminant_values() {
     
     let packages = vec![
         (Package::Block { addr_from: "test".to_string(), block: vec![] }, 0u8),
-        (Package::GetBlocks { addr_from: "test".to_string() }, 1u8),
+        (Package::GetBlocks { addr_from: "test".to_string(), locator: vec![] }, 1u8),
         (Package::GetData { addr_from: "test".to_string(), op_type: OpType::Tx, id: vec![] }, 2u8),
         (Package::Inv { addr_from: "test".to_string(), op_type: OpType::Block, items: vec![] }, 3u8),
         (Package::Tx { addr_from: "test".to_string(), transaction: vec![] }, 4u8),
-        (Package::Version { addr_from: "test".to_string(), version: 1, best_height: 1 }, 5u8),
+        (Package::Version { addr_from: "test".to_string(), version: 1, best_height: 1, magic: 1 }, 5u8),
     ];
     
     for (package, expected_discriminant) in packages {
@@ -0,0 +1,464 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bincode::config::standard;
+use data_encoding::HEXLOWER;
+use rust_blockchain::{
+    BLOCKS_TREE, Block, Blockchain, MemoryPool, ProofOfWork, SCHEMA_VERSION, SCHEMA_VERSION_KEY,
+    TIP_BLOCK_HASH_KEY, UTXOSet,
+    config::{DEFAULT_MAX_CONNECTIONS, GLOBAL_CONFIG},
+    server::{GLOBAL_MEMORY_POOL, NODE_VERSION, OpType, Package, Server},
+};
+use tempfile::TempDir;
+
+use crate::test_helpers::{
+    captured_logs_containing, create_coinbase_only_block, create_test_block, create_test_genesis_block,
+    create_test_transaction, install_capture_logger, setup_temp_test_db,
+};
+
+fn create_test_chain() -> (Blockchain, TempDir) {
+    let (db, temp_dir) = setup_temp_test_db();
+    let genesis_block = Block::generate_genesis_block(&create_test_transaction(vec![0, 0, 0, 0]));
+    let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree
+        .insert(genesis_block.get_hash(), genesis_block.serialize())
+        .unwrap();
+    blocks_tree
+        .insert(TIP_BLOCK_HASH_KEY, genesis_block.get_hash())
+        .unwrap();
+    let blockchain = Blockchain::new_with_tip(db, genesis_block.get_hash().to_string());
+    (blockchain, temp_dir)
+}
+
+fn create_test_server() -> (Server, TempDir) {
+    let (blockchain, temp_dir) = create_test_chain();
+    (Server::new(blockchain), temp_dir)
+}
+
+// =============================================================================
+// HEARTBEAT TESTS
+// =============================================================================
+
+#[test]
+fn test_status_line_reports_node_state() {
+    let (server, _temp_dir) = create_test_server();
+    let status = server.status_line();
+
+    assert!(status.starts_with("Heartbeat:"));
+    assert!(status.contains("best_height = 0"));
+    assert!(status.contains("mempool = "));
+    assert!(status.contains("peers = "));
+    assert!(status.contains("tip_age = "));
+}
+
+#[test]
+fn test_status_line_with_missing_tip_block() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let server = Server::new(Blockchain::new_with_tip(db, String::from("missing")));
+
+    let status = server.status_line();
+    assert!(status.contains("best_height = unknown"));
+    assert!(status.contains("tip_age = unknown"));
+}
+
+#[test]
+fn test_heartbeat_logs_periodically_until_shutdown() {
+    install_capture_logger();
+    let (server, _temp_dir) = create_test_server();
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    let handle = server.spawn_heartbeat(Duration::from_millis(50), shutdown.clone());
+    thread::sleep(Duration::from_millis(400));
+    shutdown.store(true, Ordering::SeqCst);
+    handle.join().unwrap();
+
+    assert!(!captured_logs_containing("Heartbeat: best_height = 0").is_empty());
+}
+
+// =============================================================================
+// SYNC FROM PEER TESTS
+// =============================================================================
+
+// Kills the node process when the test ends, even on a failed assertion
+struct NodeProcess(Child);
+
+impl Drop for NodeProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn free_local_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+fn start_node(dir: &Path, addr: &str, sync_from: Option<&str>) -> NodeProcess {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rust-blockchain"));
+    command.arg("startnode");
+    if let Some(peer) = sync_from {
+        command.args(["--sync-from", peer]);
+    }
+    let child = command
+        .current_dir(dir)
+        .env("NODE_ADDRESS", addr)
+        .env("BLOCKCHAIN_TARGET_BITS", SYNC_TARGET_BITS.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    NodeProcess(child)
+}
+
+fn wait_for_listener(addr: &str) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while TcpStream::connect(addr).is_err() {
+        assert!(Instant::now() < deadline, "node at {addr} never started listening");
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+// Difficulty the synced chain is mined at, passed to the nodes so they accept its blocks
+const SYNC_TARGET_BITS: usize = 8;
+
+// Mines `block` at `SYNC_TARGET_BITS` so peers check its proof-of-work quickly
+fn mine_sync_block(mut block: Block) -> Block {
+    let (nonce, hash) = ProofOfWork::new_proof_of_work(block.clone(), SYNC_TARGET_BITS).run();
+    block.set_nonce_for_test(nonce);
+    block.set_hash_for_test(&hash);
+    block
+}
+
+// Writes a linked chain of `len` blocks into `dir/data`, stamped with the current schema
+// version, and returns their hashes, tip first
+fn seed_chain(dir: &Path, len: usize) -> Vec<Vec<u8>> {
+    let db = sled::open(dir.join("data")).unwrap();
+    let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
+
+    // Genesis blocks are created without mining, as create_blockchain_in does
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("seed_genesis_hash");
+    let mut blocks = vec![genesis];
+    for height in 1..len {
+        let pre_hash = blocks[height - 1].get_hash().to_string();
+        blocks.push(mine_sync_block(create_coinbase_only_block(pre_hash, height)));
+    }
+    for block in &blocks {
+        blocks_tree.insert(block.get_hash(), block.serialize()).unwrap();
+    }
+    blocks_tree
+        .insert(TIP_BLOCK_HASH_KEY, blocks[len - 1].get_hash())
+        .unwrap();
+    blocks_tree
+        .insert(SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_be_bytes())
+        .unwrap();
+    db.flush().unwrap();
+
+    blocks.iter().rev().map(|block| block.get_hash_bytes()).collect()
+}
+
+// Asks the node at `addr` for its block hashes, playing the part of a peer listening on `reply_to`
+fn query_block_hashes(addr: &str, reply_to: &TcpListener) -> Option<Vec<Vec<u8>>> {
+    let request = Package::GetBlocks {
+        addr_from: reply_to.local_addr().unwrap().to_string(),
+        locator: vec![],
+    };
+    let mut stream = TcpStream::connect(addr).ok()?;
+    stream
+        .write_all(&bincode::encode_to_vec(&request, standard()).unwrap())
+        .ok()?;
+    drop(stream);
+
+    let (reply, _) = reply_to.accept().ok()?;
+    reply.set_read_timeout(Some(Duration::from_secs(2))).ok()?;
+    match bincode::decode_from_std_read(&mut &reply, standard()).ok()? {
+        Package::Inv {
+            op_type: OpType::Block,
+            items,
+            ..
+        } => Some(items),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_fresh_node_syncs_full_chain_from_peer() {
+    let seeded_dir = TempDir::new().unwrap();
+    let fresh_dir = TempDir::new().unwrap();
+    let expected_hashes = seed_chain(seeded_dir.path(), 4);
+
+    let seeded_addr = free_local_addr();
+    let fresh_addr = free_local_addr();
+    let _seeded = start_node(seeded_dir.path(), &seeded_addr, None);
+    wait_for_listener(&seeded_addr);
+    let _fresh = start_node(fresh_dir.path(), &fresh_addr, Some(&seeded_addr));
+    wait_for_listener(&fresh_addr);
+
+    let reply_to = TcpListener::bind("127.0.0.1:0").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let mut synced_hashes = None;
+    while Instant::now() < deadline {
+        synced_hashes = query_block_hashes(&fresh_addr, &reply_to);
+        if synced_hashes.as_ref() == Some(&expected_hashes) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    // Same blocks from tip back to genesis means the fresh node ends on the seeded tip
+    assert_eq!(synced_hashes, Some(expected_hashes));
+}
+
+// Runs the node binary in `dir` with `args` and returns what it printed
+fn run_cli(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-blockchain"))
+        .args(args)
+        .current_dir(dir)
+        .env("BLOCKCHAIN_TARGET_BITS", SYNC_TARGET_BITS.to_string())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{args:?} failed");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_fresh_node_syncs_chain_created_by_create_blockchain() {
+    let seeded_dir = TempDir::new().unwrap();
+    let fresh_dir = TempDir::new().unwrap();
+    // An unmined genesis from createblockchain and one block mined on top of it
+    let created = run_cli(seeded_dir.path(), &["createwallet"]);
+    let address = created.trim().rsplit(' ').next().unwrap().to_string();
+    run_cli(seeded_dir.path(), &["createblockchain", &address]);
+    run_cli(seeded_dir.path(), &["send", &address, &address, "1", "1"]);
+
+    let seeded_addr = free_local_addr();
+    let fresh_addr = free_local_addr();
+    let _seeded = start_node(seeded_dir.path(), &seeded_addr, None);
+    wait_for_listener(&seeded_addr);
+    let reply_to = TcpListener::bind("127.0.0.1:0").unwrap();
+    let expected_hashes = query_block_hashes(&seeded_addr, &reply_to).unwrap();
+    assert_eq!(expected_hashes.len(), 2);
+    let _fresh = start_node(fresh_dir.path(), &fresh_addr, Some(&seeded_addr));
+    wait_for_listener(&fresh_addr);
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let mut synced_hashes = None;
+    while Instant::now() < deadline {
+        synced_hashes = query_block_hashes(&fresh_addr, &reply_to);
+        if synced_hashes.as_ref() == Some(&expected_hashes) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    assert_eq!(synced_hashes, Some(expected_hashes));
+}
+
+// =============================================================================
+// ORPHAN BLOCK TESTS
+// =============================================================================
+
+#[test]
+fn test_submit_block_buffers_child_until_parent_arrives() {
+    let (blockchain, _temp_dir) = create_test_chain();
+    let server = Server::new(blockchain.clone());
+    let orphans_before = server.orphan_count();
+
+    let parent = create_coinbase_only_block(blockchain.get_tip_hash(), 1);
+    let child = create_coinbase_only_block(parent.get_hash().to_string(), 2);
+
+    assert!(!server.submit_block(child.clone()));
+    assert_eq!(server.orphan_count(), orphans_before + 1);
+    assert_eq!(blockchain.get_best_height(), 0);
+
+    assert!(server.submit_block(parent));
+    assert_eq!(server.orphan_count(), orphans_before);
+    assert_eq!(blockchain.get_tip_hash(), child.get_hash());
+    assert_eq!(blockchain.get_best_height(), 2);
+}
+
+// =============================================================================
+// GRACEFUL SHUTDOWN TESTS
+// =============================================================================
+
+#[test]
+fn test_run_with_shutdown_returns_when_signaled() {
+    let (server, _temp_dir) = create_test_server();
+    let addr = free_local_addr();
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (done_tx, done_rx) = mpsc::channel();
+
+    let server_addr = addr.clone();
+    let server_shutdown = shutdown.clone();
+    thread::spawn(move || {
+        server.run_with_shutdown(&server_addr, server_shutdown);
+        let _ = done_tx.send(());
+    });
+    wait_for_listener(&addr);
+
+    // Leave one connection open so shutdown has a live handler to close and join
+    let mut client = TcpStream::connect(&addr).unwrap();
+    let version = Package::Version {
+        addr_from: "127.0.0.1:3001".to_string(),
+        version: NODE_VERSION,
+        best_height: 0,
+        magic: GLOBAL_CONFIG.get_network_magic(),
+    };
+    client
+        .write_all(&bincode::encode_to_vec(&version, standard()).unwrap())
+        .unwrap();
+    thread::sleep(Duration::from_millis(200));
+
+    shutdown.store(true, Ordering::SeqCst);
+    assert!(
+        done_rx.recv_timeout(Duration::from_secs(5)).is_ok(),
+        "run_with_shutdown did not return after the flag was set"
+    );
+    drop(client);
+
+    // The listener is closed once the server has returned
+    assert!(TcpStream::connect(&addr).is_err());
+}
+
+#[test]
+fn test_run_with_shutdown_saves_mempool_file() {
+    let (server, temp_dir) = create_test_server();
+    let mempool_file = temp_dir.path().join("mempool.dat");
+    let server = server.with_mempool_file(mempool_file.clone());
+    let addr = free_local_addr();
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    let server_addr = addr.clone();
+    let server_shutdown = shutdown.clone();
+    let handle = thread::spawn(move || server.run_with_shutdown(&server_addr, server_shutdown));
+    wait_for_listener(&addr);
+    shutdown.store(true, Ordering::SeqCst);
+    handle.join().unwrap();
+
+    // The pool written on shutdown loads back cleanly
+    assert!(mempool_file.exists());
+    let (blockchain, _chain_dir) = create_test_chain();
+    let restored = MemoryPool::new();
+    assert!(restored.load_from_disk(&mempool_file, &UTXOSet::new(blockchain)).is_ok());
+}
+
+// =============================================================================
+// CONNECTION LIMIT TESTS
+// =============================================================================
+
+fn wait_until(condition: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    condition()
+}
+
+fn connect_with_retry(addr: &str) -> TcpStream {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        match TcpStream::connect(addr) {
+            Ok(stream) => return stream,
+            Err(e) => assert!(Instant::now() < deadline, "could not connect to {addr}: {e}"),
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn test_connections_beyond_limit_are_rejected() {
+    let max_connections = 2;
+    GLOBAL_CONFIG.set_max_connections(max_connections);
+
+    let (server, _temp_dir) = create_test_server();
+    let server = Arc::new(server);
+    let addr = free_local_addr();
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    let running = server.clone();
+    let server_addr = addr.clone();
+    let server_shutdown = shutdown.clone();
+    let handle = thread::spawn(move || running.run_with_shutdown(&server_addr, server_shutdown));
+
+    // Idle connections keep their handler threads busy. No probe connection is made first,
+    // so every connection the server sees is one of these.
+    let mut clients = vec![];
+    for _ in 0..max_connections + 2 {
+        let client = connect_with_retry(&addr);
+        client
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .unwrap();
+        clients.push(client);
+    }
+    assert!(wait_until(|| server.active_connections() == max_connections));
+
+    // Connections over the cap are closed straight away; the rest stay open
+    let mut closed = 0;
+    for client in &mut clients {
+        let mut buf = [0u8; 1];
+        if let Ok(0) = client.read(&mut buf) {
+            closed += 1;
+        }
+    }
+    assert_eq!(closed, 2);
+    assert_eq!(server.active_connections(), max_connections);
+
+    drop(clients);
+    assert!(wait_until(|| server.active_connections() == 0));
+
+    shutdown.store(true, Ordering::SeqCst);
+    handle.join().unwrap();
+    GLOBAL_CONFIG.set_max_connections(DEFAULT_MAX_CONNECTIONS);
+}
+
+// =============================================================================
+// INVENTORY FILTER TESTS
+// =============================================================================
+
+#[test]
+fn test_filter_unknown_items_skips_known_blocks() {
+    let (blockchain, _temp_dir) = create_test_chain();
+    let known = create_test_block(String::new(), 1);
+    blockchain.add_block(&known);
+    let server = Server::new(blockchain);
+
+    let unknown_a = b"unknown_block_a".to_vec();
+    let unknown_b = b"unknown_block_b".to_vec();
+    let items = vec![unknown_a.clone(), known.get_hash_bytes(), unknown_b.clone()];
+    assert_eq!(
+        server.filter_unknown_items(OpType::Block, &items),
+        vec![unknown_a, unknown_b]
+    );
+    assert!(server.filter_unknown_items(OpType::Block, &[known.get_hash_bytes()]).is_empty());
+}
+
+#[test]
+fn test_filter_unknown_items_skips_pooled_and_mined_transactions() {
+    let (blockchain, _temp_dir) = create_test_chain();
+    let genesis = blockchain.get_block(blockchain.get_tip_hash().as_bytes()).unwrap();
+    let mined = genesis.get_transactions()[0].clone();
+    let pooled = create_test_transaction(vec![0x51, 0x52, 0x53, 0x54]);
+    GLOBAL_MEMORY_POOL.add(pooled.clone());
+    let server = Server::new(blockchain);
+
+    let unknown = vec![0x61, 0x62, 0x63, 0x64];
+    let items = vec![pooled.get_id().to_vec(), unknown.clone(), mined.get_id().to_vec()];
+    assert_eq!(server.filter_unknown_items(OpType::Tx, &items), vec![unknown]);
+
+    GLOBAL_MEMORY_POOL.remove(&HEXLOWER.encode(pooled.get_id()));
+}
@@ -9,10 +9,11 @@ use std::{
 use bincode::config::standard;
 use data_encoding::HEXLOWER;
 use rust_blockchain::{
-    config::GLOBAL_CONFIG, memory_pool::{BlockInTransit, MemoryPool}, nodes::Nodes, server::{serve, OpType, Package, CENTRAL_NODE, GLOBAL_BLOCKS_IN_TRANSIT, GLOBAL_MEMORY_POOL, GLOBAL_NODES, NODE_VERSION, TRANSACTION_THRESHOLD}, Block, Blockchain, Transaction
+    config::{GLOBAL_CONFIG, MAX_PACKAGE_SIZE_CEILING}, memory_pool::{BlockInTransit, MemoryPool}, nodes::{Nodes, BAN_SCORE_THRESHOLD}, server::{accept_headers, accept_tx, collect_headers, connect_with_retry, send_tx, serve, verify_block_header, OpType, Package, RejectReason, Server, CENTRAL_NODE, CONNECT_RETRY_BASE_DELAY, GLOBAL_BLOCKS_IN_TRANSIT, GLOBAL_MEMORY_POOL, GLOBAL_NODES, INVALID_BLOCK_SCORE, MALFORMED_PACKAGE_SCORE, NODE_VERSION, TRANSACTION_THRESHOLD}, utxo_set::UTXO_TREE, Block, BlockHeader, Blockchain, ProofOfWork, TXOutput, Transaction, UTXOSet
 };
 use crate::test_helpers::{
-    create_test_block, create_test_transaction, setup_temp_test_db,
+    create_funded_blockchain, create_independent_test_transaction, create_spending_transaction, create_test_block, create_test_genesis_block, create_test_transaction,
+    setup_temp_test_db,
 };
 
 // Helper function to create a test blockchain
@@ -67,13 +68,14 @@ fn send_package_to_stream(stream: &mut TcpStream, pkg: &Package) -> std::io::Res
 #[test]
 fn test_package_serialization_deserialization() {
     // Test that packages can be serialized and deserialized properly
-    let packages = vec![
+    let packages = [
         Package::Block {
             addr_from: "127.0.0.1:3000".to_string(),
             block: vec![1, 2, 3, 4, 5],
         },
         Package::GetBlocks {
             addr_from: "127.0.0.1:3000".to_string(),
+            locator: vec![],
         },
         Package::GetData {
             addr_from: "127.0.0.1:3000".to_string(),
@@ -93,6 +95,7 @@ fn test_package_serialization_deserialization() {
             addr_from: "127.0.0.1:3000".to_string(),
             version: 1,
             best_height: 42,
+            magic: GLOBAL_CONFIG.get_network_magic(),
         },
     ];
     
@@ -118,8 +121,8 @@ fn test_global_state_management() {
     clear_global_state();
     
     // Test memory pool operations
-    let tx1 = create_test_transaction(vec![1, 2, 3, 4]);
-    let tx2 = create_test_transaction(vec![5, 6, 7, 8]);
+    let tx1 = create_independent_test_transaction(vec![1, 2, 3, 4]);
+    let tx2 = create_independent_test_transaction(vec![5, 6, 7, 8]);
     
     GLOBAL_MEMORY_POOL.add(tx1.clone());
     GLOBAL_MEMORY_POOL.add(tx2.clone());
@@ -187,9 +190,9 @@ fn test_central_node_detection() {
     setup_test_config();
     clear_global_state();
     
-    // Test non-central node (can't set node_addr, so test with current value)
+    // Test non-central node with the current value; other tests share GLOBAL_CONFIG,
+    // so set_node_addr() is not used here
     let _ = GLOBAL_CONFIG.get_node_addr();
-    // Note: set_node_addr() method doesn't exist, so we can't modify it
     
     // Test central node constant
     assert_eq!(CENTRAL_NODE, "127.0.0.1:2001");
@@ -241,10 +244,12 @@ fn test_package_creation() {
         addr_from: "127.0.0.1:3000".to_string(),
         version: NODE_VERSION,
         best_height: 42,
+        magic: GLOBAL_CONFIG.get_network_magic(),
     };
     
     match version_pkg {
-        Package::Version { addr_from, version, best_height } => {
+        Package::Version { addr_from, version, best_height, magic } => {
+            assert_eq!(magic, GLOBAL_CONFIG.get_network_magic());
             assert_eq!(addr_from, "127.0.0.1:3000");
             assert_eq!(version, NODE_VERSION);
             assert_eq!(best_height, 42);
@@ -399,6 +404,7 @@ fn test_integration_with_real_tcp() {
         addr_from: "127.0.0.1:3001".to_string(),
         version: NODE_VERSION,
         best_height: 0,
+        magic: GLOBAL_CONFIG.get_network_magic(),
     };
     
     send_package_to_stream(&mut client_stream, &version_pkg).unwrap();
@@ -494,8 +500,8 @@ fn test_local_memory_pool_operations() {
     let memory_pool = MemoryPool::new();
     
     // Test adding transactions
-    let tx1 = create_test_transaction(vec![1, 2, 3, 4]);
-    let tx2 = create_test_transaction(vec![5, 6, 7, 8]);
+    let tx1 = create_independent_test_transaction(vec![1, 2, 3, 4]);
+    let tx2 = create_independent_test_transaction(vec![5, 6, 7, 8]);
     
     memory_pool.add(tx1.clone());
     memory_pool.add(tx2.clone());
@@ -587,7 +593,7 @@ fn test_thread_safety() {
             let nodes_clone = nodes.clone();
             
             thread::spawn(move || {
-                let tx = create_test_transaction(vec![i as u8]);
+                let tx = create_independent_test_transaction(vec![i as u8]);
                 memory_pool_clone.add(tx);
                 
                 let block_hash = vec![i as u8; 5];
@@ -626,6 +632,7 @@ fn test_comprehensive_package_handling() {
         }),
         ("GetBlocks", Package::GetBlocks {
             addr_from: "test_node".to_string(),
+            locator: vec!["test_hash".to_string()],
         }),
         ("GetData", Package::GetData {
             addr_from: "test_node".to_string(),
@@ -645,6 +652,7 @@ fn test_comprehensive_package_handling() {
             addr_from: "test_node".to_string(),
             version: NODE_VERSION,
             best_height: 100,
+            magic: GLOBAL_CONFIG.get_network_magic(),
         }),
     ];
     
@@ -669,3 +677,843 @@ fn test_comprehensive_package_handling() {
         }
     }
 } 
+
+// Helper function to seed the UTXO set of a blockchain with outputs of `txid`
+fn seed_utxo(blockchain: &Blockchain, txid: &[u8], outputs: &[TXOutput]) {
+    let utxo_tree = blockchain.get_db().open_tree(UTXO_TREE).unwrap();
+    let entry: Vec<Option<TXOutput>> = outputs.iter().cloned().map(Some).collect();
+    let encoded = bincode::encode_to_vec(entry, standard()).unwrap();
+    utxo_tree.insert(txid, encoded).unwrap();
+}
+
+#[test]
+fn test_accept_tx_with_unspent_input() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let memory_pool = MemoryPool::new();
+
+    let prev_txid = vec![3, 3, 3, 3];
+    seed_utxo(&blockchain, &prev_txid, &[TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }]);
+
+    let tx = create_spending_transaction(vec![(prev_txid, 0)], vec![(100, vec![4, 5, 6])]);
+    assert!(accept_tx(&blockchain, &memory_pool, tx.clone()));
+    assert!(memory_pool.contains(&HEXLOWER.encode(tx.get_id())));
+}
+
+#[test]
+fn test_accept_tx_rejects_already_spent_input() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let memory_pool = MemoryPool::new();
+
+    let prev_txid = vec![3, 3, 3, 3];
+    seed_utxo(&blockchain, &prev_txid, &[TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }]);
+
+    // Confirm a transaction spending the output
+    let confirmed = create_spending_transaction(vec![(prev_txid.clone(), 0)], vec![(100, vec![4, 5, 6])]);
+    let block = Block::new_block_without_proof_of_work(
+        "prev_hash".to_string(),
+        std::slice::from_ref(&confirmed),
+        1,
+    );
+    utxo_set.update(&block);
+
+    // A stale transaction spending the same output must not enter the pool
+    let stale = Transaction::new(vec![8, 8, 8, 8], confirmed.vin.clone(), confirmed.vout.clone());
+    assert!(!accept_tx(&blockchain, &memory_pool, stale.clone()));
+    assert!(!memory_pool.contains(&HEXLOWER.encode(stale.get_id())));
+    assert!(memory_pool.is_empty());
+}
+
+#[test]
+fn test_accept_tx_rejects_malformed_transaction() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let memory_pool = MemoryPool::new();
+
+    let prev_txid = vec![3, 3, 3, 3];
+    seed_utxo(&blockchain, &prev_txid, &[TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }]);
+
+    // The input is spendable, but a negative output fails the structural check first
+    let tx = create_spending_transaction(vec![(prev_txid, 0)], vec![(-5, vec![4, 5, 6])]);
+    assert!(!accept_tx(&blockchain, &memory_pool, tx));
+    assert!(memory_pool.is_empty());
+}
+
+#[test]
+fn test_serve_drops_malformed_block_without_panicking() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let (listener, addr) = create_test_server();
+
+    let server_chain = blockchain.clone();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        serve(server_chain, stream).is_ok()
+    });
+
+    let mut client_stream = TcpStream::connect(addr).unwrap();
+    let garbage_block = Package::Block {
+        addr_from: "127.0.0.1:3001".to_string(),
+        block: vec![0xff, 0xff, 0xff, 0xff],
+    };
+    send_package_to_stream(&mut client_stream, &garbage_block).unwrap();
+    drop(client_stream);
+
+    // A panic in the handler would surface as a join error
+    assert!(handle.join().unwrap());
+    assert!(blockchain.is_empty());
+}
+
+// Serves every package in `packages` on one connection and waits for the handler to finish
+fn serve_packages(blockchain: &Blockchain, packages: &[Package]) {
+    let (listener, addr) = create_test_server();
+    let server_chain = blockchain.clone();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _ = serve(server_chain, stream);
+    });
+
+    let mut client_stream = TcpStream::connect(addr).unwrap();
+    for pkg in packages {
+        send_package_to_stream(&mut client_stream, pkg).unwrap();
+    }
+    drop(client_stream);
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_serve_bans_peer_after_repeated_malformed_blocks() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let bad_peer = "127.0.0.1:3411";
+
+    let garbage_block = || Package::Block {
+        addr_from: bad_peer.to_string(),
+        block: vec![0xff, 0xff, 0xff, 0xff],
+    };
+    serve_packages(&blockchain, &[garbage_block()]);
+    assert_eq!(GLOBAL_NODES.get_misbehavior_score(bad_peer), MALFORMED_PACKAGE_SCORE);
+    assert!(!GLOBAL_NODES.is_banned(bad_peer));
+
+    serve_packages(&blockchain, &[garbage_block()]);
+    assert!(GLOBAL_NODES.is_banned(bad_peer));
+}
+
+#[test]
+fn test_serve_ignores_packages_from_banned_peer() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = create_funded_blockchain(&db, "banned_peer_test_address");
+    let banned_peer = "127.0.0.1:3412";
+    GLOBAL_NODES.ban(banned_peer, Duration::from_secs(60));
+
+    // A version message would normally register the sender as a known node
+    let version_pkg = Package::Version {
+        addr_from: banned_peer.to_string(),
+        version: NODE_VERSION,
+        best_height: 0,
+        magic: GLOBAL_CONFIG.get_network_magic(),
+    };
+    serve_packages(&blockchain, &[version_pkg]);
+    assert!(!GLOBAL_NODES.node_is_known(banned_peer));
+    assert!(GLOBAL_NODES.get_nodes().iter().all(|node| node.get_addr() != banned_peer));
+}
+
+#[test]
+fn test_serve_refreshes_last_seen_of_sender() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let peer = "127.0.0.1:3413";
+    GLOBAL_NODES.add_node(peer.to_string());
+    let stale = rust_blockchain::util::current_timestamp() - 1_000;
+    GLOBAL_NODES.set_last_seen_for_test(peer, stale);
+
+    // Asking for an unknown transaction triggers no reply, only the last-seen update
+    let get_data = Package::GetData {
+        addr_from: peer.to_string(),
+        op_type: OpType::Tx,
+        id: vec![0u8; 32],
+    };
+    serve_packages(&blockchain, &[get_data]);
+    assert!(GLOBAL_NODES.get_last_seen(peer).unwrap() > stale);
+
+    GLOBAL_NODES.evict_node(peer);
+}
+
+// Accepts one connection on `listener` and decodes the package written to it
+fn receive_package(listener: &TcpListener) -> Package {
+    let (stream, _) = listener.accept().unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut reader = std::io::BufReader::new(stream);
+    bincode::decode_from_std_read(&mut reader, standard()).unwrap()
+}
+
+#[test]
+fn test_get_addr_and_addr_propagate_peers() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+
+    // Peer A announces a peer C that our node has never connected to
+    let (peer_a, peer_a_addr) = create_test_server();
+    let peer_a_addr = peer_a_addr.to_string();
+    let peer_c = "127.0.0.1:3421";
+    let addr_pkg = Package::Addr {
+        addr_from: peer_a_addr.clone(),
+        nodes: vec![
+            peer_c.to_string(),
+            GLOBAL_CONFIG.get_node_addr(),
+            "not an address".to_string(),
+        ],
+    };
+    serve_packages(&blockchain, &[addr_pkg]);
+    assert!(GLOBAL_NODES.node_is_known(peer_c));
+    assert!(!GLOBAL_NODES.node_is_known("not an address"));
+
+    // A later GetAddr from peer A is answered with our peer list, now including C
+    let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+    let receiver = thread::spawn(move || {
+        reply_tx.send(receive_package(&peer_a)).unwrap();
+    });
+    let get_addr_pkg = Package::GetAddr {
+        addr_from: peer_a_addr.clone(),
+    };
+    serve_packages(&blockchain, &[get_addr_pkg]);
+    receiver.join().unwrap();
+
+    match reply_rx.recv().unwrap() {
+        Package::Addr { addr_from, nodes } => {
+            assert_eq!(addr_from, GLOBAL_CONFIG.get_node_addr());
+            assert!(nodes.iter().any(|node| node == peer_c));
+            assert!(nodes.iter().all(|node| *node != peer_a_addr));
+        }
+        other => panic!("Expected Package::Addr, got {other:?}"),
+    }
+
+    GLOBAL_NODES.evict_node(peer_c);
+}
+
+#[test]
+fn test_serve_answers_ping_with_matching_pong() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let (listener, addr) = create_test_server();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _ = serve(blockchain, stream);
+    });
+
+    let mut client_stream = TcpStream::connect(addr).unwrap();
+    client_stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let nonce = 0x5eed_cafe_u64;
+    send_package_to_stream(&mut client_stream, &Package::Ping { nonce }).unwrap();
+
+    let reply: Package = bincode::decode_from_std_read(&mut client_stream, standard()).unwrap();
+    match reply {
+        Package::Pong { nonce: pong_nonce } => assert_eq!(pong_nonce, nonce),
+        other => panic!("Expected Package::Pong, got {other:?}"),
+    }
+
+    drop(client_stream);
+    handle.join().unwrap();
+}
+
+// =============================================================================
+// HEADERS-FIRST SYNC TESTS
+// =============================================================================
+
+/// A genesis block plus `len - 1` blocks on top of it, all added to `blockchain`
+fn store_header_chain(blockchain: &Blockchain, prefix: &str, len: usize) -> Vec<Block> {
+    let mut genesis = Block::generate_genesis_block(&create_test_transaction(vec![0, 0, 0, 0]));
+    genesis.set_hash_for_test(&format!("{prefix}_0"));
+    blockchain.add_block(&genesis);
+    let mut blocks = vec![genesis];
+    for height in 1..len {
+        let mut block = create_test_block(blocks[height - 1].get_hash().to_string(), height);
+        block.set_hash_for_test(&format!("{prefix}_{height}"));
+        blockchain.add_block(&block);
+        blocks.push(block);
+    }
+    blocks
+}
+
+fn decode_headers(headers: &[Vec<u8>]) -> Vec<BlockHeader> {
+    headers
+        .iter()
+        .map(|header| BlockHeader::try_deserialize(header).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_accept_headers_returns_missing_blocks_in_order() {
+    let (source_db, _source_dir) = setup_temp_test_db();
+    let source = Blockchain::new_with_empty_tip(source_db);
+    let blocks = store_header_chain(&source, "accept_headers", 4);
+
+    let all_headers = decode_headers(&collect_headers(&source, 0));
+    assert_eq!(all_headers.len(), 4);
+    assert_eq!(collect_headers(&source, 2).len(), 2);
+    assert!(collect_headers(&source, 9).is_empty());
+
+    // A node holding only the genesis block is missing the other three
+    let (db, _temp_dir) = setup_temp_test_db();
+    let local = Blockchain::new_with_empty_tip(db);
+    local.add_block(&blocks[0]);
+    let missing = accept_headers(&local, &all_headers).unwrap();
+    let expected: Vec<Vec<u8>> = blocks[1..].iter().map(|block| block.get_hash_bytes()).collect();
+    assert_eq!(missing, expected);
+    assert_eq!(accept_headers(&local, &all_headers[1..]).unwrap(), expected);
+
+    // Nothing is missing from the chain the headers came from
+    assert!(accept_headers(&source, &all_headers).unwrap().is_empty());
+    assert!(accept_headers(&local, &[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_accept_headers_rejects_unlinked_headers() {
+    let (source_db, _source_dir) = setup_temp_test_db();
+    let source = Blockchain::new_with_empty_tip(source_db);
+    store_header_chain(&source, "unlinked_headers", 4);
+    let headers = decode_headers(&collect_headers(&source, 0));
+
+    let (db, _temp_dir) = setup_temp_test_db();
+    let empty = Blockchain::new_with_empty_tip(db);
+    // The first header's parent is unknown locally
+    assert!(accept_headers(&empty, &headers[2..]).is_err());
+    // A gap in the middle breaks the chain
+    let gapped = vec![headers[0].clone(), headers[2].clone()];
+    assert!(accept_headers(&empty, &gapped).is_err());
+}
+
+#[test]
+fn test_serve_answers_get_headers() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let blocks = store_header_chain(&blockchain, "serve_get_headers", 3);
+
+    let (peer, peer_addr) = create_test_server();
+    let receiver = thread::spawn(move || receive_package(&peer));
+    let get_headers = Package::GetHeaders {
+        addr_from: peer_addr.to_string(),
+        from_height: 1,
+    };
+    serve_packages(&blockchain, &[get_headers]);
+
+    match receiver.join().unwrap() {
+        Package::Headers { headers, .. } => {
+            let headers = decode_headers(&headers);
+            assert_eq!(headers, vec![blocks[1].header(), blocks[2].header()]);
+        }
+        other => panic!("Expected Package::Headers, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_serve_requests_first_block_missing_from_headers() {
+    let (source_db, _source_dir) = setup_temp_test_db();
+    let source = Blockchain::new_with_empty_tip(source_db);
+    let blocks = store_header_chain(&source, "serve_headers", 3);
+
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    blockchain.add_block(&blocks[0]);
+
+    let (peer, peer_addr) = create_test_server();
+    let receiver = thread::spawn(move || receive_package(&peer));
+    let headers_pkg = Package::Headers {
+        addr_from: peer_addr.to_string(),
+        headers: collect_headers(&source, 0),
+    };
+    serve_packages(&blockchain, &[headers_pkg]);
+
+    match receiver.join().unwrap() {
+        Package::GetData { op_type: OpType::Block, id, .. } => {
+            assert_eq!(id, blocks[1].get_hash_bytes());
+        }
+        other => panic!("Expected Package::GetData for a block, got {other:?}"),
+    }
+    // Clear the block still queued in transit so other tests start clean
+    GLOBAL_BLOCKS_IN_TRANSIT.remove(&blocks[2].get_hash_bytes());
+}
+
+// =============================================================================
+// INVENTORY DEDUP TESTS
+// =============================================================================
+
+#[test]
+fn test_serve_inv_requests_only_unknown_blocks() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let blocks = store_header_chain(&blockchain, "inv_dedup", 2);
+
+    let (peer, peer_addr) = create_test_server();
+    let receiver = thread::spawn(move || receive_package(&peer));
+    let unknown_a = b"inv_dedup_unknown_a".to_vec();
+    let unknown_b = b"inv_dedup_unknown_b".to_vec();
+    let inv = Package::Inv {
+        addr_from: peer_addr.to_string(),
+        op_type: OpType::Block,
+        items: vec![
+            blocks[1].get_hash_bytes(),
+            unknown_a.clone(),
+            blocks[0].get_hash_bytes(),
+            unknown_b.clone(),
+        ],
+    };
+    serve_packages(&blockchain, &[inv]);
+
+    match receiver.join().unwrap() {
+        Package::GetData { op_type: OpType::Block, id, .. } => assert_eq!(id, unknown_a),
+        other => panic!("Expected Package::GetData for a block, got {other:?}"),
+    }
+    // Only the other unknown block is left queued; drop it so other tests start clean
+    GLOBAL_BLOCKS_IN_TRANSIT.remove(&unknown_b);
+}
+
+#[test]
+fn test_serve_inv_of_only_known_blocks_requests_nothing() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let blocks = store_header_chain(&blockchain, "inv_all_known", 2);
+
+    let (peer, peer_addr) = create_test_server();
+    peer.set_nonblocking(true).unwrap();
+    let inv = Package::Inv {
+        addr_from: peer_addr.to_string(),
+        op_type: OpType::Block,
+        items: blocks.iter().map(|block| block.get_hash_bytes()).collect(),
+    };
+    serve_packages(&blockchain, &[inv]);
+
+    // No GetData connection was attempted
+    assert!(peer.accept().is_err());
+}
+
+// =============================================================================
+// BLOCK HEADER PROOF TESTS
+// =============================================================================
+
+// Easy enough to mine in a test, far below the default difficulty
+const HEADER_PROOF_TARGET_BITS: usize = 8;
+
+fn mine_header_proof_block(pre_hash: &str, height: usize) -> Block {
+    let txs = create_test_block(pre_hash.to_string(), height).get_transactions().to_vec();
+    let mut block = Block::new_block_without_proof_of_work(pre_hash.to_string(), &txs, height);
+    let (nonce, hash) = ProofOfWork::new_proof_of_work(block.clone(), HEADER_PROOF_TARGET_BITS).run();
+    block.set_nonce_for_test(nonce);
+    block.set_hash_for_test(&hash);
+    block
+}
+
+#[test]
+fn test_verify_block_header_checks_hash_and_proof_of_work() {
+    let block = mine_header_proof_block("header_proof_parent", 1);
+    let header = block.header();
+    assert!(verify_block_header(&block.get_hash_bytes(), &header, HEADER_PROOF_TARGET_BITS).is_ok());
+
+    // The header must be the block that was asked for
+    assert!(verify_block_header(b"some_other_block", &header, HEADER_PROOF_TARGET_BITS).is_err());
+
+    // A claimed hash that the fields do not hash to is rejected, even if it looks like work
+    let mut forged = block.clone();
+    forged.set_hash_for_test(&"0".repeat(64));
+    assert!(verify_block_header(&forged.get_hash_bytes(), &forged.header(), HEADER_PROOF_TARGET_BITS).is_err());
+
+    // Work done at an easy difficulty does not satisfy a much harder one
+    assert!(verify_block_header(&block.get_hash_bytes(), &header, 200).is_err());
+}
+
+#[test]
+fn test_verify_block_header_exempts_unmined_genesis() {
+    let mut genesis = create_test_genesis_block();
+    genesis.set_hash_for_test("genesis_1700000000");
+    let header = genesis.header();
+    assert!(verify_block_header(&genesis.get_hash_bytes(), &header, HEADER_PROOF_TARGET_BITS).is_ok());
+    assert!(verify_block_header(b"some_other_block", &header, HEADER_PROOF_TARGET_BITS).is_err());
+
+    // Only a block on the genesis sentinel is exempt, not any block at height 0
+    let mut fake_root = create_test_block("not_genesis".to_string(), 0);
+    fake_root.set_hash_for_test("fake_root_hash");
+    assert!(verify_block_header(&fake_root.get_hash_bytes(), &fake_root.header(), HEADER_PROOF_TARGET_BITS).is_err());
+}
+
+#[test]
+fn test_serve_answers_get_block_header() {
+    let blockchain = Blockchain::new_in_memory();
+    let block = mine_header_proof_block("serve_header_proof_parent", 1);
+    blockchain.add_block(&block);
+
+    let (peer, peer_addr) = create_test_server();
+    let receiver = thread::spawn(move || receive_package(&peer));
+    let request = Package::GetBlockHeader {
+        addr_from: peer_addr.to_string(),
+        hash: block.get_hash_bytes(),
+    };
+    serve_packages(&blockchain, &[request]);
+
+    match receiver.join().unwrap() {
+        Package::BlockHeader { hash, header: Some(header), .. } => {
+            assert_eq!(hash, block.get_hash_bytes());
+            let header = BlockHeader::try_deserialize(&header).unwrap();
+            assert_eq!(header, block.header());
+            assert!(verify_block_header(&hash, &header, HEADER_PROOF_TARGET_BITS).is_ok());
+        }
+        other => panic!("Expected Package::BlockHeader with a header, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_serve_answers_get_block_header_for_unknown_block() {
+    let blockchain = Blockchain::new_in_memory();
+
+    let (peer, peer_addr) = create_test_server();
+    let receiver = thread::spawn(move || receive_package(&peer));
+    let request = Package::GetBlockHeader {
+        addr_from: peer_addr.to_string(),
+        hash: b"unknown_header_proof_block".to_vec(),
+    };
+    serve_packages(&blockchain, &[request]);
+
+    match receiver.join().unwrap() {
+        Package::BlockHeader { hash, header, .. } => {
+            assert_eq!(hash, b"unknown_header_proof_block".to_vec());
+            assert!(header.is_none());
+        }
+        other => panic!("Expected Package::BlockHeader, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_serve_penalizes_block_header_without_proof_of_work() {
+    let blockchain = Blockchain::new_in_memory();
+    let mut block = create_test_block("unproven_header_parent".to_string(), 1);
+    block.set_hash_for_test("unproven_header");
+    let peer = "127.0.0.1:3417";
+
+    let reply = Package::BlockHeader {
+        addr_from: peer.to_string(),
+        hash: block.get_hash_bytes(),
+        header: Some(block.header_bytes()),
+    };
+    serve_packages(&blockchain, &[reply]);
+
+    // An invalid header costs as much as an invalid block, enough for a ban on its own
+    assert_eq!(INVALID_BLOCK_SCORE, BAN_SCORE_THRESHOLD);
+    assert!(GLOBAL_NODES.is_banned(peer));
+}
+
+// =============================================================================
+// REJECTED BLOCK TESTS
+// =============================================================================
+
+#[test]
+fn test_serve_records_block_without_proof_of_work() {
+    let blockchain = Blockchain::new_in_memory();
+    let mut block = create_test_block("unproven_block_parent".to_string(), 1);
+    block.set_hash_for_test("unproven_block");
+
+    let pkg = Package::Block {
+        addr_from: "127.0.0.1:3418".to_string(),
+        block: block.serialize(),
+    };
+    serve_packages(&blockchain, &[pkg]);
+
+    let rejections = Server::new(blockchain.clone()).recent_rejections();
+    assert!(rejections.contains(&("unproven_block".to_string(), RejectReason::BadProofOfWork)));
+    assert!(GLOBAL_NODES.is_banned("127.0.0.1:3418"));
+}
+
+#[test]
+fn test_submit_block_records_height_not_following_parent() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = create_funded_blockchain(&db, "rejection_test_address");
+    let server = Server::new(blockchain.clone());
+    let tip_hash = blockchain.get_tip_hash();
+    let tip_height = blockchain.get_best_height();
+
+    let skipping = create_test_block(tip_hash, tip_height + 2);
+    assert!(!server.submit_block(skipping.clone()));
+
+    assert!(server
+        .recent_rejections()
+        .contains(&(skipping.get_hash().to_string(), RejectReason::BadParent)));
+    assert!(blockchain.get_block(skipping.get_hash().as_bytes()).is_none());
+}
+
+// =============================================================================
+// NETWORK MAGIC TESTS
+// =============================================================================
+
+/// Serve a version handshake carrying `magic` from `peer`, then a ping on the same
+/// connection. Returns whether the ping was answered.
+fn handshake_then_ping(peer: &str, magic: u32) -> bool {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let (listener, addr) = create_test_server();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _ = serve(blockchain, stream);
+    });
+
+    let mut client_stream = TcpStream::connect(addr).unwrap();
+    client_stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let version_pkg = Package::Version {
+        addr_from: peer.to_string(),
+        version: NODE_VERSION,
+        best_height: 0,
+        magic,
+    };
+    send_package_to_stream(&mut client_stream, &version_pkg).unwrap();
+    // The handler may already have hung up
+    let _ = send_package_to_stream(&mut client_stream, &Package::Ping { nonce: 7 });
+    let reply: Result<Package, _> = bincode::decode_from_std_read(&mut client_stream, standard());
+
+    drop(client_stream);
+    handle.join().unwrap();
+    matches!(reply, Ok(Package::Pong { nonce: 7 }))
+}
+
+#[test]
+fn test_serve_accepts_handshake_with_matching_magic() {
+    let peer = "127.0.0.1:3431";
+    assert!(handshake_then_ping(peer, GLOBAL_CONFIG.get_network_magic()));
+    assert!(GLOBAL_NODES.node_is_known(peer));
+    GLOBAL_NODES.evict_node(peer);
+}
+
+#[test]
+fn test_serve_disconnects_handshake_with_other_magic() {
+    let peer = "127.0.0.1:3432";
+    assert!(!handshake_then_ping(peer, GLOBAL_CONFIG.get_network_magic().wrapping_add(1)));
+    assert!(!GLOBAL_NODES.node_is_known(peer));
+}
+
+// =============================================================================
+// VERSION SYNC TRIGGER TESTS
+// =============================================================================
+
+/// Serve a version handshake from a peer at `peer_height` and return what the node sent
+/// back to that peer
+fn version_reply(blockchain: &Blockchain, peer_height: usize) -> Package {
+    let (peer, peer_addr) = create_test_server();
+    let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+    let receiver = thread::spawn(move || {
+        reply_tx.send(receive_package(&peer)).unwrap();
+    });
+    let version_pkg = Package::Version {
+        addr_from: peer_addr.to_string(),
+        version: NODE_VERSION,
+        best_height: peer_height,
+        magic: GLOBAL_CONFIG.get_network_magic(),
+    };
+    serve_packages(blockchain, &[version_pkg]);
+    receiver.join().unwrap();
+    GLOBAL_NODES.evict_node(peer_addr.to_string().as_str());
+    reply_rx.recv().unwrap()
+}
+
+#[test]
+fn test_version_from_taller_peer_requests_missing_blocks() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    store_header_chain(&blockchain, "version_behind", 3);
+
+    // Headers first: the blocks above our tip are fetched once their headers check out
+    match version_reply(&blockchain, 7) {
+        Package::GetHeaders { from_height, .. } => assert_eq!(from_height, 3),
+        other => panic!("Expected Package::GetHeaders, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_version_from_shorter_peer_announces_our_height() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    store_header_chain(&blockchain, "version_ahead", 3);
+
+    match version_reply(&blockchain, 1) {
+        Package::Version { best_height, .. } => assert_eq!(best_height, 2),
+        other => panic!("Expected Package::Version, got {other:?}"),
+    }
+}
+
+// =============================================================================
+// PACKAGE SIZE LIMIT TESTS
+// =============================================================================
+
+/// Write `bytes` to a fresh `serve` connection, keeping it open, and return whether the
+/// handler hung up on its own within five seconds
+fn serve_hangs_up_after(bytes: &[u8]) -> bool {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let (listener, addr) = create_test_server();
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _ = serve(blockchain, stream);
+        let _ = done_tx.send(());
+    });
+
+    let mut client_stream = TcpStream::connect(addr).unwrap();
+    // The handler stops reading at the limit, so the rest of an oversized write may fail
+    let _ = client_stream.write_all(bytes);
+    let _ = client_stream.flush();
+    let hung_up = done_rx.recv_timeout(Duration::from_secs(5)).is_ok();
+    drop(client_stream);
+    hung_up
+}
+
+#[test]
+fn test_serve_rejects_declared_length_past_ceiling_without_allocating() {
+    // A Tx package whose transaction claims a terabyte, with none of the bytes sent
+    let mut bytes = vec![4u8];
+    bytes.extend(bincode::encode_to_vec("127.0.0.1:3441", standard()).unwrap());
+    bytes.extend(bincode::encode_to_vec(1u64 << 40, standard()).unwrap());
+    const { assert!(MAX_PACKAGE_SIZE_CEILING < 1 << 40) };
+
+    assert!(serve_hangs_up_after(&bytes));
+}
+
+#[test]
+fn test_serve_drops_package_larger_than_configured_size() {
+    let transaction = vec![0u8; GLOBAL_CONFIG.get_max_package_size() + 1];
+    let oversized = Package::Tx {
+        addr_from: "127.0.0.1:3442".to_string(),
+        transaction,
+    };
+    let bytes = bincode::encode_to_vec(&oversized, standard()).unwrap();
+    assert!(bytes.len() < MAX_PACKAGE_SIZE_CEILING);
+
+    assert!(serve_hangs_up_after(&bytes));
+    assert!(!GLOBAL_NODES.is_banned("127.0.0.1:3442"));
+}
+
+// =============================================================================
+// READ TIMEOUT TESTS
+// =============================================================================
+
+#[test]
+#[ignore] // Integration test - run manually with `cargo test -- --ignored`; it changes the global read timeout
+fn test_serve_drops_silent_connection_after_read_timeout() {
+    GLOBAL_CONFIG.set_read_timeout_secs(1);
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let (listener, addr) = create_test_server();
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _ = done_tx.send(serve(blockchain, stream).is_ok());
+    });
+
+    // Connect and send nothing; the handler must hang up on its own
+    let client_stream = TcpStream::connect(addr).unwrap();
+    let returned = done_rx.recv_timeout(Duration::from_secs(5));
+    GLOBAL_CONFIG.set_read_timeout_secs(rust_blockchain::config::DEFAULT_READ_TIMEOUT_SECS);
+
+    assert_eq!(returned, Ok(true));
+    drop(client_stream);
+}
+
+// =============================================================================
+// MEMPOOL SHARING TESTS
+// =============================================================================
+
+#[test]
+fn test_serve_answers_get_mempool_with_pooled_txids() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = Blockchain::new_with_empty_tip(db);
+    let tx = create_test_transaction(b"get_mempool_pooled_tx".to_vec());
+    let txid = tx.get_id().to_vec();
+    GLOBAL_MEMORY_POOL.add(tx);
+
+    let (peer, peer_addr) = create_test_server();
+    let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+    let receiver = thread::spawn(move || {
+        reply_tx.send(receive_package(&peer)).unwrap();
+    });
+    serve_packages(&blockchain, &[Package::GetMempool { addr_from: peer_addr.to_string() }]);
+    receiver.join().unwrap();
+    GLOBAL_MEMORY_POOL.remove(HEXLOWER.encode(&txid).as_str());
+    GLOBAL_NODES.evict_node(peer_addr.to_string().as_str());
+
+    match reply_rx.recv().unwrap() {
+        Package::MempoolInv { addr_from, txids } => {
+            assert_eq!(addr_from, GLOBAL_CONFIG.get_node_addr());
+            assert!(txids.contains(&txid));
+        }
+        other => panic!("Expected Package::MempoolInv, got {other:?}"),
+    }
+}
+
+// =============================================================================
+// CONNECT RETRY TESTS
+// =============================================================================
+
+// A local address nothing is listening on
+fn unused_addr() -> std::net::SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+#[test]
+fn test_connect_with_retry_reaches_peer_that_starts_late() {
+    let addr = unused_addr();
+    // Attempts land at 0, 1 and 3 base delays; the peer only listens after 2 of them
+    let listener_thread = thread::spawn(move || {
+        thread::sleep(CONNECT_RETRY_BASE_DELAY * 2);
+        let listener = TcpListener::bind(addr).unwrap();
+        listener.accept().map(|_| ())
+    });
+
+    let started = std::time::Instant::now();
+    let stream = connect_with_retry(addr).expect("third attempt should connect");
+    assert!(started.elapsed() >= CONNECT_RETRY_BASE_DELAY * 3);
+    assert_eq!(stream.peer_addr().unwrap(), addr);
+    assert!(listener_thread.join().unwrap().is_ok());
+}
+
+#[test]
+fn test_connect_with_retry_gives_up_after_configured_attempts() {
+    let addr = unused_addr();
+    let started = std::time::Instant::now();
+    let err = connect_with_retry(addr).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+
+    // Every attempt but the last is followed by a doubling wait
+    let attempts = GLOBAL_CONFIG.get_connect_attempts();
+    let waited: u32 = (0..attempts - 1).map(|retry| 1 << retry).sum();
+    assert!(started.elapsed() >= CONNECT_RETRY_BASE_DELAY * waited);
+}
+
+// =============================================================================
+// SEND TX TESTS
+// =============================================================================
+
+#[test]
+fn test_send_tx_to_unreachable_address_returns_err() {
+    let addr = unused_addr();
+    let tx = create_test_transaction(vec![7, 7, 7, 7]);
+
+    let err = send_tx(addr.to_string().as_str(), &tx).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+}
+
+#[test]
+fn test_send_tx_to_unparsable_address_returns_err() {
+    let tx = create_test_transaction(vec![7, 7, 7, 7]);
+
+    let err = send_tx("not an address", &tx).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_send_tx_to_listening_node_returns_ok() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let tx = create_test_transaction(vec![7, 7, 7, 7]);
+
+    assert!(send_tx(addr.to_string().as_str(), &tx).is_ok());
+}
@@ -0,0 +1,145 @@
+//! Tests that mine real blocks. They lower the global proof-of-work difficulty and set a
+//! mining address, so they run as their own test binary instead of alongside the tests in
+//! `mod.rs` that expect the defaults.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use bincode::config::standard;
+use data_encoding::HEXLOWER;
+use rust_blockchain::{
+    Block, Blockchain, ChainParams, GENESIS_PRE_BLOCK_HASH, ProofOfWork, SUBSIDY, TXInput,
+    TXOutput, Transaction, UTXOSet,
+    config::GLOBAL_CONFIG,
+    server::{GLOBAL_MEMORY_POOL, Package, RejectReason, Server, serve},
+    wallet::Wallet,
+};
+use tempfile::TempDir;
+
+#[test]
+fn test_configured_target_bits_mine_quickly() {
+    GLOBAL_CONFIG.set_target_bits(4);
+
+    let coinbase = Transaction::new_coinbase_tx("target_bits_test_address");
+    let block = Block::new_block(String::from(GENESIS_PRE_BLOCK_HASH), &[coinbase], 0);
+
+    let pow = ProofOfWork::new_proof_of_work_default(block.clone());
+    assert_eq!(pow.get_target_bits(), 4);
+    assert!(pow.validate());
+
+    // Validating at the configured difficulty agrees with an explicit one
+    assert!(ProofOfWork::new_proof_of_work(block, 4).validate());
+}
+
+/// A signed transaction moving the only output of `coinbase` to `to`
+fn spend_coinbase(wallet: &Wallet, coinbase: &Transaction, to: &str) -> Transaction {
+    let value = coinbase.get_vout()[0].get_value();
+    let mut tx = Transaction::new(
+        vec![],
+        vec![TXInput::new(coinbase.get_id(), 0)],
+        vec![TXOutput::new(value, to)],
+    );
+    let prev_txs = HashMap::from([(HEXLOWER.encode(coinbase.get_id()), coinbase.clone())]);
+    tx.sign_with(wallet.get_pkcs8(), &prev_txs).unwrap();
+    tx
+}
+
+#[test]
+fn test_try_mine_at_transaction_threshold() {
+    GLOBAL_CONFIG.set_target_bits(4);
+    let temp_dir = TempDir::new().unwrap();
+    let db = sled::open(temp_dir.path().join("chain")).unwrap();
+    let wallet = Wallet::from_seed(b"try-mine-wallet");
+    let miner = Wallet::from_seed(b"try-mine-miner");
+    let blockchain = Blockchain::create_blockchain_in(db, &ChainParams::new(&wallet.get_address()));
+    let block1 = blockchain.mine_block(&[Transaction::new_coinbase_tx(&wallet.get_address())]);
+    UTXOSet::new(blockchain.clone()).reindex();
+    let genesis = blockchain.get_block_by_height(0).unwrap();
+
+    let server = Server::new(blockchain.clone());
+    let cheap = spend_coinbase(
+        &wallet,
+        &genesis.get_transactions()[0],
+        &miner.get_address(),
+    );
+    let pricey = spend_coinbase(&wallet, &block1.get_transactions()[0], &miner.get_address());
+
+    // Nothing is mined before a mining address is configured or the pool holds enough
+    assert!(GLOBAL_MEMORY_POOL.add_with_fee(cheap.clone(), 1));
+    assert!(server.try_mine().is_none());
+    GLOBAL_CONFIG.set_mining_addr(miner.get_address());
+    assert!(server.try_mine().is_none());
+
+    assert!(GLOBAL_MEMORY_POOL.add_with_fee(pricey.clone(), 5));
+    let block = server.try_mine().unwrap();
+
+    // The coinbase leads, then the pooled transactions by fee
+    let txs = block.get_transactions();
+    assert!(txs[0].is_coinbase());
+    assert!(txs[0].get_vout()[0].is_locked_with_key(
+        &rust_blockchain::wallet::wallet_util::hash_pub_key(miner.get_public_key())
+    ));
+    assert_eq!(txs[1].get_id(), pricey.get_id());
+    assert_eq!(txs[2].get_id(), cheap.get_id());
+
+    assert_eq!(blockchain.get_tip_hash(), block.get_hash());
+    assert_eq!(block.get_height(), 2);
+    assert!(GLOBAL_MEMORY_POOL.is_empty());
+    assert_eq!(blockchain.validate_chain(), Ok(()));
+}
+
+/// Serve `block` to the node's Block handler as the peer `addr_from`, waiting until it is done
+fn serve_block(blockchain: &Blockchain, addr_from: &str, block: &Block) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_chain = blockchain.clone();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _ = serve(server_chain, stream);
+    });
+    let pkg = Package::Block {
+        addr_from: addr_from.to_string(),
+        block: block.serialize(),
+    };
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(&bincode::encode_to_vec(&pkg, standard()).unwrap())
+        .unwrap();
+    drop(stream);
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_block_handler_rejects_coinbase_claiming_too_much() {
+    GLOBAL_CONFIG.set_target_bits(4);
+    let temp_dir = TempDir::new().unwrap();
+    let db = sled::open(temp_dir.path().join("chain")).unwrap();
+    let miner = Wallet::from_seed(b"over-claiming-miner").get_address();
+    let blockchain = Blockchain::create_blockchain_in(db, &ChainParams::new(&miner));
+    let genesis_hash = blockchain.get_tip_hash();
+
+    let greedy = Transaction::new_coinbase_tx_with_reward(&miner, SUBSIDY + 1);
+    let over_claiming = Block::new_block(genesis_hash.clone(), &[greedy], 1);
+    serve_block(&blockchain, "127.0.0.1:3431", &over_claiming);
+
+    assert!(
+        blockchain
+            .get_block(over_claiming.get_hash().as_bytes())
+            .is_none()
+    );
+    assert_eq!(blockchain.get_tip_hash(), genesis_hash);
+    let rejections = Server::new(blockchain.clone()).recent_rejections();
+    assert!(rejections.contains(&(
+        over_claiming.get_hash().to_string(),
+        RejectReason::InvalidTransaction
+    )));
+
+    // The same block claiming only the subsidy is connected
+    let honest = Block::new_block(genesis_hash, &[Transaction::new_coinbase_tx(&miner)], 1);
+    serve_block(&blockchain, "127.0.0.1:3431", &honest);
+    assert_eq!(blockchain.get_tip_hash(), honest.get_hash());
+}
@@ -1,9 +1,13 @@
 #![allow(dead_code)]
 
-use rust_blockchain::{BLOCKS_TREE, Block, TXInput, TXOutput, Transaction};
+use rust_blockchain::{
+    BLOCKS_TREE, Block, Blockchain, ProofOfWork, SUBSIDY, TIP_BLOCK_HASH_KEY, TXInput, TXOutput,
+    Transaction, UTXOSet,
+};
 use sled::Db;
 use std::fs;
 use std::path::Path;
+use std::sync::Mutex;
 use tempfile::TempDir;
 
 // =============================================================================
@@ -21,11 +25,21 @@ pub fn create_test_transaction(id: Vec<u8>) -> Transaction {
     let tx_output = TXOutput {
         value: 100,
         pub_key_hash: vec![10, 11, 12],
+        multisig: None,
+        data: None,
     };
 
     Transaction::new(id, vec![tx_input], vec![tx_output])
 }
 
+/// Creates a test transaction whose single input spends output 0 of `id` itself,
+/// so transactions built with different IDs never double-spend each other.
+pub fn create_independent_test_transaction(id: Vec<u8>) -> Transaction {
+    let mut tx = create_test_transaction(id.clone());
+    tx.vin[0].txid = id;
+    tx
+}
+
 // =============================================================================
 // TXOutput HELPERS
 // =============================================================================
@@ -35,6 +49,8 @@ pub fn create_sample_output() -> TXOutput {
     TXOutput {
         value: 100,
         pub_key_hash: vec![1, 2, 3, 4, 5],
+        multisig: None,
+        data: None,
     }
 }
 
@@ -43,6 +59,8 @@ pub fn create_output_with_value(value: i32) -> TXOutput {
     TXOutput {
         value,
         pub_key_hash: vec![10, 20, 30],
+        multisig: None,
+        data: None,
     }
 }
 
@@ -51,6 +69,8 @@ pub fn create_output_with_key_hash(key_hash: Vec<u8>) -> TXOutput {
     TXOutput {
         value: 50,
         pub_key_hash: key_hash,
+        multisig: None,
+        data: None,
     }
 }
 
@@ -61,11 +81,29 @@ pub fn create_output_with_key_hash(key_hash: Vec<u8>) -> TXOutput {
 /// Creates a test block with the given parameters.
 /// Consolidates similar functions from blockchain_iterator_tests.rs and proof_of_work_tests.rs.
 pub fn create_test_block(pre_hash: String, height: usize) -> Block {
-    let transaction = create_test_transaction(vec![1, 2, 3, 4]);
-    let transactions = vec![transaction];
+    let coinbase = create_test_coinbase(format!("{pre_hash}|{height}").as_bytes());
+    // A distinct id per block, so a chain of test blocks never repeats a transaction
+    let transaction = create_test_transaction(
+        rust_blockchain::util::sha256_digest(format!("{pre_hash}|{height}|tx").as_bytes()),
+    );
+    let transactions = vec![coinbase, transaction];
     let mut block = Block::new_block_without_proof_of_work(pre_hash, &transactions, height);
+    set_unique_test_hash(&mut block);
+    block
+}
+
+/// Creates a test block holding only a coinbase that claims the block subsidy, so a node
+/// accepts it on top of any parent and can apply it to its UTXO set.
+pub fn create_coinbase_only_block(pre_hash: String, height: usize) -> Block {
+    let mut coinbase = create_test_coinbase(format!("{pre_hash}|{height}").as_bytes());
+    coinbase.vout[0].value = SUBSIDY;
+    let mut block = Block::new_block_without_proof_of_work(pre_hash, &[coinbase], height);
+    set_unique_test_hash(&mut block);
+    block
+}
 
-    // Generate a unique hash for each test block based on its contents
+// Generate a unique hash for each test block based on its contents
+fn set_unique_test_hash(block: &mut Block) {
     let hash_input = format!(
         "{}|{}|{}",
         block.get_pre_block_hash(),
@@ -74,7 +112,17 @@ pub fn create_test_block(pre_hash: String, height: usize) -> Block {
     );
     let hash_bytes = rust_blockchain::util::sha256_digest(hash_input.as_bytes());
     block.set_hash_for_test(&data_encoding::HEXLOWER.encode(&hash_bytes));
+}
 
+// Difficulty `mine_test_block_at_low_difficulty` mines at, low enough to be near instant
+pub const TEST_TARGET_BITS: usize = 8;
+
+/// Mines `block` at `TEST_TARGET_BITS`, replacing its test hash with one that passes
+/// proof-of-work at that difficulty.
+pub fn mine_test_block_at_low_difficulty(mut block: Block) -> Block {
+    let (nonce, hash) = ProofOfWork::new_proof_of_work(block.clone(), TEST_TARGET_BITS).run();
+    block.set_nonce_for_test(nonce);
+    block.set_hash_for_test(&hash);
     block
 }
 
@@ -86,8 +134,24 @@ pub fn create_default_test_block() -> Block {
 
 /// Creates a genesis block for testing.
 pub fn create_test_genesis_block() -> Block {
-    let transaction = create_test_transaction(vec![0, 0, 0, 0]);
-    Block::generate_genesis_block(&transaction)
+    Block::generate_genesis_block(&create_test_coinbase(b"genesis"))
+}
+
+/// Creates a coinbase paying 50 to a fixed key hash, with its ID derived from `seed`
+/// so coinbases of different test blocks do not collide.
+pub fn create_test_coinbase(seed: &[u8]) -> Transaction {
+    let coinbase_input = TXInput::new(&[], 0);
+    let coinbase_output = TXOutput {
+        value: 50,
+        pub_key_hash: vec![0, 0, 0, 0],
+        multisig: None,
+        data: None,
+    };
+    Transaction::new(
+        rust_blockchain::util::sha256_digest(seed),
+        vec![coinbase_input],
+        vec![coinbase_output],
+    )
 }
 
 // =============================================================================
@@ -363,6 +427,26 @@ impl TestDataGenerator {
     }
 }
 
+/// Creates a blockchain in `db` whose genesis coinbase pays `address`, with the UTXO set
+/// already indexed so the output can be spent and verified with real signatures.
+pub fn create_funded_blockchain(db: &Db, address: &str) -> Blockchain {
+    let coinbase_tx = Transaction::new_coinbase_tx(address);
+    let mut genesis = Block::generate_genesis_block(&coinbase_tx);
+    genesis.set_hash_for_test("funded_genesis_hash");
+
+    let blocks_tree = db.open_tree(BLOCKS_TREE).unwrap();
+    blocks_tree
+        .insert(genesis.get_hash(), genesis.serialize())
+        .unwrap();
+    blocks_tree
+        .insert(TIP_BLOCK_HASH_KEY, genesis.get_hash())
+        .unwrap();
+
+    let blockchain = Blockchain::new_with_tip(db.clone(), genesis.get_hash().to_string());
+    UTXOSet::new(blockchain.clone()).reindex();
+    blockchain
+}
+
 // Helper function to setup test database with blocks
 pub fn setup_test_db_with_blocks() -> (TestDatabase, Vec<Block>) {
     let test_db = TestDatabase::new("iterator_setup");
@@ -406,6 +490,8 @@ pub fn create_spending_transaction(
         vout.push(TXOutput {
             value,
             pub_key_hash,
+            multisig: None,
+            data: None,
         });
     }
 
@@ -432,6 +518,8 @@ pub fn create_coinbase_transaction(reward: i32, recipient_hash: Vec<u8>) -> Tran
     let coinbase_output = TXOutput {
         value: reward,
         pub_key_hash: recipient_hash,
+        multisig: None,
+        data: None,
     };
 
     // Generate a unique ID for the transaction
@@ -458,11 +546,54 @@ pub fn create_multiple_test_transactions(count: usize) -> Vec<Transaction> {
     let mut transactions = Vec::new();
     for i in 0..count {
         let id = vec![i as u8; 4]; // Create unique IDs
-        transactions.push(create_test_transaction(id));
+        transactions.push(create_independent_test_transaction(id));
     }
     transactions
 }
 
+// =============================================================================
+// LOG CAPTURE HELPERS
+// =============================================================================
+
+static CAPTURED_LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CaptureLogger;
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            CAPTURED_LOGS.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE_LOGGER: CaptureLogger = CaptureLogger;
+
+/// Installs a process-wide logger that records every info-or-higher message.
+/// Safe to call from several tests; only the first call installs the logger.
+pub fn install_capture_logger() {
+    if log::set_logger(&CAPTURE_LOGGER).is_ok() {
+        log::set_max_level(log::LevelFilter::Info);
+    }
+}
+
+/// Returns all captured log messages containing `needle`.
+pub fn captured_logs_containing(needle: &str) -> Vec<String> {
+    CAPTURED_LOGS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|line| line.contains(needle))
+        .cloned()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -485,7 +616,8 @@ mod tests {
 
         assert_eq!(block.get_pre_block_hash(), "test_hash");
         assert_eq!(block.get_height(), 5);
-        assert_eq!(block.get_transactions().len(), 1);
+        assert_eq!(block.get_transactions().len(), 2);
+        assert!(block.validate_coinbase().is_ok());
         assert!(block.get_timestamp() > 0);
     }
 
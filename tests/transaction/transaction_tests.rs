@@ -1,7 +1,12 @@
-use rust_blockchain::{TXInput, TXOutput, Transaction, Blockchain, UTXOSet, wallet::Wallets};
+use std::collections::HashMap;
+
+use data_encoding::HEXLOWER;
+use rust_blockchain::{TXInput, TXOutput, Transaction, Blockchain, Block, UTXOSet, SigCache, GLOBAL_SIG_CACHE, MultiSigLock, LOCK_TIME_THRESHOLD, MAX_DATA_OUTPUT_LEN, wallet::Wallets};
+use rust_blockchain::wallet::{ADDRESS_CHECK_SUM_LEN, Wallet, wallet_util::hash_pub_key};
 
 use crate::test_helpers::{
-    create_output_with_key_hash, create_output_with_value, create_sample_output, TestDatabase
+    create_funded_blockchain, create_output_with_key_hash, create_output_with_value, create_sample_output,
+    setup_temp_test_db, TestDatabase
 };
 
 // Transaction tests
@@ -237,6 +242,8 @@ fn test_txoutput_struct_creation_and_fields() {
     let output = TXOutput {
         value: 42,
         pub_key_hash: vec![0xAB, 0xCD, 0xEF],
+        multisig: None,
+        data: None,
     };
 
     assert_eq!(output.value, 42);
@@ -296,6 +303,8 @@ fn test_complete_transaction_construction() {
     let tx_output = TXOutput {
         value: 100,
         pub_key_hash: vec![1, 2, 3, 4],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(vec![10, 20, 30], vec![tx_input], vec![tx_output]);
@@ -315,10 +324,14 @@ fn test_transaction_with_multiple_inputs_outputs() {
     let tx_output1 = TXOutput {
         value: 50,
         pub_key_hash: vec![1, 2],
+        multisig: None,
+        data: None,
     };
     let tx_output2 = TXOutput {
         value: 30,
         pub_key_hash: vec![3, 4],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(
@@ -341,6 +354,8 @@ fn test_transaction_serialization() {
     let tx_output = TXOutput {
         value: 200,
         pub_key_hash: vec![5, 6, 7, 8],
+        multisig: None,
+        data: None,
     };
 
     let original_transaction = Transaction::new(vec![50, 60, 70], vec![tx_input], vec![tx_output]);
@@ -431,6 +446,8 @@ fn test_transaction_get_vout_single_output() {
     let tx_output = TXOutput {
         value: 100,
         pub_key_hash: vec![1, 2, 3, 4],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(vec![1, 2, 3], vec![], vec![tx_output.clone()]);
@@ -446,14 +463,20 @@ fn test_transaction_get_vout_multiple_outputs() {
     let tx_output1 = TXOutput {
         value: 50,
         pub_key_hash: vec![1, 2],
+        multisig: None,
+        data: None,
     };
     let tx_output2 = TXOutput {
         value: 75,
         pub_key_hash: vec![3, 4],
+        multisig: None,
+        data: None,
     };
     let tx_output3 = TXOutput {
         value: 25,
         pub_key_hash: vec![5, 6],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(
@@ -525,6 +548,8 @@ fn test_transaction_is_coinbase_with_outputs() {
     let tx_output = TXOutput {
         value: 50,
         pub_key_hash: vec![10, 20, 30],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(vec![1, 2, 3], vec![tx_input], vec![tx_output]);
@@ -541,10 +566,14 @@ fn test_transaction_get_methods_consistency() {
     let tx_output1 = TXOutput {
         value: 100,
         pub_key_hash: vec![1, 2, 3],
+        multisig: None,
+        data: None,
     };
     let tx_output2 = TXOutput {
         value: 200,
         pub_key_hash: vec![4, 5, 6],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(
@@ -582,6 +611,8 @@ fn test_transaction_serialize_basic() {
     let tx_output = TXOutput {
         value: 100,
         pub_key_hash: vec![1, 2, 3, 4],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(vec![10, 20, 30], vec![tx_input], vec![tx_output]);
@@ -622,11 +653,15 @@ fn test_transaction_serialize_complex() {
     let tx_output1 = TXOutput {
         value: 250,
         pub_key_hash: vec![1, 2, 3],
+        multisig: None,
+        data: None,
     };
 
     let tx_output2 = TXOutput {
         value: -50,
         pub_key_hash: vec![4, 5, 6, 7, 8],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(
@@ -692,6 +727,8 @@ fn test_transaction_deserialize_basic() {
     let tx_output = TXOutput {
         value: 500,
         pub_key_hash: vec![10, 20],
+        multisig: None,
+        data: None,
     };
 
     let original = Transaction::new(vec![1, 2, 3, 4, 5], vec![tx_input], vec![tx_output]);
@@ -730,6 +767,8 @@ fn test_transaction_try_deserialize_success() {
     let tx_output = TXOutput {
         value: 750,
         pub_key_hash: vec![30, 40, 50],
+        multisig: None,
+        data: None,
     };
 
     let original = Transaction::new(vec![100, 200, 255], vec![tx_input], vec![tx_output]);
@@ -775,6 +814,8 @@ fn test_transaction_try_deserialize_partial_data() {
         vec![TXOutput {
             value: 100,
             pub_key_hash: vec![1, 2],
+            multisig: None,
+            data: None,
         }],
     );
 
@@ -801,6 +842,8 @@ fn test_transaction_trimmed_copy_behavior() {
     let tx_output = TXOutput {
         value: 1000,
         pub_key_hash: vec![200, 210, 220],
+        multisig: None,
+        data: None,
     };
 
     let transaction = Transaction::new(
@@ -850,11 +893,15 @@ fn test_transaction_serialize_deserialize_roundtrip() {
     let tx_output1 = TXOutput {
         value: 12345,
         pub_key_hash: vec![123, 124, 125, 126, 127],
+        multisig: None,
+        data: None,
     };
 
     let tx_output2 = TXOutput {
         value: -6789,
         pub_key_hash: vec![],
+        multisig: None,
+        data: None,
     };
 
     let original = Transaction::new(
@@ -900,6 +947,8 @@ fn test_transaction_deserialize_vs_try_deserialize_consistency() {
         vec![TXOutput {
             value: 888,
             pub_key_hash: vec![55, 56, 57, 58],
+            multisig: None,
+            data: None,
         }],
     );
 
@@ -1090,6 +1139,8 @@ fn test_txoutput_new_vs_manual_construction() {
     let manual_output = TXOutput {
         value,
         pub_key_hash: pub_key_hash.to_vec(),
+        multisig: None,
+        data: None,
     };
     
     assert_eq!(output_new.get_value(), manual_output.get_value());
@@ -1279,7 +1330,7 @@ fn test_new_coinbase_tx_basic() {
     
     // Should have exactly one output
     assert_eq!(coinbase_tx.get_vout().len(), 1);
-    assert_eq!(coinbase_tx.get_vout()[0].value, 10); // SUBSIDY value
+    assert_eq!(coinbase_tx.get_vout()[0].value, 10); // FUNDED_BALANCE value
     
     // Should have a valid transaction ID
     assert!(!coinbase_tx.get_id().is_empty());
@@ -1426,6 +1477,8 @@ fn test_verify_regular_transaction_missing_previous() {
     let tx_output = TXOutput {
         value: 50,
         pub_key_hash: vec![11, 12, 13],
+        multisig: None,
+        data: None,
     };
     
     let transaction = Transaction::new(vec![20, 21, 22], vec![tx_input], vec![tx_output]);
@@ -1443,6 +1496,8 @@ fn test_transaction_hash_consistency() {
     let tx_output = TXOutput {
         value: 100,
         pub_key_hash: vec![4, 5, 6],
+        multisig: None,
+        data: None,
     };
     
     let tx1 = Transaction::new(vec![7, 8, 9], vec![tx_input.clone()], vec![tx_output.clone()]);
@@ -1461,6 +1516,8 @@ fn test_transaction_hash_uniqueness() {
     let tx_output = TXOutput {
         value: 100,
         pub_key_hash: vec![7, 8, 9],
+        multisig: None,
+        data: None,
     };
     
     let tx1 = Transaction::new(vec![10, 11, 12], vec![tx_input1], vec![tx_output.clone()]);
@@ -1504,6 +1561,8 @@ fn test_transaction_creation_with_new_constructor() {
     let tx_output = TXOutput {
         value: 50,
         pub_key_hash: vec![20, 21, 22],
+        multisig: None,
+        data: None,
     };
     
     let transaction = Transaction::new(
@@ -1559,6 +1618,8 @@ fn test_transaction_trimmed_copy_integration() {
     let tx_output = TXOutput {
         value: 100,
         pub_key_hash: vec![30, 31, 32],
+        multisig: None,
+        data: None,
     };
     
     let transaction = Transaction::new(
@@ -1598,7 +1659,7 @@ fn test_coinbase_transaction_properties() {
     assert!(!coinbase_tx.get_vin()[0].signature.is_empty()); // Should have UUID signature
     
     // Output should have correct value and recipient
-    assert_eq!(coinbase_tx.get_vout()[0].value, 10); // SUBSIDY
+    assert_eq!(coinbase_tx.get_vout()[0].value, 10); // FUNDED_BALANCE
     assert!(!coinbase_tx.get_vout()[0].pub_key_hash.is_empty()); // Should have valid pub_key_hash from wallet
     
     // Transaction ID should be properly set
@@ -1658,6 +1719,8 @@ fn test_transaction_hash_determinism() {
     let tx_output = TXOutput {
         value: 100,
         pub_key_hash: vec![4, 5, 6],
+        multisig: None,
+        data: None,
     };
     
     let tx1 = Transaction::new(vec![7, 8, 9], vec![tx_input.clone()], vec![tx_output.clone()]);
@@ -1711,3 +1774,758 @@ fn test_transaction_edge_cases() {
     assert!(special_addr_tx.is_coinbase());
     assert!(!special_addr_tx.get_id().is_empty());
 }
+
+#[test]
+fn test_sign_offline_rejects_mismatched_prev_outputs() {
+    let wallet = rust_blockchain::wallet::Wallet::new();
+    let mut tx = Transaction::new(
+        vec![],
+        vec![TXInput::new(&[1, 2, 3], 0), TXInput::new(&[4, 5, 6], 0)],
+        vec![create_sample_output()],
+    );
+
+    let result = tx.sign_offline(wallet.get_pkcs8(), &[create_sample_output()]);
+    assert!(result.is_err());
+    assert!(tx.get_vin().iter().all(|vin| vin.signature.is_empty()));
+}
+
+#[test]
+fn test_sign_offline_rejects_invalid_key() {
+    let mut tx = Transaction::new(vec![], vec![TXInput::new(&[1, 2, 3], 0)], vec![create_sample_output()]);
+
+    let result = tx.sign_offline(&[1, 2, 3], &[create_sample_output()]);
+    assert!(result.is_err());
+}
+
+// =============================================================================
+// VALIDATE STRUCTURE TESTS
+// =============================================================================
+
+fn signed_input(txid: &[u8], vout: usize) -> TXInput {
+    let mut input = TXInput::new(txid, vout);
+    input.pub_key = vec![1, 2, 3];
+    input.signature = vec![4, 5, 6];
+    input
+}
+
+#[test]
+fn test_validate_structure_accepts_valid_transactions() {
+    let tx = Transaction::new(
+        vec![1],
+        vec![signed_input(&[9, 9], 0)],
+        vec![create_output_with_value(10), create_output_with_value(0)],
+    );
+    assert!(tx.validate_structure().is_ok());
+
+    let coinbase = Transaction::new_coinbase_tx("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2");
+    assert!(coinbase.validate_structure().is_ok());
+}
+
+#[test]
+fn test_validate_structure_rejects_negative_output() {
+    let tx = Transaction::new(
+        vec![1],
+        vec![signed_input(&[9, 9], 0)],
+        vec![create_output_with_value(10), create_output_with_value(-1)],
+    );
+    let err = tx.validate_structure().unwrap_err();
+    assert!(err.contains("negative"));
+}
+
+#[test]
+fn test_validate_structure_rejects_missing_inputs_or_outputs() {
+    let no_inputs = Transaction::new(vec![1], vec![], vec![create_output_with_value(10)]);
+    assert!(no_inputs.validate_structure().unwrap_err().contains("no inputs"));
+
+    let no_outputs = Transaction::new(vec![1], vec![signed_input(&[9, 9], 0)], vec![]);
+    assert!(no_outputs.validate_structure().unwrap_err().contains("no outputs"));
+}
+
+#[test]
+fn test_validate_structure_rejects_coinbase_with_txid() {
+    // An input with no public key marks a coinbase, which must not spend anything
+    let mut input = TXInput::new(&[9, 9], 0);
+    input.pub_key = vec![];
+    let tx = Transaction::new(vec![1], vec![input], vec![create_output_with_value(10)]);
+    assert!(tx.is_coinbase());
+    assert!(tx.validate_structure().is_err());
+}
+
+#[test]
+fn test_validate_structure_sums_outputs_without_overflow() {
+    let outputs = vec![create_output_with_value(i32::MAX); 4];
+    let tx = Transaction::new(vec![1], vec![signed_input(&[9, 9], 0)], outputs);
+    assert!(tx.validate_structure().is_ok());
+}
+
+// =============================================================================
+// MULTI-OUTPUT TRANSACTION TESTS
+// =============================================================================
+
+// Value of the single coinbase output create_funded_blockchain pays the sender
+const FUNDED_BALANCE: i32 = 10;
+
+#[test]
+fn test_new_utxo_transaction_multi_pays_two_recipients() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"multi-sender-two");
+    let alice = Wallet::from_seed(b"multi-alice").get_address();
+    let bob = Wallet::from_seed(b"multi-bob").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+
+    let outputs = vec![(alice.clone(), 3), (bob.clone(), 4)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&sender, &outputs, &utxo_set).unwrap();
+
+    assert_eq!(tx.get_vin().len(), 1);
+    assert_eq!(tx.get_vout().len(), 3);
+    assert_eq!(tx.get_vout()[0].get_value(), 3);
+    assert_eq!(tx.get_vout()[0].get_pub_key_hash(), address_hash(&alice).as_slice());
+    assert_eq!(tx.get_vout()[1].get_value(), 4);
+    assert_eq!(tx.get_vout()[1].get_pub_key_hash(), address_hash(&bob).as_slice());
+    assert!(tx.verify(&blockchain));
+}
+
+#[test]
+fn test_new_utxo_transaction_multi_returns_change_to_sender() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"multi-sender-change");
+    let recipient = Wallet::from_seed(b"multi-recipient").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let outputs = vec![(recipient.clone(), 2), (recipient, 5)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&sender, &outputs, &utxo_set).unwrap();
+
+    let change = tx.get_vout().last().unwrap();
+    assert_eq!(change.get_value(), FUNDED_BALANCE - 7);
+    assert_eq!(change.get_pub_key_hash(), hash_pub_key(sender.get_public_key()).as_slice());
+    let total: i32 = tx.get_vout().iter().map(|out| out.get_value()).sum();
+    assert_eq!(total, FUNDED_BALANCE);
+
+    // Spending the exact balance leaves no change output
+    let exact = vec![(Wallet::from_seed(b"multi-exact").get_address(), FUNDED_BALANCE)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&sender, &exact, &utxo_set).unwrap();
+    assert_eq!(tx.get_vout().len(), 1);
+}
+
+#[test]
+fn test_new_utxo_transaction_multi_rejects_insufficient_funds() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"multi-sender-poor");
+    let recipient = Wallet::from_seed(b"multi-recipient-poor").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let outputs = vec![(recipient.clone(), FUNDED_BALANCE - 4), (recipient, 5)];
+    match Transaction::new_utxo_transaction_from_wallet(&sender, &outputs, &utxo_set) {
+        Err(err) => assert!(err.contains("Not enough funds")),
+        Ok(_) => panic!("Expected insufficient funds error"),
+    }
+}
+
+#[test]
+fn test_new_utxo_transaction_multi_rejects_bad_outputs() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"multi-sender-bad");
+    let recipient = Wallet::from_seed(b"multi-recipient-bad").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let build = |outputs: &[(String, i32)]| {
+        Transaction::new_utxo_transaction_from_wallet(&sender, outputs, &utxo_set)
+    };
+    assert!(build(&[]).is_err());
+    assert!(build(&[(recipient.clone(), 0)]).is_err());
+    assert!(build(&[("not-an-address".to_string(), 1)]).is_err());
+    assert!(build(&[(recipient.clone(), i32::MAX), (recipient, 1)]).is_err());
+}
+
+fn address_hash(address: &str) -> Vec<u8> {
+    let payload = rust_blockchain::util::base58_decode(address);
+    payload[1..payload.len() - ADDRESS_CHECK_SUM_LEN].to_vec()
+}
+
+// =============================================================================
+// ESTIMATE TESTS
+// =============================================================================
+
+#[test]
+fn test_estimate_matches_built_transaction() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"estimate-sender");
+    let recipient = Wallet::from_seed(b"estimate-recipient").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    for amount in [3, FUNDED_BALANCE] {
+        let estimate =
+            Transaction::estimate(sender.get_address().as_str(), recipient.as_str(), amount, &utxo_set)
+                .unwrap();
+        let tx = Transaction::new_utxo_transaction_from_wallet(
+            &sender,
+            &[(recipient.clone(), amount)],
+            &utxo_set,
+        )
+        .unwrap();
+
+        let output_total: i64 = tx.get_vout().iter().map(|out| out.get_value() as i64).sum();
+        assert_eq!(estimate.get_input_count(), tx.get_vin().len());
+        assert_eq!(estimate.get_total_input(), FUNDED_BALANCE as i64);
+        assert_eq!(estimate.get_change(), (FUNDED_BALANCE - amount) as i64);
+        assert_eq!(estimate.get_size(), tx.serialize().len());
+        assert_eq!(estimate.get_fee(), estimate.get_total_input() - output_total);
+        assert_eq!(estimate.get_fee(), 0);
+    }
+}
+
+#[test]
+fn test_estimate_does_not_touch_utxo_set() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"estimate-readonly");
+    let recipient = Wallet::from_seed(b"estimate-readonly-recipient").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain);
+    let supply_before = utxo_set.total_supply();
+
+    Transaction::estimate(sender.get_address().as_str(), recipient.as_str(), 4, &utxo_set).unwrap();
+    assert_eq!(utxo_set.total_supply(), supply_before);
+    assert_eq!(utxo_set.count_transactions(), 1);
+}
+
+#[test]
+fn test_estimate_rejects_invalid_requests() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"estimate-invalid").get_address();
+    let recipient = Wallet::from_seed(b"estimate-invalid-recipient").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.as_str());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let err = Transaction::estimate(&sender, &recipient, FUNDED_BALANCE + 1, &utxo_set).unwrap_err();
+    assert!(err.contains("Not enough funds"));
+    assert!(Transaction::estimate(&sender, &recipient, 0, &utxo_set).is_err());
+    assert!(Transaction::estimate("bogus", &recipient, 1, &utxo_set).is_err());
+    assert!(Transaction::estimate(&sender, "bogus", 1, &utxo_set).is_err());
+}
+
+// =============================================================================
+// SIGNATURE CACHE TESTS
+// =============================================================================
+
+#[test]
+fn test_verify_twice_hits_signature_cache() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"sig-cache-sender");
+    let recipient = Wallet::from_seed(b"sig-cache-recipient").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let outputs = vec![(recipient, 6)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&sender, &outputs, &utxo_set).unwrap();
+
+    assert!(tx.verify(&blockchain));
+    let hits_before = GLOBAL_SIG_CACHE.get_hits();
+    assert!(tx.verify(&blockchain));
+    // Other tests share the global cache, so only a lower bound holds
+    assert!(GLOBAL_SIG_CACHE.get_hits() >= hits_before + tx.get_vin().len() as u64);
+}
+
+#[test]
+fn test_verify_rejects_tampered_signature_after_caching() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"sig-cache-tamper");
+    let recipient = Wallet::from_seed(b"sig-cache-tamper-recipient").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let outputs = vec![(recipient, 3)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&sender, &outputs, &utxo_set).unwrap();
+    assert!(tx.verify(&blockchain));
+
+    let mut tampered = tx.clone();
+    let mut signature = tampered.vin[0].signature.clone();
+    signature[0] ^= 0xff;
+    tampered.vin[0].signature = signature;
+    assert!(!tampered.verify(&blockchain));
+}
+
+#[test]
+fn test_sig_cache_evicts_least_recently_used() {
+    let cache = SigCache::new(2);
+    let key = |n: u8| (vec![n], 0, vec![n]);
+    cache.insert(key(1), true);
+    cache.insert(key(2), false);
+
+    // Touch key 1 so key 2 becomes the least recently used
+    assert_eq!(cache.get(&key(1)), Some(true));
+    cache.insert(key(3), true);
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&key(2)), None);
+    assert_eq!(cache.get(&key(1)), Some(true));
+    assert_eq!(cache.get(&key(3)), Some(true));
+    assert_eq!(cache.get_hits(), 3);
+}
+
+#[test]
+fn test_sig_cache_with_zero_capacity_stores_nothing() {
+    let cache = SigCache::new(0);
+    cache.insert((vec![1], 0, vec![1]), true);
+    assert!(cache.is_empty());
+    assert_eq!(cache.get(&(vec![1], 0, vec![1])), None);
+    assert_eq!(cache.get_hits(), 0);
+}
+
+// =============================================================================
+// MULTISIG TESTS
+// =============================================================================
+
+/// A chain where `funder` has paid its genesis coinbase into a 2-of-3 output of `signers`.
+/// Returns the chain, the funding transaction and the multisig output.
+fn fund_two_of_three(db: &sled::Db, funder: &Wallet, signers: &[Wallet; 3]) -> (Blockchain, Transaction, TXOutput) {
+    let blockchain = create_funded_blockchain(db, funder.get_address().as_str());
+    let genesis = blockchain.get_block(blockchain.get_tip_hash().as_bytes()).unwrap();
+    let coinbase = genesis.get_transactions()[0].clone();
+
+    let lock = MultiSigLock::new(2, signers.iter().map(|w| hash_pub_key(w.get_public_key())).collect()).unwrap();
+    let multisig_out = TXOutput::new_multisig(FUNDED_BALANCE, lock);
+    let mut fund = Transaction::new(vec![], vec![TXInput::new(coinbase.get_id(), 0)], vec![multisig_out.clone()]);
+    fund.sign_offline(funder.get_pkcs8(), &coinbase.get_vout()[..1]).unwrap();
+    assert!(fund.verify(&blockchain));
+
+    let block = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), std::slice::from_ref(&fund), 1);
+    blockchain.add_block(&block);
+    (blockchain, fund, multisig_out)
+}
+
+fn multisig_signers(prefix: &str) -> [Wallet; 3] {
+    [0, 1, 2].map(|n| Wallet::from_seed(format!("{prefix}-{n}").as_bytes()))
+}
+
+fn unsigned_spend(fund: &Transaction, to: &Wallet) -> Transaction {
+    let outputs = vec![TXOutput::new(FUNDED_BALANCE, to.get_address().as_str())];
+    Transaction::new(vec![], vec![TXInput::new(fund.get_id(), 0)], outputs)
+}
+
+#[test]
+fn test_multisig_output_is_locked_with_lock_hash() {
+    let signers = multisig_signers("multisig-lock");
+    let hashes: Vec<Vec<u8>> = signers.iter().map(|w| hash_pub_key(w.get_public_key())).collect();
+    let lock = MultiSigLock::new(2, hashes.clone()).unwrap();
+    let output = TXOutput::new_multisig(5, lock.clone());
+
+    assert_eq!(output.get_pub_key_hash(), lock.lock_hash().as_slice());
+    assert!(output.is_locked_with_key(&lock.lock_hash()));
+    assert!(hashes.iter().all(|hash| !output.is_locked_with_key(hash)));
+    assert_eq!(output.get_multisig().unwrap().get_required(), 2);
+    assert!(TXOutput::new(5, &signers[0].get_address()).get_multisig().is_none());
+}
+
+#[test]
+fn test_multisig_lock_rejects_invalid_thresholds() {
+    let hashes = vec![vec![1; 20], vec![2; 20]];
+    assert!(MultiSigLock::new(0, hashes.clone()).is_err());
+    assert!(MultiSigLock::new(3, hashes.clone()).is_err());
+    assert!(MultiSigLock::new(1, vec![vec![1; 20], vec![1; 20]]).is_err());
+    assert!(MultiSigLock::new(2, hashes).is_ok());
+}
+
+#[test]
+fn test_multisig_two_of_three_spend_with_two_signatures() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let funder = Wallet::from_seed(b"multisig-funder-two");
+    let signers = multisig_signers("multisig-two");
+    let (blockchain, fund, multisig_out) = fund_two_of_three(&db, &funder, &signers);
+
+    let mut spend = unsigned_spend(&fund, &funder);
+    spend.sign_multisig_input(0, signers[0].get_pkcs8(), &multisig_out).unwrap();
+    spend.sign_multisig_input(0, signers[2].get_pkcs8(), &multisig_out).unwrap();
+
+    assert_eq!(spend.get_vin()[0].get_multisig_signatures().len(), 2);
+    assert!(!spend.is_coinbase());
+    assert!(spend.verify(&blockchain));
+}
+
+#[test]
+fn test_multisig_two_of_three_spend_fails_with_one_signature() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let funder = Wallet::from_seed(b"multisig-funder-one");
+    let signers = multisig_signers("multisig-one");
+    let (blockchain, fund, multisig_out) = fund_two_of_three(&db, &funder, &signers);
+
+    let mut spend = unsigned_spend(&fund, &funder);
+    spend.sign_multisig_input(0, signers[1].get_pkcs8(), &multisig_out).unwrap();
+    assert!(!spend.verify(&blockchain));
+
+    // The same participant signing twice still counts once
+    spend.sign_multisig_input(0, signers[1].get_pkcs8(), &multisig_out).unwrap();
+    assert!(!spend.verify(&blockchain));
+}
+
+#[test]
+fn test_multisig_spend_rejects_foreign_or_single_signatures() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let funder = Wallet::from_seed(b"multisig-funder-foreign");
+    let signers = multisig_signers("multisig-foreign");
+    let (blockchain, fund, multisig_out) = fund_two_of_three(&db, &funder, &signers);
+
+    // Non-participants cannot add a signature
+    let mut spend = unsigned_spend(&fund, &funder);
+    assert!(spend.sign_multisig_input(0, funder.get_pkcs8(), &multisig_out).is_err());
+    assert!(spend.sign_multisig_input(1, signers[0].get_pkcs8(), &multisig_out).is_err());
+
+    // A single-sig signature does not unlock a multisig output
+    let mut single = unsigned_spend(&fund, &funder);
+    single.sign_offline(signers[0].get_pkcs8(), std::slice::from_ref(&multisig_out)).unwrap();
+    assert!(!single.verify(&blockchain));
+
+    // Nor can a multisig signature be used on a single-sig output
+    let plain_out = TXOutput::new(1, &signers[0].get_address());
+    assert!(spend.sign_multisig_input(0, signers[0].get_pkcs8(), &plain_out).is_err());
+}
+
+// =============================================================================
+// LOCK TIME TESTS
+// =============================================================================
+
+#[test]
+fn test_transaction_without_lock_time_is_always_final() {
+    let tx = Transaction::new(vec![1], vec![TXInput::new(b"prev", 0)], vec![create_sample_output()]);
+    assert_eq!(tx.get_lock_time(), 0);
+    assert!(tx.is_final(0, 0));
+}
+
+#[test]
+fn test_height_lock_is_final_from_its_height() {
+    let mut tx = Transaction::new(vec![1], vec![TXInput::new(b"prev", 0)], vec![create_sample_output()]);
+    tx.set_lock_time(5);
+    assert!(!tx.is_final(4, i64::MAX));
+    assert!(tx.is_final(5, 0));
+    assert!(tx.is_final(6, 0));
+}
+
+#[test]
+fn test_time_lock_is_final_from_its_timestamp() {
+    let unlock_at = LOCK_TIME_THRESHOLD + 1_000;
+    let mut tx = Transaction::new(vec![1], vec![TXInput::new(b"prev", 0)], vec![create_sample_output()]);
+    tx.set_lock_time(unlock_at);
+    assert!(!tx.is_final(usize::MAX, unlock_at - 1));
+    assert!(tx.is_final(0, unlock_at));
+}
+
+#[test]
+fn test_lock_time_survives_serialization() {
+    let mut tx = Transaction::new(vec![1], vec![TXInput::new(b"prev", 0)], vec![create_sample_output()]);
+    tx.set_lock_time(42);
+    let decoded = Transaction::deserialize(&tx.serialize());
+    assert_eq!(decoded.get_lock_time(), 42);
+}
+
+#[test]
+fn test_lock_time_is_covered_by_signatures() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"lock-time-sender");
+    let recipient = Wallet::from_seed(b"lock-time-recipient").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+
+    let (mut tx, prev_outputs) = utxo_set
+        .build_unsigned(&hash_pub_key(sender.get_public_key()), &[TXOutput::new(4, &recipient)])
+        .unwrap();
+    tx.set_lock_time(3);
+    tx.sign_offline(sender.get_pkcs8(), &prev_outputs).unwrap();
+    assert!(tx.verify(&blockchain));
+
+    // Moving the lock after signing invalidates the signatures
+    let mut moved = tx.clone();
+    moved.set_lock_time(0);
+    assert!(!moved.verify(&blockchain));
+}
+
+// =============================================================================
+// SIGN WITH TESTS
+// =============================================================================
+
+#[test]
+fn test_sign_with_explicit_wallet_verifies() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let funder = Wallet::from_seed(b"sign-with-funder");
+    let recipient = Wallet::from_seed(b"sign-with-recipient");
+    let blockchain = create_funded_blockchain(&db, funder.get_address().as_str());
+    let genesis = blockchain.get_block(blockchain.get_tip_hash().as_bytes()).unwrap();
+    let coinbase = genesis.get_transactions()[0].clone();
+
+    let mut tx = Transaction::new(
+        vec![],
+        vec![TXInput::new(coinbase.get_id(), 0)],
+        vec![TXOutput::new(FUNDED_BALANCE, recipient.get_address().as_str())],
+    );
+    let prev_txs = HashMap::from([(HEXLOWER.encode(coinbase.get_id()), coinbase.clone())]);
+    tx.sign_with(funder.get_pkcs8(), &prev_txs).unwrap();
+
+    assert!(!tx.get_id().is_empty());
+    assert!(tx.verify(&blockchain));
+
+    // The signatures commit to the outputs
+    let mut tampered = tx.clone();
+    tampered.vout[0].value = FUNDED_BALANCE - 1;
+    assert!(!tampered.verify(&blockchain));
+}
+
+#[test]
+fn test_sign_with_missing_prev_tx_errors() {
+    let wallet = Wallet::from_seed(b"sign-with-missing");
+    let known = Transaction::new_coinbase_tx(wallet.get_address().as_str());
+    let mut tx = Transaction::new(
+        vec![],
+        vec![TXInput::new(known.get_id(), 0), TXInput::new(&[7, 7, 7], 0)],
+        vec![create_sample_output()],
+    );
+    let prev_txs = HashMap::from([(HEXLOWER.encode(known.get_id()), known.clone())]);
+
+    let result = tx.sign_with(wallet.get_pkcs8(), &prev_txs);
+    assert!(matches!(result, Err(e) if e.contains("070707")));
+    assert!(tx.get_vin().iter().all(|vin| vin.signature.is_empty()));
+
+    // An output index past the previous transaction's outputs is missing too
+    let mut tx = Transaction::new(vec![], vec![TXInput::new(known.get_id(), 5)], vec![create_sample_output()]);
+    assert!(tx.sign_with(wallet.get_pkcs8(), &prev_txs).is_err());
+}
+
+// =============================================================================
+// VERIFY AGAINST TESTS
+// =============================================================================
+
+#[test]
+fn test_verify_against_prev_tx_map() {
+    let funder = Wallet::from_seed(b"verify-against-funder");
+    let recipient = Wallet::from_seed(b"verify-against-recipient");
+    let coinbase = Transaction::new_coinbase_tx(funder.get_address().as_str());
+    let prev_txs = HashMap::from([(HEXLOWER.encode(coinbase.get_id()), coinbase.clone())]);
+
+    let mut tx = Transaction::new(
+        vec![],
+        vec![TXInput::new(coinbase.get_id(), 0)],
+        vec![TXOutput::new(FUNDED_BALANCE, recipient.get_address().as_str())],
+    );
+    tx.sign_with(funder.get_pkcs8(), &prev_txs).unwrap();
+    assert!(tx.verify_against(&prev_txs));
+
+    let mut tampered = tx.clone();
+    tampered.vout[0].value = FUNDED_BALANCE - 1;
+    assert!(!tampered.verify_against(&prev_txs));
+
+    // Without the previous transaction there is nothing to verify against
+    assert!(!tx.verify_against(&HashMap::new()));
+    assert!(coinbase.verify_against(&HashMap::new()));
+}
+
+// =============================================================================
+// RAW HEX TESTS
+// =============================================================================
+
+#[test]
+fn test_raw_hex_round_trips() {
+    let funder = Wallet::from_seed(b"raw-hex-funder");
+    let coinbase = Transaction::new_coinbase_tx(funder.get_address().as_str());
+    let spend = Transaction::new(
+        vec![],
+        vec![TXInput::new(coinbase.get_id(), 0)],
+        vec![TXOutput::new(7, funder.get_address().as_str())],
+    );
+
+    for tx in [coinbase, spend] {
+        let decoded = Transaction::from_raw_hex(&tx.to_raw_hex()).unwrap();
+        assert_eq!(decoded.get_id(), tx.get_id());
+        assert_eq!(decoded.is_coinbase(), tx.is_coinbase());
+        assert_eq!(decoded.get_vin().len(), tx.get_vin().len());
+        assert_eq!(decoded.get_vout()[0].get_value(), tx.get_vout()[0].get_value());
+    }
+
+    // Uppercase hex and surrounding whitespace are accepted
+    let tx = Transaction::new_coinbase_tx(funder.get_address().as_str());
+    let upper = format!(" {}\n", tx.to_raw_hex().to_uppercase());
+    assert_eq!(Transaction::from_raw_hex(&upper).unwrap().get_id(), tx.get_id());
+}
+
+#[test]
+fn test_from_raw_hex_rejects_invalid_input() {
+    let error = |hex: &str| Transaction::from_raw_hex(hex).err().unwrap();
+    assert!(error("not hex").starts_with("invalid hex"));
+    assert!(error("abc").starts_with("invalid hex"));
+    assert!(error("ffff").starts_with("not a transaction"));
+
+    let tx = Transaction::new_coinbase_tx(Wallet::from_seed(b"raw-hex-trailing").get_address().as_str());
+    let padded = format!("{}00", tx.to_raw_hex());
+    assert_eq!(error(&padded), "1 trailing bytes after the transaction");
+}
+
+// =============================================================================
+// BURN OUTPUT TESTS
+// =============================================================================
+
+#[test]
+fn test_new_burn_output_is_locked_with_no_key() {
+    let burn = TXOutput::new_burn(30);
+    assert!(burn.is_burn());
+    assert_eq!(burn.get_value(), 30);
+    assert!(!burn.is_locked_with_key(burn.get_pub_key_hash()));
+    assert!(!burn.is_locked_with_key(&[]));
+
+    let wallet = Wallet::from_seed(b"burn-output-wallet");
+    let regular = TXOutput::new(30, wallet.get_address().as_str());
+    assert!(!regular.is_burn());
+    assert!(regular.is_locked_with_key(regular.get_pub_key_hash()));
+}
+
+// =============================================================================
+// DATA OUTPUT TESTS
+// =============================================================================
+
+#[test]
+fn test_data_output_round_trips_through_serialization() {
+    let funder = Wallet::from_seed(b"data-output-funder");
+    let coinbase = Transaction::new_coinbase_tx(funder.get_address().as_str());
+    let tx = Transaction::new(
+        vec![],
+        vec![TXInput::new(coinbase.get_id(), 0)],
+        vec![
+            TXOutput::new(FUNDED_BALANCE, funder.get_address().as_str()),
+            TXOutput::new_data(b"hello, chain").unwrap(),
+        ],
+    );
+
+    let decoded = Transaction::deserialize(&tx.serialize());
+    let data_out = &decoded.get_vout()[1];
+    assert!(data_out.is_data());
+    assert_eq!(data_out.get_data(), Some(&b"hello, chain"[..]));
+    assert_eq!(data_out.get_value(), 0);
+    assert!(!data_out.is_locked_with_key(data_out.get_pub_key_hash()));
+    assert!(decoded.get_vout()[0].get_data().is_none());
+}
+
+#[test]
+fn test_new_data_output_limits_payload_size() {
+    assert!(TXOutput::new_data(&[7; MAX_DATA_OUTPUT_LEN]).is_ok());
+    assert!(TXOutput::new_data(&[]).is_ok());
+    let err = TXOutput::new_data(&[7; MAX_DATA_OUTPUT_LEN + 1]).err().unwrap();
+    assert!(err.contains("at most"));
+}
+
+// =============================================================================
+// DUST OUTPUT TESTS
+// =============================================================================
+
+#[test]
+fn test_is_dust_compares_spendable_value_with_threshold() {
+    let wallet = Wallet::from_seed(b"dust-output-wallet");
+    let output = TXOutput::new(5, wallet.get_address().as_str());
+    assert!(output.is_dust(6));
+    assert!(!output.is_dust(5));
+    assert!(!output.is_dust(1));
+    assert!(TXOutput::new(0, wallet.get_address().as_str()).is_dust(1));
+
+    // Outputs nobody can spend never clutter the UTXO set, whatever they carry
+    assert!(!TXOutput::new_burn(1).is_dust(100));
+    assert!(!TXOutput::new_data(b"note").unwrap().is_dust(100));
+}
+
+// =============================================================================
+// CANONICAL ORDERING TESTS
+// =============================================================================
+
+#[test]
+fn test_new_canonical_ignores_build_order() {
+    let alice = Wallet::from_seed(b"canonical-alice").get_address();
+    let bob = Wallet::from_seed(b"canonical-bob").get_address();
+    let inputs = vec![
+        TXInput::new(&[2, 2], 0),
+        TXInput::new(&[1, 1], 3),
+        TXInput::new(&[1, 1], 1),
+    ];
+    let outputs = vec![
+        TXOutput::new(30, alice.as_str()),
+        TXOutput::new(10, bob.as_str()),
+        TXOutput::new(10, alice.as_str()),
+    ];
+    let mut reversed_inputs = inputs.clone();
+    reversed_inputs.reverse();
+    let mut reversed_outputs = outputs.clone();
+    reversed_outputs.reverse();
+
+    let canonical = Transaction::new_canonical(inputs.clone(), outputs.clone());
+    let reordered = Transaction::new_canonical(reversed_inputs, reversed_outputs);
+    assert!(!canonical.get_id().is_empty());
+    assert_eq!(canonical.get_id(), reordered.get_id());
+    let order: Vec<(Vec<u8>, usize)> = canonical
+        .get_vin()
+        .iter()
+        .map(|vin| (vin.get_txid().to_vec(), vin.get_vout()))
+        .collect();
+    assert_eq!(order, vec![(vec![1, 1], 1), (vec![1, 1], 3), (vec![2, 2], 0)]);
+    let values: Vec<i32> = canonical.get_vout().iter().map(|out| out.get_value()).collect();
+    assert_eq!(values, vec![10, 10, 30]);
+
+    // A different set of outputs is not equivalent and hashes differently
+    let other = Transaction::new_canonical(inputs, outputs[..2].to_vec());
+    assert_ne!(canonical.get_id(), other.get_id());
+}
+
+// =============================================================================
+// COINBASE DATA TESTS
+// =============================================================================
+
+#[test]
+fn test_new_coinbase_tx_with_data_stores_extra_nonce_and_message() {
+    let miner = Wallet::new().get_address();
+    let tx = Transaction::new_coinbase_tx_with_data(miner.as_str(), 25, b"miner tag", 42);
+    assert!(tx.is_coinbase());
+    assert_eq!(tx.get_vout()[0].get_value(), 25);
+
+    let mut expected = 42u64.to_le_bytes().to_vec();
+    expected.extend_from_slice(b"miner tag");
+    assert_eq!(tx.get_vin()[0].signature, expected);
+
+    // The data survives a serialization round trip and is covered by the id
+    let decoded = Transaction::deserialize(&tx.serialize());
+    assert_eq!(decoded.get_vin()[0].signature, expected);
+    assert_eq!(decoded.get_id(), tx.get_id());
+    assert!(decoded.is_coinbase());
+}
+
+#[test]
+fn test_new_coinbase_tx_with_data_extra_nonce_changes_id() {
+    let miner = Wallet::new().get_address();
+    let first = Transaction::new_coinbase_tx_with_data(miner.as_str(), 10, b"tag", 0);
+    let same = Transaction::new_coinbase_tx_with_data(miner.as_str(), 10, b"tag", 0);
+    let bumped = Transaction::new_coinbase_tx_with_data(miner.as_str(), 10, b"tag", 1);
+    assert_eq!(first.get_id(), same.get_id());
+    assert_ne!(first.get_id(), bumped.get_id());
+}
+
+// =============================================================================
+// SIGNATURE MALLEABILITY TESTS
+// =============================================================================
+
+#[test]
+fn test_sign_produces_low_s_and_verify_rejects_malleated_signature() {
+    use p256::{FieldBytes, Scalar, elliptic_curve::ff::PrimeField};
+    use rust_blockchain::util::is_low_s_signature;
+
+    let (db, _temp_dir) = setup_temp_test_db();
+    let sender = Wallet::from_seed(b"malleability-sender");
+    let recipient = Wallet::from_seed(b"malleability-recipient").get_address();
+    let blockchain = create_funded_blockchain(&db, sender.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let outputs = vec![(recipient, 4)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&sender, &outputs, &utxo_set).unwrap();
+    assert!(tx.get_vin().iter().all(|vin| is_low_s_signature(&vin.signature)));
+    assert!(tx.verify(&blockchain));
+
+    // Negating s gives a signature ECDSA alone would accept for the same transaction
+    let mut malleated = tx.clone();
+    let signature = malleated.vin[0].signature.clone();
+    let s = Scalar::from_repr(FieldBytes::try_from(&signature[32..]).unwrap()).unwrap();
+    let mut high_s = signature[..32].to_vec();
+    high_s.extend_from_slice(&(-s).to_repr());
+    malleated.vin[0].signature = high_s;
+    assert!(!malleated.verify(&blockchain));
+}
@@ -1,4 +1,5 @@
-use rust_blockchain::{Block, Blockchain, TXOutput, UTXOSet, BLOCKS_TREE, TIP_BLOCK_HASH_KEY};
+use rust_blockchain::{Block, Blockchain, BURN_PUB_KEY_HASH_LEN, MAX_UNDO_DEPTH, MemoryPool, SUBSIDY, TXOutput, Transaction, UTXOSet, BLOCKS_TREE, GENESIS_PRE_BLOCK_HASH, TIP_BLOCK_HASH_KEY};
+use rust_blockchain::wallet::{Wallet, wallet_util::hash_pub_key};
 use crate::test_helpers::*;
 
 // =============================================================================
@@ -65,9 +66,9 @@ fn test_find_spendable_outputs_sufficient_balance() {
     // Create and add UTXOs to the database
     let pub_key_hash = vec![1, 2, 3, 4, 5];
     let outputs = vec![
-        TXOutput { value: 50, pub_key_hash: pub_key_hash.clone() },
-        TXOutput { value: 30, pub_key_hash: pub_key_hash.clone() },
-        TXOutput { value: 20, pub_key_hash: pub_key_hash.clone() },
+        TXOutput { value: 50, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+        TXOutput { value: 30, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+        TXOutput { value: 20, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
     ];
     
     let txid = vec![1, 2, 3, 4];
@@ -92,8 +93,8 @@ fn test_find_spendable_outputs_insufficient_balance() {
     
     let pub_key_hash = vec![1, 2, 3, 4, 5];
     let outputs = vec![
-        TXOutput { value: 30, pub_key_hash: pub_key_hash.clone() },
-        TXOutput { value: 20, pub_key_hash: pub_key_hash.clone() },
+        TXOutput { value: 30, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+        TXOutput { value: 20, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
     ];
     
     let txid = vec![1, 2, 3, 4];
@@ -115,7 +116,7 @@ fn test_find_spendable_outputs_wrong_key_hash() {
     let pub_key_hash = vec![1, 2, 3, 4, 5];
     let different_key_hash = vec![6, 7, 8, 9, 10];
     let outputs = vec![
-        TXOutput { value: 50, pub_key_hash: different_key_hash.clone() },
+        TXOutput { value: 50, pub_key_hash: different_key_hash.clone(), multisig: None, data: None },
     ];
     
     let txid = vec![1, 2, 3, 4];
@@ -139,14 +140,14 @@ fn test_find_spendable_outputs_multiple_transactions() {
     // Add UTXOs from first transaction
     let txid1 = vec![1, 1, 1, 1];
     let outputs1 = vec![
-        TXOutput { value: 40, pub_key_hash: pub_key_hash.clone() },
+        TXOutput { value: 40, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
     ];
     add_utxos_to_db(&utxo_set, &txid1, &outputs1);
     
     // Add UTXOs from second transaction
     let txid2 = vec![2, 2, 2, 2];
     let outputs2 = vec![
-        TXOutput { value: 35, pub_key_hash: pub_key_hash.clone() },
+        TXOutput { value: 35, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
     ];
     add_utxos_to_db(&utxo_set, &txid2, &outputs2);
     
@@ -161,6 +162,74 @@ fn test_find_spendable_outputs_multiple_transactions() {
     assert!(spendable_outputs.contains_key(&txid2_hex));
 }
 
+#[test]
+fn test_find_spendable_outputs_detailed_stops_once_amount_covered() {
+    let test_db = TestDatabase::new("spendable_detailed_stops");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let pub_key_hash = vec![1, 2, 3];
+    // sled iterates keys in order, so txid [1] is considered first
+    add_utxos_to_db(&utxo_set, &[1], &[TXOutput { value: 10, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None }]);
+    add_utxos_to_db(&utxo_set, &[2], &[
+        TXOutput { value: 4, pub_key_hash: vec![9, 9], multisig: None, data: None },
+        TXOutput { value: 8, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+    ]);
+    add_utxos_to_db(&utxo_set, &[3], &[TXOutput { value: 20, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None }]);
+
+    let (accumulated, selected) = utxo_set.find_spendable_outputs_detailed(&pub_key_hash, 15);
+    assert_eq!(accumulated, 18);
+    assert_eq!(selected, vec![("01".to_string(), 0, 10), ("02".to_string(), 1, 8)]);
+
+    // An amount covered by the first output selects only that one
+    let (accumulated, selected) = utxo_set.find_spendable_outputs_detailed(&pub_key_hash, 10);
+    assert_eq!(accumulated, 10);
+    assert_eq!(selected.len(), 1);
+}
+
+#[test]
+fn test_find_spendable_outputs_detailed_insufficient_funds() {
+    let test_db = TestDatabase::new("spendable_detailed_insufficient");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let pub_key_hash = vec![4, 5];
+    add_utxos_to_db(&utxo_set, &[1], &[
+        TXOutput { value: 3, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+        TXOutput { value: 6, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+    ]);
+
+    // Every matching output is returned when they cannot cover the amount
+    let (accumulated, selected) = utxo_set.find_spendable_outputs_detailed(&pub_key_hash, 100);
+    assert_eq!(accumulated, 9);
+    assert_eq!(selected, vec![("01".to_string(), 0, 3), ("01".to_string(), 1, 6)]);
+
+    let (accumulated, selected) = utxo_set.find_spendable_outputs_detailed(&[7, 7], 1);
+    assert_eq!(accumulated, 0);
+    assert!(selected.is_empty());
+}
+
+#[test]
+fn test_find_spendable_outputs_matches_detailed_selection() {
+    let test_db = TestDatabase::new("spendable_matches_detailed");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let pub_key_hash = vec![6];
+    add_utxos_to_db(&utxo_set, &[1], &[
+        TXOutput { value: 5, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+        TXOutput { value: 5, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+    ]);
+    add_utxos_to_db(&utxo_set, &[2], &[TXOutput { value: 5, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None }]);
+
+    let (accumulated, outputs) = utxo_set.find_spendable_outputs(&pub_key_hash, 12);
+    let (detailed_accumulated, detailed) = utxo_set.find_spendable_outputs_detailed(&pub_key_hash, 12);
+    assert_eq!(accumulated as i64, detailed_accumulated);
+    assert_eq!(outputs["01"], vec![0, 1]);
+    assert_eq!(outputs["02"], vec![0]);
+    assert_eq!(detailed.len(), 3);
+}
+
 // =============================================================================
 // FIND_UTXO TESTS
 // =============================================================================
@@ -187,8 +256,8 @@ fn test_find_utxo_matching_outputs() {
     
     let pub_key_hash = vec![1, 2, 3, 4, 5];
     let outputs = vec![
-        TXOutput { value: 50, pub_key_hash: pub_key_hash.clone() },
-        TXOutput { value: 30, pub_key_hash: pub_key_hash.clone() },
+        TXOutput { value: 50, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
+        TXOutput { value: 30, pub_key_hash: pub_key_hash.clone(), multisig: None, data: None },
     ];
     
     let txid = vec![1, 2, 3, 4];
@@ -212,7 +281,7 @@ fn test_find_utxo_no_matching_key() {
     let pub_key_hash = vec![1, 2, 3, 4, 5];
     let different_key_hash = vec![6, 7, 8, 9, 10];
     let outputs = vec![
-        TXOutput { value: 50, pub_key_hash: different_key_hash },
+        TXOutput { value: 50, pub_key_hash: different_key_hash, multisig: None, data: None },
     ];
     
     let txid = vec![1, 2, 3, 4];
@@ -233,9 +302,9 @@ fn test_find_utxo_mixed_key_hashes() {
     let target_key_hash = vec![1, 2, 3, 4, 5];
     let other_key_hash = vec![6, 7, 8, 9, 10];
     let outputs = vec![
-        TXOutput { value: 50, pub_key_hash: target_key_hash.clone() },
-        TXOutput { value: 30, pub_key_hash: other_key_hash },
-        TXOutput { value: 25, pub_key_hash: target_key_hash.clone() },
+        TXOutput { value: 50, pub_key_hash: target_key_hash.clone(), multisig: None, data: None },
+        TXOutput { value: 30, pub_key_hash: other_key_hash, multisig: None, data: None },
+        TXOutput { value: 25, pub_key_hash: target_key_hash.clone(), multisig: None, data: None },
     ];
     
     let txid = vec![1, 2, 3, 4];
@@ -272,7 +341,7 @@ fn test_count_transactions_single() {
     let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
     let utxo_set = UTXOSet::new(blockchain);
     
-    let outputs = vec![TXOutput { value: 50, pub_key_hash: vec![1, 2, 3] }];
+    let outputs = vec![TXOutput { value: 50, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }];
     let txid = vec![1, 2, 3, 4];
     add_utxos_to_db(&utxo_set, &txid, &outputs);
     
@@ -289,7 +358,7 @@ fn test_count_transactions_multiple() {
     
     // Add multiple transactions
     for i in 0..5 {
-        let outputs = vec![TXOutput { value: 50, pub_key_hash: vec![1, 2, 3] }];
+        let outputs = vec![TXOutput { value: 50, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }];
         let txid = vec![i, i, i, i];
         add_utxos_to_db(&utxo_set, &txid, &outputs);
     }
@@ -310,7 +379,7 @@ fn test_reindex_empty_blockchain() {
     let utxo_set = UTXOSet::new(blockchain);
     
     // Add some UTXOs to the UTXO set first
-    let outputs = vec![TXOutput { value: 50, pub_key_hash: vec![1, 2, 3] }];
+    let outputs = vec![TXOutput { value: 50, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }];
     let txid = vec![1, 2, 3, 4];
     add_utxos_to_db(&utxo_set, &txid, &outputs);
     
@@ -395,7 +464,7 @@ fn test_update_with_spending_transaction() {
     
     // First, create and add a UTXO to spend
     let initial_outputs = vec![
-        TXOutput { value: 100, pub_key_hash: vec![1, 2, 3] }
+        TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }
     ];
     let initial_txid = vec![9, 9, 9, 9];
     add_utxos_to_db(&utxo_set, &initial_txid, &initial_outputs);
@@ -441,9 +510,9 @@ fn test_update_partial_spending() {
     
     // Create a transaction with multiple outputs
     let initial_outputs = vec![
-        TXOutput { value: 50, pub_key_hash: vec![1, 1, 1] },
-        TXOutput { value: 30, pub_key_hash: vec![2, 2, 2] },
-        TXOutput { value: 20, pub_key_hash: vec![3, 3, 3] },
+        TXOutput { value: 50, pub_key_hash: vec![1, 1, 1], multisig: None, data: None },
+        TXOutput { value: 30, pub_key_hash: vec![2, 2, 2], multisig: None, data: None },
+        TXOutput { value: 20, pub_key_hash: vec![3, 3, 3], multisig: None, data: None },
     ];
     let initial_txid = vec![8, 8, 8, 8];
     add_utxos_to_db(&utxo_set, &initial_txid, &initial_outputs);
@@ -497,7 +566,7 @@ fn test_update_complete_spending() {
     
     // Create a transaction with a single output
     let initial_outputs = vec![
-        TXOutput { value: 100, pub_key_hash: vec![1, 2, 3] },
+        TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None },
     ];
     let initial_txid = vec![7, 7, 7, 7];
     add_utxos_to_db(&utxo_set, &initial_txid, &initial_outputs);
@@ -530,6 +599,795 @@ fn test_update_complete_spending() {
     assert_eq!(new_utxos[0].value, 100);
 }
 
+// =============================================================================
+// IS_SPENDABLE TESTS
+// =============================================================================
+
+#[test]
+fn test_is_spendable_existing_output() {
+    let test_name = "is_spendable_existing";
+    let test_db = TestDatabase::new(test_name);
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let txid = vec![1, 2, 3, 4];
+    add_utxos_to_db(&utxo_set, &txid, &[
+        TXOutput { value: 50, pub_key_hash: vec![1], multisig: None, data: None },
+        TXOutput { value: 30, pub_key_hash: vec![2], multisig: None, data: None },
+    ]);
+
+    assert!(utxo_set.is_spendable(&txid, 0));
+    assert!(utxo_set.is_spendable(&txid, 1));
+    assert!(!utxo_set.is_spendable(&txid, 2));
+    assert!(!utxo_set.is_spendable(&[9, 9, 9, 9], 0));
+}
+
+#[test]
+fn test_is_spendable_after_spend() {
+    let test_name = "is_spendable_after_spend";
+    let test_db = TestDatabase::new(test_name);
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let txid = vec![5, 5, 5, 5];
+    add_utxos_to_db(&utxo_set, &txid, &[TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }]);
+    assert!(utxo_set.is_spendable(&txid, 0));
+
+    let spending_tx = create_spending_transaction(vec![(txid.clone(), 0)], vec![(100, vec![4, 5, 6])]);
+    let block = Block::new_block_without_proof_of_work(
+        "prev_hash".to_string(),
+        std::slice::from_ref(&spending_tx),
+        1,
+    );
+    utxo_set.update(&block);
+
+    assert!(!utxo_set.is_spendable(&txid, 0));
+    assert!(utxo_set.is_spendable(spending_tx.get_id(), 0));
+}
+
+#[test]
+fn test_output_indexes_survive_sibling_spend() {
+    let test_db = TestDatabase::new("output_indexes_sibling_spend");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain).with_coinbase_maturity(0);
+
+    let txid = vec![6, 6, 6, 6];
+    add_utxos_to_db(&utxo_set, &txid, &[
+        TXOutput { value: 10, pub_key_hash: vec![1], multisig: None, data: None },
+        TXOutput { value: 20, pub_key_hash: vec![2], multisig: None, data: None },
+        TXOutput { value: 30, pub_key_hash: vec![3], multisig: None, data: None },
+    ]);
+    let spending_tx = create_spending_transaction(vec![(txid.clone(), 0)], vec![(10, vec![4])]);
+    let block = Block::new_block_without_proof_of_work(
+        "prev_hash".to_string(),
+        std::slice::from_ref(&spending_tx),
+        1,
+    );
+    utxo_set.update(&block);
+
+    // Spending output 0 leaves the others at the positions inputs reference them by
+    assert!(!utxo_set.is_spendable(&txid, 0));
+    assert_eq!(utxo_set.get_output(&txid, 1).unwrap().get_value(), 20);
+    assert_eq!(utxo_set.get_output(&txid, 2).unwrap().get_value(), 30);
+    let (_, selected) = utxo_set.find_spendable_outputs(&[3], 30);
+    assert_eq!(selected[&data_encoding::HEXLOWER.encode(&txid)], vec![2]);
+
+    // Spending one of them afterwards still hits the intended output
+    let second_tx = create_spending_transaction(vec![(txid.clone(), 2)], vec![(30, vec![5])]);
+    let block = Block::new_block_without_proof_of_work(
+        "prev_hash".to_string(),
+        std::slice::from_ref(&second_tx),
+        2,
+    );
+    utxo_set.update(&block);
+    assert_eq!(utxo_set.get_output(&txid, 1).unwrap().get_value(), 20);
+    assert!(!utxo_set.is_spendable(&txid, 2));
+    assert_eq!(utxo_set.find_utxo(&[2]).len(), 1);
+    assert!(utxo_set.find_utxo(&[3]).is_empty());
+}
+
+// =============================================================================
+// BUILD_UNSIGNED TESTS
+// =============================================================================
+
+#[test]
+fn test_build_unsigned_sign_offline_and_verify() {
+    let test_db = TestDatabase::new("build_unsigned_offline");
+    let sender = Wallet::new();
+    let recipient = Wallet::new();
+    let blockchain = create_funded_blockchain(test_db.get_db(), &sender.get_address());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let sender_hash = hash_pub_key(sender.get_public_key());
+
+    let (mut tx, prev_outputs) = utxo_set
+        .build_unsigned(&sender_hash, &[TXOutput::new(4, &recipient.get_address())])
+        .expect("sender has enough funds");
+
+    // The bundle carries one previous output per input and pays change back to the sender
+    assert_eq!(tx.get_vin().len(), prev_outputs.len());
+    assert!(prev_outputs.iter().all(|out| out.is_locked_with_key(&sender_hash)));
+    assert_eq!(tx.get_vout().len(), 2);
+    assert_eq!(tx.get_vout()[1].get_value(), 6);
+    assert!(tx.get_vout()[1].is_locked_with_key(&sender_hash));
+
+    // Signing only needs the key and the bundle
+    tx.sign_offline(sender.get_pkcs8(), &prev_outputs).unwrap();
+    assert!(!tx.get_id().is_empty());
+    assert!(tx.verify(&blockchain));
+}
+
+#[test]
+fn test_build_unsigned_insufficient_funds() {
+    let test_db = TestDatabase::new("build_unsigned_insufficient");
+    let sender = Wallet::new();
+    let blockchain = create_funded_blockchain(test_db.get_db(), &sender.get_address());
+    let utxo_set = UTXOSet::new(blockchain);
+    let sender_hash = hash_pub_key(sender.get_public_key());
+
+    let result = utxo_set.build_unsigned(&sender_hash, &[TXOutput::new(1000, &sender.get_address())]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_bundle_file_round_trips_through_offline_signing() {
+    let test_db = TestDatabase::new("bundle_round_trip");
+    let sender = Wallet::new();
+    let recipient = Wallet::new();
+    let blockchain = create_funded_blockchain(test_db.get_db(), &sender.get_address());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let sender_hash = hash_pub_key(sender.get_public_key());
+    let (tx, prev_outputs) = utxo_set
+        .build_unsigned(&sender_hash, &[TXOutput::new(4, &recipient.get_address())])
+        .unwrap();
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("bundle");
+    Transaction::save_bundle(&path, &tx, &prev_outputs).unwrap();
+
+    // The signer sees only the file, and writes the signed bundle back in place
+    let (mut unsigned, bundled_outputs) = Transaction::load_bundle(&path).unwrap();
+    assert_eq!(unsigned.serialize(), tx.serialize());
+    assert_eq!(bundled_outputs.len(), prev_outputs.len());
+    unsigned.sign_offline(sender.get_pkcs8(), &bundled_outputs).unwrap();
+    Transaction::save_bundle(&path, &unsigned, &bundled_outputs).unwrap();
+
+    let (signed, _) = Transaction::load_bundle(&path).unwrap();
+    assert_eq!(signed.get_id(), unsigned.get_id());
+    assert!(signed.verify(&blockchain));
+}
+
+#[test]
+fn test_load_bundle_rejects_garbage() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("bundle");
+    std::fs::write(&path, [0xff; 3]).unwrap();
+
+    let err = Transaction::load_bundle(&path).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(Transaction::load_bundle(&dir.path().join("missing")).is_err());
+}
+
+// =============================================================================
+// SUPPLY INVARIANT TESTS
+// =============================================================================
+
+#[test]
+fn test_total_supply_sums_all_outputs() {
+    let test_name = "total_supply_sums";
+    let test_db = TestDatabase::new(test_name);
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    assert_eq!(utxo_set.total_supply(), 0);
+
+    add_utxos_to_db(&utxo_set, &[1, 1, 1, 1], &[
+        TXOutput { value: 50, pub_key_hash: vec![1], multisig: None, data: None },
+        TXOutput { value: 30, pub_key_hash: vec![2], multisig: None, data: None },
+    ]);
+    add_utxos_to_db(&utxo_set, &[2, 2, 2, 2], &[TXOutput { value: 20, pub_key_hash: vec![3], multisig: None, data: None }]);
+
+    assert_eq!(utxo_set.total_supply(), 100);
+}
+
+#[test]
+fn test_supply_invariant_holds_for_correct_block() {
+    let test_name = "supply_invariant_holds";
+    let test_db = TestDatabase::new(test_name);
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let initial_txid = vec![6, 6, 6, 6];
+    add_utxos_to_db(&utxo_set, &initial_txid, &[TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }]);
+
+    let spending_tx = create_spending_transaction(
+        vec![(initial_txid.clone(), 0)],
+        vec![(60, vec![4, 5, 6]), (40, vec![7, 8, 9])],
+    );
+    let coinbase_tx = create_coinbase_transaction(10, vec![1, 2, 3]);
+    let block = Block::new_block_without_proof_of_work(
+        "prev_hash".to_string(),
+        &[spending_tx, coinbase_tx],
+        1,
+    );
+
+    let supply_before = utxo_set.total_supply();
+    utxo_set.update(&block);
+
+    assert_eq!(utxo_set.total_supply(), supply_before + 10);
+    assert!(utxo_set.check_supply_invariant(supply_before, &block));
+}
+
+#[test]
+fn test_supply_invariant_holds_for_fee_paying_block() {
+    let (db, _temp_dir) = setup_temp_test_db();
+    let blockchain = create_funded_blockchain(&db, "supply_invariant_fee_address");
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let genesis = blockchain.get_block(blockchain.get_tip_hash().as_bytes()).unwrap();
+
+    // Spends the genesis reward leaving 3 as a fee the coinbase does not claim
+    let fee_paying = create_spending_transaction(
+        vec![(genesis.get_transactions()[0].get_id().to_vec(), 0)],
+        vec![(SUBSIDY - 3, vec![4, 5, 6])],
+    );
+    let coinbase_tx = create_coinbase_transaction(SUBSIDY, vec![1, 2, 3]);
+    let block = Block::new_block_without_proof_of_work(blockchain.get_tip_hash(), &[coinbase_tx, fee_paying], 1);
+
+    let supply_before = utxo_set.total_supply();
+    utxo_set.update(&block);
+
+    assert_eq!(utxo_set.total_supply(), supply_before + SUBSIDY as i64 - 3);
+    assert!(utxo_set.check_supply_invariant(supply_before, &block));
+}
+
+#[test]
+fn test_supply_invariant_trips_on_miscomputed_update() {
+    let test_name = "supply_invariant_trips";
+    let test_db = TestDatabase::new(test_name);
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let coinbase_tx = create_coinbase_transaction(10, vec![1, 2, 3]);
+    let block = Block::generate_genesis_block(&coinbase_tx);
+
+    let supply_before = utxo_set.total_supply();
+    utxo_set.update(&block);
+
+    // Simulate a buggy update that left an extra output behind
+    add_utxos_to_db(&utxo_set, &[9, 9, 9, 9], &[TXOutput { value: 5, pub_key_hash: vec![1], multisig: None, data: None }]);
+
+    assert!(!utxo_set.check_supply_invariant(supply_before, &block));
+}
+
+// =============================================================================
+// ROLLBACK AND REORGANIZATION TESTS
+// =============================================================================
+
+fn test_block(hash: &str, pre_hash: &str, height: usize, txs: &[Transaction]) -> Block {
+    let mut block = Block::new_block_without_proof_of_work(pre_hash.to_string(), txs, height);
+    block.set_hash_for_test(hash);
+    block
+}
+
+#[test]
+fn test_rollback_restores_state_before_update() {
+    let test_db = TestDatabase::new("utxo_rollback");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let initial_txid = vec![7, 7, 7, 7];
+    add_utxos_to_db(&utxo_set, &initial_txid, &[
+        TXOutput { value: 60, pub_key_hash: vec![1], multisig: None, data: None },
+        TXOutput { value: 40, pub_key_hash: vec![2], multisig: None, data: None },
+    ]);
+    let spending_tx = create_spending_transaction(vec![(initial_txid.clone(), 0)], vec![(60, vec![3])]);
+    let coinbase_tx = create_coinbase_transaction(10, vec![4]);
+    let block = test_block("rollback_block", "prev_hash", 1, &[spending_tx.clone(), coinbase_tx.clone()]);
+
+    utxo_set.update(&block);
+    assert_eq!(utxo_set.find_utxo(&[1]).len(), 0);
+    assert_eq!(utxo_set.find_utxo(&[3]).len(), 1);
+
+    assert!(utxo_set.rollback(&block));
+    assert_eq!(utxo_set.find_utxo(&[1]).len(), 1);
+    assert_eq!(utxo_set.find_utxo(&[2]).len(), 1);
+    assert!(utxo_set.find_utxo(&[3]).is_empty());
+    assert!(utxo_set.find_utxo(&[4]).is_empty());
+    assert!(!utxo_set.is_spendable(spending_tx.get_id(), 0));
+    assert!(!utxo_set.is_spendable(coinbase_tx.get_id(), 0));
+    assert_eq!(utxo_set.total_supply(), 100);
+
+    // Undo data is consumed by the rollback
+    assert!(!utxo_set.rollback(&block));
+}
+
+#[test]
+fn test_reorg_to_longer_fork_matches_winning_branch() {
+    let test_db = TestDatabase::new("utxo_reorg_fork");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+
+    let genesis_cb = create_coinbase_transaction(10, vec![1]);
+    let genesis = test_block("fork_genesis", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&genesis_cb));
+    assert!(blockchain.add_block(&genesis).is_none());
+    utxo_set.update(&genesis);
+
+    // Branch A: spends the genesis coinbase to [2]
+    let spend_a = create_spending_transaction(vec![(genesis_cb.get_id().to_vec(), 0)], vec![(10, vec![2])]);
+    let block_a1 = test_block("fork_a1", "fork_genesis", 1, &[spend_a.clone(), create_coinbase_transaction(10, vec![3])]);
+    assert!(blockchain.add_block(&block_a1).is_none());
+    utxo_set.update(&block_a1);
+    assert_eq!(utxo_set.find_utxo(&[2]).len(), 1);
+
+    // Branch B: same height first, so the tip stays on A
+    let block_b1 = test_block("fork_b1", "fork_genesis", 1, &[create_coinbase_transaction(10, vec![4])]);
+    assert!(blockchain.add_block(&block_b1).is_none());
+    assert_eq!(blockchain.get_tip_hash(), "fork_a1");
+
+    // Branch B grows longer and spends the genesis coinbase differently
+    let spend_b = create_spending_transaction(
+        vec![(genesis_cb.get_id().to_vec(), 0)],
+        vec![(6, vec![5]), (4, vec![6])],
+    );
+    let block_b2 = test_block("fork_b2", "fork_b1", 2, &[spend_b.clone(), create_coinbase_transaction(20, vec![4])]);
+    let reorg = blockchain.add_block(&block_b2).expect("Longer fork should trigger a reorg");
+
+    let disconnected: Vec<&str> = reorg.get_disconnected().iter().map(|b| b.get_hash()).collect();
+    let connected: Vec<&str> = reorg.get_connected().iter().map(|b| b.get_hash()).collect();
+    assert_eq!(disconnected, vec!["fork_a1"]);
+    assert_eq!(connected, vec!["fork_b1", "fork_b2"]);
+    assert_eq!(blockchain.get_tip_hash(), "fork_b2");
+    assert_eq!(blockchain.get_hash_at_height(1).as_deref(), Some("fork_b1"));
+
+    utxo_set.reorganize(&reorg);
+
+    // Nothing from branch A survives
+    assert!(utxo_set.find_utxo(&[2]).is_empty());
+    assert!(utxo_set.find_utxo(&[3]).is_empty());
+    assert!(!utxo_set.is_spendable(spend_a.get_id(), 0));
+    // Branch B's coinbases and spend are in place, and the genesis coinbase is spent
+    assert_eq!(utxo_set.find_utxo(&[4]).len(), 2);
+    assert_eq!(utxo_set.find_utxo(&[5]).len(), 1);
+    assert_eq!(utxo_set.find_utxo(&[6]).len(), 1);
+    assert!(utxo_set.find_utxo(&[1]).is_empty());
+    assert_eq!(utxo_set.total_supply(), 40);
+}
+
+#[test]
+fn test_reorganize_without_undo_data_reindexes() {
+    let test_db = TestDatabase::new("utxo_reorg_reindex");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+
+    let genesis = test_block("reindex_genesis", GENESIS_PRE_BLOCK_HASH, 0, &[create_coinbase_transaction(10, vec![1])]);
+    blockchain.add_block(&genesis);
+    let block_a1 = test_block("reindex_a1", "reindex_genesis", 1, &[create_coinbase_transaction(10, vec![2])]);
+    blockchain.add_block(&block_a1);
+    // The UTXO set was built by reindex, so no undo data exists for branch A
+    utxo_set.reindex();
+
+    let block_b1 = test_block("reindex_b1", "reindex_genesis", 1, &[create_coinbase_transaction(10, vec![3])]);
+    blockchain.add_block(&block_b1);
+    let block_b2 = test_block("reindex_b2", "reindex_b1", 2, &[create_coinbase_transaction(20, vec![3])]);
+    let reorg = blockchain.add_block(&block_b2).unwrap();
+
+    utxo_set.reorganize(&reorg);
+    assert!(utxo_set.find_utxo(&[2]).is_empty());
+    assert_eq!(utxo_set.find_utxo(&[3]).len(), 2);
+    assert_eq!(utxo_set.find_utxo(&[1]).len(), 1);
+}
+
+#[test]
+fn test_prune_undo_keeps_only_recent_blocks() {
+    let test_db = TestDatabase::new("utxo_prune_undo");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let old = test_block("prune_undo_old", "prev_hash", 1, &[create_coinbase_transaction(10, vec![1])]);
+    let recent = test_block("prune_undo_recent", "prune_undo_old", 2, &[create_coinbase_transaction(10, vec![2])]);
+    utxo_set.update(&old);
+    utxo_set.update(&recent);
+
+    assert_eq!(utxo_set.prune_undo(MAX_UNDO_DEPTH + 1), 0);
+    assert_eq!(utxo_set.prune_undo(MAX_UNDO_DEPTH + 2), 1);
+    assert!(!utxo_set.rollback(&old));
+    assert!(utxo_set.rollback(&recent));
+}
+
+#[test]
+fn test_reindex_drops_undo_data() {
+    let test_db = TestDatabase::new("utxo_reindex_drops_undo");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let block = test_block("reindex_undo_block", "prev_hash", 1, &[create_coinbase_transaction(10, vec![1])]);
+    utxo_set.update(&block);
+    utxo_set.reindex();
+    assert!(!utxo_set.rollback(&block));
+}
+
+// =============================================================================
+// GET_BALANCES TESTS
+// =============================================================================
+
+#[test]
+fn test_get_balances_aggregates_per_address() {
+    let test_db = TestDatabase::new("get_balances_aggregates");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let rich = Wallet::from_seed(b"balances-rich");
+    let poor = Wallet::from_seed(b"balances-poor");
+    let empty = Wallet::from_seed(b"balances-empty");
+    let rich_hash = hash_pub_key(rich.get_public_key());
+    let poor_hash = hash_pub_key(poor.get_public_key());
+
+    add_utxos_to_db(&utxo_set, &[1], &[
+        TXOutput { value: 50, pub_key_hash: rich_hash.clone(), multisig: None, data: None },
+        TXOutput { value: 7, pub_key_hash: poor_hash.clone(), multisig: None, data: None },
+    ]);
+    add_utxos_to_db(&utxo_set, &[2], &[TXOutput { value: 25, pub_key_hash: rich_hash, multisig: None, data: None }]);
+
+    let addresses = vec![rich.get_address(), poor.get_address(), empty.get_address()];
+    let balances = utxo_set.get_balances(&addresses);
+
+    assert_eq!(balances.len(), 3);
+    assert_eq!(balances[&rich.get_address()], 75);
+    assert_eq!(balances[&poor.get_address()], 7);
+    assert_eq!(balances[&empty.get_address()], 0);
+    assert_eq!(balances.values().map(|balance| *balance as i64).sum::<i64>(), 82);
+}
+
+#[test]
+fn test_get_balances_handles_no_and_invalid_addresses() {
+    let test_db = TestDatabase::new("get_balances_empty");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    assert!(utxo_set.get_balances(&[]).is_empty());
+    assert!(utxo_set.get_balances(&["not-an-address".to_string()]).is_empty());
+}
+
+// =============================================================================
+// BALANCE INDEX TESTS
+// =============================================================================
+
+fn scanned_balance(utxo_set: &UTXOSet, pub_key_hash: &[u8]) -> i64 {
+    utxo_set.find_utxo(pub_key_hash).iter().map(|out| out.get_value() as i64).sum()
+}
+
+fn assert_balances_match_scan(utxo_set: &UTXOSet, pub_key_hashes: &[&[u8]]) {
+    for pub_key_hash in pub_key_hashes {
+        assert_eq!(
+            utxo_set.get_balance(pub_key_hash),
+            scanned_balance(utxo_set, pub_key_hash),
+            "balance index disagrees with scan for {pub_key_hash:?}"
+        );
+    }
+}
+
+#[test]
+fn test_balance_index_matches_scan_after_updates() {
+    let test_db = TestDatabase::new("balance_index_updates");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob, carol): (&[u8], &[u8], &[u8]) = (&[0xa1], &[0xb0], &[0xc4]);
+
+    let coinbase_a = create_coinbase_transaction(50, alice.to_vec());
+    let coinbase_b = create_coinbase_transaction(30, bob.to_vec());
+    let block1 = test_block("balance_b1", GENESIS_PRE_BLOCK_HASH, 0, &[coinbase_a.clone(), coinbase_b]);
+    blockchain.add_block(&block1);
+    utxo_set.update(&block1);
+    assert_eq!(utxo_set.get_balance(alice), 50);
+    assert_eq!(utxo_set.get_balance(bob), 30);
+    assert_balances_match_scan(&utxo_set, &[alice, bob, carol]);
+
+    let pay_bob = create_spending_transaction(
+        vec![(coinbase_a.get_id().to_vec(), 0)],
+        vec![(20, bob.to_vec()), (30, alice.to_vec())],
+    );
+    let block2 = test_block("balance_b2", "balance_b1", 1, std::slice::from_ref(&pay_bob));
+    blockchain.add_block(&block2);
+    utxo_set.update(&block2);
+    assert_eq!(utxo_set.get_balance(alice), 30);
+    assert_eq!(utxo_set.get_balance(bob), 50);
+    assert_balances_match_scan(&utxo_set, &[alice, bob, carol]);
+
+    let pay_carol = create_spending_transaction(
+        vec![(pay_bob.get_id().to_vec(), 0)],
+        vec![(5, carol.to_vec()), (10, alice.to_vec()), (5, bob.to_vec())],
+    );
+    let block3 = test_block("balance_b3", "balance_b2", 2, &[pay_carol]);
+    blockchain.add_block(&block3);
+    utxo_set.update(&block3);
+    assert_eq!(utxo_set.get_balance(carol), 5);
+    assert_balances_match_scan(&utxo_set, &[alice, bob, carol]);
+    assert_eq!(utxo_set.get_balance(&[0xee]), 0);
+
+    // Rolling back restores the balances from before the block
+    assert!(utxo_set.rollback(&block3));
+    assert_eq!(utxo_set.get_balance(carol), 0);
+    assert_eq!(utxo_set.get_balance(bob), 50);
+    assert_balances_match_scan(&utxo_set, &[alice, bob, carol]);
+}
+
+#[test]
+fn test_reindex_rebuilds_balance_index() {
+    let test_db = TestDatabase::new("balance_index_reindex");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob): (&[u8], &[u8]) = (&[0xa2], &[0xb2]);
+
+    let coinbase = create_coinbase_transaction(40, alice.to_vec());
+    let block1 = test_block("reindex_balance_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase));
+    let spend = create_spending_transaction(
+        vec![(coinbase.get_id().to_vec(), 0)],
+        vec![(15, bob.to_vec()), (25, alice.to_vec())],
+    );
+    let block2 = test_block("reindex_balance_b2", "reindex_balance_b1", 1, &[spend]);
+    blockchain.add_block(&block1);
+    blockchain.add_block(&block2);
+
+    // Leave a stale entry behind; reindex must replace it
+    let balance_tree = test_db.get_db().open_tree(rust_blockchain::utxo_set::BALANCE_INDEX_TREE).unwrap();
+    balance_tree.insert([0xdd], &999i64.to_be_bytes()).unwrap();
+
+    utxo_set.reindex();
+    assert_eq!(utxo_set.get_balance(alice), 25);
+    assert_eq!(utxo_set.get_balance(bob), 15);
+    assert_eq!(utxo_set.get_balance(&[0xdd]), 0);
+    assert_balances_match_scan(&utxo_set, &[alice, bob]);
+}
+
+// =============================================================================
+// REVERT TESTS
+// =============================================================================
+
+fn utxo_snapshot(utxo_set: &UTXOSet, txids: &[&[u8]], pub_key_hashes: &[&[u8]]) -> Vec<i64> {
+    let mut snapshot = vec![utxo_set.count_transactions() as i64, utxo_set.total_supply()];
+    for txid in txids {
+        snapshot.extend((0..3).map(|vout| utxo_set.is_spendable(txid, vout) as i64));
+    }
+    snapshot.extend(pub_key_hashes.iter().map(|pub_key_hash| utxo_set.get_balance(pub_key_hash)));
+    snapshot
+}
+
+#[test]
+fn test_revert_restores_state_without_undo_data() {
+    let test_db = TestDatabase::new("utxo_revert");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob, carol): (&[u8], &[u8], &[u8]) = (&[0xa3], &[0xb3], &[0xc3]);
+
+    let coinbase = create_coinbase_transaction(50, alice.to_vec());
+    let block1 = test_block("revert_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase));
+    let split = create_spending_transaction(
+        vec![(coinbase.get_id().to_vec(), 0)],
+        vec![(20, bob.to_vec()), (20, alice.to_vec()), (10, carol.to_vec())],
+    );
+    let block2 = test_block("revert_b2", "revert_b1", 1, std::slice::from_ref(&split));
+    blockchain.add_block(&block1);
+    blockchain.add_block(&block2);
+    // Built by reindex, so block3 is the only block with undo data
+    utxo_set.reindex();
+
+    let txids: &[&[u8]] = &[coinbase.get_id(), split.get_id()];
+    let before = utxo_snapshot(&utxo_set, txids, &[alice, bob, carol]);
+
+    // Spend the middle output, then spend the new output within the same block
+    let spend_middle = create_spending_transaction(vec![(split.get_id().to_vec(), 1)], vec![(20, carol.to_vec())]);
+    let chained = create_spending_transaction(
+        vec![(spend_middle.get_id().to_vec(), 0)],
+        vec![(15, bob.to_vec()), (5, carol.to_vec())],
+    );
+    let reward = create_coinbase_transaction(10, alice.to_vec());
+    let block3 = test_block("revert_b3", "revert_b2", 2, &[spend_middle.clone(), chained.clone(), reward.clone()]);
+    blockchain.add_block(&block3);
+    utxo_set.update(&block3);
+    assert_eq!(utxo_set.find_utxo(alice).len(), 1);
+    assert_eq!(utxo_set.get_balance(bob), 35);
+
+    assert!(utxo_set.revert(&block3));
+    assert_eq!(utxo_snapshot(&utxo_set, txids, &[alice, bob, carol]), before);
+    for created in [&spend_middle, &chained, &reward] {
+        assert!(!utxo_set.is_spendable(created.get_id(), 0));
+    }
+    assert_balances_match_scan(&utxo_set, &[alice, bob, carol]);
+    // The undo data for the block is dropped along with its effect
+    assert!(!utxo_set.rollback(&block3));
+}
+
+#[test]
+fn test_revert_and_rollback_restore_the_exact_spent_vout() {
+    let test_db = TestDatabase::new("utxo_revert_exact_vout");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob): (&[u8], &[u8]) = (&[0xa4], &[0xb4]);
+
+    let coinbase = create_coinbase_transaction(20, alice.to_vec());
+    let block1 = test_block("exact_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase));
+    // Two outputs with the same value and owner, told apart only by their vout
+    let twins = create_spending_transaction(
+        vec![(coinbase.get_id().to_vec(), 0)],
+        vec![(10, alice.to_vec()), (10, alice.to_vec())],
+    );
+    let block2 = test_block("exact_b2", "exact_b1", 1, std::slice::from_ref(&twins));
+    let spend_first = create_spending_transaction(vec![(twins.get_id().to_vec(), 0)], vec![(10, bob.to_vec())]);
+    let block3 = test_block("exact_b3", "exact_b2", 2, std::slice::from_ref(&spend_first));
+    let spend_second = create_spending_transaction(vec![(twins.get_id().to_vec(), 1)], vec![(10, bob.to_vec())]);
+    let block4 = test_block("exact_b4", "exact_b3", 3, std::slice::from_ref(&spend_second));
+    blockchain.add_block(&block1);
+    blockchain.add_block(&block2);
+    utxo_set.reindex();
+    blockchain.add_block(&block3);
+    utxo_set.update(&block3);
+    blockchain.add_block(&block4);
+
+    // Without undo data the spent output is looked up on the chain by its vout
+    utxo_set.update(&block4);
+    assert!(utxo_set.revert(&block4));
+    assert!(!utxo_set.is_spendable(twins.get_id(), 0));
+    assert!(utxo_set.is_spendable(twins.get_id(), 1));
+
+    // The undo record holds the spent output itself
+    utxo_set.update(&block4);
+    assert!(utxo_set.rollback(&block4));
+    assert!(!utxo_set.is_spendable(twins.get_id(), 0));
+    assert!(utxo_set.is_spendable(twins.get_id(), 1));
+    assert_eq!(utxo_set.get_balance(alice), 10);
+    assert_balances_match_scan(&utxo_set, &[alice, bob]);
+}
+
+#[test]
+fn test_revert_fails_without_spent_transaction() {
+    let test_db = TestDatabase::new("utxo_revert_missing");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let unknown_txid = vec![9, 9, 9, 9];
+    add_utxos_to_db(&utxo_set, &unknown_txid, &[TXOutput { value: 30, pub_key_hash: vec![1], multisig: None, data: None }]);
+    let spend = create_spending_transaction(vec![(unknown_txid.clone(), 0)], vec![(30, vec![2])]);
+    let block = test_block("revert_missing", "prev_hash", 1, &[spend]);
+    utxo_set.update(&block);
+
+    // The spent transaction is not on the chain, so nothing is touched
+    assert!(!utxo_set.revert(&block));
+    assert_eq!(utxo_set.find_utxo(&[2]).len(), 1);
+    assert!(utxo_set.find_utxo(&[1]).is_empty());
+    assert!(utxo_set.rollback(&block));
+    assert_eq!(utxo_set.find_utxo(&[1]).len(), 1);
+}
+
+#[test]
+fn test_revert_fails_across_pruned_parent() {
+    let test_db = TestDatabase::new("utxo_revert_pruned");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob): (&[u8], &[u8]) = (&[0xa5], &[0xb5]);
+
+    let coinbase = create_coinbase_transaction(20, alice.to_vec());
+    let block1 = test_block("pruned_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase));
+    let parent = create_spending_transaction(
+        vec![(coinbase.get_id().to_vec(), 0)],
+        vec![(10, alice.to_vec()), (10, alice.to_vec())],
+    );
+    let block2 = test_block("pruned_b2", "pruned_b1", 1, std::slice::from_ref(&parent));
+    let spend_both = create_spending_transaction(
+        vec![(parent.get_id().to_vec(), 0), (parent.get_id().to_vec(), 1)],
+        vec![(20, bob.to_vec())],
+    );
+    let block3 = test_block("pruned_b3", "pruned_b2", 2, std::slice::from_ref(&spend_both));
+    for block in [&block1, &block2, &block3] {
+        blockchain.add_block(block);
+    }
+    utxo_set.reindex();
+    assert_eq!(blockchain.prune_below(2).unwrap(), 2);
+    assert!(blockchain.find_transaction(parent.get_id()).unwrap().is_pruned());
+
+    // The pruned parent no longer holds the spent outputs, so nothing is touched
+    assert!(!utxo_set.revert(&block3));
+    assert_eq!(utxo_set.get_balance(bob), 20);
+    assert!(!utxo_set.is_spendable(parent.get_id(), 0));
+    assert!(!utxo_set.is_spendable(parent.get_id(), 1));
+    assert_balances_match_scan(&utxo_set, &[alice, bob]);
+}
+
+// =============================================================================
+// COINBASE MATURITY TESTS
+// =============================================================================
+
+// Extend a funded chain with empty-reward blocks until its tip is at `tip_height`
+fn extend_chain_to(blockchain: &Blockchain, tip_height: usize) {
+    for height in blockchain.get_best_height() + 1..=tip_height {
+        blockchain.add_block(&create_test_block(blockchain.get_tip_hash(), height));
+    }
+    assert_eq!(blockchain.get_best_height(), tip_height);
+}
+
+#[test]
+fn test_coinbase_maturity_defaults_to_config() {
+    let test_db = TestDatabase::new("coinbase_maturity_default");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+
+    let utxo_set = UTXOSet::new(blockchain);
+    assert_eq!(utxo_set.get_coinbase_maturity(), rust_blockchain::config::GLOBAL_CONFIG.get_coinbase_maturity());
+    assert_eq!(utxo_set.with_coinbase_maturity(5).get_coinbase_maturity(), 5);
+}
+
+#[test]
+fn test_coinbase_height_recorded_by_reindex_and_update() {
+    let test_db = TestDatabase::new("coinbase_height_recorded");
+    let miner = Wallet::new();
+    let blockchain = create_funded_blockchain(test_db.get_db(), &miner.get_address());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+
+    let genesis = blockchain.get_block(blockchain.get_tip_hash().as_bytes()).unwrap();
+    let genesis_coinbase = &genesis.get_transactions()[0];
+    assert_eq!(utxo_set.get_coinbase_height(genesis_coinbase.get_id()), Some(0));
+
+    let coinbase_tx = create_coinbase_transaction(10, vec![4]);
+    let block = test_block("coinbase_height_block", genesis.get_hash(), 1, std::slice::from_ref(&coinbase_tx));
+    utxo_set.update(&block);
+    assert_eq!(utxo_set.get_coinbase_height(coinbase_tx.get_id()), Some(1));
+
+    // Non-coinbase transactions have no recorded height, and rolling back forgets the coinbase
+    let spending_tx = create_spending_transaction(vec![(coinbase_tx.get_id().to_vec(), 0)], vec![(10, vec![5])]);
+    assert_eq!(utxo_set.get_coinbase_height(spending_tx.get_id()), None);
+    assert!(utxo_set.rollback(&block));
+    assert_eq!(utxo_set.get_coinbase_height(coinbase_tx.get_id()), None);
+}
+
+#[test]
+fn test_immature_coinbase_is_not_spendable() {
+    let test_db = TestDatabase::new("coinbase_immature");
+    let miner = Wallet::new();
+    let blockchain = create_funded_blockchain(test_db.get_db(), &miner.get_address());
+    let utxo_set = UTXOSet::new(blockchain.clone()).with_coinbase_maturity(3);
+    let miner_hash = hash_pub_key(miner.get_public_key());
+
+    // The genesis reward is still in the balance but cannot be selected yet
+    extend_chain_to(&blockchain, 1);
+    assert_eq!(utxo_set.get_balance(&miner_hash), 10);
+    let (accumulated, outputs) = utxo_set.find_spendable_outputs(&miner_hash, 5);
+    assert_eq!(accumulated, 0);
+    assert!(outputs.is_empty());
+
+    let recipient = Wallet::new();
+    let outputs = [(recipient.get_address(), 5)];
+    let result = Transaction::new_utxo_transaction_from_wallet(&miner, &outputs, &utxo_set);
+    assert!(matches!(result, Err(e) if e == "Not enough funds: have 0, need 5"));
+}
+
+#[test]
+fn test_matured_coinbase_is_spendable() {
+    let test_db = TestDatabase::new("coinbase_matured");
+    let miner = Wallet::new();
+    let blockchain = create_funded_blockchain(test_db.get_db(), &miner.get_address());
+    let utxo_set = UTXOSet::new(blockchain.clone()).with_coinbase_maturity(3);
+    let miner_hash = hash_pub_key(miner.get_public_key());
+
+    // A spend mined at height 3 is three blocks on top of the genesis reward
+    extend_chain_to(&blockchain, 2);
+    let (accumulated, outputs) = utxo_set.find_spendable_outputs(&miner_hash, 5);
+    assert_eq!(accumulated, 10);
+    assert_eq!(outputs.len(), 1);
+
+    let recipient = Wallet::new();
+    let outputs = [(recipient.get_address(), 5)];
+    let tx = Transaction::new_utxo_transaction_from_wallet(&miner, &outputs, &utxo_set).unwrap();
+    assert_eq!(tx.get_vin().len(), 1);
+    assert!(tx.get_vout().iter().any(|out| out.is_locked_with_key(&miner_hash) && out.get_value() == 5));
+}
+
+#[test]
+fn test_coinbase_maturity_does_not_hold_back_regular_outputs() {
+    let test_db = TestDatabase::new("coinbase_maturity_regular");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain).with_coinbase_maturity(100);
+
+    add_utxos_to_db(&utxo_set, &[8, 8, 8, 8], &[TXOutput { value: 30, pub_key_hash: vec![1], multisig: None, data: None }]);
+    let (accumulated, _) = utxo_set.find_spendable_outputs(&[1], 20);
+    assert_eq!(accumulated, 30);
+}
+
 // =============================================================================
 // HELPER FUNCTIONS
 // =============================================================================
@@ -541,6 +1399,340 @@ fn add_utxos_to_db(utxo_set: &UTXOSet, txid: &[u8], outputs: &[TXOutput]) {
     let db = utxo_set.get_blockchain().get_db();
     let utxo_tree = db.open_tree(rust_blockchain::utxo_set::UTXO_TREE).unwrap();
     
-    let encoded_outputs = bincode::encode_to_vec(outputs, standard()).unwrap();
+    let entry: Vec<Option<TXOutput>> = outputs.iter().cloned().map(Some).collect();
+    let encoded_outputs = bincode::encode_to_vec(entry, standard()).unwrap();
     utxo_tree.insert(txid, encoded_outputs).unwrap();
 } 
+
+// =============================================================================
+// BURN OUTPUT TESTS
+// =============================================================================
+
+#[test]
+fn test_burn_output_is_never_spendable() {
+    let test_name = "burn_output_never_spendable";
+    let test_db = TestDatabase::new(test_name);
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    add_utxos_to_db(&utxo_set, &[3, 3, 3, 3], &[TXOutput::new_burn(25), TXOutput { value: 5, pub_key_hash: vec![1], multisig: None, data: None }]);
+
+    // Not even its own all-zero hash unlocks a burn output
+    let burn_hash = vec![0; BURN_PUB_KEY_HASH_LEN];
+    assert!(utxo_set.find_utxo(&burn_hash).is_empty());
+    let (accumulated, outputs) = utxo_set.find_spendable_outputs(&burn_hash, 1);
+    assert_eq!(accumulated, 0);
+    assert!(outputs.is_empty());
+    assert_eq!(utxo_set.find_utxo(&[1]).len(), 1);
+}
+
+#[test]
+fn test_burn_output_reduces_supply() {
+    let test_name = "burn_output_reduces_supply";
+    let test_db = TestDatabase::new(test_name);
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let initial_txid = vec![8, 8, 8, 8];
+    add_utxos_to_db(&utxo_set, &initial_txid, &[TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }]);
+
+    let mut burning_tx = create_spending_transaction(vec![(initial_txid.clone(), 0)], vec![(60, vec![4, 5, 6])]);
+    burning_tx.vout.push(TXOutput::new_burn(40));
+    let coinbase_tx = create_coinbase_transaction(10, vec![1, 2, 3]);
+    let block = Block::new_block_without_proof_of_work("prev_hash".to_string(), &[burning_tx, coinbase_tx], 1);
+
+    let supply_before = utxo_set.total_supply();
+    utxo_set.update(&block);
+
+    assert_eq!(utxo_set.total_supply(), supply_before + 10 - 40);
+    assert!(utxo_set.check_supply_invariant(supply_before, &block));
+}
+
+// =============================================================================
+// DATA OUTPUT TESTS
+// =============================================================================
+
+#[test]
+fn test_data_output_never_becomes_spendable_utxo() {
+    let test_name = "data_output_never_spendable";
+    let test_db = TestDatabase::new(test_name);
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain);
+
+    let initial_txid = vec![5, 5, 5, 5];
+    add_utxos_to_db(&utxo_set, &initial_txid, &[TXOutput { value: 100, pub_key_hash: vec![1, 2, 3], multisig: None, data: None }]);
+
+    let mut data_tx = create_spending_transaction(vec![(initial_txid.clone(), 0)], vec![(100, vec![4, 5, 6])]);
+    data_tx.vout.push(TXOutput::new_data(b"anchored note").unwrap());
+    let data_txid = data_tx.get_id().to_vec();
+    let coinbase_tx = create_coinbase_transaction(10, vec![1, 2, 3]);
+    let block = Block::new_block_without_proof_of_work("prev_hash".to_string(), &[data_tx, coinbase_tx], 1);
+    utxo_set.update(&block);
+
+    // The value output keeps its index and stays spendable; the data output never is
+    assert!(utxo_set.is_spendable(&data_txid, 0));
+    assert!(!utxo_set.is_spendable(&data_txid, 1));
+    assert!(utxo_set.find_utxo(&[]).is_empty());
+    assert_eq!(utxo_set.find_utxo(&[4, 5, 6]).len(), 1);
+    assert!(utxo_set.check_supply_invariant(100, &block));
+}
+
+// =============================================================================
+// RESCAN TESTS
+// =============================================================================
+
+#[test]
+fn test_reindex_matches_incrementally_updated_set() {
+    let test_db = TestDatabase::new("utxo_rescan");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob, carol): (&[u8], &[u8], &[u8]) = (&[0xa5], &[0xb5], &[0xc5]);
+
+    let coinbase1 = create_coinbase_transaction(50, alice.to_vec());
+    let block1 = test_block("rescan_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase1));
+    let spend1 = create_spending_transaction(
+        vec![(coinbase1.get_id().to_vec(), 0)],
+        vec![(20, bob.to_vec()), (30, alice.to_vec())],
+    );
+    let coinbase2 = create_coinbase_transaction(10, carol.to_vec());
+    let block2 = test_block("rescan_b2", "rescan_b1", 1, &[spend1.clone(), coinbase2.clone()]);
+    // A different output count keeps its id distinct from spend1's
+    let spend2 = create_spending_transaction(vec![(spend1.get_id().to_vec(), 0)], vec![(20, carol.to_vec())]);
+    let block3 = test_block("rescan_b3", "rescan_b2", 2, std::slice::from_ref(&spend2));
+
+    for block in [&block1, &block2, &block3] {
+        blockchain.add_block(block);
+        utxo_set.update(block);
+    }
+    let txids: Vec<&[u8]> = vec![coinbase1.get_id(), spend1.get_id(), coinbase2.get_id(), spend2.get_id()];
+    let incremental = utxo_snapshot(&utxo_set, &txids, &[alice, bob, carol]);
+
+    let count = utxo_set.reindex();
+    assert_eq!(count, utxo_set.count_transactions() as usize);
+    assert_eq!(count, 3);
+    assert_eq!(utxo_snapshot(&utxo_set, &txids, &[alice, bob, carol]), incremental);
+    assert_eq!(utxo_set.get_balance(alice), 30);
+    assert_eq!(utxo_set.get_balance(bob), 0);
+    assert_eq!(utxo_set.get_balance(carol), 30);
+}
+
+// =============================================================================
+// SNAPSHOT TESTS
+// =============================================================================
+
+#[test]
+fn test_snapshot_round_trip_into_fresh_database() {
+    let test_db = TestDatabase::new("utxo_snapshot_export");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob): (&[u8], &[u8]) = (&[0xa6], &[0xb6]);
+
+    let coinbase = create_coinbase_transaction(50, alice.to_vec());
+    let block1 = test_block("snapshot_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase));
+    let spend = create_spending_transaction(
+        vec![(coinbase.get_id().to_vec(), 0)],
+        vec![(20, bob.to_vec()), (30, alice.to_vec())],
+    );
+    let block2 = test_block("snapshot_b2", "snapshot_b1", 1, std::slice::from_ref(&spend));
+    for block in [&block1, &block2] {
+        blockchain.add_block(block);
+        utxo_set.update(block);
+    }
+    let txids: Vec<&[u8]> = vec![coinbase.get_id(), spend.get_id()];
+    let exported = utxo_snapshot(&utxo_set, &txids, &[alice, bob]);
+
+    let (_temp_db, temp_dir) = setup_temp_test_db();
+    let path = temp_dir.path().join("utxo.snapshot");
+    assert_eq!(utxo_set.export_snapshot(&path).unwrap(), 1);
+
+    let fresh_db = TestDatabase::new("utxo_snapshot_import");
+    let fresh_set = UTXOSet::new(Blockchain::new_with_empty_tip(fresh_db.get_db().clone()));
+    assert_eq!(fresh_set.import_snapshot(&path).unwrap(), 1);
+    assert_eq!(utxo_snapshot(&fresh_set, &txids, &[alice, bob]), exported);
+    assert_eq!(fresh_set.get_balance(alice), 30);
+    assert_eq!(fresh_set.get_balance(bob), 20);
+    assert_eq!(fresh_set.get_coinbase_height(coinbase.get_id()), Some(0));
+}
+
+#[test]
+fn test_import_snapshot_drops_undo_records() {
+    let test_db = TestDatabase::new("utxo_snapshot_undo");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob): (&[u8], &[u8]) = (&[0xa8], &[0xb8]);
+
+    let coinbase = create_coinbase_transaction(50, alice.to_vec());
+    let block1 = test_block("snapshot_undo_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase));
+    let spend = create_spending_transaction(vec![(coinbase.get_id().to_vec(), 0)], vec![(50, bob.to_vec())]);
+    let block2 = test_block("snapshot_undo_b2", "snapshot_undo_b1", 1, std::slice::from_ref(&spend));
+    for block in [&block1, &block2] {
+        blockchain.add_block(block);
+        utxo_set.update(block);
+    }
+    let (_temp_db, temp_dir) = setup_temp_test_db();
+    let path = temp_dir.path().join("utxo.snapshot");
+    utxo_set.export_snapshot(&path).unwrap();
+
+    // The undo records describe the set being replaced, so none survive the import
+    utxo_set.import_snapshot(&path).unwrap();
+    assert!(!utxo_set.rollback(&block2));
+    assert_eq!(utxo_set.get_balance(bob), 50);
+    assert_balances_match_scan(&utxo_set, &[alice, bob]);
+}
+
+#[test]
+fn test_import_snapshot_rejects_corrupted_file() {
+    let test_db = TestDatabase::new("utxo_snapshot_corrupt");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let coinbase = create_coinbase_transaction(50, vec![0xa7]);
+    let block = test_block("corrupt_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase));
+    blockchain.add_block(&block);
+    utxo_set.update(&block);
+
+    let (_temp_db, temp_dir) = setup_temp_test_db();
+    let path = temp_dir.path().join("utxo.snapshot");
+    utxo_set.export_snapshot(&path).unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[0] ^= 0xff;
+    std::fs::write(&path, &bytes).unwrap();
+
+    let other_db = TestDatabase::new("utxo_snapshot_corrupt_import");
+    let other_set = UTXOSet::new(Blockchain::new_with_empty_tip(other_db.get_db().clone()));
+    let other_coinbase = create_coinbase_transaction(10, vec![0xb7]);
+    let other_block = test_block("corrupt_other", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&other_coinbase));
+    other_set.get_blockchain().add_block(&other_block);
+    other_set.update(&other_block);
+
+    let err = other_set.import_snapshot(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(other_set.count_transactions(), 1);
+    assert_eq!(other_set.get_balance(&[0xb7]), 10);
+
+    std::fs::write(&path, &bytes[..4]).unwrap();
+    assert!(other_set.import_snapshot(&path).is_err());
+    assert_eq!(other_set.count_transactions(), 1);
+}
+
+// =============================================================================
+// UTXO DIFF TESTS
+// =============================================================================
+
+#[test]
+fn test_apply_diff_lists_created_and_spent_outputs() {
+    let test_db = TestDatabase::new("utxo_diff_apply");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob): (&[u8], &[u8]) = (&[0xa8], &[0xb8]);
+
+    let coinbase = create_coinbase_transaction(50, alice.to_vec());
+    let block1 = test_block("diff_b1", GENESIS_PRE_BLOCK_HASH, 0, std::slice::from_ref(&coinbase));
+    blockchain.add_block(&block1);
+    let diff1 = utxo_set.apply(&block1);
+    assert_eq!(diff1.get_block_hash(), "diff_b1");
+    assert!(diff1.get_spent().is_empty());
+    assert_eq!(diff1.get_created().len(), 1);
+    assert_eq!(diff1.get_created()[0].0, coinbase.get_id());
+    assert_eq!(diff1.get_created()[0].1, 0);
+    assert_eq!(diff1.get_created()[0].2.get_value(), 50);
+
+    let spend = create_spending_transaction(
+        vec![(coinbase.get_id().to_vec(), 0)],
+        vec![(20, bob.to_vec()), (30, alice.to_vec())],
+    );
+    let block2 = test_block("diff_b2", "diff_b1", 1, std::slice::from_ref(&spend));
+    blockchain.add_block(&block2);
+    let diff2 = utxo_set.apply(&block2);
+    let created: Vec<(Vec<u8>, usize, i32)> = diff2
+        .get_created()
+        .iter()
+        .map(|(txid, vout, out)| (txid.clone(), *vout, out.get_value()))
+        .collect();
+    assert_eq!(created, vec![(spend.get_id().to_vec(), 0, 20), (spend.get_id().to_vec(), 1, 30)]);
+    assert_eq!(diff2.get_spent().len(), 1);
+    let (spent_txid, spent_vout, spent_out) = &diff2.get_spent()[0];
+    assert_eq!(spent_txid, coinbase.get_id());
+    assert_eq!(*spent_vout, 0);
+    assert_eq!(spent_out.get_value(), 50);
+    assert_eq!(spent_out.get_pub_key_hash(), alice);
+}
+
+#[test]
+fn test_revert_diff_restores_state_before_apply() {
+    let test_db = TestDatabase::new("utxo_diff_revert");
+    let blockchain = Blockchain::new_with_empty_tip(test_db.get_db().clone());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let (alice, bob, carol): (&[u8], &[u8], &[u8]) = (&[0xa9], &[0xb9], &[0xc9]);
+
+    let coinbase = create_coinbase_transaction(50, alice.to_vec());
+    let funding = create_spending_transaction(
+        vec![(coinbase.get_id().to_vec(), 0)],
+        vec![(10, alice.to_vec()), (15, bob.to_vec()), (25, alice.to_vec())],
+    );
+    let block1 = test_block("diff_revert_b1", GENESIS_PRE_BLOCK_HASH, 0, &[coinbase.clone(), funding.clone()]);
+    blockchain.add_block(&block1);
+    utxo_set.update(&block1);
+    let txids: Vec<&[u8]> = vec![coinbase.get_id(), funding.get_id()];
+    let before = utxo_snapshot(&utxo_set, &txids, &[alice, bob, carol]);
+
+    // Spend two of funding's outputs, and chain a second spend inside the same block
+    let spend = create_spending_transaction(
+        vec![(funding.get_id().to_vec(), 0), (funding.get_id().to_vec(), 1)],
+        vec![(25, carol.to_vec())],
+    );
+    let chained = create_spending_transaction(vec![(spend.get_id().to_vec(), 0)], vec![(5, bob.to_vec()), (20, carol.to_vec())]);
+    let coinbase2 = create_coinbase_transaction(10, carol.to_vec());
+    let block2 = test_block("diff_revert_b2", "diff_revert_b1", 1, &[spend.clone(), chained.clone(), coinbase2.clone()]);
+    blockchain.add_block(&block2);
+    let diff = utxo_set.apply(&block2);
+    assert_eq!(diff.get_spent().len(), 3);
+    assert_eq!(diff.get_created().len(), 4);
+    assert_ne!(utxo_snapshot(&utxo_set, &txids, &[alice, bob, carol]), before);
+
+    utxo_set.revert_diff(diff);
+    assert_eq!(utxo_snapshot(&utxo_set, &txids, &[alice, bob, carol]), before);
+    assert_eq!(utxo_set.find_utxo(alice).iter().map(|out| out.get_value()).collect::<Vec<_>>(), vec![10, 25]);
+    assert!(!utxo_set.is_spendable(spend.get_id(), 0));
+    assert!(!utxo_set.is_spendable(chained.get_id(), 0));
+    assert_eq!(utxo_set.get_coinbase_height(coinbase2.get_id()), None);
+    assert!(!utxo_set.rollback(&block2));
+}
+
+// =============================================================================
+// MEMPOOL BALANCE TESTS
+// =============================================================================
+
+#[test]
+fn test_get_balance_with_mempool_counts_pooled_spends_and_outputs() {
+    let test_db = TestDatabase::new("balance_with_mempool");
+    let alice = Wallet::from_seed(b"mempool-balance-alice");
+    let bob_hash = hash_pub_key(Wallet::from_seed(b"mempool-balance-bob").get_public_key());
+    let carol_hash = hash_pub_key(Wallet::from_seed(b"mempool-balance-carol").get_public_key());
+    let alice_hash = hash_pub_key(alice.get_public_key());
+    let blockchain = create_funded_blockchain(test_db.get_db(), alice.get_address().as_str());
+    let utxo_set = UTXOSet::new(blockchain.clone());
+    let genesis = blockchain.get_block(b"funded_genesis_hash").unwrap();
+    let funding_txid = genesis.get_transactions()[0].get_id().to_vec();
+
+    let mempool = MemoryPool::new();
+    assert_eq!(utxo_set.get_balance_with_mempool(&alice_hash, &mempool), (10, 0));
+
+    // Alice spends her confirmed 10, paying 4 to bob and 6 back to herself
+    let payment = create_spending_transaction(
+        vec![(funding_txid, 0)],
+        vec![(4, bob_hash.clone()), (6, alice_hash.clone())],
+    );
+    assert!(mempool.add(payment.clone()));
+    assert_eq!(utxo_set.get_balance_with_mempool(&alice_hash, &mempool), (10, -4));
+    assert_eq!(utxo_set.get_balance_with_mempool(&bob_hash, &mempool), (0, 4));
+
+    // Bob passes his still unconfirmed 4 on to carol
+    let forward = create_spending_transaction(
+        vec![(payment.get_id().to_vec(), 0)],
+        vec![(4, carol_hash.clone())],
+    );
+    assert!(mempool.add(forward));
+    assert_eq!(utxo_set.get_balance_with_mempool(&bob_hash, &mempool), (0, 0));
+    assert_eq!(utxo_set.get_balance_with_mempool(&carol_hash, &mempool), (0, 4));
+    assert_eq!(utxo_set.get_balance_with_mempool(&alice_hash, &mempool), (10, -4));
+}
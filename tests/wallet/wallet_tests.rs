@@ -577,4 +577,140 @@ mod tests {
         let new_wallet = Wallet::new();
         assert!(!new_wallet.get_address().is_empty());
     }
-} 
+
+    // =============================================================================
+    // PRIVATE KEY IMPORT/EXPORT TESTS
+    // =============================================================================
+
+    #[test]
+    fn test_export_import_private_key_roundtrip() {
+        let wallet = Wallet::new();
+        let exported = wallet.export_private_key();
+        assert!(!exported.is_empty());
+        assert_eq!(util::base58_decode(exported.as_str()), wallet.get_pkcs8());
+
+        let restored = Wallet::from_private_key(exported.as_str()).unwrap();
+        assert_eq!(restored.get_address(), wallet.get_address());
+        assert_eq!(restored.get_public_key(), wallet.get_public_key());
+        assert_eq!(restored.get_pkcs8(), wallet.get_pkcs8());
+    }
+
+    #[test]
+    fn test_imported_wallet_can_sign() {
+        let wallet = Wallet::new();
+        let restored = Wallet::from_private_key(wallet.export_private_key().as_str()).unwrap();
+
+        let message = b"imported key";
+        let signature = util::ecdsa_p256_sha256_sign_digest(restored.get_pkcs8(), message);
+        assert!(util::ecdsa_p256_sha256_sign_verify(
+            wallet.get_public_key(),
+            signature.as_slice(),
+            message
+        ));
+    }
+
+    #[test]
+    fn test_from_private_key_rejects_malformed_input() {
+        // Not base58 ('0', 'O', 'I' and 'l' are excluded from the alphabet)
+        assert!(Wallet::from_private_key("0OIl").is_err());
+        assert!(Wallet::from_private_key("").is_err());
+        // Valid base58 but not a PKCS#8 document
+        assert!(Wallet::from_private_key(util::base58_encode(b"not a key").as_str()).is_err());
+
+        // A truncated export
+        let exported = Wallet::new().export_private_key();
+        let truncated = &exported[..exported.len() / 2];
+        assert!(Wallet::from_private_key(truncated).is_err());
+    }
+
+    // =============================================================================
+    // SEED-BASED WALLET TESTS
+    // =============================================================================
+
+    #[test]
+    fn test_from_seed_is_deterministic() {
+        let wallet1 = Wallet::from_seed(b"reproducible test seed");
+        let wallet2 = Wallet::from_seed(b"reproducible test seed");
+
+        assert_eq!(wallet1.get_address(), wallet2.get_address());
+        assert_eq!(wallet1.get_public_key(), wallet2.get_public_key());
+        assert_eq!(wallet1.get_pkcs8(), wallet2.get_pkcs8());
+    }
+
+    #[test]
+    fn test_from_seed_differs_per_seed() {
+        let seeds: [&[u8]; 4] = [b"", b"seed-a", b"seed-b", b"seed-a "];
+        let addresses: HashSet<String> = seeds
+            .iter()
+            .map(|seed| Wallet::from_seed(seed).get_address())
+            .collect();
+        assert_eq!(addresses.len(), seeds.len());
+    }
+
+    #[test]
+    fn test_from_seed_wallet_is_usable() {
+        let wallet = Wallet::from_seed(b"signing seed");
+        assert!(rust_blockchain::wallet::wallet_util::validate_address(
+            wallet.get_address().as_str()
+        ));
+
+        let message = b"seeded wallet";
+        let signature = util::ecdsa_p256_sha256_sign_digest(wallet.get_pkcs8(), message);
+        assert!(util::ecdsa_p256_sha256_sign_verify(
+            wallet.get_public_key(),
+            signature.as_slice(),
+            message
+        ));
+
+        // Seeded wallets round-trip through export/import like random ones
+        let restored = Wallet::from_private_key(wallet.export_private_key().as_str()).unwrap();
+        assert_eq!(restored.get_address(), wallet.get_address());
+    }
+
+    // =============================================================================
+    // COMPRESSED PUBLIC KEY TESTS
+    // =============================================================================
+
+    #[test]
+    fn test_compressed_public_key_is_sec1_form_of_full_key() {
+        let wallet = Wallet::from_seed(b"compressed key seed");
+        let compressed = wallet.get_compressed_public_key();
+        assert_eq!(compressed.len(), rust_blockchain::COMPRESSED_PUBLIC_KEY_LEN);
+        assert!(compressed[0] == 0x02 || compressed[0] == 0x03);
+        assert_eq!(compressed[1..], wallet.get_public_key()[1..33]);
+        assert_eq!(
+            util::uncompressed_public_key(compressed.as_slice()).unwrap(),
+            wallet.get_public_key()
+        );
+
+        // Signatures check out against either form of the key
+        let message = b"compressed wallet";
+        let signature = util::ecdsa_p256_sha256_sign_digest(wallet.get_pkcs8(), message);
+        assert!(util::ecdsa_p256_sha256_sign_verify(
+            compressed.as_slice(),
+            signature.as_slice(),
+            message
+        ));
+        assert!(!util::ecdsa_p256_sha256_sign_verify(
+            &[0x02; 33],
+            signature.as_slice(),
+            message
+        ));
+    }
+
+    #[test]
+    fn test_compressed_key_address_round_trips_and_differs() {
+        use rust_blockchain::wallet::wallet_util::{convert_address, hash_pub_key, validate_address};
+
+        let wallet = Wallet::from_seed(b"compressed address seed");
+        let compressed_hash = hash_pub_key(wallet.get_compressed_public_key().as_slice());
+        let address = convert_address(compressed_hash.as_slice());
+        assert!(validate_address(address.as_str()));
+        let (_, decoded_hash) = util::base58check_decode(address.as_str()).unwrap();
+        assert_eq!(decoded_hash, compressed_hash);
+
+        // Compressed keys are off by default, so the wallet keeps its uncompressed address
+        assert_eq!(wallet.get_address_public_key(), wallet.get_public_key());
+        assert_ne!(address, wallet.get_address());
+    }
+}
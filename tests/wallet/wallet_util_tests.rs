@@ -1,9 +1,10 @@
 #[cfg(test)]
 mod tests {
     use rust_blockchain::wallet::wallet_util::{
-        checksum, convert_address, hash_pub_key, validate_address,
+        checksum, convert_address, convert_address_with_version, hash_pub_key, validate_address,
+        validate_address_with_version,
     };
-    use rust_blockchain::wallet::{ADDRESS_CHECK_SUM_LEN, VERSION};
+    use rust_blockchain::wallet::{Wallet, ADDRESS_CHECK_SUM_LEN, VERSION};
     use rust_blockchain::util;
 
     // =============================================================================
@@ -102,6 +103,15 @@ mod tests {
         assert_eq!(result.len(), 20);
     }
 
+    #[test]
+    fn test_hash_pub_key_matches_sha256_then_ripemd160() {
+        for pub_key in [&b""[..], b"test_public_key", &[7u8; 65]] {
+            let expected = util::ripemd160_digest(&util::sha256_digest(pub_key));
+            assert_eq!(hash_pub_key(pub_key), expected);
+            assert_eq!(hash_pub_key(pub_key), util::hash160(pub_key));
+        }
+    }
+
     // =============================================================================
     // CONVERT_ADDRESS FUNCTION TESTS
     // =============================================================================
@@ -342,4 +352,37 @@ mod tests {
         assert_eq!(hash_65.len(), 20);
         assert_ne!(hash_33, hash_65);
     }
-} 
+
+    // =============================================================================
+    // ADDRESS VERSION TESTS
+    // =============================================================================
+
+    const TESTNET_VERSION: u8 = 0x6f;
+
+    #[test]
+    fn test_address_from_one_version_fails_under_another() {
+        let pub_hash_key = hash_pub_key(b"address_version_key");
+        let testnet_address = convert_address_with_version(&pub_hash_key, TESTNET_VERSION);
+        let mainnet_address = convert_address_with_version(&pub_hash_key, VERSION);
+
+        assert_ne!(testnet_address, mainnet_address);
+        assert!(validate_address_with_version(&testnet_address, TESTNET_VERSION));
+        assert!(!validate_address_with_version(&testnet_address, VERSION));
+        assert!(validate_address_with_version(&mainnet_address, VERSION));
+        assert!(!validate_address_with_version(&mainnet_address, TESTNET_VERSION));
+    }
+
+    #[test]
+    fn test_wallet_address_under_configured_version() {
+        let wallet = Wallet::from_seed(b"address_version_wallet");
+        let version = rust_blockchain::config::GLOBAL_CONFIG.get_address_version();
+
+        // The default derivation uses the configured version
+        assert_eq!(wallet.get_address(), wallet.get_address_with_version(version));
+        assert!(validate_address(&wallet.get_address()));
+
+        let testnet_address = wallet.get_address_with_version(TESTNET_VERSION);
+        assert!(validate_address_with_version(&testnet_address, TESTNET_VERSION));
+        assert!(!validate_address(&testnet_address));
+    }
+}
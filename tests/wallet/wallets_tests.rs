@@ -397,4 +397,52 @@ mod tests {
         
         cleanup_test_env(&temp_dir);
     }
-} 
+
+    #[test]
+    fn test_add_wallet_persists_imported_wallet() {
+        let (temp_dir, wallet_file_path) = setup_test_env();
+        let wallet = rust_blockchain::wallet::Wallet::new();
+        let expected_address = wallet.get_address();
+
+        {
+            let mut wallets = Wallets::new_with_file_path(wallet_file_path.clone());
+            let address = wallets.add_wallet(wallet);
+            assert_eq!(address, expected_address);
+        }
+
+        let reloaded = Wallets::new_with_file_path(wallet_file_path);
+        assert!(reloaded.get_wallet(expected_address.as_str()).is_some());
+
+        cleanup_test_env(&temp_dir);
+    }
+
+    #[test]
+    fn test_create_wallets_batch() {
+        let (temp_dir, wallet_file_path) = setup_test_env();
+        let created = {
+            let mut wallets = Wallets::new_with_file_path(wallet_file_path.clone());
+            wallets.create_wallet();
+            let created = wallets.create_wallets(25);
+            assert_eq!(created.len(), 25);
+            assert_eq!(wallets.get_addresses().len(), 26);
+            created
+        };
+
+        let unique: std::collections::HashSet<&String> = created.iter().collect();
+        assert_eq!(unique.len(), 25);
+
+        // The whole batch was saved
+        let reloaded = Wallets::new_with_file_path(wallet_file_path);
+        assert_eq!(reloaded.get_addresses().len(), 26);
+        for address in &created {
+            assert!(reloaded.get_wallet(address).is_some());
+        }
+
+        // An empty batch creates nothing
+        let mut wallets = Wallets::new_with_file_path(temp_dir.path().join("empty_batch.dat"));
+        assert!(wallets.create_wallets(0).is_empty());
+        assert!(wallets.get_addresses().is_empty());
+
+        cleanup_test_env(&temp_dir);
+    }
+}